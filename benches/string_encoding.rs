@@ -0,0 +1,39 @@
+//! Encoding cost of a large `BYTES` batch: JSON via `serde_json::Value`,
+//! JSON serialized in place, and the binary tensor extension.
+//!
+//! Run with `cargo bench --bench string_encoding`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use truston::client::binary::encode_tensor;
+use truston::client::io::DataType;
+
+fn texts(count: usize) -> DataType {
+    DataType::String(
+        (0..count)
+            .map(|i| format!("short input text number {}", i))
+            .collect(),
+    )
+}
+
+fn bench_string_encoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bytes_encoding");
+    for count in [1_000, 10_000] {
+        let data = texts(count);
+        let DataType::String(values) = &data else { unreachable!() };
+        group.throughput(Throughput::Elements(count as u64));
+
+        group.bench_with_input(BenchmarkId::new("json_value", count), values, |b, values| {
+            b.iter(|| serde_json::to_vec(&serde_json::json!(black_box(values))).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("json_direct", count), &data, |b, data| {
+            b.iter(|| serde_json::to_vec(black_box(data)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("binary", count), &data, |b, data| {
+            b.iter(|| encode_tensor(black_box(data)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_string_encoding);
+criterion_main!(benches);
@@ -1,4 +1,3 @@
-use tokio;
 use truston::client::http::TritonRestClient;
 use truston::init_tracing;
 
@@ -7,9 +6,9 @@ async fn main() {
     init_tracing();
 
     let my_client = TritonRestClient::new("http://localhost:50000");
-    let is_alive = my_client.is_server_live().await;
-    match is_alive {
-        Ok(_) => println!("Server is live!"),
+    let live = my_client.server_live().await;
+    match live {
+        Ok(health) => println!("{}", health),
         Err(e) => println!("error: {:#?}", e),
     }
 }
\ No newline at end of file
@@ -1,6 +1,5 @@
 use std::sync::Arc;
 use ndarray::{ArrayD, IxDyn};
-use tokio;
 use truston::client::http::TritonRestClient;
 use truston::client::io::InferInput;
 use truston::utils::errors::TrustonError;
@@ -14,7 +13,7 @@ async fn run_infer(client: Arc<TritonRestClient>) -> Result<(), TrustonError> {
 
     let inputs = vec![input_ids, input_attention_mask];
 
-    let _ = client.infer(inputs, "hierarchical_clf").await?;
+    let _ = client.infer(inputs, "hierarchical_clf", None).await?;
     Ok(())
 }
 
@@ -0,0 +1,278 @@
+//! Response size prediction and admission control.
+//!
+//! The REST protocol returns outputs as JSON, which the client holds three
+//! times while decoding: the body, the parsed JSON tree and the typed
+//! vectors. For models with large outputs (segmentation masks, embeddings of
+//! big batches) this can exceed the memory of a constrained container.
+//! [`AdmissionControl`] predicts the peak from the model metadata and the
+//! batch size of a request, and rejects (or warns about) requests over a
+//! configured budget before they are sent.
+
+use crate::client::dtype::TritonDtype;
+use crate::client::http::TritonRestClient;
+use crate::client::io::InferInput;
+use crate::client::metadata::{ModelMetadata, TensorMetadata};
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+/// Predicted size of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResponseEstimate {
+    /// Values across all outputs.
+    pub elements: u64,
+    /// Approximate size of the JSON body.
+    pub body_bytes: u64,
+    /// Size of the decoded values.
+    pub decoded_bytes: u64,
+}
+
+impl ResponseEstimate {
+    /// Approximate peak memory while decoding: the body, the parsed JSON
+    /// values and the decoded values, all alive at once.
+    pub fn peak_bytes(&self) -> u64 {
+        let json_values = self.elements.saturating_mul(size_of::<serde_json::Value>() as u64);
+        self.body_bytes.saturating_add(json_values).saturating_add(self.decoded_bytes)
+    }
+}
+
+/// What to do with a request whose response would exceed the budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetAction {
+    /// Fail with [`TrustonError::AdmissionRejected`] without sending it.
+    #[default]
+    Reject,
+    /// Log a warning and let it through.
+    Warn,
+}
+
+/// Memory budget for the responses of one model.
+///
+/// # Example
+/// ```
+/// use truston::client::admission::AdmissionControl;
+/// use truston::client::dtype::TritonDtype;
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::metadata::TensorMetadata;
+/// use truston::client::options::InferOptions;
+///
+/// // a segmentation model returning one 512x512 FP32 mask per image
+/// let outputs = vec![TensorMetadata {
+///     name: "mask".into(),
+///     datatype: TritonDtype::Fp32,
+///     shape: vec![-1, 512, 512],
+/// }];
+/// let admission = AdmissionControl::new(outputs, 64 * 1024 * 1024);
+///
+/// let images = |n: usize| {
+///     vec![InferInput::new("image".into(), vec![n, 3], DataType::F32(vec![0.0; n * 3]))]
+/// };
+/// assert!(admission.check(&images(1), &InferOptions::new()).is_ok());
+/// assert!(admission.check(&images(64), &InferOptions::new()).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct AdmissionControl {
+    outputs: Vec<TensorMetadata>,
+    /// Largest accepted [`ResponseEstimate::peak_bytes`].
+    pub max_bytes: u64,
+    pub action: BudgetAction,
+    /// Size assumed for dynamic dims other than the batch dim. Defaults to `1`.
+    pub dynamic_size: usize,
+    /// Length assumed for `BYTES` elements. Defaults to `64`.
+    pub bytes_per_string: usize,
+}
+
+impl AdmissionControl {
+    /// Budget of `max_bytes` for responses made of `outputs`.
+    pub fn new(outputs: Vec<TensorMetadata>, max_bytes: u64) -> Self {
+        AdmissionControl {
+            outputs,
+            max_bytes,
+            action: BudgetAction::default(),
+            dynamic_size: 1,
+            bytes_per_string: 64,
+        }
+    }
+
+    pub fn from_metadata(metadata: &ModelMetadata, max_bytes: u64) -> Self {
+        Self::new(metadata.outputs.clone(), max_bytes)
+    }
+
+    pub fn with_action(mut self, action: BudgetAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    pub fn with_dynamic_size(mut self, size: usize) -> Self {
+        self.dynamic_size = size;
+        self
+    }
+
+    pub fn with_bytes_per_string(mut self, bytes: usize) -> Self {
+        self.bytes_per_string = bytes;
+        self
+    }
+
+    /// Predict the response to `inputs`, counting only the outputs requested
+    /// in `options` (all of them if none are).
+    ///
+    /// The batch size is the first dim of the first input.
+    pub fn estimate(&self, inputs: &[InferInput], options: &InferOptions) -> ResponseEstimate {
+        let batch = inputs
+            .first()
+            .and_then(|input| input.input_shape.first().copied())
+            .unwrap_or(1);
+        let requested = |name: &str| match &options.outputs {
+            Some(outputs) => outputs.iter().any(|output| output.name == name),
+            None => true,
+        };
+
+        let mut estimate = ResponseEstimate::default();
+        for output in self.outputs.iter().filter(|output| requested(&output.name)) {
+            // saturating, so absurd shapes are estimated huge and rejected
+            let elements = output
+                .shape
+                .iter()
+                .enumerate()
+                .map(|(i, &dim)| match dim {
+                    d if d >= 0 => d as u64,
+                    _ if i == 0 => batch as u64,
+                    _ => self.dynamic_size as u64,
+                })
+                .fold(1u64, u64::saturating_mul);
+            let (value_bytes, json_bytes) = element_sizes(&output.datatype, self.bytes_per_string);
+            estimate.elements = estimate.elements.saturating_add(elements);
+            estimate.decoded_bytes =
+                estimate.decoded_bytes.saturating_add(elements.saturating_mul(value_bytes));
+            estimate.body_bytes =
+                estimate.body_bytes.saturating_add(elements.saturating_mul(json_bytes));
+        }
+        estimate
+    }
+
+    /// Estimate the response to `inputs` and apply the budget.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::AdmissionRejected`] if the estimated peak is
+    /// over the budget and the action is [`BudgetAction::Reject`].
+    pub fn check(
+        &self,
+        inputs: &[InferInput],
+        options: &InferOptions,
+    ) -> Result<ResponseEstimate, TrustonError> {
+        let estimate = self.estimate(inputs, options);
+        let peak = estimate.peak_bytes();
+        if peak <= self.max_bytes {
+            return Ok(estimate);
+        }
+        match self.action {
+            BudgetAction::Reject => Err(TrustonError::AdmissionRejected {
+                estimated_bytes: peak,
+                budget_bytes: self.max_bytes,
+            }),
+            BudgetAction::Warn => {
+                tracing::warn!(
+                    "response estimated at {} bytes, over the budget of {}",
+                    peak,
+                    self.max_bytes
+                );
+                Ok(estimate)
+            }
+        }
+    }
+}
+
+impl TritonRestClient {
+    /// Fetch the metadata of `model_name` and build its [`AdmissionControl`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # use truston::client::io::{DataType, InferInput};
+    /// use truston::client::options::InferOptions;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let admission = client.admission_control("segmenter", 256 * 1024 * 1024).await?;
+    ///
+    /// # let inputs = vec![InferInput::new("x".into(), vec![1], DataType::F32(vec![0.0]))];
+    /// let options = InferOptions::new();
+    /// admission.check(&inputs, &options)?;
+    /// let results = client.infer_with_options(inputs, "segmenter", &options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn admission_control(
+        &self,
+        model_name: &str,
+        max_bytes: u64,
+    ) -> Result<AdmissionControl, TrustonError> {
+        let metadata = self.cached_model_metadata(model_name).await?;
+        Ok(AdmissionControl::from_metadata(&metadata, max_bytes))
+    }
+}
+
+/// Decoded size and approximate JSON size of one element of `datatype`.
+fn element_sizes(datatype: &TritonDtype, bytes_per_string: usize) -> (u64, u64) {
+    match datatype {
+        TritonDtype::Bool => (1, 6),
+        TritonDtype::Uint8 | TritonDtype::Int8 => (1, 4),
+        TritonDtype::Uint16 | TritonDtype::Int16 => (2, 6),
+        TritonDtype::Uint32 | TritonDtype::Int32 => (4, 11),
+        TritonDtype::Uint64 | TritonDtype::Int64 => (8, 20),
+        TritonDtype::Fp16 | TritonDtype::Bf16 => (2, 12),
+        TritonDtype::Fp32 => (4, 14),
+        // kept as raw JSON, counted as the widest numbers
+        TritonDtype::Fp64 | TritonDtype::Other(_) => (8, 24),
+        // a String per element, plus quotes and separator in the body
+        TritonDtype::Bytes => {
+            let len = bytes_per_string as u64;
+            (size_of::<String>() as u64 + len, len + 3)
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::{DataType, InferRequestedOutput};
+
+    fn tensor(name: &str, datatype: &str, shape: Vec<i64>) -> TensorMetadata {
+        TensorMetadata { name: name.into(), datatype: datatype.parse().unwrap(), shape }
+    }
+
+    fn batch(n: usize) -> Vec<InferInput> {
+        vec![InferInput::new("x".into(), vec![n, 2], DataType::F32(vec![0.0; n * 2]))]
+    }
+
+    #[test]
+    fn test_estimate_scales_with_batch_and_requested_outputs() {
+        let admission = AdmissionControl::new(
+            vec![tensor("probs", "FP32", vec![-1, 1000]), tensor("ids", "INT64", vec![-1, -1])],
+            u64::MAX,
+        )
+        .with_dynamic_size(5);
+
+        let estimate = admission.estimate(&batch(2), &InferOptions::new());
+        assert_eq!(estimate.elements, 2 * 1000 + 2 * 5);
+        assert_eq!(estimate.decoded_bytes, 2 * 1000 * 4 + 2 * 5 * 8);
+
+        let only_ids = InferOptions::new().with_outputs(vec![InferRequestedOutput::new("ids")]);
+        assert_eq!(admission.estimate(&batch(2), &only_ids).elements, 10);
+    }
+
+    #[test]
+    fn test_budget_rejects_or_warns() {
+        let outputs = vec![tensor("embedding", "FP32", vec![-1, 768])];
+        let admission = AdmissionControl::new(outputs, 1024 * 1024);
+        let options = InferOptions::new();
+
+        assert!(admission.check(&batch(1), &options).is_ok());
+        assert!(matches!(
+            admission.check(&batch(256), &options),
+            Err(TrustonError::AdmissionRejected { budget_bytes: 1048576, .. })
+        ));
+        let warn_only = admission.with_action(BudgetAction::Warn);
+        assert!(warn_only.check(&batch(256), &options).is_ok());
+    }
+}
@@ -0,0 +1,198 @@
+//! Session affinity for multi-turn conversations.
+//!
+//! LLM backends keep the KV cache of a conversation on the instance that
+//! served its previous turn. Sending the next turn elsewhere recomputes the
+//! whole prompt. An [`AffinityToken`] attached to the requests of a
+//! conversation (see [`InferOptions::with_affinity`](crate::client::options::InferOptions::with_affinity))
+//! keeps them on the same backend:
+//! - among the client's endpoints (see [`TritonRestClientBuilder::failover_endpoint`](crate::client::http::TritonRestClientBuilder::failover_endpoint)),
+//!   the token is pinned to the endpoint that answered its last request,
+//!   until it goes unused for the affinity TTL or that endpoint fails,
+//! - behind a load balancer, an [`AffinityCarrier`] sends the token in a
+//!   header or request parameter the balancer can hash on.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{Map, Value};
+
+use crate::client::http::TritonRestClient;
+
+/// How long a pin survives without use, unless configured otherwise.
+pub const DEFAULT_AFFINITY_TTL: Duration = Duration::from_secs(600);
+
+/// Identifier of a conversation whose requests should hit the same backend.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AffinityToken(String);
+
+impl AffinityToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        AffinityToken(token.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for AffinityToken {
+    fn from(token: &str) -> Self {
+        AffinityToken::new(token)
+    }
+}
+
+impl From<String> for AffinityToken {
+    fn from(token: String) -> Self {
+        AffinityToken(token)
+    }
+}
+
+/// How an affinity token is sent with a request.
+///
+/// Implement it to match what the load balancer in front of the servers
+/// routes on; [`HeaderCarrier`] and [`ParameterCarrier`] cover the usual cases.
+pub trait AffinityCarrier: Debug + Send + Sync {
+    /// Headers added to every request carrying `token`.
+    fn headers(&self, token: &AffinityToken) -> Vec<(String, String)> {
+        let _ = token;
+        Vec::new()
+    }
+
+    /// Parameters added to every inference request carrying `token`.
+    fn parameters(&self, token: &AffinityToken) -> Map<String, Value> {
+        let _ = token;
+        Map::new()
+    }
+}
+
+/// Send the token as an HTTP header, `x-affinity-token` by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderCarrier {
+    pub name: String,
+}
+
+impl HeaderCarrier {
+    pub fn new(name: impl Into<String>) -> Self {
+        HeaderCarrier { name: name.into() }
+    }
+}
+
+impl Default for HeaderCarrier {
+    fn default() -> Self {
+        HeaderCarrier::new("x-affinity-token")
+    }
+}
+
+impl AffinityCarrier for HeaderCarrier {
+    fn headers(&self, token: &AffinityToken) -> Vec<(String, String)> {
+        vec![(self.name.clone(), token.as_str().to_string())]
+    }
+}
+
+/// Send the token as a request parameter of inference requests, for
+/// routers and backends that read the request body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParameterCarrier {
+    pub name: String,
+}
+
+impl ParameterCarrier {
+    pub fn new(name: impl Into<String>) -> Self {
+        ParameterCarrier { name: name.into() }
+    }
+}
+
+impl AffinityCarrier for ParameterCarrier {
+    fn parameters(&self, token: &AffinityToken) -> Map<String, Value> {
+        let mut parameters = Map::new();
+        parameters.insert(self.name.clone(), Value::from(token.as_str()));
+        parameters
+    }
+}
+
+/// Endpoint each token is pinned to, expiring after `ttl` without use.
+#[derive(Debug)]
+pub(crate) struct AffinityTable {
+    ttl: Duration,
+    pins: Mutex<HashMap<AffinityToken, (usize, Instant)>>,
+}
+
+impl AffinityTable {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        AffinityTable { ttl, pins: Mutex::new(HashMap::new()) }
+    }
+
+    /// Endpoint `token` is pinned to, if the pin has not expired.
+    pub(crate) fn endpoint(&self, token: &AffinityToken) -> Option<usize> {
+        let mut pins = self.pins.lock().unwrap();
+        let now = Instant::now();
+        pins.retain(|_, (_, last_used)| now.duration_since(*last_used) < self.ttl);
+        pins.get(token).map(|&(endpoint, _)| endpoint)
+    }
+
+    /// Pin `token` to `endpoint`, restarting its TTL.
+    pub(crate) fn pin(&self, token: &AffinityToken, endpoint: usize) {
+        self.pins
+            .lock()
+            .unwrap()
+            .insert(token.clone(), (endpoint, Instant::now()));
+    }
+
+    pub(crate) fn release(&self, token: &AffinityToken) {
+        self.pins.lock().unwrap().remove(token);
+    }
+
+    /// Number of unexpired pins.
+    pub(crate) fn len(&self) -> usize {
+        let pins = self.pins.lock().unwrap();
+        pins.values().filter(|(_, last_used)| last_used.elapsed() < self.ttl).count()
+    }
+}
+
+impl TritonRestClient {
+    /// Base URL the requests carrying `token` are sent to, if it is pinned.
+    pub fn affinity_endpoint(&self, token: &AffinityToken) -> Option<&str> {
+        self.affinity
+            .endpoint(token)
+            .map(|endpoint| self.endpoints[endpoint].as_str())
+    }
+
+    /// Forget the endpoint of `token`, e.g. when its conversation ends.
+    pub fn release_affinity(&self, token: &AffinityToken) {
+        self.affinity.release(token);
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pins_expire_after_ttl() {
+        let token = AffinityToken::from("conversation-1");
+        let table = AffinityTable::new(Duration::from_secs(60));
+        assert_eq!(table.endpoint(&token), None);
+        table.pin(&token, 2);
+        assert_eq!(table.endpoint(&token), Some(2));
+        table.release(&token);
+        assert_eq!(table.endpoint(&token), None);
+
+        let expired = AffinityTable::new(Duration::ZERO);
+        expired.pin(&token, 1);
+        assert_eq!(expired.endpoint(&token), None);
+    }
+
+    #[test]
+    fn test_carriers() {
+        let token = AffinityToken::from("abc");
+        assert_eq!(
+            HeaderCarrier::default().headers(&token),
+            vec![("x-affinity-token".to_string(), "abc".to_string())]
+        );
+        assert!(HeaderCarrier::default().parameters(&token).is_empty());
+        assert_eq!(ParameterCarrier::new("session_id").parameters(&token)["session_id"], "abc");
+    }
+}
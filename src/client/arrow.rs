@@ -0,0 +1,295 @@
+//! Arrow record batches (requires the `arrow` feature).
+//!
+//! Tabular models (the FIL backend, Python backends scoring feature tables)
+//! take columns of a table as inputs. A [`RecordBatch`] from DataFusion,
+//! Polars or a Parquet reader converts into inputs in two ways:
+//! - [`InferInput::from_arrow_columns`]: one `[rows, 1]` input per column,
+//!   named after it (`[rows, n]` for fixed-size list columns),
+//! - [`InferInput::from_arrow_batch`]: every column in one `[rows, columns]`
+//!   input, as the FIL backend expects.
+//!
+//! [`InferResults::to_record_batch`] turns the outputs back into a batch of
+//! one column per output, rows along the first dimension; outputs with more
+//! than one value per row become fixed-size list columns.
+//!
+//! Columns with null values are rejected: Triton tensors have no nulls.
+//!
+//! # Example
+//! ```
+//! use std::sync::Arc;
+//!
+//! use arrow::array::{Float32Array, RecordBatch};
+//! use truston::client::io::InferInput;
+//!
+//! let batch = RecordBatch::try_from_iter([
+//!     ("age", Arc::new(Float32Array::from(vec![31.0, 45.0])) as _),
+//!     ("income", Arc::new(Float32Array::from(vec![52.0, 87.5])) as _),
+//! ])?;
+//! let input = InferInput::from_arrow_batch("input__0", &batch)?;
+//! assert_eq!(input.input_shape, vec![2, 2]);
+//! assert_eq!(input.input_data.as_f32_vec(), Some(vec![31.0, 52.0, 45.0, 87.5]));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::sync::Arc;
+
+use ::arrow::array::{
+    Array, ArrayRef, AsArray, BinaryArray, BooleanArray, FixedSizeListArray, Float32Array,
+    Float64Array, Int8Array, Int16Array, Int32Array, Int64Array, RecordBatch, StringArray,
+    UInt8Array, UInt16Array, UInt32Array, UInt64Array,
+};
+use ::arrow::datatypes::{
+    DataType as ArrowType, Field, Float32Type, Float64Type, Int8Type, Int16Type, Int32Type,
+    Int64Type, Schema, UInt8Type, UInt16Type, UInt32Type, UInt64Type,
+};
+
+use crate::client::io::{DataType, InferInput, InferResults, interleave_columns};
+use crate::utils::errors::TrustonError;
+
+impl InferInput {
+    /// One input per column of `batch`, named after it: `[rows, 1]`, or
+    /// `[rows, n]` for a fixed-size list column of `n` values.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] for columns with nulls or of
+    /// a type without Triton equivalent.
+    pub fn from_arrow_columns(batch: &RecordBatch) -> Result<Vec<InferInput>, TrustonError> {
+        let schema = batch.schema();
+        schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, column)| {
+                let (data, per_row) = column_data(field.name(), column.as_ref())?;
+                Ok(InferInput::new(field.name().clone(), vec![batch.num_rows(), per_row], data))
+            })
+            .collect()
+    }
+
+    /// Every column of `batch` in one `[rows, columns]` input, row by row.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the columns don't all have
+    /// the same type (cast them first, e.g. with `arrow::compute::cast`), or
+    /// one of them has nulls or is not a column of scalars.
+    pub fn from_arrow_batch(
+        name: impl Into<String>,
+        batch: &RecordBatch,
+    ) -> Result<InferInput, TrustonError> {
+        let schema = batch.schema();
+        let mut columns = Vec::with_capacity(batch.num_columns());
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let (data, per_row) = column_data(field.name(), column.as_ref())?;
+            if per_row != 1 {
+                return Err(TrustonError::InferenceError(format!(
+                    "column {} holds {} values per row, expected one",
+                    field.name(),
+                    per_row
+                )));
+            }
+            if let Some(first) = columns.first()
+                && data.get_type_str() != DataType::get_type_str(first)
+            {
+                return Err(TrustonError::InferenceError(format!(
+                    "column {} is {}, the first column is {}",
+                    field.name(),
+                    data.get_type_str(),
+                    DataType::get_type_str(first)
+                )));
+            }
+            columns.push(data);
+        }
+        let rows = batch.num_rows();
+        let data = interleave_columns(&columns, rows);
+        Ok(InferInput::new(name.into(), vec![rows, columns.len()], data))
+    }
+}
+
+impl InferResults {
+    /// The outputs as the columns of a batch, rows along their first
+    /// dimension; an output of more than one value per row becomes a
+    /// fixed-size list column (the remaining dimensions are flattened).
+    ///
+    /// `BF16` outputs become `UInt16` columns of the raw bits.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the outputs have different
+    /// numbers of rows, or one of them holds raw JSON.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, TrustonError> {
+        let mut fields = Vec::with_capacity(self.outputs.len());
+        let mut columns = Vec::with_capacity(self.outputs.len());
+        let mut rows = None;
+        for output in &self.outputs {
+            let output_rows = output.shape.first().copied().unwrap_or(1);
+            if *rows.get_or_insert(output_rows) != output_rows {
+                return Err(TrustonError::InferenceError(format!(
+                    "output {} has {} rows, the previous outputs {}",
+                    output.name,
+                    output_rows,
+                    rows.unwrap_or_default()
+                )));
+            }
+            let values = flat_array(&output.name, &output.data)?;
+            let per_row: usize = output.shape.iter().skip(1).product();
+            let column: ArrayRef = if per_row == 1 && output.shape.len() <= 2 {
+                values
+            } else {
+                let item = Arc::new(Field::new("item", values.data_type().clone(), false));
+                let size = i32::try_from(per_row).map_err(|_| {
+                    TrustonError::InferenceError(format!("output {} rows are too long", output.name))
+                })?;
+                Arc::new(FixedSizeListArray::try_new(item, size, values, None).map_err(arrow_error)?)
+            };
+            fields.push(Field::new(&output.name, column.data_type().clone(), false));
+            columns.push(column);
+        }
+        RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(arrow_error)
+    }
+}
+
+/// The values of `column` and their number per row.
+fn column_data(name: &str, column: &dyn Array) -> Result<(DataType, usize), TrustonError> {
+    if column.null_count() > 0 {
+        return Err(TrustonError::InferenceError(format!(
+            "column {} has {} null values",
+            name,
+            column.null_count()
+        )));
+    }
+    let data = match column.data_type() {
+        ArrowType::Boolean => DataType::Bool(column.as_boolean().values().iter().collect()),
+        ArrowType::UInt8 => DataType::U8(column.as_primitive::<UInt8Type>().values().to_vec()),
+        ArrowType::UInt16 => DataType::U16(column.as_primitive::<UInt16Type>().values().to_vec()),
+        ArrowType::UInt32 => DataType::U32(column.as_primitive::<UInt32Type>().values().to_vec()),
+        ArrowType::UInt64 => DataType::U64(column.as_primitive::<UInt64Type>().values().to_vec()),
+        ArrowType::Int8 => DataType::I8(column.as_primitive::<Int8Type>().values().to_vec()),
+        ArrowType::Int16 => DataType::I16(column.as_primitive::<Int16Type>().values().to_vec()),
+        ArrowType::Int32 => DataType::I32(column.as_primitive::<Int32Type>().values().to_vec()),
+        ArrowType::Int64 => DataType::I64(column.as_primitive::<Int64Type>().values().to_vec()),
+        ArrowType::Float32 => DataType::F32(column.as_primitive::<Float32Type>().values().to_vec()),
+        ArrowType::Float64 => DataType::F64(column.as_primitive::<Float64Type>().values().to_vec()),
+        #[cfg(feature = "half")]
+        ArrowType::Float16 => DataType::F16(
+            column.as_primitive::<::arrow::datatypes::Float16Type>().values().to_vec(),
+        ),
+        ArrowType::Utf8 => DataType::String(
+            column.as_string::<i32>().iter().map(|v| v.unwrap_or_default().to_string()).collect(),
+        ),
+        ArrowType::LargeUtf8 => DataType::String(
+            column.as_string::<i64>().iter().map(|v| v.unwrap_or_default().to_string()).collect(),
+        ),
+        ArrowType::Binary => DataType::Bytes(
+            column.as_binary::<i32>().iter().map(|v| v.unwrap_or_default().to_vec()).collect(),
+        ),
+        ArrowType::LargeBinary => DataType::Bytes(
+            column.as_binary::<i64>().iter().map(|v| v.unwrap_or_default().to_vec()).collect(),
+        ),
+        ArrowType::FixedSizeList(_, size) => {
+            let list = column.as_fixed_size_list();
+            let (data, per_value) = column_data(name, list.values().as_ref())?;
+            return Ok((data, *size as usize * per_value));
+        }
+        other => {
+            return Err(TrustonError::InferenceError(format!(
+                "column {} has type {}, which has no Triton datatype",
+                name, other
+            )));
+        }
+    };
+    Ok((data, 1))
+}
+
+/// A flat array of the values of `data`.
+fn flat_array(name: &str, data: &DataType) -> Result<ArrayRef, TrustonError> {
+    let array: ArrayRef = match data {
+        DataType::Bool(v) => Arc::new(BooleanArray::from(v.clone())),
+        DataType::U8(v) => Arc::new(UInt8Array::from(v.clone())),
+        DataType::U16(v) | DataType::Bf16(v) => Arc::new(UInt16Array::from(v.clone())),
+        DataType::U32(v) => Arc::new(UInt32Array::from(v.clone())),
+        DataType::U64(v) => Arc::new(UInt64Array::from(v.clone())),
+        DataType::I8(v) => Arc::new(Int8Array::from(v.clone())),
+        DataType::I16(v) => Arc::new(Int16Array::from(v.clone())),
+        DataType::I32(v) => Arc::new(Int32Array::from(v.clone())),
+        DataType::I64(v) => Arc::new(Int64Array::from(v.clone())),
+        DataType::F32(v) => Arc::new(Float32Array::from(v.clone())),
+        DataType::F64(v) => Arc::new(Float64Array::from(v.clone())),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => Arc::new(::arrow::array::Float16Array::from(v.clone())),
+        DataType::String(v) => Arc::new(StringArray::from(v.clone())),
+        DataType::Bytes(v) => Arc::new(BinaryArray::from_iter_values(v)),
+        DataType::Raw(_) => {
+            return Err(TrustonError::InferenceError(format!(
+                "output {} holds raw JSON, which has no Arrow type",
+                name
+            )));
+        }
+    };
+    Ok(array)
+}
+
+fn arrow_error(e: ::arrow::error::ArrowError) -> TrustonError {
+    TrustonError::InferenceError(format!("invalid record batch: {}", e))
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::InferOutput;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter([
+            ("id", Arc::new(Int64Array::from(vec![7, 8, 9])) as ArrayRef),
+            ("name", Arc::new(StringArray::from(vec!["a", "b", "c"])) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_columns_to_inputs() {
+        let inputs = InferInput::from_arrow_columns(&batch()).unwrap();
+        assert_eq!(inputs[0].input_name, "id");
+        assert_eq!(inputs[0].input_shape, vec![3, 1]);
+        assert_eq!(inputs[0].input_data.as_i64_vec(), Some(vec![7, 8, 9]));
+        assert_eq!(inputs[1].input_data.as_str_vec().unwrap(), vec!["a", "b", "c"]);
+
+        // mixed types can't share a tensor
+        assert!(InferInput::from_arrow_batch("x", &batch()).is_err());
+
+        let nulls = Arc::new(Float32Array::from(vec![Some(1.0), None])) as ArrayRef;
+        let batch = RecordBatch::try_from_iter([("x", nulls)]).unwrap();
+        assert!(matches!(
+            InferInput::from_arrow_columns(&batch),
+            Err(TrustonError::InferenceError(_))
+        ));
+    }
+
+    #[test]
+    fn test_results_to_record_batch_and_back() {
+        let output = |name: &str, shape: Vec<usize>, data: DataType| InferOutput {
+            name: name.into(),
+            datatype: data.dtype().unwrap(),
+            shape,
+            data,
+        };
+        let results = InferResults {
+            outputs: vec![
+                output("label", vec![2], DataType::I32(vec![1, 0])),
+                output("probs", vec![2, 2], DataType::F32(vec![0.1, 0.9, 0.8, 0.2])),
+            ],
+            ..Default::default()
+        };
+        let batch = results.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.schema().field(0).data_type(), &ArrowType::Int32);
+        assert!(matches!(batch.schema().field(1).data_type(), ArrowType::FixedSizeList(_, 2)));
+
+        let inputs = InferInput::from_arrow_columns(&batch).unwrap();
+        assert_eq!(inputs[1].input_shape, vec![2, 2]);
+        assert_eq!(inputs[1].input_data.as_f32_vec(), Some(vec![0.1, 0.9, 0.8, 0.2]));
+
+        let mut results = results;
+        results.outputs.push(output("extra", vec![3], DataType::U8(vec![0; 3])));
+        assert!(results.to_record_batch().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Audio preprocessing (requires the `audio` feature).
+//!
+//! Speech and audio models take mono `FP32` samples in `[-1, 1]` at a
+//! fixed sample rate. [`Audio`] loads WAV files or raw PCM, mixes the
+//! channels down to mono and resamples, and builds `[1, samples]` inputs,
+//! either whole or chunked with overlap for streaming ASR models.
+//!
+//! # Example
+//! ```
+//! use truston::client::audio::Audio;
+//! use truston::client::io::InferInput;
+//!
+//! // one second of stereo 44.1 kHz PCM, as read from a sound card
+//! let pcm = vec![0i16; 2 * 44_100];
+//! let audio = Audio::from_pcm_i16(&pcm, 44_100, 2).resample(16_000);
+//! assert_eq!(audio.samples.len(), 16_000);
+//!
+//! let input = InferInput::from_audio("waveform", &audio);
+//! assert_eq!(input.input_shape, vec![1, 16_000]);
+//!
+//! // 0.5 s windows overlapping by 0.1 s
+//! let chunks = InferInput::from_audio_chunks("waveform", &audio, 8_000, 1_600);
+//! assert_eq!(chunks.len(), 3);
+//! ```
+
+use std::io::Read;
+use std::path::Path;
+
+use crate::client::io::{DataType, InferInput};
+use crate::utils::errors::TrustonError;
+
+/// Mono samples in `[-1, 1]` and their sample rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl Audio {
+    /// Decode a WAV stream (integer PCM of 8 to 32 bits, or 32-bit float).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the stream is not a valid WAV.
+    pub fn from_wav<R: Read>(reader: R) -> Result<Audio, TrustonError> {
+        let reader = hound::WavReader::new(reader).map_err(wav_error)?;
+        let spec = reader.spec();
+        let interleaved = match spec.sample_format {
+            hound::SampleFormat::Float => {
+                reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>()
+            }
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .into_samples::<i32>()
+                    .map(|sample| sample.map(|s| s as f32 * scale))
+                    .collect::<Result<Vec<_>, _>>()
+            }
+        }
+        .map_err(wav_error)?;
+        Ok(Audio::from_pcm_f32(&interleaved, spec.sample_rate, spec.channels))
+    }
+
+    /// Decode the WAV file at `path`, see [`Audio::from_wav`].
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the file can't be read or is
+    /// not a valid WAV.
+    pub fn from_wav_file(path: impl AsRef<Path>) -> Result<Audio, TrustonError> {
+        let path = path.as_ref();
+        let file = std::fs::File::open(path).map_err(|e| {
+            TrustonError::ParseError(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        Audio::from_wav(std::io::BufReader::new(file))
+    }
+
+    /// Interleaved 16-bit PCM of `channels` channels, mixed down to mono.
+    pub fn from_pcm_i16(samples: &[i16], sample_rate: u32, channels: u16) -> Audio {
+        let samples: Vec<f32> = samples.iter().map(|&s| s as f32 / 32768.0).collect();
+        Audio::from_pcm_f32(&samples, sample_rate, channels)
+    }
+
+    /// Interleaved float PCM of `channels` channels, mixed down to mono.
+    pub fn from_pcm_f32(samples: &[f32], sample_rate: u32, channels: u16) -> Audio {
+        let channels = channels.max(1) as usize;
+        let samples = if channels == 1 {
+            samples.to_vec()
+        } else {
+            samples
+                .chunks_exact(channels)
+                .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+                .collect()
+        };
+        Audio { samples, sample_rate }
+    }
+
+    /// Duration in seconds.
+    pub fn duration_secs(&self) -> f32 {
+        if self.sample_rate == 0 { 0.0 } else { self.samples.len() as f32 / self.sample_rate as f32 }
+    }
+
+    /// The audio at `sample_rate`, by linear interpolation.
+    ///
+    /// Good enough for speech models; frequencies above the new Nyquist
+    /// frequency are not filtered out when downsampling.
+    pub fn resample(&self, sample_rate: u32) -> Audio {
+        if sample_rate == self.sample_rate || self.samples.is_empty() || self.sample_rate == 0 {
+            return Audio { samples: self.samples.clone(), sample_rate };
+        }
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        let len = (self.samples.len() as f64 / ratio).round() as usize;
+        let last = self.samples.len() - 1;
+        let samples = (0..len)
+            .map(|i| {
+                let position = i as f64 * ratio;
+                let left = (position.floor() as usize).min(last);
+                let right = (left + 1).min(last);
+                let t = (position - left as f64) as f32;
+                self.samples[left] * (1.0 - t) + self.samples[right] * t
+            })
+            .collect();
+        Audio { samples, sample_rate }
+    }
+}
+
+fn wav_error(e: hound::Error) -> TrustonError {
+    TrustonError::ParseError(format!("invalid WAV: {}", e))
+}
+
+impl InferInput {
+    /// Build a `[1, samples]` `FP32` input from `audio`.
+    pub fn from_audio(name: impl Into<String>, audio: &Audio) -> InferInput {
+        let samples = audio.samples.clone();
+        InferInput::new(name.into(), vec![1, samples.len()], DataType::F32(samples))
+    }
+
+    /// Split `audio` into `[1, chunk_len]` `FP32` inputs, each starting
+    /// `chunk_len - overlap` samples after the previous one, for streaming
+    /// models. The last chunk is padded with silence.
+    ///
+    /// # Panics
+    /// If `overlap` is not smaller than `chunk_len`.
+    pub fn from_audio_chunks(
+        name: impl Into<String>,
+        audio: &Audio,
+        chunk_len: usize,
+        overlap: usize,
+    ) -> Vec<InferInput> {
+        assert!(overlap < chunk_len, "overlap {} must be below chunk_len {}", overlap, chunk_len);
+        let name = name.into();
+        let step = chunk_len - overlap;
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            let end = (start + chunk_len).min(audio.samples.len());
+            let mut samples = audio.samples[start.min(end)..end].to_vec();
+            samples.resize(chunk_len, 0.0);
+            chunks.push(InferInput::new(name.clone(), vec![1, chunk_len], DataType::F32(samples)));
+            if end == audio.samples.len() {
+                return chunks;
+            }
+            start += step;
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(spec: hound::WavSpec, samples: &[i16]) -> Vec<u8> {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut bytes, spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_from_wav_mixes_down() {
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8_000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let bytes = wav(spec, &[16384, 0, -32768, -32768]);
+        let audio = Audio::from_wav(bytes.as_slice()).unwrap();
+        assert_eq!(audio, Audio { samples: vec![0.25, -1.0], sample_rate: 8_000 });
+        assert!(Audio::from_wav(&b"RIFF"[..]).is_err());
+    }
+
+    #[test]
+    fn test_resample() {
+        let audio = Audio { samples: vec![0.0, 1.0, 0.0, -1.0], sample_rate: 4 };
+        let up = audio.resample(8);
+        assert_eq!(up.samples, vec![0.0, 0.5, 1.0, 0.5, 0.0, -0.5, -1.0, -1.0]);
+        assert_eq!(up.duration_secs(), 1.0);
+        assert_eq!(audio.resample(2).samples, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_chunks_overlap_and_pad() {
+        let audio = Audio { samples: (0..7).map(|i| i as f32).collect(), sample_rate: 1 };
+        let chunks = InferInput::from_audio_chunks("x", &audio, 4, 1);
+        let values: Vec<_> = chunks.iter().map(|c| c.input_data.as_f32_vec().unwrap()).collect();
+        assert_eq!(values, vec![vec![0.0, 1.0, 2.0, 3.0], vec![3.0, 4.0, 5.0, 6.0]]);
+
+        let chunks = InferInput::from_audio_chunks("x", &audio, 3, 0);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].input_data.as_f32_vec(), Some(vec![6.0, 0.0, 0.0]));
+        assert_eq!(chunks[2].input_shape, vec![1, 3]);
+    }
+}
@@ -0,0 +1,199 @@
+//! Authentication for servers behind an authenticating gateway.
+//!
+//! An [`Auth`] set with
+//! [`TritonRestClientBuilder::auth`](crate::client::http::TritonRestClientBuilder::auth)
+//! is applied to every request of the client. Static credentials are sent as
+//! default headers; a [`TokenProvider`] is asked for a bearer token before
+//! each attempt, so refreshed OAuth tokens are picked up without rebuilding
+//! the client.
+
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use reqwest::header::{AUTHORIZATION, HeaderName, HeaderValue};
+
+use crate::utils::errors::TrustonError;
+
+/// Source of bearer tokens that may change over time.
+///
+/// Called before every request attempt: implementations should cache the
+/// token and only fetch a new one when it is about to expire.
+///
+/// # Example
+/// ```
+/// use async_trait::async_trait;
+/// use truston::client::auth::TokenProvider;
+/// use truston::TrustonError;
+///
+/// #[derive(Debug)]
+/// struct EnvToken;
+///
+/// #[async_trait]
+/// impl TokenProvider for EnvToken {
+///     async fn token(&self) -> Result<String, TrustonError> {
+///         std::env::var("TRITON_TOKEN").map_err(|e| TrustonError::AuthError(e.to_string()))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait TokenProvider: fmt::Debug + Send + Sync {
+    /// The token to send, without the `Bearer ` prefix.
+    ///
+    /// # Errors
+    /// Its error fails the request, usually as [`TrustonError::AuthError`].
+    async fn token(&self) -> Result<String, TrustonError>;
+}
+
+/// Credentials sent with every request.
+///
+/// Secrets are redacted from the `Debug` output.
+#[derive(Clone)]
+pub enum Auth {
+    /// `Authorization: Bearer <token>`.
+    Bearer(String),
+    /// `Authorization: Basic <base64(username:password)>`.
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// A custom header, e.g. an API key in `x-api-key`.
+    Header { name: String, value: String },
+    /// `Authorization: Bearer <token>`, with the token asked to the provider
+    /// before each attempt.
+    Provider(Arc<dyn TokenProvider>),
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Auth::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            Auth::Basic { username, .. } => {
+                write!(f, "Basic {{ username: {:?}, password: <redacted> }}", username)
+            }
+            Auth::Header { name, .. } => {
+                write!(f, "Header {{ name: {:?}, value: <redacted> }}", name)
+            }
+            Auth::Provider(provider) => f.debug_tuple("Provider").field(provider).finish(),
+        }
+    }
+}
+
+impl Auth {
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Auth::Bearer(token.into())
+    }
+
+    pub fn basic(username: impl Into<String>, password: Option<impl Into<String>>) -> Self {
+        Auth::Basic { username: username.into(), password: password.map(Into::into) }
+    }
+
+    /// Basic credentials without a password, same as `Auth::basic(username, None::<String>)`.
+    pub fn basic_user(username: impl Into<String>) -> Self {
+        Auth::Basic { username: username.into(), password: None }
+    }
+
+    pub fn header(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Auth::Header { name: name.into(), value: value.into() }
+    }
+
+    pub fn provider(provider: impl TokenProvider + 'static) -> Self {
+        Auth::Provider(Arc::new(provider))
+    }
+
+    /// Header sent with every request for static credentials, `None` for a provider.
+    pub(crate) fn static_header(&self) -> Result<Option<(HeaderName, HeaderValue)>, TrustonError> {
+        let (name, value) = match self {
+            Auth::Bearer(token) => (AUTHORIZATION, format!("Bearer {}", token)),
+            Auth::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                (AUTHORIZATION, format!("Basic {}", BASE64.encode(credentials)))
+            }
+            Auth::Header { name, value } => {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                    TrustonError::ConfigError(format!("invalid auth header name {:?}: {}", name, e))
+                })?;
+                (name, value.clone())
+            }
+            Auth::Provider(_) => return Ok(None),
+        };
+        Ok(Some((name, sensitive(&value)?)))
+    }
+}
+
+/// `value` as a header value kept out of logs.
+pub(crate) fn sensitive(value: &str) -> Result<HeaderValue, TrustonError> {
+    let mut value = HeaderValue::from_str(value).map_err(|_| {
+        TrustonError::ConfigError("credentials are not a valid header value".to_string())
+    })?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::http::TritonRestClient;
+    use crate::client::test_server::serve;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[derive(Debug, Default)]
+    struct CountingProvider(AtomicU32);
+
+    #[async_trait]
+    impl TokenProvider for CountingProvider {
+        async fn token(&self) -> Result<String, TrustonError> {
+            Ok(format!("tok-{}", self.0.fetch_add(1, Ordering::Relaxed)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_provider_token_is_sent_with_each_request() {
+        let (base_url, server) = serve(vec![(200, ""); 2]).await;
+        let client = TritonRestClient::builder(&base_url)
+            .auth(Auth::provider(CountingProvider::default()))
+            .build()
+            .unwrap();
+        assert!(client.server_live().await.unwrap().is_healthy());
+        assert!(client.server_live().await.unwrap().is_healthy());
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].header("authorization"), Some("Bearer tok-0"));
+        assert_eq!(requests[1].header("authorization"), Some("Bearer tok-1"));
+    }
+
+    #[tokio::test]
+    async fn test_static_bearer_is_a_default_header() {
+        let (base_url, server) = serve(vec![(200, "")]).await;
+        let client = TritonRestClient::builder(&base_url)
+            .auth(Auth::bearer("abc"))
+            .build()
+            .unwrap();
+        assert!(client.server_live().await.unwrap().is_healthy());
+        assert_eq!(server.await.unwrap()[0].header("authorization"), Some("Bearer abc"));
+    }
+
+    #[test]
+    fn test_static_headers() {
+        let (name, value) = Auth::basic("user", Some("pass")).static_header().unwrap().unwrap();
+        assert_eq!(name, AUTHORIZATION);
+        assert_eq!(value, "Basic dXNlcjpwYXNz");
+        assert!(value.is_sensitive());
+        let (_, value) = Auth::basic_user("user").static_header().unwrap().unwrap();
+        assert_eq!(value, "Basic dXNlcjo=");
+
+        let (name, value) = Auth::header("x-api-key", "k").static_header().unwrap().unwrap();
+        assert_eq!((name.as_str(), value.to_str().unwrap()), ("x-api-key", "k"));
+        assert!(Auth::bearer("a\nb").static_header().is_err());
+    }
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let debug = format!("{:?}", Auth::basic("user", Some("hunter2")));
+        assert!(debug.contains("user") && !debug.contains("hunter2"));
+        assert!(!format!("{:?}", Auth::bearer("t0ken")).contains("t0ken"));
+    }
+}
@@ -0,0 +1,838 @@
+//! Client-side batching helpers.
+//!
+//! Models with `max_batch_size > 0` process several samples per request
+//! along a leading batch dimension. [`Batch`] stacks the inputs of several
+//! samples into one request and splits the outputs back per sample, keeping
+//! any per-sample metadata (ids, keys, ...) aligned with the results so
+//! callers don't need to track row offsets themselves.
+//!
+//! [`BatchedInfer`] goes the other way: it splits inputs larger than the
+//! model's `max_batch_size` into several requests and joins their outputs.
+//! For offline jobs, [`BatchedInfer::infer_checkpointed`] records its
+//! progress in a serializable [`BatchCheckpoint`], so a job retried by an
+//! orchestrator resumes where it stopped.
+
+use std::ops::Range;
+
+use futures::{StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::client::http::TritonRestClient;
+use crate::client::io::{DataType, InferInput, InferOutput, InferResults, element_count};
+use crate::client::options::InferOptions;
+use crate::client::validation::validate_batch_dims;
+use crate::utils::errors::TrustonError;
+
+/// Samples stacked along the batch dimension, with their metadata.
+///
+/// Each sample is a set of inputs sharing the same leading (batch)
+/// dimension, usually `1`. Samples must provide the same inputs, with the
+/// same datatypes and the same dims after the batch dimension.
+///
+/// # Example
+/// ```
+/// use truston::client::batch::Batch;
+/// use truston::client::dtype::TritonDtype;
+/// use truston::client::io::{DataType, InferInput, InferOutput, InferResults};
+///
+/// let sample = |x: f32| {
+///     vec![InferInput::new("x".into(), vec![1, 2], DataType::F32(vec![x, x]))]
+/// };
+/// let mut batch = Batch::new(vec![("req-a", sample(1.0)), ("req-b", sample(2.0))]).unwrap();
+///
+/// let inputs = batch.take_inputs();
+/// assert_eq!(inputs[0].input_shape, vec![2, 2]);
+///
+/// // results as returned by the server for the stacked inputs
+/// let results = InferResults {
+///     outputs: vec![InferOutput {
+///         name: "y".into(),
+///         datatype: TritonDtype::Fp32,
+///         shape: vec![2, 1],
+///         data: DataType::F32(vec![10.0, 20.0]),
+///     }],
+///     ..Default::default()
+/// };
+/// let split = batch.split(results).unwrap();
+/// assert_eq!(split[1].0, "req-b");
+/// assert_eq!(split[1].1.outputs[0].data.as_f32_vec(), Some(vec![20.0]));
+/// ```
+#[derive(Debug)]
+pub struct Batch<M> {
+    inputs: Vec<InferInput>,
+    metadata: Vec<M>,
+    rows: Vec<usize>,
+}
+
+impl<M> Batch<M> {
+    /// Stack `samples`, each given with its metadata.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if there are no samples, or
+    /// if samples don't agree on their inputs (see [`stack_inputs`]).
+    pub fn new(
+        samples: impl IntoIterator<Item = (M, Vec<InferInput>)>,
+    ) -> Result<Self, TrustonError> {
+        let (metadata, samples): (Vec<M>, Vec<Vec<InferInput>>) = samples.into_iter().unzip();
+        let rows = samples.iter().map(|sample| sample_rows(sample)).collect::<Result<_, _>>()?;
+        let inputs = stack_inputs(samples)?;
+        Ok(Batch { inputs, metadata, rows })
+    }
+
+    /// Number of samples.
+    pub fn len(&self) -> usize {
+        self.metadata.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+    }
+
+    /// Size of the batch dimension of the stacked inputs.
+    pub fn batch_size(&self) -> usize {
+        self.rows.iter().sum()
+    }
+
+    pub fn metadata(&self) -> &[M] {
+        &self.metadata
+    }
+
+    /// Take the stacked inputs, to send them.
+    pub fn take_inputs(&mut self) -> Vec<InferInput> {
+        std::mem::take(&mut self.inputs)
+    }
+
+    /// Split the results of the stacked inputs back per sample, in order,
+    /// each paired with its metadata.
+    ///
+    /// # Errors
+    /// See [`split_results`].
+    pub fn split(self, results: InferResults) -> Result<Vec<(M, InferResults)>, TrustonError> {
+        let split = split_results(&results, &self.rows)?;
+        Ok(self.metadata.into_iter().zip(split).collect())
+    }
+}
+
+/// Concatenate the inputs of `samples` along the batch dimension.
+///
+/// Inputs are matched by name and returned in the order of the first sample.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] if `samples` is empty, if an
+/// input is a scalar, missing from a sample, or differs from the first
+/// sample's in datatype or non-batch dims.
+pub fn stack_inputs(samples: Vec<Vec<InferInput>>) -> Result<Vec<InferInput>, TrustonError> {
+    let mut samples = samples.into_iter();
+    let mut stacked = samples
+        .next()
+        .ok_or_else(|| TrustonError::InferenceError("cannot stack an empty batch".to_string()))?;
+    if let Some(input) = stacked.iter().find(|input| input.input_shape.is_empty()) {
+        return Err(TrustonError::InferenceError(format!(
+            "input {} has no batch dimension",
+            input.input_name
+        )));
+    }
+
+    for (index, sample) in samples.enumerate() {
+        if sample.len() != stacked.len() {
+            return Err(TrustonError::InferenceError(format!(
+                "sample {} has {} inputs, expected {}",
+                index + 1,
+                sample.len(),
+                stacked.len()
+            )));
+        }
+        for input in sample {
+            let target = stacked
+                .iter_mut()
+                .find(|target| target.input_name == input.input_name)
+                .ok_or_else(|| {
+                    TrustonError::InferenceError(format!(
+                        "sample {} has unexpected input {}",
+                        index + 1,
+                        input.input_name
+                    ))
+                })?;
+            if input.input_shape.get(1..) != target.input_shape.get(1..) {
+                return Err(TrustonError::InferenceError(format!(
+                    "input {} of sample {} has shape {:?}, incompatible with {:?}",
+                    input.input_name,
+                    index + 1,
+                    input.input_shape,
+                    target.input_shape
+                )));
+            }
+            target.input_shape[0] += input.input_shape[0];
+            append(&mut target.input_data, input.input_data).map_err(|e| {
+                TrustonError::InferenceError(format!("input {}: {}", input.input_name, e))
+            })?;
+        }
+    }
+    Ok(stacked)
+}
+
+/// Split batched `results` into one [`InferResults`] per sample, where
+/// sample `i` spans `rows[i]` entries of the batch dimension.
+///
+/// The provenance of `results` is copied into every sample.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] if an output's batch dimension
+/// is not the sum of `rows`, or if an output holds raw JSON data.
+pub fn split_results(
+    results: &InferResults,
+    rows: &[usize],
+) -> Result<Vec<InferResults>, TrustonError> {
+    let batch_size: usize = rows.iter().sum();
+    let mut split: Vec<InferResults> = rows
+        .iter()
+        .map(|_| InferResults {
+            outputs: Vec::with_capacity(results.outputs.len()),
+            provenance: results.provenance.clone(),
+        })
+        .collect();
+
+    for output in &results.outputs {
+        if output.shape.first() != Some(&batch_size) {
+            return Err(TrustonError::InferenceError(format!(
+                "output {} with shape {:?} does not have a batch dimension of {}",
+                output.name, output.shape, batch_size
+            )));
+        }
+        // the rows of the samples add up to the batch, so no offset overflows
+        element_count(&output.name, &output.shape)?;
+        let row_len = element_count(&output.name, &output.shape[1..])?;
+        let mut start = 0;
+        for (sample, &sample_rows) in split.iter_mut().zip(rows) {
+            let end = start + sample_rows * row_len;
+            let mut shape = output.shape.clone();
+            shape[0] = sample_rows;
+            sample.outputs.push(InferOutput {
+                name: output.name.clone(),
+                datatype: output.datatype.clone(),
+                shape,
+                data: slice(&output.data, start..end)?,
+            });
+            start = end;
+        }
+    }
+    Ok(split)
+}
+
+/// Split `inputs` along the batch dimension into chunks of at most
+/// `max_rows` rows.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] if `max_rows` is `0`, if the
+/// inputs don't share their batch dimension (see [`validate_batch_dims`]),
+/// or if an input holds raw JSON data.
+pub fn split_inputs(
+    inputs: Vec<InferInput>,
+    max_rows: usize,
+) -> Result<Vec<Vec<InferInput>>, TrustonError> {
+    if max_rows == 0 {
+        return Err(TrustonError::InferenceError(
+            "cannot split into chunks of 0 rows".to_string(),
+        ));
+    }
+    let rows = sample_rows(&inputs)?;
+    if rows <= max_rows {
+        return Ok(vec![inputs]);
+    }
+
+    let mut chunks = Vec::with_capacity(rows.div_ceil(max_rows));
+    for start in (0..rows).step_by(max_rows) {
+        chunks.push(slice_rows(&inputs, start..(start + max_rows).min(rows))?);
+    }
+    Ok(chunks)
+}
+
+/// Concatenate the results of consecutive chunks along the batch dimension.
+///
+/// Outputs are matched by name. The provenance is the first chunk's, with
+/// the attempts of all chunks.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] if `results` is empty or the
+/// chunks don't return the same outputs with the same non-batch dims.
+pub fn concat_results(results: Vec<InferResults>) -> Result<InferResults, TrustonError> {
+    let mut chunks = results.into_iter();
+    let mut joined = chunks
+        .next()
+        .ok_or_else(|| TrustonError::InferenceError("no results to concatenate".to_string()))?;
+
+    for chunk in chunks {
+        joined.provenance.attempts += chunk.provenance.attempts;
+        for output in chunk.outputs {
+            let target = joined
+                .outputs
+                .iter_mut()
+                .find(|target| target.name == output.name)
+                .ok_or_else(|| {
+                    TrustonError::InferenceError(format!("unexpected output {}", output.name))
+                })?;
+            if output.shape.is_empty() || output.shape.get(1..) != target.shape.get(1..) {
+                return Err(TrustonError::InferenceError(format!(
+                    "output {} has shape {:?}, incompatible with {:?}",
+                    output.name, output.shape, target.shape
+                )));
+            }
+            target.shape[0] += output.shape[0];
+            append(&mut target.data, output.data).map_err(|e| {
+                TrustonError::InferenceError(format!("output {}: {}", output.name, e))
+            })?;
+        }
+    }
+    Ok(joined)
+}
+
+/// Inference on inputs larger than the model's `max_batch_size`.
+///
+/// The inputs are split into chunks of at most `max_batch_size` rows, sent
+/// with up to `parallelism` requests in flight, and the outputs are joined
+/// back in order.
+///
+/// # Example
+/// ```no_run
+/// # use truston::client::http::TritonRestClient;
+/// use truston::client::batch::BatchedInfer;
+/// use truston::client::io::{DataType, InferInput};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = TritonRestClient::new("http://localhost:8000");
+/// let embedder = BatchedInfer::from_config(&client, "embedder").await?.with_parallelism(8);
+///
+/// let ids = DataType::I64(vec![0; 10_000 * 16]);
+/// let tokens = InferInput::new("input_ids".into(), vec![10_000, 16], ids);
+/// let results = embedder.infer(vec![tokens]).await?;
+/// assert_eq!(results.outputs[0].shape[0], 10_000);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BatchedInfer<'a> {
+    client: &'a TritonRestClient,
+    model_name: String,
+    max_batch_size: usize,
+    parallelism: usize,
+    options: InferOptions,
+}
+
+impl<'a> BatchedInfer<'a> {
+    /// Send chunks of at most `max_batch_size` rows to `model_name`, four at a time.
+    pub fn new(
+        client: &'a TritonRestClient,
+        model_name: impl Into<String>,
+        max_batch_size: usize,
+    ) -> Self {
+        BatchedInfer {
+            client,
+            model_name: model_name.into(),
+            max_batch_size,
+            parallelism: 4,
+            options: InferOptions::new(),
+        }
+    }
+
+    /// Like [`BatchedInfer::new`], with the `max_batch_size` of the model config.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the model doesn't support
+    /// batching, or any error of [`TritonRestClient::cached_model_config`].
+    pub async fn from_config(
+        client: &'a TritonRestClient,
+        model_name: &str,
+    ) -> Result<Self, TrustonError> {
+        let config = client.cached_model_config(model_name).await?;
+        if config.max_batch_size <= 0 {
+            return Err(TrustonError::InferenceError(format!(
+                "model {} does not support batching",
+                model_name
+            )));
+        }
+        Ok(Self::new(client, model_name, config.max_batch_size as usize))
+    }
+
+    /// Maximum number of chunk requests in flight (at least 1).
+    pub fn with_parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Options sent with every chunk request.
+    pub fn with_options(mut self, options: InferOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Split `inputs`, run the chunks and join their outputs.
+    ///
+    /// # Errors
+    /// Fails with the first failing chunk, or as [`split_inputs`] and
+    /// [`concat_results`] do.
+    pub async fn infer(&self, inputs: Vec<InferInput>) -> Result<InferResults, TrustonError> {
+        let chunks = split_inputs(inputs, self.max_batch_size)?;
+        let results: Vec<InferResults> = futures::stream::iter(chunks)
+            .map(|chunk| self.client.infer_with_options(chunk, &self.model_name, &self.options))
+            .buffered(self.parallelism)
+            .try_collect()
+            .await?;
+        concat_results(results)
+    }
+
+    /// Run the rows of `inputs` that `checkpoint` hasn't completed, handing
+    /// the results of each chunk to `on_chunk` with its range of rows, in
+    /// order, instead of joining them.
+    ///
+    /// A chunk that fails is recorded in the failures of `checkpoint` and the
+    /// other chunks go on; its rows are sent again by the next run with the
+    /// same checkpoint. Rows are only marked completed once `on_chunk`
+    /// returned, so a checkpoint saved after this call never skips results
+    /// that weren't stored.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// use truston::client::batch::{BatchCheckpoint, BatchedInfer};
+    /// use truston::client::io::{DataType, InferInput};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let embedder = BatchedInfer::from_config(&client, "embedder").await?;
+    /// let mut checkpoint: BatchCheckpoint = match std::fs::read("checkpoint.json") {
+    ///     Ok(saved) => serde_json::from_slice(&saved)?,
+    ///     Err(_) => BatchCheckpoint::new(),
+    /// };
+    ///
+    /// let ids = DataType::I64(vec![0; 10_000 * 16]);
+    /// let tokens = InferInput::new("input_ids".into(), vec![10_000, 16], ids);
+    /// let run = embedder.infer_checkpointed(vec![tokens], &mut checkpoint, |rows, results| {
+    ///     println!("rows {:?}: {:?}", rows, results.outputs[0].shape);
+    ///     Ok(())
+    /// });
+    /// let outcome = run.await;
+    /// std::fs::write("checkpoint.json", serde_json::to_vec(&checkpoint)?)?;
+    /// outcome?;
+    /// assert!(checkpoint.is_complete(), "{:?}", checkpoint.failures);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if `checkpoint` was made for
+    /// inputs with another number of rows, or fails as [`split_inputs`] does
+    /// or with the first error of `on_chunk`. Failing chunks are not errors.
+    pub async fn infer_checkpointed<F>(
+        &self,
+        inputs: Vec<InferInput>,
+        checkpoint: &mut BatchCheckpoint,
+        mut on_chunk: F,
+    ) -> Result<(), TrustonError>
+    where
+        F: FnMut(Range<usize>, InferResults) -> Result<(), TrustonError>,
+    {
+        if self.max_batch_size == 0 {
+            return Err(TrustonError::InferenceError(
+                "cannot split into chunks of 0 rows".to_string(),
+            ));
+        }
+        let rows = sample_rows(&inputs)?;
+        if *checkpoint == BatchCheckpoint::default() {
+            checkpoint.total_rows = rows;
+        } else if checkpoint.total_rows != rows {
+            return Err(TrustonError::InferenceError(format!(
+                "checkpoint is for {} rows, the inputs have {}",
+                checkpoint.total_rows, rows
+            )));
+        }
+
+        let max_rows = self.max_batch_size;
+        let chunks: Vec<Range<usize>> = checkpoint
+            .pending()
+            .into_iter()
+            .flat_map(|pending| {
+                let end = pending.end;
+                pending.step_by(max_rows).map(move |start| start..(start + max_rows).min(end))
+            })
+            .collect();
+        checkpoint.failures.clear();
+
+        let inputs = &inputs;
+        let mut results = futures::stream::iter(chunks)
+            .map(|rows| async move {
+                let chunk = slice_rows(inputs, rows.clone())?;
+                let results =
+                    self.client.infer_with_options(chunk, &self.model_name, &self.options).await;
+                Ok::<_, TrustonError>((rows, results))
+            })
+            .buffered(self.parallelism);
+        while let Some(chunk) = results.next().await {
+            match chunk? {
+                (rows, Ok(results)) => {
+                    on_chunk(rows.clone(), results)?;
+                    checkpoint.complete(rows);
+                }
+                (rows, Err(e)) => {
+                    tracing::warn!("rows {:?} of {} failed: {}", rows, self.model_name, e);
+                    checkpoint.failures.push(ChunkFailure { rows, error: e.to_string() });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Progress of a [`BatchedInfer::infer_checkpointed`] job, in rows of its
+/// inputs.
+///
+/// Serializable so it can be stored between the retries of a job, e.g. in
+/// the state of an orchestrator's task.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchCheckpoint {
+    /// Rows of the inputs, set by the first run.
+    pub total_rows: usize,
+    /// Rows whose results were handed over, as sorted disjoint ranges.
+    pub completed: Vec<Range<usize>>,
+    /// Chunks that failed during the last run.
+    pub failures: Vec<ChunkFailure>,
+}
+
+/// A chunk of a [`BatchCheckpoint`] whose inference failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkFailure {
+    pub rows: Range<usize>,
+    /// The error of the inference, as text.
+    pub error: String,
+}
+
+impl BatchCheckpoint {
+    /// A checkpoint for a job that hasn't run yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of rows completed.
+    pub fn completed_rows(&self) -> usize {
+        self.completed.iter().map(|rows| rows.len()).sum()
+    }
+
+    /// Whether every row of the inputs is completed.
+    pub fn is_complete(&self) -> bool {
+        self.completed_rows() == self.total_rows
+    }
+
+    /// Ranges of rows not completed yet, in order.
+    pub fn pending(&self) -> Vec<Range<usize>> {
+        let mut pending = Vec::new();
+        let mut start = 0;
+        for done in &self.completed {
+            if done.start > start {
+                pending.push(start..done.start);
+            }
+            start = start.max(done.end);
+        }
+        if start < self.total_rows {
+            pending.push(start..self.total_rows);
+        }
+        pending
+    }
+
+    /// Mark `rows` completed, merging it with the adjacent ranges.
+    fn complete(&mut self, rows: Range<usize>) {
+        let at = self.completed.partition_point(|done| done.start < rows.start);
+        self.completed.insert(at, rows);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(self.completed.len());
+        for rows in self.completed.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.end >= rows.start => last.end = last.end.max(rows.end),
+                _ => merged.push(rows),
+            }
+        }
+        self.completed = merged;
+    }
+}
+
+impl TritonRestClient {
+    /// Run a [`Batch`] as one inference and return the results per sample,
+    /// each paired with its metadata.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// use truston::client::batch::Batch;
+    /// use truston::client::io::{DataType, InferInput};
+    /// use truston::client::options::InferOptions;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let samples = (0..4u64).map(|id| {
+    ///     let x = InferInput::new("x".into(), vec![1, 3], DataType::F32(vec![id as f32; 3]));
+    ///     (id, vec![x])
+    /// });
+    /// let batch = Batch::new(samples)?;
+    ///
+    /// for (id, results) in client.infer_batch(batch, "model", &InferOptions::new()).await? {
+    ///     println!("{}: {:?}", id, results.outputs[0].data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn infer_batch<M>(
+        &self,
+        mut batch: Batch<M>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<Vec<(M, InferResults)>, TrustonError> {
+        let inputs = batch.take_inputs();
+        let results = self.infer_with_options(inputs, model_name, options).await?;
+        batch.split(results)
+    }
+}
+
+/// Batch dimension of a sample, which all its inputs must share.
+fn sample_rows(sample: &[InferInput]) -> Result<usize, TrustonError> {
+    validate_batch_dims(sample)?;
+    sample
+        .first()
+        .and_then(|input| input.input_shape.first().copied())
+        .ok_or_else(|| TrustonError::InferenceError("sample has no batched input".to_string()))
+}
+
+/// The `rows` of `inputs` along the batch dimension.
+fn slice_rows(inputs: &[InferInput], rows: Range<usize>) -> Result<Vec<InferInput>, TrustonError> {
+    inputs
+        .iter()
+        .map(|input| {
+            let row_len = element_count(&input.input_name, &input.input_shape[1..])?;
+            let mut shape = input.input_shape.clone();
+            shape[0] = rows.len();
+            let data = slice(&input.input_data, rows.start * row_len..rows.end * row_len)?;
+            Ok(InferInput::new(input.input_name.clone(), shape, data))
+        })
+        .collect()
+}
+
+fn append(target: &mut DataType, data: DataType) -> Result<(), TrustonError> {
+    match (target, data) {
+        (DataType::Bool(a), DataType::Bool(b)) => a.extend(b),
+        (DataType::U8(a), DataType::U8(b)) => a.extend(b),
+        (DataType::U16(a), DataType::U16(b)) => a.extend(b),
+        (DataType::U32(a), DataType::U32(b)) => a.extend(b),
+        (DataType::U64(a), DataType::U64(b)) => a.extend(b),
+        (DataType::I8(a), DataType::I8(b)) => a.extend(b),
+        (DataType::I16(a), DataType::I16(b)) => a.extend(b),
+        (DataType::I32(a), DataType::I32(b)) => a.extend(b),
+        (DataType::I64(a), DataType::I64(b)) => a.extend(b),
+        (DataType::F32(a), DataType::F32(b)) => a.extend(b),
+        (DataType::F64(a), DataType::F64(b)) => a.extend(b),
+        (DataType::String(a), DataType::String(b)) => a.extend(b),
+        (DataType::Bytes(a), DataType::Bytes(b)) => a.extend(b),
+        (DataType::Bf16(a), DataType::Bf16(b)) => a.extend(b),
+        #[cfg(feature = "half")]
+        (DataType::F16(a), DataType::F16(b)) => a.extend(b),
+        (DataType::Raw(_), _) | (_, DataType::Raw(_)) => {
+            return Err(TrustonError::InferenceError(
+                "raw JSON data cannot be batched".to_string(),
+            ));
+        }
+        (target, data) => {
+            return Err(TrustonError::InferenceError(format!(
+                "datatype {} does not match {}",
+                data.get_type_str(),
+                target.get_type_str()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn slice(data: &DataType, range: Range<usize>) -> Result<DataType, TrustonError> {
+    if range.end > data.num_elements() {
+        return Err(TrustonError::InferenceError(format!(
+            "{} elements expected, got {}",
+            range.end,
+            data.num_elements()
+        )));
+    }
+    let sliced = match data {
+        DataType::Bool(v) => DataType::Bool(v[range].to_vec()),
+        DataType::U8(v) => DataType::U8(v[range].to_vec()),
+        DataType::U16(v) => DataType::U16(v[range].to_vec()),
+        DataType::U32(v) => DataType::U32(v[range].to_vec()),
+        DataType::U64(v) => DataType::U64(v[range].to_vec()),
+        DataType::I8(v) => DataType::I8(v[range].to_vec()),
+        DataType::I16(v) => DataType::I16(v[range].to_vec()),
+        DataType::I32(v) => DataType::I32(v[range].to_vec()),
+        DataType::I64(v) => DataType::I64(v[range].to_vec()),
+        DataType::F32(v) => DataType::F32(v[range].to_vec()),
+        DataType::F64(v) => DataType::F64(v[range].to_vec()),
+        DataType::String(v) => DataType::String(v[range].to_vec()),
+        DataType::Bytes(v) => DataType::Bytes(v[range].to_vec()),
+        DataType::Bf16(v) => DataType::Bf16(v[range].to_vec()),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => DataType::F16(v[range].to_vec()),
+        DataType::Raw(_) => {
+            return Err(TrustonError::InferenceError(
+                "raw JSON data cannot be split".to_string(),
+            ));
+        }
+    };
+    Ok(sliced)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use crate::client::test_server::serve;
+
+    fn sample(ids: Vec<i64>, mask: Vec<i64>) -> Vec<InferInput> {
+        let rows = ids.len() / 2;
+        vec![
+            InferInput::new("input_ids".into(), vec![rows, 2], DataType::I64(ids)),
+            InferInput::new("mask".into(), vec![rows, 2], DataType::I64(mask)),
+        ]
+    }
+
+    #[test]
+    fn test_stack_matches_inputs_by_name() {
+        let mut second = sample(vec![3, 4, 5, 6], vec![0, 0, 1, 1]);
+        second.reverse();
+        let stacked = stack_inputs(vec![sample(vec![1, 2], vec![1, 1]), second]).unwrap();
+
+        assert_eq!(stacked[0].input_name, "input_ids");
+        assert_eq!(stacked[0].input_shape, vec![3, 2]);
+        assert_eq!(stacked[0].input_data.as_i64_vec(), Some(vec![1, 2, 3, 4, 5, 6]));
+        assert_eq!(stacked[1].input_data.as_i64_vec(), Some(vec![1, 1, 0, 0, 1, 1]));
+    }
+
+    #[test]
+    fn test_stack_rejects_mismatched_samples() {
+        let wide = vec![
+            InferInput::new("input_ids".into(), vec![1, 3], DataType::I64(vec![0; 3])),
+            InferInput::new("mask".into(), vec![1, 3], DataType::I64(vec![0; 3])),
+        ];
+        assert!(stack_inputs(vec![]).is_err());
+        assert!(stack_inputs(vec![sample(vec![1, 2], vec![1, 1]), wide]).is_err());
+
+        let mut floats = sample(vec![1, 2], vec![1, 1]);
+        floats[1].input_data = DataType::F32(vec![1.0, 1.0]);
+        assert!(stack_inputs(vec![sample(vec![1, 2], vec![1, 1]), floats]).is_err());
+    }
+
+    #[test]
+    fn test_split_keeps_metadata_aligned() {
+        let batch = Batch::new(vec![
+            ("a", sample(vec![1, 2], vec![1, 1])),
+            ("b", sample(vec![3, 4, 5, 6], vec![1, 1, 1, 1])),
+        ])
+        .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.batch_size(), 3);
+
+        let results = InferResults {
+            outputs: vec![InferOutput {
+                name: "logits".into(),
+                datatype: TritonDtype::Fp32,
+                shape: vec![3, 2],
+                data: DataType::F32(vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]),
+            }],
+            ..Default::default()
+        };
+        let split = batch.split(results).unwrap();
+
+        assert_eq!(split[0].0, "a");
+        assert_eq!(split[0].1.outputs[0].shape, vec![1, 2]);
+        assert_eq!(split[1].0, "b");
+        assert_eq!(split[1].1.outputs[0].shape, vec![2, 2]);
+        assert_eq!(split[1].1.outputs[0].data.as_f32_vec(), Some(vec![2.0, 3.0, 4.0, 5.0]));
+    }
+
+    #[test]
+    fn test_split_inputs_and_concat_results() {
+        let inputs = sample((0..10).collect(), vec![1; 10]);
+        let chunks = split_inputs(inputs, 2).unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2][0].input_shape, vec![1, 2]);
+        assert_eq!(chunks[2][0].input_data.as_i64_vec(), Some(vec![8, 9]));
+
+        // echo the ids back, as a model would
+        let results = chunks
+            .iter()
+            .map(|chunk| InferResults {
+                outputs: vec![InferOutput {
+                    name: "ids".into(),
+                    datatype: TritonDtype::Int64,
+                    shape: chunk[0].input_shape.clone(),
+                    data: chunk[0].input_data.clone(),
+                }],
+                ..Default::default()
+            })
+            .collect();
+        let joined = concat_results(results).unwrap();
+        assert_eq!(joined.outputs[0].shape, vec![5, 2]);
+        assert_eq!(joined.outputs[0].data.as_i64_vec(), Some((0..10).collect()));
+    }
+
+    #[test]
+    fn test_split_rejects_unbatched_output() {
+        let results = InferResults {
+            outputs: vec![InferOutput {
+                name: "count".into(),
+                datatype: TritonDtype::Int64,
+                shape: vec![1],
+                data: DataType::I64(vec![2]),
+            }],
+            ..Default::default()
+        };
+        assert!(split_results(&results, &[1, 1]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_resumes_failed_chunks() {
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT64","shape":[1],"data":[0]}]}"#;
+        let bad = r#"{"error":"bad input"}"#;
+        let (url, server) = serve(vec![(200, ok), (400, bad), (200, ok)]).await;
+        let client = TritonRestClient::new(&url);
+        let batched = BatchedInfer::new(&client, "m", 2).with_parallelism(1);
+        let inputs = || sample((0..10).collect(), vec![1; 10]);
+
+        let mut checkpoint = BatchCheckpoint::new();
+        let mut done = Vec::new();
+        let mut on_chunk = |rows, _| {
+            done.push(rows);
+            Ok(())
+        };
+        batched.infer_checkpointed(inputs(), &mut checkpoint, &mut on_chunk).await.unwrap();
+        assert_eq!(done, vec![0..2, 4..5]);
+        assert_eq!(checkpoint.completed, vec![0..2, 4..5]);
+        assert_eq!(checkpoint.failures.len(), 1);
+        assert_eq!(checkpoint.failures[0].rows, 2..4);
+        assert!(!checkpoint.is_complete());
+        let requests = server.await.unwrap();
+        let header: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(header["inputs"][0]["data"], serde_json::json!([4, 5, 6, 7]));
+
+        // the checkpoint survives a round trip through storage
+        let saved = serde_json::to_string(&checkpoint).unwrap();
+        let mut checkpoint: BatchCheckpoint = serde_json::from_str(&saved).unwrap();
+        let (url, server) = serve(vec![(200, ok)]).await;
+        let client = TritonRestClient::new(&url);
+        let batched = BatchedInfer::new(&client, "m", 2);
+        let mut done = Vec::new();
+        let on_chunk = |rows, _| {
+            done.push(rows);
+            Ok(())
+        };
+        batched.infer_checkpointed(inputs(), &mut checkpoint, on_chunk).await.unwrap();
+        assert_eq!(done, vec![2..4]);
+        assert_eq!(checkpoint.completed, vec![0..5]);
+        assert!(checkpoint.failures.is_empty() && checkpoint.is_complete());
+        assert_eq!(server.await.unwrap().len(), 1);
+
+        let other = sample((0..4).collect(), vec![1; 4]);
+        let result = batched.infer_checkpointed(other, &mut checkpoint, |_, _| Ok(())).await;
+        assert!(matches!(result, Err(TrustonError::InferenceError(_))));
+    }
+}
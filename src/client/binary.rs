@@ -0,0 +1,428 @@
+//! Raw (binary) tensor encoding.
+//!
+//! Triton exchanges tensors as raw bytes in two places: the binary tensor
+//! data extension of the HTTP protocol and the `raw_input_contents` /
+//! `raw_output_contents` fields of the gRPC protocol. Both use the same
+//! layout:
+//! - fixed-size types are stored as consecutive little-endian values
+//!   (`BOOL` as one byte per element),
+//! - `BYTES` elements are each prefixed by their length as a little-endian `u32`.
+
+use crate::client::dtype::TritonDtype;
+use crate::client::io::DataType;
+use crate::utils::errors::TrustonError;
+
+/// Encode tensor data into Triton's raw byte layout.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] for [`DataType::Raw`], whose
+/// element type is unknown.
+///
+/// # Example
+/// ```
+/// use truston::client::binary::encode_tensor;
+/// use truston::client::io::DataType;
+///
+/// let bytes = encode_tensor(&DataType::I32(vec![1, -1]))?;
+/// assert_eq!(bytes, vec![1, 0, 0, 0, 255, 255, 255, 255]);
+/// # Ok::<(), truston::TrustonError>(())
+/// ```
+pub fn encode_tensor(data: &DataType) -> Result<Vec<u8>, TrustonError> {
+    let bytes = match data {
+        DataType::Bool(v) => v.iter().map(|&b| b as u8).collect(),
+        DataType::U8(v) => v.clone(),
+        DataType::U16(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::U32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::U64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::I8(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::I16(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::I32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::I64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::F32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::F64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::Bf16(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        DataType::String(v) => encode_bytes_elements(v.iter().map(String::as_bytes)),
+        DataType::Bytes(v) => encode_bytes_elements(v.iter().map(Vec::as_slice)),
+        DataType::Raw(_) => {
+            return Err(TrustonError::InferenceError(
+                "raw JSON data cannot be binary encoded".to_string(),
+            ));
+        }
+    };
+    Ok(bytes)
+}
+
+/// Encode `BYTES` elements, each prefixed by its length as a little-endian `u32`.
+pub fn encode_bytes_elements<'a>(elements: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+    let mut out = Vec::new();
+    for element in elements {
+        out.extend_from_slice(&(element.len() as u32).to_le_bytes());
+        out.extend_from_slice(element);
+    }
+    out
+}
+
+/// Split a length-prefixed `BYTES` buffer into its elements.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] if a length prefix points past the end of the buffer.
+pub fn decode_bytes_elements(bytes: &[u8]) -> Result<Vec<&[u8]>, TrustonError> {
+    let mut elements = Vec::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(TrustonError::ParseError(
+                "truncated BYTES length prefix".to_string(),
+            ));
+        }
+        let (len, tail) = rest.split_at(4);
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        if tail.len() < len {
+            return Err(TrustonError::ParseError(format!(
+                "BYTES element of length {} exceeds the remaining {} bytes",
+                len,
+                tail.len()
+            )));
+        }
+        let (element, tail) = tail.split_at(len);
+        elements.push(element);
+        rest = tail;
+    }
+    Ok(elements)
+}
+
+/// Decode raw bytes of a tensor with the given Triton `datatype`.
+///
+/// `BYTES` elements are decoded as UTF-8 strings, or kept as raw bytes
+/// ([`DataType::Bytes`]) when one of them is not valid UTF-8.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] if the datatype is unknown or `FP16`
+/// without the `half` feature, or if the buffer length is not a multiple of
+/// the element size.
+pub fn decode_tensor(datatype: &TritonDtype, bytes: &[u8]) -> Result<DataType, TrustonError> {
+    let data = match datatype {
+        TritonDtype::Bool => DataType::Bool(bytes.iter().map(|&b| b != 0).collect()),
+        TritonDtype::Uint8 => DataType::U8(bytes.to_vec()),
+        TritonDtype::Uint16 => DataType::U16(decode_fixed(datatype, bytes, u16::from_le_bytes)?),
+        TritonDtype::Uint32 => DataType::U32(decode_fixed(datatype, bytes, u32::from_le_bytes)?),
+        TritonDtype::Uint64 => DataType::U64(decode_fixed(datatype, bytes, u64::from_le_bytes)?),
+        TritonDtype::Int8 => DataType::I8(decode_fixed(datatype, bytes, i8::from_le_bytes)?),
+        TritonDtype::Int16 => DataType::I16(decode_fixed(datatype, bytes, i16::from_le_bytes)?),
+        TritonDtype::Int32 => DataType::I32(decode_fixed(datatype, bytes, i32::from_le_bytes)?),
+        TritonDtype::Int64 => DataType::I64(decode_fixed(datatype, bytes, i64::from_le_bytes)?),
+        TritonDtype::Fp32 => DataType::F32(decode_fixed(datatype, bytes, f32::from_le_bytes)?),
+        TritonDtype::Fp64 => DataType::F64(decode_fixed(datatype, bytes, f64::from_le_bytes)?),
+        TritonDtype::Bf16 => DataType::Bf16(decode_fixed(datatype, bytes, u16::from_le_bytes)?),
+        #[cfg(feature = "half")]
+        TritonDtype::Fp16 => {
+            DataType::F16(decode_fixed(datatype, bytes, half::f16::from_le_bytes)?)
+        }
+        TritonDtype::Bytes => bytes_data(decode_bytes_elements(bytes)?),
+        TritonDtype::Other(name) => {
+            return Err(TrustonError::ParseError(format!("cannot decode {} tensors", name)));
+        }
+        #[cfg(not(feature = "half"))]
+        TritonDtype::Fp16 => {
+            return Err(TrustonError::ParseError(
+                "FP16 data requires the `half` feature".to_string(),
+            ));
+        }
+    };
+    Ok(data)
+}
+
+/// Strings if every element is valid UTF-8, the raw bytes otherwise.
+fn bytes_data(elements: Vec<&[u8]>) -> DataType {
+    match elements.iter().map(|e| std::str::from_utf8(e).map(str::to_owned)).collect() {
+        Ok(strings) => DataType::String(strings),
+        Err(_) => DataType::Bytes(elements.into_iter().map(<[u8]>::to_vec).collect()),
+    }
+}
+
+fn decode_fixed<const N: usize, T>(
+    datatype: &TritonDtype,
+    bytes: &[u8],
+    from_le: fn([u8; N]) -> T,
+) -> Result<Vec<T>, TrustonError> {
+    if !bytes.len().is_multiple_of(N) {
+        return Err(TrustonError::ParseError(format!(
+            "{} bytes is not a whole number of {} elements",
+            bytes.len(),
+            datatype
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(N)
+        .map(|chunk| {
+            let mut buf = [0u8; N];
+            buf.copy_from_slice(chunk);
+            from_le(buf)
+        })
+        .collect())
+}
+
+/// An empty tensor of `datatype`, with room for `capacity` values.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] for unknown datatypes and for `FP16`
+/// without the `half` feature.
+pub(crate) fn tensor_buffer(
+    datatype: &TritonDtype,
+    capacity: usize,
+) -> Result<DataType, TrustonError> {
+    let data = match datatype {
+        TritonDtype::Bool => DataType::Bool(Vec::with_capacity(capacity)),
+        TritonDtype::Uint8 => DataType::U8(Vec::with_capacity(capacity)),
+        TritonDtype::Uint16 => DataType::U16(Vec::with_capacity(capacity)),
+        TritonDtype::Uint32 => DataType::U32(Vec::with_capacity(capacity)),
+        TritonDtype::Uint64 => DataType::U64(Vec::with_capacity(capacity)),
+        TritonDtype::Int8 => DataType::I8(Vec::with_capacity(capacity)),
+        TritonDtype::Int16 => DataType::I16(Vec::with_capacity(capacity)),
+        TritonDtype::Int32 => DataType::I32(Vec::with_capacity(capacity)),
+        TritonDtype::Int64 => DataType::I64(Vec::with_capacity(capacity)),
+        TritonDtype::Fp32 => DataType::F32(Vec::with_capacity(capacity)),
+        TritonDtype::Fp64 => DataType::F64(Vec::with_capacity(capacity)),
+        TritonDtype::Bf16 => DataType::Bf16(Vec::with_capacity(capacity)),
+        #[cfg(feature = "half")]
+        TritonDtype::Fp16 => DataType::F16(Vec::with_capacity(capacity)),
+        TritonDtype::Bytes => DataType::String(Vec::with_capacity(capacity)),
+        TritonDtype::Other(name) => {
+            return Err(TrustonError::ParseError(format!("cannot decode {} tensors", name)));
+        }
+        #[cfg(not(feature = "half"))]
+        TritonDtype::Fp16 => {
+            return Err(TrustonError::ParseError(
+                "FP16 data requires the `half` feature".to_string(),
+            ));
+        }
+    };
+    Ok(data)
+}
+
+/// Decoder of a tensor received in chunks, into an existing buffer.
+pub(crate) struct TensorDecoder<'a> {
+    data: &'a mut DataType,
+    /// Bytes of an element split across chunks; the whole tensor for `BYTES`.
+    carry: Vec<u8>,
+}
+
+impl<'a> TensorDecoder<'a> {
+    /// A decoder replacing the values of `data`, reusing its allocation.
+    pub(crate) fn new(data: &'a mut DataType) -> Self {
+        match &mut *data {
+            DataType::Bool(v) => v.clear(),
+            DataType::U8(v) => v.clear(),
+            DataType::U16(v) | DataType::Bf16(v) => v.clear(),
+            DataType::U32(v) => v.clear(),
+            DataType::U64(v) => v.clear(),
+            DataType::I8(v) => v.clear(),
+            DataType::I16(v) => v.clear(),
+            DataType::I32(v) => v.clear(),
+            DataType::I64(v) => v.clear(),
+            DataType::F32(v) => v.clear(),
+            DataType::F64(v) => v.clear(),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => v.clear(),
+            DataType::String(v) => v.clear(),
+            DataType::Bytes(v) => v.clear(),
+            DataType::Raw(_) => {}
+        }
+        TensorDecoder { data, carry: Vec::new() }
+    }
+
+    /// Decode the next `bytes` of the tensor.
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        let carry = &mut self.carry;
+        match &mut *self.data {
+            DataType::Bool(v) => v.extend(bytes.iter().map(|&b| b != 0)),
+            DataType::U8(v) => v.extend_from_slice(bytes),
+            DataType::U16(v) => extend_fixed(v, carry, bytes, u16::from_le_bytes),
+            DataType::U32(v) => extend_fixed(v, carry, bytes, u32::from_le_bytes),
+            DataType::U64(v) => extend_fixed(v, carry, bytes, u64::from_le_bytes),
+            DataType::I8(v) => extend_fixed(v, carry, bytes, i8::from_le_bytes),
+            DataType::I16(v) => extend_fixed(v, carry, bytes, i16::from_le_bytes),
+            DataType::I32(v) => extend_fixed(v, carry, bytes, i32::from_le_bytes),
+            DataType::I64(v) => extend_fixed(v, carry, bytes, i64::from_le_bytes),
+            DataType::F32(v) => extend_fixed(v, carry, bytes, f32::from_le_bytes),
+            DataType::F64(v) => extend_fixed(v, carry, bytes, f64::from_le_bytes),
+            DataType::Bf16(v) => extend_fixed(v, carry, bytes, u16::from_le_bytes),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => extend_fixed(v, carry, bytes, half::f16::from_le_bytes),
+            // elements may span chunks; split once complete
+            DataType::String(_) | DataType::Bytes(_) | DataType::Raw(_) => {
+                carry.extend_from_slice(bytes)
+            }
+        }
+    }
+
+    /// Decode what is left in the buffer.
+    ///
+    /// A [`DataType::String`] buffer is replaced by [`DataType::Bytes`] when
+    /// an element is not valid UTF-8, so no data is lost.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the bytes received are not a
+    /// whole number of elements, or the buffer holds raw JSON.
+    pub(crate) fn finish(self) -> Result<(), TrustonError> {
+        let data = self.data;
+        if let DataType::String(v) = data {
+            match bytes_data(decode_bytes_elements(&self.carry)?) {
+                DataType::String(strings) => v.extend(strings),
+                bytes => *data = bytes,
+            }
+            return Ok(());
+        }
+        match data {
+            DataType::Bytes(v) => {
+                v.extend(decode_bytes_elements(&self.carry)?.into_iter().map(<[u8]>::to_vec))
+            }
+            DataType::Raw(_) => {
+                return Err(TrustonError::ParseError(
+                    "binary data cannot be decoded as raw JSON".to_string(),
+                ));
+            }
+            data if !self.carry.is_empty() => {
+                return Err(TrustonError::ParseError(format!(
+                    "binary data ends within a {} element",
+                    data.get_type_str()
+                )));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Decode the whole `N`-byte elements of `carry` followed by `bytes` into
+/// `out`, keeping the bytes of an incomplete last element in `carry`.
+fn extend_fixed<const N: usize, T>(
+    out: &mut Vec<T>,
+    carry: &mut Vec<u8>,
+    mut bytes: &[u8],
+    from_le: fn([u8; N]) -> T,
+) {
+    if !carry.is_empty() {
+        let take = (N - carry.len()).min(bytes.len());
+        carry.extend_from_slice(&bytes[..take]);
+        bytes = &bytes[take..];
+        if carry.len() < N {
+            return;
+        }
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(carry);
+        out.push(from_le(buf));
+        carry.clear();
+    }
+    let chunks = bytes.chunks_exact(N);
+    carry.extend_from_slice(chunks.remainder());
+    out.extend(chunks.map(|chunk| {
+        let mut buf = [0u8; N];
+        buf.copy_from_slice(chunk);
+        from_le(buf)
+    }));
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_fixed_size_types() {
+        let cases = vec![
+            DataType::Bool(vec![true, false]),
+            DataType::U8(vec![0, 255]),
+            DataType::U16(vec![1, u16::MAX]),
+            DataType::U32(vec![1, u32::MAX]),
+            DataType::U64(vec![u64::MAX]),
+            DataType::I8(vec![-128, 127]),
+            DataType::I16(vec![-2, 2]),
+            DataType::I32(vec![i32::MIN, 7]),
+            DataType::I64(vec![-1, i64::MAX]),
+            DataType::F32(vec![1.5, -0.25]),
+            DataType::F64(vec![1e-300, 2.5]),
+            DataType::Bf16(vec![0x3f80]),
+        ];
+        for data in cases {
+            let bytes = encode_tensor(&data).unwrap();
+            let decoded = decode_tensor(&data.dtype().unwrap(), &bytes).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", data));
+        }
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_roundtrip_f16() {
+        let values = vec![half::f16::from_f32(0.5), half::f16::MAX];
+        let bytes = encode_tensor(&DataType::F16(values.clone())).unwrap();
+        assert_eq!(bytes.len(), 4);
+        assert_eq!(decode_tensor(&TritonDtype::Fp16, &bytes).unwrap().as_f16_vec(), Some(values));
+    }
+
+    #[test]
+    fn test_bytes_are_length_prefixed() {
+        let data = DataType::String(vec!["ab".into(), "".into(), "c".into()]);
+        let bytes = encode_tensor(&data).unwrap();
+        assert_eq!(bytes, vec![2, 0, 0, 0, b'a', b'b', 0, 0, 0, 0, 1, 0, 0, 0, b'c']);
+        assert_eq!(
+            decode_tensor(&TritonDtype::Bytes, &bytes).unwrap().as_str_vec(),
+            Some(vec!["ab".into(), "".into(), "c".into()])
+        );
+    }
+
+    #[test]
+    fn test_non_utf8_bytes_are_kept() {
+        let bytes = encode_bytes_elements([&b"ok"[..], &[0xff, 0xd8]]);
+        assert_eq!(
+            decode_tensor(&TritonDtype::Bytes, &bytes).unwrap().as_bytes_vec(),
+            Some(vec![b"ok".to_vec(), vec![0xff, 0xd8]])
+        );
+
+        let mut data = tensor_buffer(&TritonDtype::Bytes, 2).unwrap();
+        let mut decoder = TensorDecoder::new(&mut data);
+        decoder.push(&bytes);
+        decoder.finish().unwrap();
+        assert_eq!(data.as_bytes_slice(), Some(&[b"ok".to_vec(), vec![0xff, 0xd8]][..]));
+    }
+
+    #[test]
+    fn test_truncated_input_is_rejected() {
+        assert!(decode_tensor(&TritonDtype::Fp32, &[0, 0, 0]).is_err());
+        assert!(decode_tensor(&TritonDtype::Bytes, &[5, 0, 0, 0, b'a']).is_err());
+        assert!(decode_tensor(&TritonDtype::Bytes, &[1, 0]).is_err());
+        assert!(encode_tensor(&DataType::Raw(serde_json::json!([1]))).is_err());
+    }
+
+    #[test]
+    fn test_decoder_joins_elements_split_across_chunks() {
+        let values = vec![1.5f32, -2.0, 1e10];
+        let bytes = encode_tensor(&DataType::F32(values.clone())).unwrap();
+        let mut data = tensor_buffer(&TritonDtype::Fp32, 3).unwrap();
+        for split in 0..bytes.len() {
+            let mut decoder = TensorDecoder::new(&mut data);
+            let (head, tail) = bytes.split_at(split);
+            decoder.push(head);
+            decoder.push(&[]);
+            decoder.push(tail);
+            decoder.finish().unwrap();
+            assert_eq!(data.as_f32_slice(), Some(values.as_slice()));
+        }
+
+        let strings = encode_bytes_elements([&b"ab"[..], b"c"]);
+        let mut data = tensor_buffer(&TritonDtype::Bytes, 2).unwrap();
+        let mut decoder = TensorDecoder::new(&mut data);
+        for byte in &strings {
+            decoder.push(std::slice::from_ref(byte));
+        }
+        decoder.finish().unwrap();
+        assert_eq!(format!("{:?}", data), r#"String(["ab", "c"])"#);
+
+        let mut data = DataType::I16(vec![5]);
+        let mut decoder = TensorDecoder::new(&mut data);
+        decoder.push(&[1, 0, 2]);
+        assert!(matches!(decoder.finish(), Err(TrustonError::ParseError(_))));
+    }
+}
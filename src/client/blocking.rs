@@ -0,0 +1,162 @@
+//! Synchronous client for code that does not run an async runtime (CLI
+//! tools, legacy codebases).
+//!
+//! The blocking [`TritonRestClient`] wraps the async
+//! [`TritonRestClient`](crate::client::http::TritonRestClient) and drives it
+//! on a private single-threaded Tokio runtime.
+//!
+//! Only available with the `blocking` feature.
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::client::config::ModelConfig;
+use crate::client::health::Health;
+use crate::client::http;
+use crate::client::io::{InferInput, InferRequestedOutput, InferResults};
+use crate::client::metadata::ModelMetadata;
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+/// Blocking version of [`http::TritonRestClient`].
+///
+/// # Panics
+/// Its methods panic when called from within an async runtime; use the
+/// async client there.
+///
+/// # Example
+/// ```no_run
+/// use truston::client::blocking::TritonRestClient;
+/// use truston::client::io::{DataType, InferInput};
+///
+/// let client = TritonRestClient::new("http://localhost:8000")?;
+/// if client.server_ready()?.is_healthy() {
+///     let input = InferInput::new("x".into(), vec![1, 2], DataType::F32(vec![0.5, 1.5]));
+///     let results = client.infer(vec![input], "my_model", None)?;
+///     println!("{:?}", results.outputs[0].data);
+/// }
+/// # Ok::<(), truston::TrustonError>(())
+/// ```
+pub struct TritonRestClient {
+    inner: http::TritonRestClient,
+    runtime: Runtime,
+}
+
+impl TritonRestClient {
+    /// Client for the server at `base_url`, with the default settings.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ConfigError`] if the runtime cannot be created.
+    pub fn new(base_url: &str) -> Result<Self, TrustonError> {
+        Self::from_async(http::TritonRestClient::new(base_url))
+    }
+
+    /// Blocking wrapper around `client`, e.g. one configured with
+    /// [`http::TritonRestClient::builder`].
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ConfigError`] if the runtime cannot be created.
+    pub fn from_async(client: http::TritonRestClient) -> Result<Self, TrustonError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| TrustonError::ConfigError(format!("cannot start runtime: {}", e)))?;
+        Ok(TritonRestClient { inner: client, runtime })
+    }
+
+    /// The wrapped async client.
+    pub fn get_async(&self) -> &http::TritonRestClient {
+        &self.inner
+    }
+
+    /// See [`http::TritonRestClient::is_server_live`].
+    #[deprecated(
+        since = "0.1.2",
+        note = "use `server_live`, `server_ready` or `model_ready`, which return a `Health`"
+    )]
+    #[allow(deprecated)]
+    pub fn is_server_live(&self) -> Result<bool, TrustonError> {
+        self.runtime.block_on(self.inner.is_server_live())
+    }
+
+    /// See [`http::TritonRestClient::server_live`].
+    pub fn server_live(&self) -> Result<Health, TrustonError> {
+        self.runtime.block_on(self.inner.server_live())
+    }
+
+    /// See [`http::TritonRestClient::server_ready`].
+    pub fn server_ready(&self) -> Result<Health, TrustonError> {
+        self.runtime.block_on(self.inner.server_ready())
+    }
+
+    /// See [`http::TritonRestClient::model_ready`].
+    pub fn model_ready(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Health, TrustonError> {
+        self.runtime.block_on(self.inner.model_ready(model_name, version))
+    }
+
+    /// See [`http::TritonRestClient::model_metadata`].
+    pub fn model_metadata(&self, model_name: &str) -> Result<ModelMetadata, TrustonError> {
+        self.runtime.block_on(self.inner.model_metadata(model_name))
+    }
+
+    /// See [`http::TritonRestClient::model_metadata_with_options`].
+    pub fn model_metadata_with_options(
+        &self,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<ModelMetadata, TrustonError> {
+        self.runtime.block_on(self.inner.model_metadata_with_options(model_name, options))
+    }
+
+    /// See [`http::TritonRestClient::model_config`].
+    pub fn model_config(&self, model_name: &str) -> Result<ModelConfig, TrustonError> {
+        self.runtime.block_on(self.inner.model_config(model_name))
+    }
+
+    /// See [`http::TritonRestClient::infer`].
+    pub fn infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        outputs: Option<Vec<InferRequestedOutput>>,
+    ) -> Result<InferResults, TrustonError> {
+        self.runtime.block_on(self.inner.infer(inputs, model_name, outputs))
+    }
+
+    /// See [`http::TritonRestClient::infer_with_options`].
+    pub fn infer_with_options(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<InferResults, TrustonError> {
+        self.runtime
+            .block_on(self.inner.infer_with_options(inputs, model_name, options))
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::DataType;
+    use crate::client::test_server::serve;
+
+    #[test]
+    fn test_blocking_infer() {
+        // the server runs on a runtime of its own, off the thread of the client
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let body = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = runtime.block_on(serve(vec![(200, body)]));
+
+        let client = TritonRestClient::new(&base_url).unwrap();
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let results = client.infer(vec![input], "m", None).unwrap();
+        assert_eq!(results.outputs[0].data.as_i32_vec(), Some(vec![7]));
+        let requests = runtime.block_on(server).unwrap();
+        assert_eq!(requests[0].line(), "POST /v2/models/m/infer HTTP/1.1");
+    }
+}
@@ -0,0 +1,182 @@
+//! Caller-provided output buffers.
+//!
+//! A serving loop running the same model over and over allocates the output
+//! tensors of every response. [`OutputBuffers`] holds one tensor per output,
+//! allocated once by the caller: [`TritonRestClient::infer_into`] asks for
+//! these outputs in binary and decodes them straight into the buffers,
+//! reusing their allocations from one request to the next.
+//!
+//! A buffer is for one shape and datatype. An output returned with another
+//! shape or datatype fails the inference with
+//! [`TrustonError::InferenceError`], leaving the buffer as it was. So does
+//! an output missing from the response or whose values can't be decoded:
+//! its buffer would still hold the values of an earlier response. A
+//! `BYTES` buffer holding [`DataType::String`] becomes [`DataType::Bytes`]
+//! when an element received is not valid UTF-8.
+//!
+//! # Example
+//! ```no_run
+//! # use truston::client::http::TritonRestClient;
+//! # use truston::client::io::{DataType, InferInput};
+//! use truston::client::buffers::OutputBuffers;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = TritonRestClient::new("http://localhost:8000");
+//! let mut outputs = OutputBuffers::new()
+//!     .with_buffer("logits", vec![1, 1000], DataType::F32(vec![0.0; 1000]));
+//!
+//! loop {
+//!     let input = InferInput::new("x".into(), vec![1, 4], DataType::F32(vec![0.5; 4]));
+//!     client.infer_into(vec![input], "classifier", &mut outputs).await?;
+//!     let logits = outputs.get("logits").and_then(DataType::as_f32_slice).unwrap();
+//!     # let _ = logits;
+//! }
+//! # }
+//! ```
+
+use crate::client::dtype::TritonDtype;
+use crate::client::http::TritonRestClient;
+use crate::client::io::{DataType, InferInput, InferRequestedOutput, Provenance};
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+/// The buffer of one output.
+#[derive(Debug, Clone)]
+pub struct OutputBuffer {
+    pub name: String,
+    /// Shape the output must have.
+    pub shape: Vec<usize>,
+    /// Values of the last response; its variant is the datatype expected.
+    pub data: DataType,
+}
+
+impl OutputBuffer {
+    /// Check that an output of `datatype` and `shape` fits in the buffer.
+    pub(crate) fn check(
+        &self,
+        datatype: &TritonDtype,
+        shape: &[usize],
+    ) -> Result<(), TrustonError> {
+        if Some(datatype) != self.data.dtype().as_ref() {
+            return Err(TrustonError::InferenceError(format!(
+                "output {} was returned as {}, its buffer holds {}",
+                self.name,
+                datatype,
+                self.data.get_type_str()
+            )));
+        }
+        if shape != self.shape {
+            return Err(TrustonError::InferenceError(format!(
+                "output {} has shape {:?}, its buffer is for {:?}",
+                self.name, shape, self.shape
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Preallocated tensors receiving the outputs of
+/// [`TritonRestClient::infer_into`].
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuffers {
+    buffers: Vec<OutputBuffer>,
+}
+
+impl OutputBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive the output `name` of `shape` into `data`, whose variant is the
+    /// datatype expected, e.g. `DataType::F32(Vec::with_capacity(1000))`.
+    pub fn with_buffer(
+        mut self,
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        data: DataType,
+    ) -> Self {
+        let name = name.into();
+        self.buffers.retain(|buffer| buffer.name != name);
+        self.buffers.push(OutputBuffer { name, shape, data });
+        self
+    }
+
+    /// The values of the output `name` received last.
+    pub fn get(&self, name: &str) -> Option<&DataType> {
+        self.buffers.iter().find(|buffer| buffer.name == name).map(|buffer| &buffer.data)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OutputBuffer> {
+        self.buffers.iter()
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut OutputBuffer> {
+        self.buffers.iter_mut().find(|buffer| buffer.name == name)
+    }
+}
+
+impl TritonRestClient {
+    /// Run an inference, decoding its outputs into `outputs` (see
+    /// [`buffers`](crate::client::buffers)).
+    ///
+    /// Only the outputs having a buffer are requested, in binary.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if an output doesn't match
+    /// the shape or datatype of its buffer or no values were received for a
+    /// buffer, and the errors of
+    /// [`infer_with_options`](TritonRestClient::infer_with_options).
+    pub async fn infer_into(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        outputs: &mut OutputBuffers,
+    ) -> Result<Provenance, TrustonError> {
+        self.infer_into_with_options(inputs, model_name, &InferOptions::new(), outputs).await
+    }
+
+    /// Like [`TritonRestClient::infer_into`], with request options. Requested
+    /// outputs of `options` are replaced by the outputs having a buffer.
+    pub async fn infer_into_with_options(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+        outputs: &mut OutputBuffers,
+    ) -> Result<Provenance, TrustonError> {
+        let mut options = options.clone();
+        options.outputs = Some(
+            outputs
+                .iter()
+                .map(|buffer| InferRequestedOutput::new(&buffer.name).with_binary_data(true))
+                .collect(),
+        );
+        let results = self.run_infer(inputs, model_name, &options, Some(outputs)).await?;
+        Ok(results.provenance)
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buffer_checks_shape_and_datatype() {
+        let buffers = OutputBuffers::new()
+            .with_buffer("y", vec![2], DataType::F32(vec![0.0; 2]))
+            .with_buffer("y", vec![1, 2], DataType::F32(vec![0.0; 2]))
+            .with_buffer("s", vec![1], DataType::String(Vec::new()));
+        assert_eq!(buffers.iter().count(), 2);
+        let y = buffers.iter().find(|buffer| buffer.name == "y").unwrap();
+        assert!(y.check(&TritonDtype::Fp32, &[1, 2]).is_ok());
+        let wrong_datatype = y.check(&TritonDtype::Fp64, &[1, 2]);
+        assert!(matches!(wrong_datatype, Err(TrustonError::InferenceError(_))));
+        assert!(matches!(y.check(&TritonDtype::Fp32, &[2]), Err(TrustonError::InferenceError(_))));
+
+        let s = buffers.iter().find(|buffer| buffer.name == "s").unwrap();
+        assert!(s.check(&TritonDtype::Bytes, &[1]).is_ok());
+        assert_eq!(buffers.get("s").and_then(DataType::as_str_slice), Some(&[][..]));
+    }
+}
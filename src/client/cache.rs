@@ -0,0 +1,171 @@
+//! Cache of model metadata and configs.
+//!
+//! Input validation, batch sizing, padding and admission control all need
+//! the metadata or config of a model. The client caches them for a TTL (see
+//! [`TritonRestClientBuilder::metadata_cache_ttl`](crate::client::http::TritonRestClientBuilder::metadata_cache_ttl))
+//! instead of fetching them on every inference. Loading or unloading a model
+//! through the client drops its entries.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::client::config::ModelConfig;
+use crate::client::http::TritonRestClient;
+use crate::client::metadata::ModelMetadata;
+use crate::utils::errors::TrustonError;
+
+/// How long cached metadata and configs are used, unless configured otherwise.
+pub const DEFAULT_METADATA_TTL: Duration = Duration::from_secs(300);
+
+/// Values per model name, expiring `ttl` after they were fetched.
+#[derive(Debug)]
+pub(crate) struct TtlCache<V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Arc<V>, Instant)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Lookups of a [`TtlCache`] since it was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CacheStats {
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) entries: usize,
+}
+
+impl<V> TtlCache<V> {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The value of `key`, if fetched less than `ttl` ago.
+    pub(crate) fn get(&self, key: &str) -> Option<Arc<V>> {
+        let mut entries = self.entries.lock().unwrap();
+        let value = match entries.get(key) {
+            Some((value, fetched)) if fetched.elapsed() < self.ttl => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        };
+        let counter = if value.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+
+    pub(crate) fn insert(&self, key: &str, value: Arc<V>) {
+        if !self.ttl.is_zero() {
+            self.entries
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), (value, Instant::now()));
+        }
+    }
+
+    pub(crate) fn remove(&self, key: &str) {
+        self.entries.lock().unwrap().remove(key);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+impl TritonRestClient {
+    /// Metadata of `model_name`, served from the cache while fresh.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let metadata = client.cached_model_metadata("resnet50").await?;
+    /// // no request this time
+    /// let again = client.cached_model_metadata("resnet50").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn cached_model_metadata(
+        &self,
+        model_name: &str,
+    ) -> Result<Arc<ModelMetadata>, TrustonError> {
+        if let Some(metadata) = self.metadata_cache.get(model_name) {
+            return Ok(metadata);
+        }
+        let metadata = Arc::new(self.model_metadata(model_name).await?);
+        self.metadata_cache.insert(model_name, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Config of `model_name`, served from the cache while fresh.
+    pub async fn cached_model_config(
+        &self,
+        model_name: &str,
+    ) -> Result<Arc<ModelConfig>, TrustonError> {
+        if let Some(config) = self.config_cache.get(model_name) {
+            return Ok(config);
+        }
+        let config = Arc::new(self.model_config(model_name).await?);
+        self.config_cache.insert(model_name, config.clone());
+        Ok(config)
+    }
+
+    /// Drop the cached metadata and config of `model_name`, e.g. after it
+    /// was reloaded with different inputs by someone else. They are fetched
+    /// again on next use.
+    pub fn forget_metadata(&self, model_name: &str) {
+        self.metadata_cache.remove(model_name);
+        self.config_cache.remove(model_name);
+    }
+
+    /// Drop every cached metadata and config.
+    pub fn clear_metadata_cache(&self) {
+        self.metadata_cache.clear();
+        self.config_cache.clear();
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = TtlCache::new(Duration::from_secs(60));
+        cache.insert("m", Arc::new(1));
+        assert_eq!(cache.get("m").as_deref(), Some(&1));
+        cache.remove("m");
+        assert_eq!(cache.get("m"), None);
+
+        let expired = TtlCache::new(Duration::from_nanos(1));
+        expired.insert("m", Arc::new(1));
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(expired.get("m"), None);
+
+        let disabled = TtlCache::new(Duration::ZERO);
+        disabled.insert("m", Arc::new(1));
+        assert_eq!(disabled.get("m"), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 0));
+    }
+}
@@ -0,0 +1,354 @@
+//! Pluggable body compression.
+//!
+//! A [`Codec`] compresses and decompresses byte buffers. Codecs are used by
+//! [`TritonRestClient`](crate::client::http::TritonRestClient) for HTTP
+//! bodies (see
+//! [`TritonRestClientBuilder::request_codec`](crate::client::http::TritonRestClientBuilder::request_codec)
+//! and [`TritonRestClientBuilder::response_codec`](crate::client::http::TritonRestClientBuilder::response_codec)),
+//! but work on any buffer, e.g. payloads stored on disk.
+//!
+//! Implementations are provided behind Cargo features, so only the codecs
+//! available in your environment are compiled:
+//! - `gzip`: [`Gzip`] and [`Deflate`] (both accepted by Triton for requests
+//!   and responses),
+//! - `zstd`: [`Zstd`],
+//! - `lz4`: [`Lz4`] (LZ4 frame format).
+//!
+//! Other codecs can be plugged in by implementing the trait.
+
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::utils::errors::TrustonError;
+
+/// A compression algorithm.
+///
+/// # Example
+/// A custom codec (here, one that doesn't compress at all):
+/// ```
+/// use truston::TrustonError;
+/// use truston::client::compression::Codec;
+///
+/// #[derive(Debug)]
+/// struct Identity;
+///
+/// impl Codec for Identity {
+///     fn name(&self) -> &'static str {
+///         "identity"
+///     }
+///     fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+///         Ok(data.to_vec())
+///     }
+///     fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+///         Ok(data.to_vec())
+///     }
+/// }
+/// ```
+pub trait Codec: Debug + Send + Sync {
+    /// Token used in the `Content-Encoding` / `Accept-Encoding` headers.
+    fn name(&self) -> &'static str;
+
+    /// Compress `data`.
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError>;
+
+    /// Decompress `data` produced by [`Codec::compress`] (or a compatible encoder).
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError>;
+
+    /// Like [`Codec::decompress`], failing with
+    /// [`TrustonError::CompressionError`] if `data` decompresses to more than
+    /// `limit` bytes.
+    ///
+    /// The default implementation decompresses `data` whole before checking
+    /// its size; codecs able to stop early should override it.
+    fn decompress_limited(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, TrustonError> {
+        let out = self.decompress(data)?;
+        if out.len() > limit {
+            return Err(too_large(limit));
+        }
+        Ok(out)
+    }
+}
+
+/// Most bytes a response body is decompressed to, guarding against small
+/// bodies expanding to exhaust the memory (decompression bombs).
+pub(crate) const MAX_DECOMPRESSED_SIZE: usize = 1 << 30;
+
+/// Decode a response body according to its `Content-Encoding`.
+///
+/// Bodies without encoding (or `identity`) are returned as-is.
+///
+/// # Errors
+/// Returns [`TrustonError::CompressionError`] if the encoding is not one of
+/// `codecs`, the body cannot be decompressed or it decompresses to more than
+/// `limit` bytes.
+pub(crate) fn decode_body(
+    codecs: &[Arc<dyn Codec>],
+    content_encoding: Option<&str>,
+    body: bytes::Bytes,
+    limit: usize,
+) -> Result<bytes::Bytes, TrustonError> {
+    let encoding = match content_encoding.map(str::trim) {
+        None | Some("") | Some("identity") => return Ok(body),
+        Some(encoding) => encoding,
+    };
+    let codec = codecs
+        .iter()
+        .find(|codec| codec.name().eq_ignore_ascii_case(encoding))
+        .ok_or_else(|| {
+            TrustonError::CompressionError(format!("unsupported content encoding {}", encoding))
+        })?;
+    Ok(codec.decompress_limited(&body, limit)?.into())
+}
+
+/// Gzip (`Content-Encoding: gzip`), requires the `gzip` feature.
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy)]
+pub struct Gzip {
+    /// Compression level, `0` (none) to `9` (best). Defaults to `6`.
+    pub level: u32,
+}
+
+#[cfg(feature = "gzip")]
+impl Default for Gzip {
+    fn default() -> Self {
+        Gzip { level: 6 }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Codec for Gzip {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Write;
+
+        let level = flate2::Compression::new(self.level.min(9));
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), level);
+        encoder.write_all(data).map_err(compression_error)?;
+        encoder.finish().map_err(compression_error)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Read;
+
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(data)
+            .read_to_end(&mut out)
+            .map_err(compression_error)?;
+        Ok(out)
+    }
+
+    fn decompress_limited(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, TrustonError> {
+        read_limited(flate2::read::GzDecoder::new(data), limit)
+    }
+}
+
+/// Deflate (`Content-Encoding: deflate`, zlib format), requires the `gzip` feature.
+#[cfg(feature = "gzip")]
+#[derive(Debug, Clone, Copy)]
+pub struct Deflate {
+    /// Compression level, `0` (none) to `9` (best). Defaults to `6`.
+    pub level: u32,
+}
+
+#[cfg(feature = "gzip")]
+impl Default for Deflate {
+    fn default() -> Self {
+        Deflate { level: 6 }
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl Codec for Deflate {
+    fn name(&self) -> &'static str {
+        "deflate"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Write;
+
+        let level = flate2::Compression::new(self.level.min(9));
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), level);
+        encoder.write_all(data).map_err(compression_error)?;
+        encoder.finish().map_err(compression_error)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Read;
+
+        let mut out = Vec::new();
+        flate2::read::ZlibDecoder::new(data)
+            .read_to_end(&mut out)
+            .map_err(compression_error)?;
+        Ok(out)
+    }
+
+    fn decompress_limited(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, TrustonError> {
+        read_limited(flate2::read::ZlibDecoder::new(data), limit)
+    }
+}
+
+/// Zstandard (`Content-Encoding: zstd`), requires the `zstd` feature.
+#[cfg(feature = "zstd")]
+#[derive(Debug, Clone, Copy)]
+pub struct Zstd {
+    /// Compression level, `1` to `22`. Defaults to `3`.
+    pub level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl Default for Zstd {
+    fn default() -> Self {
+        Zstd { level: 3 }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Codec for Zstd {
+    fn name(&self) -> &'static str {
+        "zstd"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        zstd::encode_all(data, self.level).map_err(compression_error)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        zstd::decode_all(data).map_err(compression_error)
+    }
+
+    fn decompress_limited(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, TrustonError> {
+        let decoder = zstd::stream::read::Decoder::new(data).map_err(compression_error)?;
+        read_limited(decoder, limit)
+    }
+}
+
+/// LZ4 frame format (`Content-Encoding: lz4`), requires the `lz4` feature.
+///
+/// Very fast, with a lower ratio than gzip or zstd.
+#[cfg(feature = "lz4")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lz4;
+
+#[cfg(feature = "lz4")]
+impl Codec for Lz4 {
+    fn name(&self) -> &'static str {
+        "lz4"
+    }
+
+    fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Write;
+
+        let mut encoder = lz4_flex::frame::FrameEncoder::new(Vec::new());
+        encoder.write_all(data).map_err(compression_error)?;
+        encoder.finish().map_err(compression_error)
+    }
+
+    fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+        use std::io::Read;
+
+        let mut out = Vec::new();
+        lz4_flex::frame::FrameDecoder::new(data)
+            .read_to_end(&mut out)
+            .map_err(compression_error)?;
+        Ok(out)
+    }
+
+    fn decompress_limited(&self, data: &[u8], limit: usize) -> Result<Vec<u8>, TrustonError> {
+        read_limited(lz4_flex::frame::FrameDecoder::new(data), limit)
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "lz4"))]
+fn compression_error(e: impl std::fmt::Display) -> TrustonError {
+    TrustonError::CompressionError(e.to_string())
+}
+
+fn too_large(limit: usize) -> TrustonError {
+    TrustonError::CompressionError(format!("body decompresses to more than {} bytes", limit))
+}
+
+/// Read `decoder` to the end, stopping past `limit` bytes.
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "lz4"))]
+fn read_limited(decoder: impl std::io::Read, limit: usize) -> Result<Vec<u8>, TrustonError> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    decoder
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut out)
+        .map_err(compression_error)?;
+    if out.len() > limit {
+        return Err(too_large(limit));
+    }
+    Ok(out)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reverses the bytes, enough to tell encoded and decoded bodies apart.
+    #[derive(Debug)]
+    struct Reverse;
+
+    impl Codec for Reverse {
+        fn name(&self) -> &'static str {
+            "reverse"
+        }
+        fn compress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+            Ok(data.iter().rev().copied().collect())
+        }
+        fn decompress(&self, data: &[u8]) -> Result<Vec<u8>, TrustonError> {
+            self.compress(data)
+        }
+    }
+
+    #[test]
+    fn test_decode_body_by_content_encoding() {
+        let codecs: Vec<Arc<dyn Codec>> = vec![Arc::new(Reverse)];
+        let body = bytes::Bytes::from_static(b"abc");
+
+        let decode = |encoding, limit| decode_body(&codecs, encoding, body.clone(), limit);
+
+        assert_eq!(decode(None, 3).unwrap(), "abc");
+        assert_eq!(decode(Some("identity"), 3).unwrap(), "abc");
+        assert_eq!(decode(Some("Reverse"), 3).unwrap(), "cba");
+        assert!(matches!(decode(Some("br"), 3), Err(TrustonError::CompressionError(_))));
+        assert!(matches!(decode(Some("reverse"), 2), Err(TrustonError::CompressionError(_))));
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd", feature = "lz4"))]
+    fn assert_roundtrip(codec: &dyn Codec) {
+        let data = br#"{"inputs":[{"name":"x","data":[0,0,0,0,0,0,0,0]}]}"#.repeat(100);
+        let compressed = codec.compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(codec.decompress(&compressed).unwrap(), data);
+        assert!(codec.decompress(b"not compressed").is_err());
+        assert_eq!(codec.decompress_limited(&compressed, data.len()).unwrap(), data);
+        let err = codec.decompress_limited(&compressed, data.len() - 1).unwrap_err();
+        assert!(matches!(err, TrustonError::CompressionError(_)), "{:?}", err);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip_roundtrip() {
+        assert_roundtrip(&Gzip::default());
+        assert_roundtrip(&Deflate::default());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_roundtrip() {
+        assert_roundtrip(&Zstd::default());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn test_lz4_roundtrip() {
+        assert_roundtrip(&Lz4);
+    }
+}
@@ -0,0 +1,280 @@
+//! Model configuration for Triton Inference Server.
+//!
+//! Wraps `GET /v2/models/{model_name}/config`, which returns the model's
+//! `config.pbtxt` as JSON. Unlike the metadata, the configuration tells apart
+//! the batch dimension (`max_batch_size`) from the fixed per-item dims, and
+//! describes how many instances of the model run and how they are scheduled.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::client::http::TritonRestClient;
+use crate::utils::errors::TrustonError;
+
+/// Configuration of a model, limited to the fields used by this client.
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "name": "bert",
+///   "max_batch_size": 8,
+///   "input": [{ "name": "input_ids", "data_type": "TYPE_INT64", "dims": [128] }],
+///   "output": [{ "name": "logits", "data_type": "TYPE_FP32", "dims": [2] }],
+///   "instance_group": [{ "name": "bert_0", "kind": "KIND_GPU", "count": 2, "gpus": [0, 1] }]
+/// }
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct ModelConfig {
+    pub name: String,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub backend: String,
+    /// `0` when the model does not support batching; otherwise inputs carry
+    /// an extra leading batch dimension not listed in `dims`.
+    #[serde(default)]
+    pub max_batch_size: i64,
+    #[serde(default)]
+    pub input: Vec<ModelConfigTensor>,
+    #[serde(default)]
+    pub output: Vec<ModelConfigTensor>,
+    /// Instances of the model. The server fills in a default group when the
+    /// config file has none.
+    #[serde(default)]
+    pub instance_group: Vec<InstanceGroup>,
+}
+
+impl ModelConfig {
+    /// Instances taking inference requests, across all groups.
+    ///
+    /// `visible_gpus` is the number of GPUs the server sees, used for GPU
+    /// groups that don't list their GPUs.
+    pub fn instances(&self, visible_gpus: usize) -> usize {
+        self.instance_group
+            .iter()
+            .map(|group| group.instances(visible_gpus))
+            .sum()
+    }
+
+    /// Upper bound on the inferences per second, if every instance runs full
+    /// batches back to back and one execution takes `latency`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use truston::client::config::ModelConfig;
+    ///
+    /// let config: ModelConfig = serde_json::from_str(r#"{
+    ///     "name": "bert",
+    ///     "max_batch_size": 8,
+    ///     "instance_group": [{"kind": "KIND_GPU", "count": 2, "gpus": [0, 1]}]
+    /// }"#)?;
+    /// // 2 instances on each of 2 GPUs, 8 items per 20 ms execution
+    /// assert_eq!(config.theoretical_throughput(2, Duration::from_millis(20)), 1600.0);
+    /// # Ok::<(), serde_json::Error>(())
+    /// ```
+    pub fn theoretical_throughput(&self, visible_gpus: usize, latency: Duration) -> f64 {
+        let batch = self.max_batch_size.max(1) as f64;
+        self.instances(visible_gpus) as f64 * batch / latency.as_secs_f64()
+    }
+}
+
+/// A group of identical model instances (`instance_group` in the config).
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct InstanceGroup {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub kind: InstanceKind,
+    /// Instances per GPU for GPU groups, in total otherwise.
+    #[serde(default = "default_instance_count")]
+    pub count: usize,
+    /// GPUs the instances run on; empty for all visible GPUs.
+    #[serde(default)]
+    pub gpus: Vec<u32>,
+    /// Passive instances are loaded but receive no inference requests.
+    #[serde(default)]
+    pub passive: bool,
+    #[serde(default)]
+    pub rate_limiter: Option<RateLimiter>,
+}
+
+fn default_instance_count() -> usize {
+    1
+}
+
+impl InstanceGroup {
+    /// Instances of this group taking inference requests.
+    pub fn instances(&self, visible_gpus: usize) -> usize {
+        if self.passive {
+            return 0;
+        }
+        match self.kind {
+            InstanceKind::Gpu if self.gpus.is_empty() => self.count * visible_gpus,
+            InstanceKind::Gpu => self.count * self.gpus.len(),
+            _ => self.count,
+        }
+    }
+}
+
+/// Where the instances of a group run.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InstanceKind {
+    /// Chosen by the server: GPU if available and supported, else CPU.
+    #[default]
+    #[serde(rename = "KIND_AUTO")]
+    Auto,
+    #[serde(rename = "KIND_GPU")]
+    Gpu,
+    #[serde(rename = "KIND_CPU")]
+    Cpu,
+    /// Placement decided by the model itself (e.g. Python backend models).
+    #[serde(rename = "KIND_MODEL")]
+    Model,
+}
+
+/// Resources an instance must acquire before each execution, when the
+/// server runs with `--rate-limit=execution_count`.
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+pub struct RateLimiter {
+    #[serde(default)]
+    pub resources: Vec<RateLimiterResource>,
+    /// Weight of the instance when several compete for resources; a priority
+    /// of 2 is scheduled half as often as a priority of 1. `0` counts as `1`.
+    #[serde(default)]
+    pub priority: u32,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RateLimiterResource {
+    pub name: String,
+    /// Shared by all devices rather than per device.
+    #[serde(default)]
+    pub global: bool,
+    pub count: u32,
+}
+
+/// An input or output as declared in the model configuration.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ModelConfigTensor {
+    pub name: String,
+    /// Config type name, e.g. `TYPE_FP32`.
+    pub data_type: String,
+    /// Per-item dims, without the batch dimension. `-1` marks a variable dim.
+    #[serde(deserialize_with = "deserialize_dims")]
+    pub dims: Vec<i64>,
+}
+
+impl ModelConfigTensor {
+    /// Datatype as used by the inference protocol (`TYPE_FP32` -> `FP32`,
+    /// `TYPE_STRING` -> `BYTES`).
+    pub fn datatype(&self) -> &str {
+        match self.data_type.strip_prefix("TYPE_").unwrap_or(&self.data_type) {
+            "STRING" => "BYTES",
+            other => other,
+        }
+    }
+}
+
+/// Dims are 64-bit integers, which some JSON encoders emit as strings.
+fn deserialize_dims<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<i64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Dim {
+        Number(i64),
+        Text(String),
+    }
+
+    Vec::<Dim>::deserialize(deserializer)?
+        .into_iter()
+        .map(|dim| match dim {
+            Dim::Number(n) => Ok(n),
+            Dim::Text(s) => s.parse().map_err(serde::de::Error::custom),
+        })
+        .collect()
+}
+
+impl TritonRestClient {
+    /// Fetch the configuration of a model.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let config = client.model_config("bert").await?;
+    /// println!("max batch size: {}", config.max_batch_size);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[tracing::instrument(skip_all, fields(model = model_name, status = tracing::field::Empty))]
+    pub async fn model_config(&self, model_name: &str) -> Result<ModelConfig, TrustonError> {
+        let url = format!("{}/config", self.model_url(model_name, None));
+        let resp = self.send_request(true, || self.http.get(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_config() {
+        let body = r#"{
+            "name": "bert",
+            "backend": "onnxruntime",
+            "max_batch_size": 8,
+            "input": [
+                {"name": "input_ids", "data_type": "TYPE_INT64", "dims": [128]},
+                {"name": "text", "data_type": "TYPE_STRING", "dims": ["-1"]}
+            ],
+            "output": [{"name": "logits", "data_type": "TYPE_FP32", "dims": [2]}]
+        }"#;
+        let config: ModelConfig = serde_json::from_str(body).unwrap();
+
+        assert_eq!(config.max_batch_size, 8);
+        assert_eq!(config.input[0].dims, vec![128]);
+        assert_eq!(config.input[0].datatype(), "INT64");
+        assert_eq!(config.input[1].dims, vec![-1]);
+        assert_eq!(config.input[1].datatype(), "BYTES");
+        assert_eq!(config.output[0].datatype(), "FP32");
+        assert!(config.instance_group.is_empty());
+    }
+
+    #[test]
+    fn test_parse_instance_groups() {
+        let body = r#"{
+            "name": "bert",
+            "max_batch_size": 4,
+            "instance_group": [
+                {"name": "bert_0", "kind": "KIND_GPU", "count": 2, "gpus": [],
+                 "rate_limiter": {"resources": [{"name": "R1", "global": false, "count": 4}],
+                                  "priority": 2}},
+                {"name": "bert_1", "kind": "KIND_CPU", "count": 3, "gpus": []},
+                {"name": "bert_2", "kind": "KIND_CPU", "count": 1, "passive": true}
+            ]
+        }"#;
+        let config: ModelConfig = serde_json::from_str(body).unwrap();
+
+        let gpu = &config.instance_group[0];
+        assert_eq!(gpu.kind, InstanceKind::Gpu);
+        let limiter = gpu.rate_limiter.as_ref().unwrap();
+        assert_eq!(limiter.priority, 2);
+        assert_eq!(limiter.resources[0].name, "R1");
+        assert_eq!(limiter.resources[0].count, 4);
+
+        // 2 per GPU on 4 GPUs, 3 on CPU, the passive one takes no requests
+        assert_eq!(config.instances(4), 11);
+        let throughput = config.theoretical_throughput(4, Duration::from_millis(100));
+        assert_eq!(throughput, 440.0);
+    }
+}
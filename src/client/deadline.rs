@@ -0,0 +1,69 @@
+//! Caller deadlines.
+//!
+//! A [`Deadline`] is the point in time by which the caller needs an answer,
+//! typically derived from the SLO of an upstream request. Attached to an
+//! inference (see [`InferOptions::with_deadline`](crate::client::options::InferOptions::with_deadline)),
+//! the time left bounds:
+//! - the server-side `timeout` parameter, so Triton drops work nobody waits for,
+//! - the HTTP timeout of each attempt,
+//! - retries: no retry is attempted if its backoff would outlast the deadline.
+
+use std::time::{Duration, Instant};
+
+/// A point in time after which a result is no longer useful.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use truston::client::deadline::Deadline;
+///
+/// let deadline = Deadline::after(Duration::from_millis(250));
+/// assert!(!deadline.is_expired());
+/// assert!(deadline.remaining() <= Duration::from_millis(250));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// Deadline `budget` from now.
+    pub fn after(budget: Duration) -> Self {
+        Deadline(Instant::now() + budget)
+    }
+
+    /// Deadline at `instant`.
+    pub fn at(instant: Instant) -> Self {
+        Deadline(instant)
+    }
+
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// Time left, zero once expired.
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
+
+impl From<Instant> for Deadline {
+    fn from(instant: Instant) -> Self {
+        Deadline(instant)
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expired_deadline_has_no_time_left() {
+        let deadline = Deadline::at(Instant::now() - Duration::from_secs(1));
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,186 @@
+//! Triton datatypes.
+//!
+//! [`TritonDtype`] names the datatype of a tensor (`"FP32"`, `"BF16"`, ...)
+//! without holding any values: the `datatype` of outputs, of the tensors of
+//! the model metadata and of request payloads, or the target of
+//! [`DataType::cast_to`](crate::client::io::DataType::cast_to). It
+//! (de)serializes as its name and compares equal to it, so
+//! `output.datatype == "FP32"` reads as before. A datatype the client
+//! doesn't know deserializes to [`TritonDtype::Other`], so outputs of a
+//! newer server still decode, as [`DataType::Raw`](crate::client::io::DataType::Raw).
+//!
+//! # Example
+//! ```
+//! use truston::client::dtype::TritonDtype;
+//!
+//! let dtype: TritonDtype = "INT64".parse()?;
+//! assert_eq!(dtype, TritonDtype::Int64);
+//! assert_eq!(TritonDtype::Fp32.as_str(), "FP32");
+//! assert_eq!(TritonDtype::Fp32.element_size(), Some(4));
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::utils::errors::TrustonError;
+
+/// A datatype of the Triton protocol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TritonDtype {
+    Bool,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Fp16,
+    Bf16,
+    Fp32,
+    Fp64,
+    Bytes,
+    /// A datatype this client doesn't know, by name.
+    Other(String),
+}
+
+impl TritonDtype {
+    /// The name of the datatype in the protocol, e.g. `"FP32"`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TritonDtype::Other(name) => name,
+            known => known.known_name().unwrap_or_default(),
+        }
+    }
+
+    /// The name of a datatype the client knows, `None` for
+    /// [`TritonDtype::Other`].
+    pub(crate) fn known_name(&self) -> Option<&'static str> {
+        Some(match self {
+            TritonDtype::Bool => "BOOL",
+            TritonDtype::Uint8 => "UINT8",
+            TritonDtype::Uint16 => "UINT16",
+            TritonDtype::Uint32 => "UINT32",
+            TritonDtype::Uint64 => "UINT64",
+            TritonDtype::Int8 => "INT8",
+            TritonDtype::Int16 => "INT16",
+            TritonDtype::Int32 => "INT32",
+            TritonDtype::Int64 => "INT64",
+            TritonDtype::Fp16 => "FP16",
+            TritonDtype::Bf16 => "BF16",
+            TritonDtype::Fp32 => "FP32",
+            TritonDtype::Fp64 => "FP64",
+            TritonDtype::Bytes => "BYTES",
+            TritonDtype::Other(_) => return None,
+        })
+    }
+
+    /// The datatype named `name`, [`TritonDtype::Other`] when the client
+    /// doesn't know it; `STRING`, used by some backends, is `BYTES`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "BOOL" => TritonDtype::Bool,
+            "UINT8" => TritonDtype::Uint8,
+            "UINT16" => TritonDtype::Uint16,
+            "UINT32" => TritonDtype::Uint32,
+            "UINT64" => TritonDtype::Uint64,
+            "INT8" => TritonDtype::Int8,
+            "INT16" => TritonDtype::Int16,
+            "INT32" => TritonDtype::Int32,
+            "INT64" => TritonDtype::Int64,
+            "FP16" => TritonDtype::Fp16,
+            "BF16" => TritonDtype::Bf16,
+            "FP32" => TritonDtype::Fp32,
+            "FP64" => TritonDtype::Fp64,
+            "BYTES" | "STRING" => TritonDtype::Bytes,
+            other => TritonDtype::Other(other.to_string()),
+        }
+    }
+
+    /// Size in bytes of one element, `None` for `BYTES`, whose elements each
+    /// have their own length, and for unknown datatypes.
+    pub fn element_size(&self) -> Option<usize> {
+        match self {
+            TritonDtype::Bool | TritonDtype::Uint8 | TritonDtype::Int8 => Some(1),
+            TritonDtype::Uint16 | TritonDtype::Int16 | TritonDtype::Fp16 | TritonDtype::Bf16 => {
+                Some(2)
+            }
+            TritonDtype::Uint32 | TritonDtype::Int32 | TritonDtype::Fp32 => Some(4),
+            TritonDtype::Uint64 | TritonDtype::Int64 | TritonDtype::Fp64 => Some(8),
+            TritonDtype::Bytes | TritonDtype::Other(_) => None,
+        }
+    }
+}
+
+/// Parses the name of a datatype; `STRING`, used by some backends, is
+/// `BYTES`. Unknown names are an error, see [`TritonDtype::from_name`] to
+/// keep them.
+impl FromStr for TritonDtype {
+    type Err = TrustonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match TritonDtype::from_name(s) {
+            TritonDtype::Other(name) => {
+                Err(TrustonError::ParseError(format!("unknown datatype {}", name)))
+            }
+            dtype => Ok(dtype),
+        }
+    }
+}
+
+impl fmt::Display for TritonDtype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for TritonDtype {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for TritonDtype {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl Serialize for TritonDtype {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TritonDtype {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(TritonDtype::from_name(&name))
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_round_trip() {
+        for dtype in [TritonDtype::Bool, TritonDtype::Bf16, TritonDtype::Uint64, TritonDtype::Fp64] {
+            assert_eq!(dtype.as_str().parse::<TritonDtype>().unwrap(), dtype);
+            let json = serde_json::to_string(&dtype).unwrap();
+            assert_eq!(serde_json::from_str::<TritonDtype>(&json).unwrap(), dtype);
+        }
+        assert_eq!("STRING".parse::<TritonDtype>().unwrap(), TritonDtype::Bytes);
+        assert!(matches!("FP8".parse::<TritonDtype>(), Err(TrustonError::ParseError(_))));
+        let fp8: TritonDtype = serde_json::from_str(r#""FP8""#).unwrap();
+        assert_eq!(fp8, TritonDtype::Other("FP8".into()));
+        assert_eq!(serde_json::to_string(&fp8).unwrap(), r#""FP8""#);
+        assert_eq!(fp8.element_size(), None);
+        assert_eq!(TritonDtype::Int16, "INT16");
+    }
+}
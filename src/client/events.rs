@@ -0,0 +1,161 @@
+//! Typed client events.
+//!
+//! Every [`TritonRestClient`] publishes what it does on a broadcast channel,
+//! so applications can feed dashboards or structured logs without parsing
+//! the `tracing` output. Subscribe with [`TritonRestClient::events`].
+//!
+//! Events are only built while at least one receiver is subscribed. A
+//! receiver that falls behind by more than the channel capacity (see
+//! [`TritonRestClientBuilder::event_capacity`](crate::client::http::TritonRestClientBuilder::event_capacity))
+//! skips the oldest events and gets [`RecvError::Lagged`](tokio::sync::broadcast::error::RecvError::Lagged).
+
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+
+use crate::client::http::TritonRestClient;
+use crate::client::queue::FlushReason;
+
+/// Default number of events buffered per receiver.
+pub(crate) const DEFAULT_EVENT_CAPACITY: usize = 256;
+
+/// Something that happened inside a client.
+///
+/// New variants are added as the client grows, so match with a wildcard arm.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum ClientEvent {
+    /// An HTTP request is about to be sent (once per attempt).
+    RequestStarted {
+        method: String,
+        url: String,
+        attempt: u32,
+    },
+    /// The server answered, whatever the status.
+    RequestCompleted {
+        method: String,
+        url: String,
+        status: u16,
+        elapsed: Duration,
+    },
+    /// The request failed before a response was received (connection error, timeout, ...).
+    RequestFailed {
+        method: String,
+        url: String,
+        error: String,
+        elapsed: Duration,
+    },
+    /// The attempt failed transiently and will be retried after `delay`.
+    Retry {
+        method: String,
+        url: String,
+        attempt: u32,
+        delay: Duration,
+        reason: String,
+    },
+    /// The endpoint could not be reached; the request is sent to the next one at once.
+    Failover {
+        method: String,
+        from: String,
+        to: String,
+        error: String,
+    },
+    /// An inference failed with a server error and is sent to a fallback target.
+    Fallback {
+        model: String,
+        version: Option<String>,
+        status: u16,
+        fallback_model: String,
+        fallback_version: Option<String>,
+    },
+    /// An inference got no response within `delay` and a duplicate is sent
+    /// to `endpoint` (see [`hedge`](crate::client::hedge)).
+    Hedged {
+        endpoint: String,
+        delay: Duration,
+    },
+    /// An [`InferQueue`](crate::client::queue::InferQueue) sent a batch of
+    /// `samples` samples, `rows` rows in total.
+    BatchFlushed {
+        model: String,
+        samples: usize,
+        rows: usize,
+        reason: FlushReason,
+    },
+}
+
+impl TritonRestClient {
+    /// Subscribe to the events of this client.
+    ///
+    /// Only events emitted after the call are received.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use truston::client::events::ClientEvent;
+    /// use truston::client::http::TritonRestClient;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let mut events = client.events();
+    /// tokio::spawn(async move {
+    ///     while let Ok(event) = events.recv().await {
+    ///         if let ClientEvent::RequestCompleted { url, status, elapsed, .. } = event {
+    ///             println!("{} {} in {:?}", url, status, elapsed);
+    ///         }
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn events(&self) -> broadcast::Receiver<ClientEvent> {
+        self.events.subscribe()
+    }
+
+    /// Publish the event built by `event`, if anyone is listening.
+    pub(crate) fn emit(&self, event: impl FnOnce() -> ClientEvent) {
+        if self.events.receiver_count() > 0 {
+            // sending only fails when every receiver was dropped meanwhile
+            let _ = self.events.send(event());
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::retry::RetryPolicy;
+
+    #[tokio::test]
+    async fn test_failed_request_emits_retry_and_failure() {
+        let policy = RetryPolicy::new(2)
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let client = TritonRestClient::builder("http://localhost:12345")
+            .retry_policy(policy)
+            .build()
+            .unwrap();
+        let mut events = client.events();
+
+        assert!(client.server_live().await.is_err());
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(received.len(), 5);
+        assert!(matches!(
+            &received[0],
+            ClientEvent::RequestStarted { method, attempt: 1, .. } if method == "GET"
+        ));
+        assert!(matches!(received[1], ClientEvent::RequestFailed { .. }));
+        assert!(matches!(received[2], ClientEvent::Retry { attempt: 1, .. }));
+        assert!(matches!(received[3], ClientEvent::RequestStarted { attempt: 2, .. }));
+        assert!(matches!(received[4], ClientEvent::RequestFailed { .. }));
+    }
+
+    #[test]
+    fn test_emit_without_receivers_is_noop() {
+        let client = TritonRestClient::new("http://localhost:12345");
+        client.emit(|| unreachable!("event built without receivers"));
+    }
+}
@@ -0,0 +1,250 @@
+//! Fault injection for chaos tests.
+//!
+//! A [`FaultInjector`] installed on a client (see
+//! [`TritonRestClientBuilder::fault_injector`](crate::client::http::TritonRestClientBuilder::fault_injector))
+//! makes some requests misbehave the way a flaky network or server would,
+//! so the retry, failover and error handling of an application can be
+//! tested without a chaos proxy. Faults happen below the retry logic of the
+//! client, which handles them as it would real failures.
+//!
+//! Only available with the `test-util` feature.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use reqwest::ResponseBuilderExt;
+
+/// A fault applied to a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The connection fails, as if the server were down.
+    Drop,
+    /// The server answers with this status, without processing the request.
+    Status(u16),
+    /// The server answers `200` with a body that is not valid JSON.
+    MalformedJson,
+    /// The response body is cut in half.
+    TruncatedBody,
+}
+
+/// Faults injected at random, each with its own probability.
+///
+/// Latency is added independently of the other faults; of those, at most
+/// one applies to a request, checked in the order drop, statuses, malformed
+/// JSON, truncated body.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use truston::client::fault::FaultInjector;
+/// use truston::client::http::TritonRestClient;
+///
+/// let faults = FaultInjector::new()
+///     .with_seed(42)
+///     .with_latency(0.2, Duration::from_millis(300))
+///     .with_drops(0.05)
+///     .with_status(0.05, 503);
+/// let client = TritonRestClient::builder("http://localhost:8000")
+///     .fault_injector(faults)
+///     .build()?;
+/// # Ok::<(), truston::TrustonError>(())
+/// ```
+#[derive(Debug)]
+pub struct FaultInjector {
+    latency: Option<(f64, Duration)>,
+    drops: f64,
+    statuses: Vec<(f64, u16)>,
+    malformed_json: f64,
+    truncated_bodies: f64,
+    rng: Mutex<StdRng>,
+    injected: AtomicU64,
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        FaultInjector {
+            latency: None,
+            drops: 0.0,
+            statuses: Vec::new(),
+            malformed_json: 0.0,
+            truncated_bodies: 0.0,
+            rng: Mutex::new(StdRng::from_os_rng()),
+            injected: AtomicU64::new(0),
+        }
+    }
+}
+
+impl FaultInjector {
+    /// An injector with no faults, to be configured with the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw faults from a generator seeded with `seed`, to replay a run.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Delay requests by `delay` with `probability`.
+    pub fn with_latency(mut self, probability: f64, delay: Duration) -> Self {
+        self.latency = Some((clamp(probability), delay));
+        self
+    }
+
+    /// Fail connections with `probability`.
+    pub fn with_drops(mut self, probability: f64) -> Self {
+        self.drops = clamp(probability);
+        self
+    }
+
+    /// Answer with `status` with `probability`. Can be called for several statuses.
+    pub fn with_status(mut self, probability: f64, status: u16) -> Self {
+        self.statuses.push((clamp(probability), status));
+        self
+    }
+
+    /// Answer with a body that is not valid JSON with `probability`.
+    pub fn with_malformed_json(mut self, probability: f64) -> Self {
+        self.malformed_json = clamp(probability);
+        self
+    }
+
+    /// Cut response bodies in half with `probability`.
+    pub fn with_truncated_bodies(mut self, probability: f64) -> Self {
+        self.truncated_bodies = clamp(probability);
+        self
+    }
+
+    /// Number of requests a fault (latency included) was injected into.
+    pub fn injected(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    /// Draw the latency and fault of one request.
+    fn roll(&self) -> (Option<Duration>, Option<Fault>) {
+        let mut rng = self.rng.lock().unwrap();
+        let delay = self
+            .latency
+            .filter(|&(probability, _)| rng.random_bool(probability))
+            .map(|(_, delay)| delay);
+
+        let statuses = self.statuses.iter().map(|&(p, status)| (p, Fault::Status(status)));
+        let fault = std::iter::once((self.drops, Fault::Drop))
+            .chain(statuses)
+            .chain([
+                (self.malformed_json, Fault::MalformedJson),
+                (self.truncated_bodies, Fault::TruncatedBody),
+            ])
+            .find(|&(probability, _)| rng.random_bool(probability))
+            .map(|(_, fault)| fault);
+        (delay, fault)
+    }
+
+    /// Send `request` with `client`, injecting the faults drawn for it.
+    pub(crate) async fn execute(
+        &self,
+        client: &reqwest::Client,
+        mut request: reqwest::Request,
+    ) -> Result<reqwest::Response, reqwest::Error> {
+        let (delay, fault) = self.roll();
+        if delay.is_some() || fault.is_some() {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("injecting {:?} (delay {:?}) into {}", fault, delay, request.url());
+        }
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let url = request.url().clone();
+        match fault {
+            None => client.execute(request).await,
+            // nothing listens on port 0, so this is a genuine connection error
+            Some(Fault::Drop) => {
+                let target = request.url_mut();
+                let _ = target.set_ip_host([127, 0, 0, 1].into());
+                let _ = target.set_port(Some(0));
+                client.execute(request).await
+            }
+            Some(Fault::Status(status)) => {
+                let body = format!(r#"{{"error":"injected status {}"}}"#, status);
+                Ok(synthetic_response(url, status, body.into_bytes()))
+            }
+            Some(Fault::MalformedJson) => {
+                Ok(synthetic_response(url, 200, br#"{"outputs": [{"name": oops"#.to_vec()))
+            }
+            Some(Fault::TruncatedBody) => {
+                let resp = client.execute(request).await?;
+                let status = resp.status();
+                let mut headers = resp.headers().clone();
+                let body = resp.bytes().await?;
+                headers.remove(reqwest::header::CONTENT_LENGTH);
+
+                let mut builder = http::Response::builder().status(status).url(url);
+                if let Some(target) = builder.headers_mut() {
+                    *target = headers;
+                }
+                let truncated = body.slice(..body.len() / 2);
+                Ok(builder.body(truncated).expect("valid response parts").into())
+            }
+        }
+    }
+}
+
+fn clamp(probability: f64) -> f64 {
+    if probability.is_nan() { 0.0 } else { probability.clamp(0.0, 1.0) }
+}
+
+fn synthetic_response(url: reqwest::Url, status: u16, body: Vec<u8>) -> reqwest::Response {
+    http::Response::builder()
+        .status(status)
+        .url(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .expect("valid response parts")
+        .into()
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::http::TritonRestClient;
+    use crate::utils::errors::TrustonError;
+
+    fn client_with(faults: FaultInjector) -> TritonRestClient {
+        TritonRestClient::builder("http://127.0.0.1:1")
+            .fault_injector(faults)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_injected_responses() {
+        let client = client_with(FaultInjector::new().with_status(1.0, 503));
+        let err = client.model_metadata("m").await.unwrap_err();
+        assert!(matches!(err, TrustonError::ServerError { status: 503, .. }), "{:?}", err);
+
+        let client = client_with(FaultInjector::new().with_malformed_json(1.0));
+        let err = client.model_metadata("m").await.unwrap_err();
+        assert!(matches!(err, TrustonError::ParseError(_)), "{:?}", err);
+
+        let client = client_with(FaultInjector::new().with_drops(1.0));
+        let err = client.model_metadata("m").await.unwrap_err();
+        assert!(matches!(&err, TrustonError::Http(e) if e.is_connect()), "{:?}", err);
+    }
+
+    #[test]
+    fn test_seeded_rolls_are_reproducible() {
+        let rolls = |seed| {
+            let faults = FaultInjector::new().with_seed(seed).with_drops(0.5).with_status(0.5, 500);
+            (0..32).map(|_| faults.roll().1).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert!(rolls(7).contains(&None));
+        assert!(rolls(7).contains(&Some(Fault::Drop)));
+    }
+}
@@ -0,0 +1,330 @@
+//! Generate extension of Triton Inference Server (LLM text generation).
+//!
+//! Wraps `POST /v2/models/{model_name}/generate`, which takes and returns
+//! plain JSON objects instead of tensors, and `/generate_stream`, which sends
+//! one response per generated chunk as server-sent events (SSE). This is the
+//! endpoint used by TensorRT-LLM and vLLM models.
+
+use std::collections::VecDeque;
+
+use futures::{Stream, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::client::http::TritonRestClient;
+use crate::utils::errors::TrustonError;
+
+/// Body of a generate request.
+///
+/// Top-level fields are mapped by Triton to the model inputs of the same
+/// name; `parameters` are passed as request parameters.
+///
+/// # Example
+/// ```
+/// use truston::client::generate::GenerateRequest;
+///
+/// let request = GenerateRequest::new("What is machine learning?")
+///     .with_max_tokens(64)
+///     .with_temperature(0.7)
+///     .with_input("stop_words", "");
+///
+/// let json = serde_json::to_value(&request).unwrap();
+/// assert_eq!(json["max_tokens"], 64);
+/// assert_eq!(json["stop_words"], "");
+/// ```
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GenerateRequest {
+    /// The prompt.
+    pub text_input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Additional model inputs, serialized next to `text_input`.
+    #[serde(flatten)]
+    pub inputs: Map<String, Value>,
+    /// Request parameters.
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub parameters: Map<String, Value>,
+}
+
+impl GenerateRequest {
+    pub fn new(text_input: impl Into<String>) -> Self {
+        GenerateRequest {
+            text_input: text_input.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_top_p(mut self, top_p: f32) -> Self {
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn with_top_k(mut self, top_k: u32) -> Self {
+        self.top_k = Some(top_k);
+        self
+    }
+
+    /// Set any other model input (e.g. `bad_words`, `stop_words`, `beam_width`).
+    pub fn with_input(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.inputs.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_parameter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// A generate response, or one chunk of a streamed generation.
+///
+/// # Example JSON
+/// ```json
+/// { "model_name": "llama", "model_version": "1", "text_output": "Machine learning is" }
+/// ```
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct GenerateResponse {
+    #[serde(default)]
+    pub model_name: String,
+    #[serde(default)]
+    pub model_version: String,
+    /// Generated text (only the new tokens when streaming).
+    #[serde(default)]
+    pub text_output: String,
+    /// Every other output of the model.
+    #[serde(flatten)]
+    pub outputs: Map<String, Value>,
+}
+
+impl TritonRestClient {
+    /// Generate text with `model_name` and wait for the complete answer.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// use truston::client::generate::GenerateRequest;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let request = GenerateRequest::new("What is machine learning?").with_max_tokens(64);
+    /// let response = client.generate("ensemble", &request).await?;
+    /// println!("{}", response.text_output);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn generate(
+        &self,
+        model_name: &str,
+        request: &GenerateRequest,
+    ) -> Result<GenerateResponse, TrustonError> {
+        let url = format!("{}/generate", self.model_url(model_name, None));
+        let resp = self
+            .send_request(true, || self.http.post(&url).json(request))
+            .await?;
+
+        tracing::info!("generate: {} -> {}", url, resp.status());
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+
+    /// Generate text with `model_name`, receiving each chunk as soon as it is produced.
+    ///
+    /// The request is sent before this method returns; the stream then yields
+    /// one [`GenerateResponse`] per server-sent event and ends with the
+    /// generation. An error event ends the stream with
+    /// [`TrustonError::InferenceError`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// use futures::StreamExt;
+    /// use truston::client::generate::GenerateRequest;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let request = GenerateRequest::new("Once upon a time").with_max_tokens(128);
+    /// let mut chunks = Box::pin(client.generate_stream("ensemble", &request).await?);
+    /// while let Some(chunk) = chunks.next().await {
+    ///     print!("{}", chunk?.text_output);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn generate_stream(
+        &self,
+        model_name: &str,
+        request: &GenerateRequest,
+    ) -> Result<impl Stream<Item = Result<GenerateResponse, TrustonError>> + use<>, TrustonError>
+    {
+        let url = format!("{}/generate_stream", self.model_url(model_name, None));
+        // events are decoded as they arrive, so the stream must not be compressed
+        let resp = self
+            .send_request(true, || {
+                self.http
+                    .post(&url)
+                    .header(reqwest::header::ACCEPT_ENCODING, "identity")
+                    .json(request)
+            })
+            .await?;
+
+        tracing::info!("generate_stream: {} -> {}", url, resp.status());
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        let state = SseState {
+            resp,
+            decoder: SseDecoder::default(),
+            pending: VecDeque::new(),
+            done: false,
+        };
+        Ok(stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(data) = state.pending.pop_front() {
+                    return Some((parse_event(&data), state));
+                }
+                if state.done {
+                    return None;
+                }
+                match state.resp.chunk().await {
+                    Ok(Some(chunk)) => {
+                        let events = state.decoder.push(&chunk);
+                        state.pending.extend(events);
+                    }
+                    Ok(None) => {
+                        state.done = true;
+                        let events = state.decoder.finish();
+                        state.pending.extend(events);
+                    }
+                    Err(e) => {
+                        state.done = true;
+                        return Some((Err(e.into()), state));
+                    }
+                }
+            }
+        }))
+    }
+}
+
+struct SseState {
+    resp: reqwest::Response,
+    decoder: SseDecoder,
+    pending: VecDeque<String>,
+    done: bool,
+}
+
+/// Turn the `data` of one event into a response, or the error it reports.
+fn parse_event(data: &str) -> Result<GenerateResponse, TrustonError> {
+    let value: Value =
+        serde_json::from_str(data).map_err(|e| TrustonError::ParseError(e.to_string()))?;
+    if let Some(error) = value.get("error").and_then(Value::as_str) {
+        return Err(TrustonError::InferenceError(error.to_string()));
+    }
+    serde_json::from_value(value).map_err(|e| TrustonError::ParseError(e.to_string()))
+}
+
+/// Incremental parser of a `text/event-stream` body, keeping only `data` fields.
+#[derive(Debug, Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Feed a chunk of the body and return the data of every completed event.
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(self.data.join("\n"));
+                    self.data.clear();
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                self.data.push(value.strip_prefix(' ').unwrap_or(value).to_string());
+            }
+            // other fields (`event:`, `id:`, comments) are not used by Triton
+        }
+        events
+    }
+
+    /// Flush the last event of a body that doesn't end with a blank line.
+    fn finish(&mut self) -> Vec<String> {
+        let mut events = self.push(b"\n");
+        if !self.data.is_empty() {
+            events.push(self.data.join("\n"));
+            self.data.clear();
+        }
+        events
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_serialization() {
+        let request = GenerateRequest::new("hi")
+            .with_top_k(5)
+            .with_input("beam_width", 1)
+            .with_parameter("stream", true);
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "text_input": "hi",
+                "top_k": 5,
+                "beam_width": 1,
+                "parameters": {"stream": true}
+            })
+        );
+    }
+
+    #[test]
+    fn test_sse_decoder_handles_split_chunks() {
+        let mut decoder = SseDecoder::default();
+        assert!(decoder.push(b"data: {\"text_output\":\"a\"}\r\n").is_empty());
+        assert_eq!(decoder.push(b"\r\ndata: {\"text_out"), vec![r#"{"text_output":"a"}"#]);
+        assert!(decoder.push(b"put\":\"b\"}").is_empty());
+        assert_eq!(decoder.finish(), vec![r#"{"text_output":"b"}"#]);
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let chunk = parse_event(r#"{"model_name":"llm","text_output":"x","seq":1}"#).unwrap();
+        assert_eq!(chunk.text_output, "x");
+        assert_eq!(chunk.outputs["seq"], 1);
+
+        assert!(matches!(
+            parse_event(r#"{"error":"boom"}"#),
+            Err(TrustonError::InferenceError(msg)) if msg == "boom"
+        ));
+    }
+}
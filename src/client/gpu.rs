@@ -0,0 +1,472 @@
+//! GPU image preprocessing (experimental, requires the `wgpu` feature).
+//!
+//! Resizing and normalizing image batches is often the most expensive part
+//! of a vision request on small edge machines. [`GpuPreprocessor`] runs both
+//! in a single compute shader on whatever GPU `wgpu` finds (Vulkan, Metal,
+//! DX12 or GL) and returns a tensor ready to be sent to Triton, in `FP32`, or
+//! `FP16` with the `half` feature.
+//!
+//! Images are 8-bit, interleaved (`HWC`) pixels, e.g. RGB as decoded by most
+//! image libraries. Resizing is bilinear with half-pixel centers, like
+//! OpenCV's `INTER_LINEAR`.
+
+use std::sync::mpsc;
+
+use wgpu::util::DeviceExt;
+
+pub use crate::client::io::TensorLayout;
+use crate::client::io::{DataType, InferInput};
+use crate::utils::errors::TrustonError;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+const SHADER: &str = r#"
+struct Params {
+    in_width: u32,
+    in_height: u32,
+    out_width: u32,
+    out_height: u32,
+    channels: u32,
+    batch: u32,
+    nchw: u32,
+    _pad: u32,
+    mean: vec4<f32>,
+    inv_std: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> pixels: array<u32>;
+@group(0) @binding(2) var<storage, read_write> tensor: array<f32>;
+
+fn pixel(n: u32, y: u32, x: u32, c: u32) -> f32 {
+    let i = ((n * params.in_height + y) * params.in_width + x) * params.channels + c;
+    return f32((pixels[i / 4u] >> ((i % 4u) * 8u)) & 0xffu);
+}
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let p = id.x + id.y * groups.x * 64u;
+    if (p >= params.batch * params.out_height * params.out_width) {
+        return;
+    }
+    let x = p % params.out_width;
+    let y = (p / params.out_width) % params.out_height;
+    let n = p / (params.out_width * params.out_height);
+
+    let sx = max((f32(x) + 0.5) * f32(params.in_width) / f32(params.out_width) - 0.5, 0.0);
+    let sy = max((f32(y) + 0.5) * f32(params.in_height) / f32(params.out_height) - 0.5, 0.0);
+    let x0 = min(u32(sx), params.in_width - 1u);
+    let y0 = min(u32(sy), params.in_height - 1u);
+    let x1 = min(x0 + 1u, params.in_width - 1u);
+    let y1 = min(y0 + 1u, params.in_height - 1u);
+    let fx = sx - f32(x0);
+    let fy = sy - f32(y0);
+
+    for (var c = 0u; c < params.channels; c++) {
+        let top = mix(pixel(n, y0, x0, c), pixel(n, y0, x1, c), fx);
+        let bottom = mix(pixel(n, y1, x0, c), pixel(n, y1, x1, c), fx);
+        let value = (mix(top, bottom, fy) / 255.0 - params.mean[c]) * params.inv_std[c];
+
+        var o: u32;
+        if (params.nchw == 1u) {
+            o = ((n * params.channels + c) * params.out_height + y) * params.out_width + x;
+        } else {
+            o = ((n * params.out_height + y) * params.out_width + x) * params.channels + c;
+        }
+        tensor[o] = value;
+    }
+}
+"#;
+
+/// Target size and normalization of a preprocessing run.
+///
+/// Pixels are scaled to `[0, 1]`, then normalized per channel as
+/// `(value - mean) / std`.
+///
+/// # Example
+/// ```
+/// use truston::client::gpu::{Preprocess, TensorLayout};
+///
+/// // ImageNet normalization at 224x224
+/// let preprocess = Preprocess::new(224, 224)
+///     .with_normalization(vec![0.485, 0.456, 0.406], vec![0.229, 0.224, 0.225])
+///     .with_layout(TensorLayout::Nchw);
+/// assert_eq!(preprocess.width, 224);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preprocess {
+    pub width: u32,
+    pub height: u32,
+    /// Per-channel mean; empty for none.
+    pub mean: Vec<f32>,
+    /// Per-channel standard deviation; empty for none.
+    pub std: Vec<f32>,
+    pub layout: TensorLayout,
+    /// Produce an `FP16` tensor instead of `FP32`.
+    #[cfg(feature = "half")]
+    pub half_precision: bool,
+}
+
+impl Preprocess {
+    /// Resize to `width` x `height`, scale to `[0, 1]`, no normalization, `NCHW`.
+    pub fn new(width: u32, height: u32) -> Self {
+        Preprocess {
+            width,
+            height,
+            mean: Vec::new(),
+            std: Vec::new(),
+            layout: TensorLayout::default(),
+            #[cfg(feature = "half")]
+            half_precision: false,
+        }
+    }
+
+    pub fn with_normalization(mut self, mean: Vec<f32>, std: Vec<f32>) -> Self {
+        self.mean = mean;
+        self.std = std;
+        self
+    }
+
+    pub fn with_layout(mut self, layout: TensorLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Produce an `FP16` tensor, for models deployed in half precision.
+    #[cfg(feature = "half")]
+    pub fn with_half_precision(mut self) -> Self {
+        self.half_precision = true;
+        self
+    }
+}
+
+/// A batch of 8-bit images of the same size, with interleaved channels.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageBatch<'a> {
+    /// `batch * height * width * channels` bytes.
+    pub pixels: &'a [u8],
+    pub batch: u32,
+    pub width: u32,
+    pub height: u32,
+    /// `1` to `4`.
+    pub channels: u32,
+}
+
+/// Resizes and normalizes image batches on the GPU.
+///
+/// Creating a preprocessor selects the adapter and compiles the shader, so
+/// it should be created once and reused.
+///
+/// # Example
+/// ```no_run
+/// use truston::client::gpu::{GpuPreprocessor, ImageBatch, Preprocess};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let gpu = GpuPreprocessor::new().await?;
+/// let frames = vec![0u8; 2 * 480 * 640 * 3];
+/// let images = ImageBatch { pixels: &frames, batch: 2, width: 640, height: 480, channels: 3 };
+///
+/// let input = gpu.preprocess("images", &images, &Preprocess::new(224, 224))?;
+/// assert_eq!(input.input_shape, vec![2, 3, 224, 224]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GpuPreprocessor {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuPreprocessor {
+    /// Use the default GPU adapter.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::GpuError`] if no adapter or device is available.
+    pub async fn new() -> Result<Self, TrustonError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(gpu_error)?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .await
+            .map_err(gpu_error)?;
+
+        tracing::info!("gpu preprocessor: using {}", adapter.get_info().name);
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("truston preprocess"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("truston preprocess"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        Ok(GpuPreprocessor { device, queue, pipeline })
+    }
+
+    /// Resize and normalize `images` into an `FP32` (or `FP16`, see
+    /// [`Preprocess`]) input called `name`.
+    ///
+    /// Blocks the current thread until the GPU is done.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if `images` or `preprocess`
+    /// are inconsistent, and [`TrustonError::GpuError`] if the GPU fails or
+    /// the batch does not fit in a storage buffer of the device.
+    pub fn preprocess(
+        &self,
+        name: impl Into<String>,
+        images: &ImageBatch<'_>,
+        preprocess: &Preprocess,
+    ) -> Result<InferInput, TrustonError> {
+        let params = params_bytes(images, preprocess)?;
+        let shape = output_shape(images, preprocess);
+        let (invocations, size) = buffer_sizes(images, preprocess, &self.device.limits())?;
+
+        // the shader reads pixels as u32 words
+        let mut pixels = images.pixels.to_vec();
+        pixels.resize(images.pixels.len().next_multiple_of(4), 0);
+
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("truston params"),
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let pixels = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("truston pixels"),
+            contents: &pixels,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let tensor = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("truston tensor"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("truston staging"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("truston preprocess"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: pixels.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: tensor.as_entire_binding() },
+            ],
+        });
+
+        let (x, y) = dispatch_size(invocations);
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(x, y, 1);
+        }
+        encoder.copy_buffer_to_buffer(&tensor, 0, &staging, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait).map_err(gpu_error)?;
+        receiver
+            .recv()
+            .map_err(gpu_error)?
+            .map_err(gpu_error)?;
+
+        let data: Vec<f32> = slice
+            .get_mapped_range()
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        staging.unmap();
+
+        #[cfg(feature = "half")]
+        if preprocess.half_precision {
+            let data = data.into_iter().map(half::f16::from_f32).collect();
+            return Ok(InferInput::new(name.into(), shape, DataType::F16(data)));
+        }
+        Ok(InferInput::new(name.into(), shape, DataType::F32(data)))
+    }
+}
+
+fn gpu_error(e: impl std::fmt::Display) -> TrustonError {
+    TrustonError::GpuError(e.to_string())
+}
+
+fn output_shape(images: &ImageBatch<'_>, preprocess: &Preprocess) -> Vec<usize> {
+    let (n, c) = (images.batch as usize, images.channels as usize);
+    let (h, w) = (preprocess.height as usize, preprocess.width as usize);
+    match preprocess.layout {
+        TensorLayout::Nchw => vec![n, c, h, w],
+        TensorLayout::Nhwc => vec![n, h, w, c],
+    }
+}
+
+/// Number of shader invocations (one per output pixel) and size in bytes of
+/// the output tensor, checked against the storage buffer limits of the device.
+fn buffer_sizes(
+    images: &ImageBatch<'_>,
+    preprocess: &Preprocess,
+    limits: &wgpu::Limits,
+) -> Result<(u32, u64), TrustonError> {
+    let limit = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+    let too_large = |what: &str| {
+        TrustonError::GpuError(format!(
+            "{} exceeds the device limit of {} bytes per buffer, split the batch",
+            what, limit
+        ))
+    };
+
+    let pixels = (images.pixels.len() as u64).next_multiple_of(4);
+    if pixels > limit {
+        return Err(too_large(&format!("{} bytes of pixels", pixels)));
+    }
+    let invocations = [images.batch, preprocess.width, preprocess.height]
+        .iter()
+        .try_fold(1u64, |acc, &d| acc.checked_mul(d as u64));
+    let size = invocations
+        .and_then(|n| n.checked_mul(images.channels as u64 * size_of::<f32>() as u64))
+        .filter(|&size| size <= limit)
+        .ok_or_else(|| too_large("the output tensor"))?;
+    // the limit is a u32, so the invocations fit one too
+    Ok((invocations.unwrap_or_default() as u32, size))
+}
+
+/// Workgroups to dispatch for `invocations`, spread over two dimensions
+/// to stay under the per-dimension limit.
+fn dispatch_size(invocations: u32) -> (u32, u32) {
+    let groups = invocations.div_ceil(WORKGROUP_SIZE).max(1);
+    let x = groups.min(u16::MAX as u32);
+    (x, groups.div_ceil(x))
+}
+
+/// Validate the request and encode the shader's `Params` uniform.
+fn params_bytes(images: &ImageBatch<'_>, preprocess: &Preprocess) -> Result<Vec<u8>, TrustonError> {
+    let invalid = |msg: String| Err(TrustonError::InferenceError(msg));
+    let channels = images.channels as usize;
+
+    if !(1..=4).contains(&channels) {
+        return invalid(format!("{} channels, expected 1 to 4", channels));
+    }
+    let sizes = [images.batch, images.width, images.height, preprocess.width, preprocess.height];
+    if sizes.contains(&0) {
+        return invalid("image and target sizes must not be zero".to_string());
+    }
+    let expected = [images.batch, images.height, images.width, images.channels]
+        .iter()
+        .map(|&d| d as usize)
+        .product::<usize>();
+    if images.pixels.len() != expected {
+        return invalid(format!("{} bytes of pixels, expected {}", images.pixels.len(), expected));
+    }
+
+    let mut mean = [0.0f32; 4];
+    let mut inv_std = [1.0f32; 4];
+    if !preprocess.mean.is_empty() || !preprocess.std.is_empty() {
+        if preprocess.mean.len() != channels || preprocess.std.len() != channels {
+            return invalid(format!("mean and std need {} values", channels));
+        }
+        if preprocess.std.contains(&0.0) {
+            return invalid("std must not be zero".to_string());
+        }
+        for c in 0..channels {
+            mean[c] = preprocess.mean[c];
+            inv_std[c] = 1.0 / preprocess.std[c];
+        }
+    }
+
+    let nchw = (preprocess.layout == TensorLayout::Nchw) as u32;
+    let words = [
+        images.width,
+        images.height,
+        preprocess.width,
+        preprocess.height,
+        images.channels,
+        images.batch,
+        nchw,
+        0,
+    ];
+    let mut bytes = Vec::with_capacity(64);
+    words.iter().for_each(|w| bytes.extend_from_slice(&w.to_le_bytes()));
+    mean.iter().chain(&inv_std).for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+    Ok(bytes)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn images(pixels: &[u8], width: u32, height: u32) -> ImageBatch<'_> {
+        ImageBatch { pixels, batch: 1, width, height, channels: 1 }
+    }
+
+    #[test]
+    fn test_params_validation() {
+        let pixels = [0u8; 12];
+        assert_eq!(params_bytes(&images(&pixels, 4, 3), &Preprocess::new(2, 2)).unwrap().len(), 64);
+        assert!(params_bytes(&images(&pixels, 4, 4), &Preprocess::new(2, 2)).is_err());
+        assert!(params_bytes(&images(&pixels, 4, 3), &Preprocess::new(0, 2)).is_err());
+
+        let normalized = Preprocess::new(2, 2).with_normalization(vec![0.5, 0.5], vec![0.5, 0.5]);
+        assert!(params_bytes(&images(&pixels, 4, 3), &normalized).is_err());
+    }
+
+    #[test]
+    fn test_buffer_sizes_respect_device_limits() {
+        let limits = wgpu::Limits::default();
+        let pixels = [0u8; 12];
+        let sizes = buffer_sizes(&images(&pixels, 4, 3), &Preprocess::new(2, 2), &limits);
+        assert_eq!(sizes.unwrap(), (4, 16));
+
+        // 32 x 640 x 640 RGB in f32 is 150 MiB, over the default 128 MiB
+        let rgb = ImageBatch { batch: 32, channels: 3, ..images(&pixels, 2, 2) };
+        let sizes = buffer_sizes(&rgb, &Preprocess::new(640, 640), &limits);
+        assert!(matches!(sizes, Err(TrustonError::GpuError(_))));
+
+        // overflows u32 and u64 arithmetic alike
+        let huge = ImageBatch { batch: u32::MAX, channels: 4, ..images(&pixels, 2, 2) };
+        let sizes = buffer_sizes(&huge, &Preprocess::new(u32::MAX, u32::MAX), &limits);
+        assert!(matches!(sizes, Err(TrustonError::GpuError(_))));
+    }
+
+    #[test]
+    fn test_dispatch_size_covers_invocations() {
+        assert_eq!(dispatch_size(1), (1, 1));
+        assert_eq!(dispatch_size(65), (2, 1));
+        let (x, y) = dispatch_size(8 * 1024 * 1024);
+        assert!(x <= u16::MAX as u32 && x * y * WORKGROUP_SIZE >= 8 * 1024 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_preprocess_on_available_gpu() {
+        // most CI machines have no GPU
+        let Ok(gpu) = GpuPreprocessor::new().await else {
+            return;
+        };
+        let pixels = [0u8, 255, 255, 0];
+        let preprocess = Preprocess::new(2, 2).with_normalization(vec![0.5], vec![0.5]);
+        let input = gpu.preprocess("x", &images(&pixels, 2, 2), &preprocess).unwrap();
+
+        assert_eq!(input.input_shape, vec![1, 1, 2, 2]);
+        assert_eq!(input.input_data.as_f32_vec(), Some(vec![-1.0, 1.0, 1.0, -1.0]));
+    }
+}
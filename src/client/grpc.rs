@@ -0,0 +1,632 @@
+//! gRPC client for Triton Inference Server (requires the `grpc` feature).
+//!
+//! The REST protocol answers every inference request with exactly one
+//! response. Decoupled models (text-to-speech, LLMs generating token by
+//! token, ...) may send any number of responses per request, which is only
+//! possible over the bidirectional `ModelStreamInfer` RPC of the gRPC
+//! protocol. [`TritonGrpcClient::stream_infer`] exposes it as a
+//! [`Stream`] of [`InferResults`].
+//!
+//! Tensors are exchanged in the raw binary layout (see [`crate::client::binary`]).
+
+use std::collections::HashMap;
+use std::time::{Instant, SystemTime};
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt, TryStreamExt, stream};
+use serde_json::{Map, Value};
+use tonic::client::Grpc;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, Endpoint};
+
+use crate::client::binary::{decode_tensor, encode_tensor};
+use crate::client::dtype::TritonDtype;
+use crate::client::http::TritonClient;
+use crate::client::io::{
+    DataType, InferInput, InferOutput, InferRequestedOutput, InferResults, Provenance,
+    element_count,
+};
+use crate::client::metadata::{ModelMetadata, TensorMetadata};
+use crate::client::options::{CancellationToken, InferOptions};
+use crate::utils::capabilities;
+use crate::utils::errors::TrustonError;
+
+const SERVER_LIVE: &str = "/inference.GRPCInferenceService/ServerLive";
+const SERVER_READY: &str = "/inference.GRPCInferenceService/ServerReady";
+const MODEL_READY: &str = "/inference.GRPCInferenceService/ModelReady";
+const MODEL_METADATA: &str = "/inference.GRPCInferenceService/ModelMetadata";
+const MODEL_INFER: &str = "/inference.GRPCInferenceService/ModelInfer";
+const MODEL_STREAM_INFER: &str = "/inference.GRPCInferenceService/ModelStreamInfer";
+
+/// Client for the gRPC endpoint of Triton (port `8001` by default).
+///
+/// # Example
+/// ```no_run
+/// use futures::StreamExt;
+/// use truston::client::grpc::TritonGrpcClient;
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::options::InferOptions;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = TritonGrpcClient::connect("http://localhost:8001").await?;
+/// let prompt = InferInput::new(
+///     "text_input".into(),
+///     vec![1],
+///     DataType::String(vec!["Once upon a time".into()]),
+/// );
+///
+/// let mut responses = client.stream_infer(vec![prompt], "llm", &InferOptions::new());
+/// while let Some(results) = responses.next().await {
+///     println!("{:?}", results?.outputs[0].data);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TritonGrpcClient {
+    endpoint: String,
+    channel: Channel,
+}
+
+impl TritonGrpcClient {
+    /// Connect to the gRPC endpoint at `endpoint` (e.g. `http://localhost:8001`).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ConfigError`] for an invalid endpoint URI and
+    /// [`TrustonError::GrpcError`] if the connection cannot be established.
+    pub async fn connect(endpoint: impl Into<String>) -> Result<Self, TrustonError> {
+        let endpoint = endpoint.into();
+        let channel = Endpoint::from_shared(endpoint.clone())
+            .map_err(|e| TrustonError::ConfigError(format!("invalid endpoint: {}", e)))?
+            .user_agent(capabilities::default_user_agent())
+            .map_err(|e| TrustonError::ConfigError(format!("invalid user agent: {}", e)))?
+            .connect()
+            .await
+            .map_err(transport_error)?;
+        Ok(TritonGrpcClient { endpoint, channel })
+    }
+
+    /// Send a unary call to `path`.
+    async fn unary<Req, Resp>(&self, path: &'static str, request: Req) -> Result<Resp, TrustonError>
+    where
+        Req: prost::Message + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        let mut grpc = Grpc::new(self.channel.clone());
+        grpc.ready().await.map_err(transport_error)?;
+
+        let codec = ProstCodec::<Req, Resp>::default();
+        let response = grpc
+            .unary(tonic::Request::new(request), PathAndQuery::from_static(path), codec)
+            .await
+            .map_err(status_error)?;
+        Ok(response.into_inner())
+    }
+
+    /// Send one inference request and stream back every response the model produces.
+    ///
+    /// The stream ends once the server has sent the last response for the
+    /// request. A response carrying an error ends the stream with
+    /// [`TrustonError::InferenceError`]. The `elapsed` time in the provenance
+    /// of each response is measured from the start of the call.
+    ///
+    /// Only `request_id`, `model_version`, `priority`, `timeout_us`,
+    /// `parameters`, `outputs` and `cancellation` of `options` are used.
+    /// Cancelling ends the stream with [`TrustonError::Cancelled`] and drops
+    /// the call.
+    ///
+    /// The stream is boxed and pinned, so it can be polled with
+    /// [`StreamExt::next`] as is.
+    pub fn stream_infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> BoxStream<'static, Result<InferResults, TrustonError>> {
+        let request = build_request(inputs, model_name, options);
+        let channel = self.channel.clone();
+        let endpoint = self.endpoint.clone();
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+        let cancellation = options.cancellation.clone();
+
+        let responses = stream::once(async move {
+            let request = request?;
+            let mut grpc = Grpc::new(channel);
+            grpc.ready().await.map_err(transport_error)?;
+
+            let codec = ProstCodec::<ModelInferRequest, ModelStreamInferResponse>::default();
+            let path = PathAndQuery::from_static(MODEL_STREAM_INFER);
+            let response = grpc
+                .streaming(tonic::Request::new(stream::iter(vec![request])), path, codec)
+                .await
+                .map_err(status_error)?;
+
+            tracing::info!("stream_infer: {} -> streaming", MODEL_STREAM_INFER);
+
+            let responses = response.into_inner().map(move |item| {
+                let mut results = decode_stream_response(item.map_err(status_error)?)?;
+                results.provenance.endpoint = endpoint.clone();
+                results.provenance.attempts = 1;
+                results.provenance.started_at = started_at;
+                results.provenance.elapsed = started.elapsed();
+                Ok(results)
+            });
+            Ok::<_, TrustonError>(responses)
+        })
+        .try_flatten();
+        match cancellation {
+            Some(token) => cancellable(responses, token).boxed(),
+            None => responses.boxed(),
+        }
+    }
+}
+
+/// `responses`, ended with [`TrustonError::Cancelled`] as soon as `token` is cancelled.
+fn cancellable<S>(
+    responses: S,
+    token: CancellationToken,
+) -> impl Stream<Item = Result<InferResults, TrustonError>> + Send + 'static
+where
+    S: Stream<Item = Result<InferResults, TrustonError>> + Send + 'static,
+{
+    stream::unfold(Some((Box::pin(responses), token)), |state| async move {
+        let (mut responses, token) = state?;
+        tokio::select! {
+            biased;
+            _ = token.cancelled() => Some((Err(TrustonError::Cancelled), None)),
+            item = responses.next() => Some((item?, Some((responses, token)))),
+        }
+    })
+}
+
+#[cfg_attr(not(feature = "local"), async_trait)]
+#[cfg_attr(feature = "local", async_trait(?Send))]
+impl TritonClient for TritonGrpcClient {
+    async fn is_server_live(&self) -> Result<bool, TrustonError> {
+        let response: ServerLiveResponse = self.unary(SERVER_LIVE, ServerLiveRequest {}).await?;
+        tracing::info!("is_server_live: {} -> {}", SERVER_LIVE, response.live);
+        Ok(response.live)
+    }
+
+    async fn is_server_ready(&self) -> Result<bool, TrustonError> {
+        let response: ServerReadyResponse = self.unary(SERVER_READY, ServerReadyRequest {}).await?;
+        tracing::info!("is_server_ready: {} -> {}", SERVER_READY, response.ready);
+        Ok(response.ready)
+    }
+
+    async fn is_model_ready(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<bool, TrustonError> {
+        let request = ModelReadyRequest {
+            name: model_name.to_string(),
+            version: version.unwrap_or_default().to_string(),
+        };
+        let response: ModelReadyResponse = self.unary(MODEL_READY, request).await?;
+        tracing::info!("is_model_ready: {} {} -> {}", MODEL_READY, model_name, response.ready);
+        Ok(response.ready)
+    }
+
+    async fn model_metadata(&self, model_name: &str) -> Result<ModelMetadata, TrustonError> {
+        let request = ModelMetadataRequest { name: model_name.to_string(), version: String::new() };
+        let response: ModelMetadataResponse = self.unary(MODEL_METADATA, request).await?;
+        let tensors = |tensors: Vec<model_metadata_response::TensorMetadata>| {
+            tensors
+                .into_iter()
+                .map(|tensor| {
+                    Ok(TensorMetadata {
+                        name: tensor.name,
+                        datatype: TritonDtype::from_name(&tensor.datatype),
+                        shape: tensor.shape,
+                    })
+                })
+                .collect::<Result<Vec<_>, TrustonError>>()
+        };
+        Ok(ModelMetadata {
+            name: response.name,
+            versions: response.versions,
+            platform: response.platform,
+            inputs: tensors(response.inputs)?,
+            outputs: tensors(response.outputs)?,
+        })
+    }
+
+    /// Only the options used by [`stream_infer`](TritonGrpcClient::stream_infer) apply.
+    async fn infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<InferResults, TrustonError> {
+        let request = build_request(inputs, model_name, options)?;
+        let started_at = SystemTime::now();
+        let started = Instant::now();
+        let call = self.unary::<_, ModelInferResponse>(MODEL_INFER, request);
+        let response = match &options.cancellation {
+            Some(token) => tokio::select! {
+                biased;
+                _ = token.cancelled() => return Err(TrustonError::Cancelled),
+                response = call => response?,
+            },
+            None => call.await?,
+        };
+        tracing::info!("infer: {} {} -> ok", MODEL_INFER, model_name);
+
+        let mut results = decode_response(response)?;
+        results.provenance.endpoint = self.endpoint.clone();
+        results.provenance.attempts = 1;
+        results.provenance.started_at = started_at;
+        results.provenance.elapsed = started.elapsed();
+        Ok(results)
+    }
+}
+
+fn transport_error(e: impl std::fmt::Display) -> TrustonError {
+    TrustonError::GrpcError {
+        code: tonic::Code::Unavailable as i32,
+        message: e.to_string(),
+    }
+}
+
+fn status_error(status: tonic::Status) -> TrustonError {
+    TrustonError::GrpcError {
+        code: status.code() as i32,
+        message: status.message().to_string(),
+    }
+}
+
+fn build_request(
+    inputs: Vec<InferInput>,
+    model_name: &str,
+    options: &InferOptions,
+) -> Result<ModelInferRequest, TrustonError> {
+    let mut tensors = Vec::with_capacity(inputs.len());
+    let mut raw_input_contents = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        input.check_shape()?;
+        raw_input_contents.push(encode_tensor(&input.input_data)?);
+        tensors.push(model_infer_request::InferInputTensor {
+            name: input.input_name,
+            datatype: input.input_data.get_type_str().to_string(),
+            shape: input.input_shape.iter().map(|&d| d as i64).collect(),
+            parameters: HashMap::new(),
+        });
+    }
+
+    let outputs = options
+        .outputs
+        .iter()
+        .flatten()
+        .map(|output: &InferRequestedOutput| model_infer_request::InferRequestedOutputTensor {
+            name: output.name.clone(),
+            parameters: to_grpc_parameters(&output.parameters),
+        })
+        .collect();
+
+    Ok(ModelInferRequest {
+        model_name: model_name.to_string(),
+        model_version: options.model_version.clone().unwrap_or_default(),
+        id: options.request_id.clone().unwrap_or_default(),
+        parameters: to_grpc_parameters(&options.request_parameters()),
+        inputs: tensors,
+        outputs,
+        raw_input_contents,
+    })
+}
+
+/// Convert JSON parameters to their gRPC counterparts; arrays and objects
+/// are sent as their JSON string.
+fn to_grpc_parameters(parameters: &Map<String, Value>) -> HashMap<String, InferParameter> {
+    use infer_parameter::ParameterChoice;
+
+    parameters
+        .iter()
+        .map(|(key, value)| {
+            let choice = match value {
+                Value::Bool(b) => ParameterChoice::Bool(*b),
+                Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+                    (Some(i), _) => ParameterChoice::Int64(i),
+                    (None, Some(u)) => ParameterChoice::Uint64(u),
+                    _ => ParameterChoice::Double(n.as_f64().unwrap_or_default()),
+                },
+                Value::String(s) => ParameterChoice::String(s.clone()),
+                other => ParameterChoice::String(other.to_string()),
+            };
+            let parameter = InferParameter { parameter_choice: Some(choice) };
+            (key.clone(), parameter)
+        })
+        .collect()
+}
+
+fn decode_stream_response(item: ModelStreamInferResponse) -> Result<InferResults, TrustonError> {
+    if !item.error_message.is_empty() {
+        return Err(TrustonError::InferenceError(item.error_message));
+    }
+    decode_response(item.infer_response.unwrap_or_default())
+}
+
+fn decode_response(response: ModelInferResponse) -> Result<InferResults, TrustonError> {
+    let mut outputs = Vec::with_capacity(response.outputs.len());
+    for (i, output) in response.outputs.into_iter().enumerate() {
+        let raw = response.raw_output_contents.get(i).ok_or_else(|| {
+            TrustonError::ParseError(format!("missing raw contents for output {}", output.name))
+        })?;
+        let shape = output
+            .shape
+            .iter()
+            .map(|&d| usize::try_from(d))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                TrustonError::InvalidShape(format!(
+                    "output {} has negative dims {:?}",
+                    output.name, output.shape
+                ))
+            })?;
+        element_count(&output.name, &shape)?;
+        let datatype = TritonDtype::from_name(&output.datatype);
+        let data = match datatype {
+            // the bytes as they came, like the JSON of unknown REST outputs
+            TritonDtype::Other(_) => DataType::Raw(raw.as_slice().into()),
+            _ => decode_tensor(&datatype, raw)?,
+        };
+        outputs.push(InferOutput {
+            data,
+            shape,
+            name: output.name,
+            datatype,
+        });
+    }
+
+    let provenance = Provenance {
+        model_name: response.model_name,
+        model_version: response.model_version,
+        ..Default::default()
+    };
+    Ok(InferResults { outputs, provenance })
+}
+
+// Messages of Triton's `grpc_service.proto`, limited to the fields used by this client.
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerLiveRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerLiveResponse {
+    #[prost(bool, tag = "1")]
+    live: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerReadyRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ServerReadyResponse {
+    #[prost(bool, tag = "1")]
+    ready: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelReadyRequest {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    version: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelReadyResponse {
+    #[prost(bool, tag = "1")]
+    ready: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelMetadataRequest {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    version: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelMetadataResponse {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, repeated, tag = "2")]
+    versions: Vec<String>,
+    #[prost(string, tag = "3")]
+    platform: String,
+    #[prost(message, repeated, tag = "4")]
+    inputs: Vec<model_metadata_response::TensorMetadata>,
+    #[prost(message, repeated, tag = "5")]
+    outputs: Vec<model_metadata_response::TensorMetadata>,
+}
+
+mod model_metadata_response {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct TensorMetadata {
+        #[prost(string, tag = "1")]
+        pub(super) name: String,
+        #[prost(string, tag = "2")]
+        pub(super) datatype: String,
+        #[prost(int64, repeated, tag = "3")]
+        pub(super) shape: Vec<i64>,
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct InferParameter {
+    #[prost(oneof = "infer_parameter::ParameterChoice", tags = "1, 2, 3, 4, 5")]
+    parameter_choice: Option<infer_parameter::ParameterChoice>,
+}
+
+mod infer_parameter {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub(super) enum ParameterChoice {
+        #[prost(bool, tag = "1")]
+        Bool(bool),
+        #[prost(int64, tag = "2")]
+        Int64(i64),
+        #[prost(string, tag = "3")]
+        String(String),
+        #[prost(double, tag = "4")]
+        Double(f64),
+        #[prost(uint64, tag = "5")]
+        Uint64(u64),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelInferRequest {
+    #[prost(string, tag = "1")]
+    model_name: String,
+    #[prost(string, tag = "2")]
+    model_version: String,
+    #[prost(string, tag = "3")]
+    id: String,
+    #[prost(map = "string, message", tag = "4")]
+    parameters: HashMap<String, InferParameter>,
+    #[prost(message, repeated, tag = "5")]
+    inputs: Vec<model_infer_request::InferInputTensor>,
+    #[prost(message, repeated, tag = "6")]
+    outputs: Vec<model_infer_request::InferRequestedOutputTensor>,
+    #[prost(bytes = "vec", repeated, tag = "7")]
+    raw_input_contents: Vec<Vec<u8>>,
+}
+
+mod model_infer_request {
+    use std::collections::HashMap;
+
+    use super::InferParameter;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct InferInputTensor {
+        #[prost(string, tag = "1")]
+        pub(super) name: String,
+        #[prost(string, tag = "2")]
+        pub(super) datatype: String,
+        #[prost(int64, repeated, tag = "3")]
+        pub(super) shape: Vec<i64>,
+        #[prost(map = "string, message", tag = "4")]
+        pub(super) parameters: HashMap<String, InferParameter>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct InferRequestedOutputTensor {
+        #[prost(string, tag = "1")]
+        pub(super) name: String,
+        #[prost(map = "string, message", tag = "2")]
+        pub(super) parameters: HashMap<String, InferParameter>,
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelInferResponse {
+    #[prost(string, tag = "1")]
+    model_name: String,
+    #[prost(string, tag = "2")]
+    model_version: String,
+    #[prost(string, tag = "3")]
+    id: String,
+    #[prost(message, repeated, tag = "5")]
+    outputs: Vec<model_infer_response::InferOutputTensor>,
+    #[prost(bytes = "vec", repeated, tag = "6")]
+    raw_output_contents: Vec<Vec<u8>>,
+}
+
+mod model_infer_response {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub(super) struct InferOutputTensor {
+        #[prost(string, tag = "1")]
+        pub(super) name: String,
+        #[prost(string, tag = "2")]
+        pub(super) datatype: String,
+        #[prost(int64, repeated, tag = "3")]
+        pub(super) shape: Vec<i64>,
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct ModelStreamInferResponse {
+    #[prost(string, tag = "1")]
+    error_message: String,
+    #[prost(message, optional, tag = "2")]
+    infer_response: Option<ModelInferResponse>,
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::DataType;
+
+    #[test]
+    fn test_build_request_uses_raw_contents() {
+        let inputs = vec![InferInput::new("x".into(), vec![1, 2], DataType::F32(vec![1.0, 2.0]))];
+        let options = InferOptions::new()
+            .with_request_id("req-1")
+            .with_model_version("3")
+            .with_priority(2)
+            .with_outputs(["y"]);
+        let request = build_request(inputs, "model", &options).unwrap();
+
+        assert_eq!(request.model_version, "3");
+        assert_eq!(request.id, "req-1");
+        assert_eq!(request.inputs[0].datatype, "FP32");
+        assert_eq!(request.inputs[0].shape, vec![1, 2]);
+        assert_eq!(request.raw_input_contents[0].len(), 8);
+        assert_eq!(request.outputs[0].name, "y");
+        assert_eq!(
+            request.parameters["priority"].parameter_choice,
+            Some(infer_parameter::ParameterChoice::Int64(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_stream_ends_with_error() {
+        let token = CancellationToken::new();
+        let pending = stream::pending::<Result<InferResults, TrustonError>>();
+        let mut responses = Box::pin(cancellable(pending, token.clone()));
+        token.cancel();
+        assert!(matches!(responses.next().await, Some(Err(TrustonError::Cancelled))));
+        assert!(responses.next().await.is_none());
+    }
+
+    #[test]
+    fn test_decode_stream_response() {
+        let item = ModelStreamInferResponse {
+            error_message: String::new(),
+            infer_response: Some(ModelInferResponse {
+                outputs: vec![model_infer_response::InferOutputTensor {
+                    name: "text_output".into(),
+                    datatype: "BYTES".into(),
+                    shape: vec![1],
+                }],
+                raw_output_contents: vec![vec![2, 0, 0, 0, b'h', b'i']],
+                model_version: "3".into(),
+                ..Default::default()
+            }),
+        };
+        let results = decode_stream_response(item).unwrap();
+
+        assert_eq!(results.outputs[0].shape, vec![1]);
+        assert_eq!(results.outputs[0].data.as_str_vec(), Some(vec!["hi".to_string()]));
+        assert_eq!(results.provenance.model_version, "3");
+    }
+
+    #[test]
+    fn test_stream_error_message_is_inference_error() {
+        let item = ModelStreamInferResponse {
+            error_message: "model crashed".into(),
+            infer_response: None,
+        };
+        assert!(matches!(
+            decode_stream_response(item),
+            Err(TrustonError::InferenceError(msg)) if msg == "model crashed"
+        ));
+    }
+}
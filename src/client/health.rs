@@ -0,0 +1,373 @@
+//! Health probes of the server and its models.
+//!
+//! Triton has three distinct probes: the server is *live* when it can answer
+//! at all, *ready* when it can serve inferences, and a model is *ready* when
+//! it is loaded. Each probe returns a [`Health`] keeping the raw status and
+//! body of the answer, so a server that answers "not ready" is not confused
+//! with one that cannot be reached (an error).
+//!
+//! A [`HealthMonitor`] polls the probes in the background, e.g. to hold the
+//! startup of a service until its models are loaded and to notice when one
+//! is unloaded.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+use crate::client::http::TritonRestClient;
+use crate::utils::errors::TrustonError;
+
+/// What a [`Health`] was probed for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Probe {
+    /// `GET /v2/health/live`.
+    Live,
+    /// `GET /v2/health/ready`.
+    Ready,
+    /// `GET /v2/models/{model_name}[/versions/{version}]/ready`.
+    ModelReady {
+        model_name: String,
+        version: Option<String>,
+    },
+}
+
+impl fmt::Display for Probe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Probe::Live => f.write_str("server live"),
+            Probe::Ready => f.write_str("server ready"),
+            Probe::ModelReady { model_name, version: None } => {
+                write!(f, "model {} ready", model_name)
+            }
+            Probe::ModelReady { model_name, version: Some(version) } => {
+                write!(f, "model {} version {} ready", model_name, version)
+            }
+        }
+    }
+}
+
+/// Answer of the server to a health probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Health {
+    pub probe: Probe,
+    /// HTTP status of the answer.
+    pub status: u16,
+    /// Body of the answer, usually empty; kept for diagnostics.
+    pub body: String,
+}
+
+impl Health {
+    /// Whether the probe succeeded, i.e. the server answered with a 2xx status.
+    pub fn is_healthy(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// e.g. `server ready: no (503)`.
+impl fmt::Display for Health {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let answer = if self.is_healthy() { "yes" } else { "no" };
+        write!(f, "{}: {} ({})", self.probe, answer, self.status)?;
+        if !self.body.is_empty() {
+            write!(f, ": {}", self.body)?;
+        }
+        Ok(())
+    }
+}
+
+impl TritonRestClient {
+    /// Whether the server is live.
+    ///
+    /// # Errors
+    /// Only when the server cannot be reached; a server answering with an
+    /// error status gives an unhealthy [`Health`].
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let ready = client.server_ready().await?;
+    /// if !ready.is_healthy() {
+    ///     eprintln!("{}", ready);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn server_live(&self) -> Result<Health, TrustonError> {
+        self.health(Probe::Live).await
+    }
+
+    /// Whether the server is ready to serve inferences. See [`server_live`](Self::server_live).
+    pub async fn server_ready(&self) -> Result<Health, TrustonError> {
+        self.health(Probe::Ready).await
+    }
+
+    /// Whether `model_name` (or one of its versions) is loaded and ready.
+    /// See [`server_live`](Self::server_live).
+    pub async fn model_ready(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<Health, TrustonError> {
+        self.health(Probe::ModelReady {
+            model_name: model_name.to_string(),
+            version: version.map(str::to_string),
+        })
+        .await
+    }
+
+    /// Send `probe` to the server. See [`server_live`](Self::server_live).
+    #[tracing::instrument(skip_all, fields(probe = ?probe, status = tracing::field::Empty))]
+    pub async fn health(&self, probe: Probe) -> Result<Health, TrustonError> {
+        let url = match &probe {
+            Probe::Live => format!("{}/v2/health/live", self.base_url),
+            Probe::Ready => format!("{}/v2/health/ready", self.base_url),
+            Probe::ModelReady { model_name, version } => {
+                format!("{}/ready", self.model_url(model_name, version.as_deref()))
+            }
+        };
+
+        let resp = self.send_request(true, || self.http.get(&url)).await?;
+
+        tracing::debug!("health: {} -> {}", url, resp.status());
+
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        Ok(Health { probe, status, body })
+    }
+}
+
+/// Health of the server and the watched models, as of the last poll.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HealthState {
+    pub live: bool,
+    pub ready: bool,
+    /// Readiness of each watched model, in the order they were given.
+    pub models: Vec<(String, bool)>,
+    /// Why the last poll failed, if the server could not be reached; every
+    /// probe then reads as not ready.
+    pub error: Option<String>,
+}
+
+impl HealthState {
+    /// Whether the server is live and ready, and every watched model is ready.
+    pub fn is_ready(&self) -> bool {
+        self.live && self.ready && self.models.iter().all(|(_, ready)| *ready)
+    }
+
+    /// Readiness of `model_name`, if it is watched.
+    pub fn model_ready(&self, model_name: &str) -> Option<bool> {
+        self.models.iter().find(|(name, _)| name == model_name).map(|(_, ready)| *ready)
+    }
+}
+
+/// Polls the liveness and readiness of a server and some of its models.
+///
+/// The state starts as not ready and is updated after each poll; receivers
+/// from [`subscribe`](Self::subscribe) are only notified when it changes.
+/// A poll not answered within the period fails like an unreachable server
+/// (see [`HealthState::error`]).
+/// The polling task stops once the monitor and every receiver are dropped.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// # use truston::client::http::TritonRestClient;
+/// use truston::client::health::HealthMonitor;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Arc::new(TritonRestClient::new("http://localhost:8000"));
+/// let monitor = HealthMonitor::new(client, ["bert"], Duration::from_secs(2));
+///
+/// // hold the startup until the model is loaded
+/// monitor.wait_until_ready(Duration::from_secs(120)).await?;
+///
+/// let mut changes = monitor.subscribe();
+/// tokio::spawn(async move {
+///     while changes.changed().await.is_ok() {
+///         if !changes.borrow().is_ready() {
+///             eprintln!("not ready anymore: {:?}", *changes.borrow());
+///         }
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct HealthMonitor {
+    receiver: watch::Receiver<HealthState>,
+}
+
+impl HealthMonitor {
+    /// Start polling the server of `client` and `models` every `period`.
+    /// Must be called within a Tokio runtime.
+    ///
+    /// # Panics
+    /// If `period` is zero.
+    pub fn new<S: Into<String>>(
+        client: Arc<TritonRestClient>,
+        models: impl IntoIterator<Item = S>,
+        period: Duration,
+    ) -> Self {
+        let models: Vec<String> = models.into_iter().map(Into::into).collect();
+        let (sender, receiver) = watch::channel(HealthState {
+            models: models.iter().map(|model| (model.clone(), false)).collect(),
+            ..HealthState::default()
+        });
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = sender.closed() => return,
+                    _ = interval.tick() => {}
+                }
+                let state = poll(&client, &models, period).await;
+                sender.send_if_modified(|current| {
+                    let changed = *current != state;
+                    if changed {
+                        let (was, is) = (current.is_ready(), state.is_ready());
+                        tracing::info!("health: ready {} -> {}", was, is);
+                        *current = state;
+                    }
+                    changed
+                });
+            }
+        });
+        HealthMonitor { receiver }
+    }
+
+    /// State as of the last poll.
+    pub fn state(&self) -> HealthState {
+        self.receiver.borrow().clone()
+    }
+
+    /// A receiver notified whenever the state changes.
+    pub fn subscribe(&self) -> watch::Receiver<HealthState> {
+        self.receiver.clone()
+    }
+
+    /// Wait until [`HealthState::is_ready`], and return that state.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::DeadlineExceeded`] if it is not ready within `timeout`.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<HealthState, TrustonError> {
+        let mut receiver = self.receiver.clone();
+        match tokio::time::timeout(timeout, receiver.wait_for(HealthState::is_ready)).await {
+            Ok(Ok(state)) => Ok(state.clone()),
+            // only if the polling task panicked
+            Ok(Err(_)) => Err(TrustonError::InferenceError("health monitor stopped".into())),
+            Err(_) => {
+                tracing::warn!("not ready after {:?}: {:?}", timeout, *self.receiver.borrow());
+                Err(TrustonError::DeadlineExceeded)
+            }
+        }
+    }
+}
+
+/// Probe the server, then each model. A poll taking longer than `period`
+/// fails, so a server that stopped answering reads as not ready.
+async fn poll(client: &TritonRestClient, models: &[String], period: Duration) -> HealthState {
+    let probes = async {
+        let live = client.server_live().await?.is_healthy();
+        let ready = client.server_ready().await?.is_healthy();
+        let mut ready_models = Vec::with_capacity(models.len());
+        for model in models {
+            let health = client.model_ready(model, None).await?;
+            ready_models.push((model.clone(), health.is_healthy()));
+        }
+        Ok::<_, TrustonError>(HealthState { live, ready, models: ready_models, error: None })
+    };
+    let error = match tokio::time::timeout(period, probes).await {
+        Ok(Ok(state)) => return state,
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("health probes got no answer within {:?}", period),
+    };
+    HealthState {
+        models: models.iter().map(|model| (model.clone(), false)).collect(),
+        error: Some(error),
+        ..HealthState::default()
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::test_server::serve;
+
+    #[tokio::test]
+    async fn test_probes() {
+        let (base_url, server) = serve(vec![
+            (200, ""),
+            (400, r#"{"error":"model not ready"}"#),
+        ])
+        .await;
+        let client = TritonRestClient::new(&base_url);
+
+        let live = client.server_live().await.unwrap();
+        assert!(live.is_healthy());
+        assert_eq!(live.probe, Probe::Live);
+
+        let model = client.model_ready("m", Some("2")).await.unwrap();
+        assert!(!model.is_healthy());
+        assert_eq!(model.status, 400);
+        let expected = r#"model m version 2 ready: no (400): {"error":"model not ready"}"#;
+        assert_eq!(model.to_string(), expected);
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].line(), "GET /v2/health/live HTTP/1.1");
+        assert_eq!(requests[1].line(), "GET /v2/models/m/versions/2/ready HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn test_monitor_waits_for_models() {
+        let (base_url, server) = serve(vec![
+            (200, ""),
+            (200, ""),
+            (400, r#"{"error":"loading"}"#),
+            (200, ""),
+            (200, ""),
+            (200, ""),
+        ])
+        .await;
+        let client = Arc::new(TritonRestClient::new(&base_url));
+        let monitor = HealthMonitor::new(client, ["m"], Duration::from_millis(100));
+        assert_eq!(monitor.state().model_ready("m"), Some(false));
+
+        let state = monitor.wait_until_ready(Duration::from_secs(5)).await.unwrap();
+        assert_eq!(state.models, vec![("m".to_string(), true)]);
+        assert_eq!(state.error, None);
+        let requests = server.await.unwrap();
+        assert_eq!(requests[2].line(), "GET /v2/models/m/ready HTTP/1.1");
+
+        let unreachable = Arc::new(TritonRestClient::new("http://127.0.0.1:1"));
+        let monitor = HealthMonitor::new(unreachable, ["m"], Duration::from_millis(10));
+        let mut changes = monitor.subscribe();
+        changes.changed().await.unwrap();
+        assert!(changes.borrow().error.is_some());
+        let err = monitor.wait_until_ready(Duration::from_millis(20)).await.unwrap_err();
+        assert!(matches!(err, TrustonError::DeadlineExceeded));
+    }
+
+    #[tokio::test]
+    async fn test_monitor_times_out_silent_server() {
+        // connections are accepted by the OS but never answered
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let client = Arc::new(TritonRestClient::new(&base_url));
+        let monitor = HealthMonitor::new(client, ["m"], Duration::from_millis(20));
+
+        let mut changes = monitor.subscribe();
+        tokio::time::timeout(Duration::from_secs(5), changes.changed()).await.unwrap().unwrap();
+        let error = changes.borrow().error.clone().unwrap();
+        assert!(error.contains("no answer within 20ms"), "{}", error);
+        drop(listener);
+    }
+}
@@ -0,0 +1,190 @@
+//! Request hedging.
+//!
+//! A few slow responses (a GC pause, a busy instance, a lost packet) make
+//! up most of the tail latency. With a [`HedgePolicy`] set by
+//! [`TritonRestClientBuilder::hedging`](crate::client::http::TritonRestClientBuilder::hedging),
+//! an inference that got no response after the hedge delay is sent a second
+//! time, to the next failover endpoint (or the same one when there is only
+//! one), and the first response wins; the other request is dropped.
+//!
+//! The delay is either fixed or a percentile of the recent latencies of the
+//! client, so only the slowest requests are duplicated: at the 95th
+//! percentile, about 5% more requests are sent. Requests marked
+//! [`with_non_idempotent`](crate::client::options::InferOptions::with_non_idempotent)
+//! or carrying an [affinity token](crate::client::affinity) are never hedged.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use truston::client::hedge::HedgePolicy;
+//! use truston::client::http::TritonRestClient;
+//!
+//! let client = TritonRestClient::builder("http://triton-a:8000")
+//!     .failover_endpoint("http://triton-b:8000")
+//!     .hedging(HedgePolicy::percentile(0.95, Duration::from_millis(50)))
+//!     .build()?;
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use futures::future::{Either, select};
+
+use crate::client::events::ClientEvent;
+use crate::client::http::TritonRestClient;
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+/// Number of recent latencies the percentile is computed on.
+const LATENCY_WINDOW: usize = 256;
+
+/// When to send the duplicate of a slow inference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HedgePolicy {
+    /// Fixed delay, or the delay used until `min_samples` latencies are known.
+    pub delay: Duration,
+    /// Percentile (in `[0, 1]`) of the recent latencies used as the delay.
+    pub percentile: Option<f64>,
+    /// Latencies needed before the percentile is used (20 by default).
+    pub min_samples: usize,
+}
+
+impl HedgePolicy {
+    /// Hedge requests without response after `delay`.
+    pub fn fixed(delay: Duration) -> Self {
+        HedgePolicy { delay, percentile: None, min_samples: 20 }
+    }
+
+    /// Hedge requests slower than the `percentile` of the recent latencies
+    /// (e.g. `0.95`), after `initial_delay` until enough latencies are known.
+    pub fn percentile(percentile: f64, initial_delay: Duration) -> Self {
+        HedgePolicy {
+            delay: initial_delay,
+            percentile: Some(percentile.clamp(0.0, 1.0)),
+            min_samples: 20,
+        }
+    }
+
+    pub fn with_min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples;
+        self
+    }
+}
+
+/// Latencies of the last inferences of a client.
+#[derive(Debug, Default)]
+pub(crate) struct LatencyWindow {
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl LatencyWindow {
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == LATENCY_WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// The hedge delay of `policy` given the latencies so far.
+    fn delay(&self, policy: &HedgePolicy) -> Duration {
+        let Some(percentile) = policy.percentile else {
+            return policy.delay;
+        };
+        let mut latencies: Vec<Duration> = {
+            let latencies = self.latencies.lock().unwrap();
+            if latencies.is_empty() || latencies.len() < policy.min_samples {
+                return policy.delay;
+            }
+            latencies.iter().copied().collect()
+        };
+        latencies.sort_unstable();
+        let rank = (percentile * latencies.len() as f64).ceil() as usize;
+        latencies[rank.clamp(1, latencies.len()) - 1]
+    }
+}
+
+impl TritonRestClient {
+    /// Number of hedged requests sent (see [`hedge`](crate::client::hedge)).
+    pub fn hedges_sent(&self) -> u64 {
+        self.hedges_sent.load(Ordering::Relaxed)
+    }
+
+    /// Send an inference request, hedged if the client has a policy and
+    /// `options` allow it.
+    ///
+    /// The response is returned with the number of requests sent for it.
+    pub(crate) async fn send_hedged<F>(
+        &self,
+        options: &InferOptions,
+        build: F,
+    ) -> Result<(reqwest::Response, u32), TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let idempotent = !options.non_idempotent;
+        let affinity = options.affinity.as_ref();
+        let Some(policy) = self.hedging.as_ref().filter(|_| idempotent && affinity.is_none())
+        else {
+            return self.send_request_until(idempotent, options.deadline, affinity, build).await;
+        };
+
+        let started = Instant::now();
+        let delay = self.latencies.delay(policy);
+        let send = |endpoint| self.send_request_from(true, options.deadline, None, endpoint, &build);
+        let mut primary = Box::pin(send(None));
+        let result = tokio::select! {
+            result = &mut primary => result,
+            _ = tokio::time::sleep(delay) => {
+                let active = self.active_endpoint.load(Ordering::Relaxed);
+                let endpoint = (active + 1) % self.endpoints.len();
+                let to = &self.endpoints[endpoint];
+                tracing::debug!("no response after {:?}, hedging to {}", delay, to);
+                self.hedges_sent.fetch_add(1, Ordering::Relaxed);
+                self.emit(|| ClientEvent::Hedged { endpoint: to.clone(), delay });
+                let hedge = Box::pin(send(Some(endpoint)));
+                // the first response wins; an error waits for the other request
+                match select(primary, hedge).await {
+                    Either::Left((Ok((resp, sent)), _)) | Either::Right((Ok((resp, sent)), _)) => {
+                        Ok((resp, sent + 1))
+                    }
+                    Either::Left((Err(_), other)) | Either::Right((Err(_), other)) => {
+                        other.await.map(|(resp, sent)| (resp, sent + 1))
+                    }
+                }
+            }
+        };
+        if result.is_ok() {
+            self.latencies.record(started.elapsed());
+        }
+        result
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_delay() {
+        let window = LatencyWindow::default();
+        let policy = HedgePolicy::percentile(0.9, Duration::from_millis(50)).with_min_samples(10);
+        assert_eq!(window.delay(&policy), Duration::from_millis(50));
+
+        for ms in 1..=10 {
+            window.record(Duration::from_millis(ms));
+        }
+        assert_eq!(window.delay(&policy), Duration::from_millis(9));
+        assert_eq!(window.delay(&HedgePolicy::fixed(Duration::ZERO)), Duration::ZERO);
+
+        for _ in 0..LATENCY_WINDOW {
+            window.record(Duration::from_millis(100));
+        }
+        assert_eq!(window.delay(&policy), Duration::from_millis(100));
+        assert_eq!(window.latencies.lock().unwrap().len(), LATENCY_WINDOW);
+    }
+}
@@ -4,357 +4,1332 @@
 //! Triton Inference Server via its REST API.
 
 
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
+use reqwest::header::{
+    ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING, HeaderMap, HeaderName, HeaderValue,
+};
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Duration;
 use async_trait::async_trait;
-use crate::utils::errors::TrustonError;
-use crate::client::io::{
-    DataType, 
-    InferInput, 
-    InferInputPayload,
-    TritonServerResponse,
-    InferRequest,
-    InferResponse,
-    InferResults, 
-    InferOutput,
+use crate::utils::capabilities;
+use crate::utils::errors::{TrustonError, triton_message};
+use crate::client::auth::{self, Auth, TokenProvider};
+use crate::client::affinity::{
+    AffinityCarrier, AffinityTable, AffinityToken, DEFAULT_AFFINITY_TTL, HeaderCarrier,
 };
-use num_traits::NumCast;
+use crate::client::cache::{DEFAULT_METADATA_TTL, TtlCache};
+use crate::client::compression::{self, Codec};
+use crate::client::deadline::Deadline;
+use crate::client::events::{ClientEvent, DEFAULT_EVENT_CAPACITY};
+use crate::client::hedge::{HedgePolicy, LatencyWindow};
+#[cfg(feature = "test-util")]
+use crate::client::fault::FaultInjector;
+use crate::client::config::ModelConfig;
+use crate::client::interceptor::{Interceptor, RequestInfo};
+use crate::client::limit::{Limiter, RateLimit};
+use crate::client::io::{InferInput, InferResults};
+use crate::client::metadata::ModelMetadata;
+use crate::client::metrics::InferRecorder;
+use crate::client::options::InferOptions;
+use crate::client::raw::ApiPath;
+use crate::client::retry::{FailureKind, RetryPolicy};
 use serde_json;
 
 /// Trait defining the core operations for a Triton Inference Server client.
 ///
-/// This trait can be implemented for different communication protocols
-/// (REST, gRPC, etc.). Currently, only REST is implemented via `TritonRestClient`.
-#[async_trait]
-pub trait TritonClient: Send + Sync {
+/// Implemented by [`TritonRestClient`] and, with the `grpc` feature, by
+/// `TritonGrpcClient`. Code written against the trait works with either
+/// protocol, and tests can substitute a mock implementation.
+///
+/// # Example
+/// ```
+/// use truston::client::http::TritonClient;
+/// use truston::client::io::InferInput;
+/// use truston::client::options::InferOptions;
+/// use truston::TrustonError;
+///
+/// async fn count_outputs(
+///     client: &impl TritonClient,
+///     image: InferInput,
+/// ) -> Result<usize, TrustonError> {
+///     let results = client.infer(vec![image], "classifier", &InferOptions::new()).await?;
+///     Ok(results.outputs.len())
+/// }
+/// ```
+///
+/// By default implementations must be `Send + Sync` and return `Send` futures.
+/// With the `local` feature these bounds are dropped so the trait can be
+/// implemented by `!Send` clients running on thread-per-core runtimes
+/// (e.g. inside a `tokio::task::LocalSet`).
+#[cfg_attr(not(feature = "local"), async_trait)]
+#[cfg_attr(feature = "local", async_trait(?Send))]
+pub trait TritonClient: MaybeSendSync {
+    /// `true` if the server is live, `false` if it answers that it is not.
+    ///
+    /// The REST client probes `/v2/health/live`; use
+    /// [`TritonRestClient::server_live`] and friends for a
+    /// [`Health`](crate::client::health::Health) keeping the answer.
     async fn is_server_live(&self) -> Result<bool, TrustonError>;
+
+    /// `true` if the server is ready to serve inferences, `false` if it
+    /// answers that it is not.
+    async fn is_server_ready(&self) -> Result<bool, TrustonError>;
+
+    /// `true` if `model_name` (or one of its versions) is loaded and ready.
+    async fn is_model_ready(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<bool, TrustonError>;
+
+    /// Inputs, outputs and versions of `model_name`.
+    async fn model_metadata(&self, model_name: &str) -> Result<ModelMetadata, TrustonError>;
+
+    /// Run one inference with per-request `options`.
+    async fn infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<InferResults, TrustonError>;
 }
 
+/// `Send + Sync` unless the `local` feature is enabled, in which case it is
+/// implemented for every type.
+#[cfg(not(feature = "local"))]
+pub trait MaybeSendSync: Send + Sync {}
+#[cfg(not(feature = "local"))]
+impl<T: Send + Sync> MaybeSendSync for T {}
+
+/// `Send + Sync` unless the `local` feature is enabled, in which case it is
+/// implemented for every type.
+#[cfg(feature = "local")]
+pub trait MaybeSendSync {}
+#[cfg(feature = "local")]
+impl<T> MaybeSendSync for T {}
+
 pub struct TritonRestClient {
-    base_url: String,
-    http: Client,
+    pub(crate) base_url: String,
+    /// Endpoints tried when the active one cannot be reached, `base_url` first.
+    pub(crate) endpoints: Vec<String>,
+    /// Index in `endpoints` of the endpoint requests are sent to.
+    pub(crate) active_endpoint: AtomicUsize,
+    pub(crate) http: Client,
+    /// Request timeout of `http`, unknown for a custom client. Requests with
+    /// a deadline are bounded by both.
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) events: tokio::sync::broadcast::Sender<ClientEvent>,
+    pub(crate) fallbacks_used: AtomicU64,
+    pub(crate) hedging: Option<HedgePolicy>,
+    /// Latencies of the last inferences, for the hedge delay.
+    pub(crate) latencies: LatencyWindow,
+    pub(crate) hedges_sent: AtomicU64,
+    pub(crate) limiter: Limiter,
+    pub(crate) request_codec: Option<Arc<dyn Codec>>,
+    pub(crate) response_codecs: Vec<Arc<dyn Codec>>,
+    pub(crate) string_datatype: StringDatatype,
+    pub(crate) float_format: FloatFormat,
+    /// Whether inputs are checked against the model metadata before inference.
+    pub(crate) validate_inputs: bool,
+    /// Whether output values that fail to decode fail the inference.
+    pub(crate) strict_outputs: bool,
+    /// Whether inferences without a trace context start a new trace.
+    pub(crate) trace_propagation: bool,
+    pub(crate) metadata_cache: TtlCache<ModelMetadata>,
+    pub(crate) config_cache: TtlCache<ModelConfig>,
+    pub(crate) affinity: AffinityTable,
+    pub(crate) affinity_carrier: Arc<dyn AffinityCarrier>,
+    /// Asked for a bearer token before each attempt.
+    pub(crate) token_provider: Option<Arc<dyn TokenProvider>>,
+    /// Told about every inference.
+    pub(crate) recorders: Vec<Arc<dyn InferRecorder>>,
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    #[cfg(feature = "test-util")]
+    pub(crate) faults: Option<Arc<FaultInjector>>,
 }
 
 impl TritonRestClient {
+    /// Create a client with the default settings (5 second request timeout).
+    ///
+    /// # Panics
+    /// Panics if the underlying HTTP client cannot be built. Use
+    /// [`TritonRestClient::builder`] to handle that case as an error.
     pub fn new(base_url: &str) -> Self {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(5))
+        Self::builder(base_url)
             .build()
-            .expect("failed to build client");
+            .expect("failed to build client")
+    }
+
+    /// Start configuring a client for the server at `base_url`.
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use truston::client::http::TritonRestClient;
+    ///
+    /// let client = TritonRestClient::builder("http://localhost:8000")
+    ///     .timeout(Duration::from_secs(30))
+    ///     .connect_timeout(Duration::from_secs(2))
+    ///     .default_header("x-model-tier", "gold")
+    ///     .user_agent("my-service/1.0")
+    ///     .build()?;
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn builder(base_url: &str) -> TritonRestClientBuilder {
+        TritonRestClientBuilder::new(base_url)
+    }
+}
+
+/// Builder for [`TritonRestClient`].
+///
+/// Created with [`TritonRestClient::builder`]. Every setting is optional;
+/// invalid values (header names, proxy URLs, ...) are reported by
+/// [`TritonRestClientBuilder::build`] as [`TrustonError::ConfigError`].
+#[derive(Debug, Clone)]
+pub struct TritonRestClientBuilder {
+    base_url: String,
+    failover_endpoints: Vec<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    auth: Option<Auth>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    redirect: RedirectPolicy,
+    retry: RetryPolicy,
+    hedging: Option<HedgePolicy>,
+    max_concurrent_requests: Option<usize>,
+    rate_limit: Option<RateLimit>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Option<Duration>>,
+    tcp_keepalive: Option<Duration>,
+    tcp_nodelay: bool,
+    http2_prior_knowledge: bool,
+    #[cfg(unix)]
+    unix_socket: Option<PathBuf>,
+    http_client: Option<Client>,
+    event_capacity: usize,
+    request_codec: Option<Arc<dyn Codec>>,
+    response_codecs: Vec<Arc<dyn Codec>>,
+    string_datatype: StringDatatype,
+    float_format: FloatFormat,
+    validate_inputs: bool,
+    strict_outputs: bool,
+    trace_propagation: bool,
+    metadata_cache_ttl: Duration,
+    affinity_carrier: Arc<dyn AffinityCarrier>,
+    affinity_ttl: Duration,
+    recorders: Vec<Arc<dyn InferRecorder>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    #[cfg(feature = "test-util")]
+    faults: Option<Arc<FaultInjector>>,
+}
 
+impl TritonRestClientBuilder {
+    fn new(base_url: &str) -> Self {
         Self {
             base_url: base_url.to_string(),
-            http,
+            failover_endpoints: Vec::new(),
+            timeout: Some(Duration::from_secs(5)),
+            connect_timeout: None,
+            headers: Vec::new(),
+            auth: None,
+            user_agent: None,
+            proxy: None,
+            redirect: RedirectPolicy::default(),
+            retry: RetryPolicy::default(),
+            hedging: None,
+            max_concurrent_requests: None,
+            rate_limit: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            tcp_nodelay: true,
+            http2_prior_knowledge: false,
+            #[cfg(unix)]
+            unix_socket: None,
+            http_client: None,
+            event_capacity: DEFAULT_EVENT_CAPACITY,
+            request_codec: None,
+            response_codecs: Vec::new(),
+            string_datatype: StringDatatype::default(),
+            float_format: FloatFormat::default(),
+            validate_inputs: false,
+            strict_outputs: false,
+            trace_propagation: false,
+            metadata_cache_ttl: DEFAULT_METADATA_TTL,
+            affinity_carrier: Arc::new(HeaderCarrier::default()),
+            affinity_ttl: DEFAULT_AFFINITY_TTL,
+            recorders: Vec::new(),
+            interceptors: Vec::new(),
+            #[cfg(feature = "test-util")]
+            faults: None,
         }
     }
-}
 
-#[async_trait]
-impl TritonClient for TritonRestClient {
-    async fn is_server_live(&self) -> Result<bool, TrustonError> {
-        let url = format!("{}/v2/health/ready", self.base_url);
-
-        let resp = self
-            .http
-            .get(&url)
-            .send()
-            .await
-            .map_err(TrustonError::Http)?;
-
-        tracing::info!("is_server_live: {} -> {}", url, resp.status());
-
-        let status = resp.status();
-        if status.is_success() {
-            Ok(true)
-        } else {
-            let status_code = status.as_u16();
-            let body_text = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| "No response body".to_string());
-            let error_message = format!(
-                "Server is dead or unhealthy. Status: {}. Response body: {}",
-                status_code, body_text
-            );
-            Err(TrustonError::ServerError{status: status_code, message: error_message})
-        }
+    /// Total time allowed for a request, from connecting until the body is read.
+    ///
+    /// Requests with a [`Deadline`] stop at whichever comes first.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
     }
-}
 
-impl TritonRestClient {
-    pub async fn is_server_live(&self) -> Result<bool, TrustonError> {
-        TritonClient::is_server_live(self).await
+    /// Disable the request timeout entirely (useful for very long inferences).
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Time allowed for establishing the TCP/TLS connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
     }
 
-    /// Converts an `InferInput` into the JSON payload format required by Triton.
+    /// Add a header sent with every request.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Authenticate every request with `auth`, see [`Auth`].
     ///
-    /// This is an internal method that handles the conversion of Rust types
-    /// to Triton's JSON format.
-    /// 
-    fn convert_input<'a>(
-        &self,
-        infer_input: &'a InferInput,
-    ) -> InferInputPayload<'a, serde_json::Value> {
-        let (datatype, data_json) = match &infer_input.input_data {
-            DataType::Bool(v) => ("BOOL", serde_json::json!(v)),
-            DataType::U8(v) => ("UINT8", serde_json::json!(v)),
-            DataType::U16(v) => ("UINT16", serde_json::json!(v)),
-            DataType::U64(v) => ("UINT64", serde_json::json!(v)),
-            DataType::I8(v) => ("INT8", serde_json::json!(v)),
-            DataType::I16(v) => ("INT16", serde_json::json!(v)),
-            DataType::I32(v) => ("INT32", serde_json::json!(v)),
-            DataType::I64(v) => ("INT64", serde_json::json!(v)),
-            DataType::F32(v) => ("FP32", serde_json::json!(v)),
-            DataType::F64(v) => ("FP64", serde_json::json!(v)),
-            DataType::String(v) => ("BYTES", serde_json::json!(v)),
-            DataType::Bf16(v) => ("BF16", serde_json::json!(v)),
-            DataType::Raw(v) => ("none", serde_json::json!(v)),
-        };
+    /// # Example
+    /// ```
+    /// use truston::client::auth::Auth;
+    /// use truston::client::http::TritonRestClient;
+    ///
+    /// let client = TritonRestClient::builder("https://gateway.example.com/triton")
+    ///     .auth(Auth::header("x-api-key", "secret"))
+    ///     .build()?;
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
 
-        InferInputPayload {
-            name: &infer_input.input_name,
-            shape: infer_input.input_shape.clone(),
-            datatype,
-            data: data_json,
-        }
+    /// Override the `User-Agent` header, `truston/<version> (<features>)` by
+    /// default (see [`Capabilities`](crate::Capabilities)).
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Route every request through the given proxy URL.
+    pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy = Some(proxy_url.into());
+        self
     }
 
-    /// Convert the output data from a Triton server response into a vector of numeric values.
+    /// How HTTP redirects are handled, see [`RedirectPolicy`].
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect = policy;
+        self
+    }
+
+    /// Add an endpoint serving the same models, used when the current one
+    /// cannot be reached. Can be called several times; endpoints are tried in order.
     ///
-    /// This function attempts to parse the raw JSON `data` field returned by the Triton Inference Server
-    /// into a strongly-typed `Vec<T>`, where `T` implements [`NumCast`].  
-    /// It supports multiple Triton output datatypes (`FP32`, `INT64`, `BOOL`, etc.)
-    /// and automatically casts the values into the requested type.
+    /// Only connection failures fail over (see [`FailureKind`]), immediately
+    /// and for every request, idempotent or not. The client then keeps using
+    /// the endpoint that answered.
     ///
-    /// # Type Parameters
-    /// * `T: NumCast` - The target numeric type (e.g., `f32`, `f64`, `i32`, `u64`, etc.).
+    /// # Example
+    /// ```
+    /// use truston::client::http::TritonRestClient;
     ///
-    /// # Arguments
-    /// * `output_data` - A reference to a [`TritonServerResponse`] containing the model output.
+    /// let client = TritonRestClient::builder("http://triton-a:8000")
+    ///     .failover_endpoint("http://triton-b:8000")
+    ///     .build()?;
+    /// assert_eq!(client.active_endpoint(), "http://triton-a:8000");
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn failover_endpoint(mut self, base_url: impl Into<String>) -> Self {
+        self.failover_endpoints.push(base_url.into());
+        self
+    }
+
+    /// Retry transient failures according to `policy`. No retries by default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Send a duplicate of inferences that got no response within the delay
+    /// of `policy`, and keep the first response. Not hedged by default.
     ///
-    /// # Supported Datatypes
-    /// - Floating point: `"FP32"`, `"FP64"` → parsed as `f64` then cast into `T`.
-    /// - Unsigned integers: `"UINT8"`, `"UINT16"`, `"UINT32"`, `"UINT64"` → parsed as `u64` then cast.
-    /// - Signed integers: `"INT8"`, `"INT16"`, `"INT32"`, `"INT64"` → parsed as `i64` then cast.
-    /// - Boolean: `"BOOL"` → parsed as `bool`, then converted to `0` or `1` (`u8`) before casting.
-    /// - Anything else returns `None`.
+    /// See [`hedge`](crate::client::hedge).
+    pub fn hedging(mut self, policy: HedgePolicy) -> Self {
+        self.hedging = Some(policy);
+        self
+    }
+
+    /// Keep at most `max` requests in flight; further ones wait for a slot, by
+    /// [queue priority](crate::client::options::InferOptions::with_queue_priority).
+    /// Unbounded by default.
     ///
-    /// # Returns
-    /// * `Some(Vec<T>)` if the datatype is supported and the cast succeeds.
-    /// * `None` if the datatype is unsupported or the JSON field is invalid.
+    /// See [`limit`](crate::client::limit).
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Send requests at the pace of `limit`; further ones wait for a token.
+    /// Unlimited by default.
     ///
-    /// # Behavior
-    /// - Iterates over the JSON array inside `data`.
-    /// - Uses `filter_map` twice:
-    ///   1. To parse the JSON into a base type (`f64`, `i64`, `u64`, or `bool`).
-    ///   2. To cast the parsed value into the target type `T`.
-    /// - Invalid entries or failed casts are skipped silently.
+    /// See [`limit`](crate::client::limit).
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Maximum number of idle connections kept per host (unbounded by default).
+    ///
+    /// Raise it when many concurrent requests otherwise cause connection churn.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle pooled connection is kept open (90 seconds by default).
+    /// `None` keeps idle connections forever.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable TCP keepalive probes with the given interval.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Set `TCP_NODELAY` on connections (enabled by default).
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = enabled;
+        self
+    }
+
+    /// Talk HTTP/2 without upgrade negotiation, multiplexing all requests over
+    /// a single connection. The server (or proxy) must support HTTP/2 over cleartext.
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Connect over the Unix domain socket at `path` instead of TCP, e.g. to
+    /// a proxy in front of Triton on the same host.
+    ///
+    /// The base URL still gives the scheme and the `Host` header, its host
+    /// isn't resolved. TCP and proxy settings are ignored.
     ///
     /// # Example
-    /// ```ignore
-    /// let response: TritonServerResponse = client.infer(...).await?;
-    ///
-    /// // Convert float output
-    /// if let Some(values) = my_client.convert_output::<f32>(&response) {
-    ///     println!("Model float output: {:?}", values);
-    /// }
-    ///
-    /// // Convert integer output
-    /// if let Some(values) = my_client.convert_output::<i64>(&response) {
-    ///     println!("Model int output: {:?}", values);
-    /// }
-    ///
-    /// // Convert boolean output
-    /// if let Some(values) = my_client.convert_output::<u8>(&response) {
-    ///     println!("Model bool output (as 0/1): {:?}", values);
-    /// }
+    /// ```no_run
+    /// use truston::client::http::TritonRestClient;
+    ///
+    /// let client = TritonRestClient::builder("http://localhost")
+    ///     .unix_socket("/run/triton/proxy.sock")
+    ///     .build()?;
+    /// # Ok::<(), truston::TrustonError>(())
     /// ```
+    #[cfg(unix)]
+    pub fn unix_socket(mut self, path: impl Into<PathBuf>) -> Self {
+        self.unix_socket = Some(path.into());
+        self
+    }
+
+    /// Send requests with `client` instead of building one, for full control
+    /// of the transport (custom connector, TLS setup, ...).
     ///
-    /// # Notes
-    /// - The function does not fail hard: if a single element in the array fails parsing/casting,
-    ///   it is skipped, but the rest of the vector is still returned.
-    /// - For non-numeric outputs like `"STRING"`, use [`convert_output_string`] instead.
-    fn convert_output<T: NumCast>(&self, output_data: &TritonServerResponse) -> Option<Vec<T>> {
-        match output_data.datatype.as_str() {
-            "FP32" | "FP64" => output_data.data.as_array().map(|arr| {
-                arr.iter()
-                    .filter_map(|item| item.as_f64())
-                    .filter_map(|num| NumCast::from(num))
-                    .collect()
-            }),
-            "UINT8" | "UINT16" | "UINT32" | "UINT64" => output_data.data.as_array().map(|arr| {
-                arr.iter()
-                    .filter_map(|item| item.as_u64())
-                    .filter_map(|num| NumCast::from(num))
-                    .collect()
-            }),
-            "INT8" | "INT16" | "INT32" | "INT64" => output_data.data.as_array().map(|arr| {
-                arr.iter()
-                    .filter_map(|item| item.as_i64())
-                    .filter_map(|num| NumCast::from(num))
-                    .collect()
-            }),
-            "BOOL" => output_data.data.as_array().map(|arr| {
-                arr.iter()
-                    .filter_map(|item| item.as_bool())
-                    .filter_map(|b| NumCast::from(b as u8))
-                    .collect()
-            }),
-            _ => None,
-        }
+    /// The settings of `client` replace the transport settings of this
+    /// builder: timeouts, user agent, proxy, redirect policy, pool, TCP
+    /// options, Unix socket and TLS backend.
+    /// Everything else (retries, failover, limits, request codec, token
+    /// providers, interceptors, ...) applies as usual.
+    ///
+    /// Default headers, static [`Auth`] headers and the `Accept-Encoding` of
+    /// [`response_codec`](Self::response_codec) are sent as default headers
+    /// of the client built here, so they can't be combined with `client`:
+    /// set them on `client` instead.
+    pub fn http_client(mut self, client: Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Number of events buffered for each [`TritonRestClient::events`] receiver (256 by default).
+    ///
+    /// Receivers lagging further behind lose the oldest events.
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        self.event_capacity = capacity.max(1);
+        self
+    }
+
+    /// Compress the body of inference requests with `codec` (sent with `Content-Encoding`).
+    ///
+    /// Triton accepts `gzip` (and `deflate`) encoded requests; other codecs
+    /// need a proxy that decodes them. See [`compression`] for the available codecs.
+    pub fn request_codec(mut self, codec: impl Codec + 'static) -> Self {
+        self.request_codec = Some(Arc::new(codec));
+        self
     }
 
-    /// Convert the output data from a Triton server response into a vector of strings.
+    /// Accept responses compressed with `codec` (advertised in `Accept-Encoding`).
     ///
-    /// # Arguments
-    /// * `output_data` - A reference to a [`TritonServerResponse`] object that contains
-    ///   the inference result returned by the Triton Inference Server.
+    /// Can be called several times; the server picks one of the codecs.
+    pub fn response_codec(mut self, codec: impl Codec + 'static) -> Self {
+        self.response_codecs.push(Arc::new(codec));
+        self
+    }
+
+    /// Datatype name sent for [`DataType::String`](crate::client::io::DataType::String)
+    /// inputs (`BYTES` by default).
     ///
-    /// # Returns
-    /// * `Some(Vec<String>)` if:
-    ///   - The `datatype` of the output is `"STRING"`.
-    ///   - The `data` field can be parsed as an array of string values.
-    /// * `None` if the `datatype` is not `"STRING"` or the data is not an array of strings.
+    /// Responses are parsed the same way whichever name the server uses.
+    pub fn string_datatype(mut self, datatype: StringDatatype) -> Self {
+        self.string_datatype = datatype;
+        self
+    }
+
+    /// How `FP32`/`FP64` input values are written in the JSON of inference
+    /// requests (shortest round-trip form by default).
     ///
-    /// # Behavior
-    /// - When the datatype is `"STRING"`, this function attempts to parse the `data`
-    ///   field as an array of JSON values and filter out only the valid string entries.
-    /// - Non-string entries inside the array will be ignored (they are skipped using
-    ///   `filter_map`).
+    /// Inputs holding NaN or infinite values, which JSON has no numbers for,
+    /// are sent with the binary tensor extension whatever the format.
     ///
     /// # Example
-    /// ```ignore
-    /// let response: TritonServerResponse = client.infer(...).await?;
-    /// if let Some(strings) = my_client.convert_output_string(&response) {
-    ///     println!("Model output: {:?}", strings);
-    /// } else {
-    ///     println!("No valid string output found.");
-    /// }
     /// ```
+    /// use truston::client::http::{FloatFormat, TritonRestClient};
     ///
-    /// # Notes
-    /// - This helper is only meaningful for Triton model outputs with `datatype = "STRING"`.
-    /// - For numeric outputs (e.g., `"FP32"`, `"INT64"`), consider using a different
-    ///   converter function.
-    fn convert_output_string(&self, output_data: &TritonServerResponse) -> Option<Vec<String>> {
-        match output_data.datatype.as_str() {
-            "BYTES" => output_data.data.as_array().map(|arr| {
-                arr.iter()
-                    .filter_map(|item| item.as_str().map(|s| s.to_string()))
-                    .collect()
-            }),
-            _ => None,
-        }
+    /// // a proxy parsing numbers as doubles, without exponents
+    /// let client = TritonRestClient::builder("http://localhost:8000")
+    ///     .float_format(FloatFormat::default().with_significant_digits(15).without_exponent())
+    ///     .build()?;
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
     }
 
-    /// Perform an inference request to the Triton Inference Server.
+    /// Check the inputs of every inference against the model metadata before
+    /// sending it (see [`validate_inputs`](crate::client::validation::validate_inputs)).
     ///
-    /// This method sends a `POST` request to the Triton server's
-    /// `/v2/models/{model_name}/infer` endpoint with the provided input tensors,
-    /// waits for the response, parses it into structured outputs, and converts the
-    /// raw response data into strongly-typed [`InferResults`].
+    /// The metadata of each model is cached for
+    /// [`metadata_cache_ttl`](Self::metadata_cache_ttl); see
+    /// [`TritonRestClient::forget_metadata`] after a model reload.
+    pub fn validate_inputs(mut self) -> Self {
+        self.validate_inputs = true;
+        self
+    }
+
+    /// Fail inferences with [`TrustonError::ParseError`] when an output value
+    /// cannot be decoded into its datatype (e.g. a string in an `INT64`
+    /// output, or `300` in a `UINT8` one), naming the element and its raw value.
     ///
-    /// # Arguments
-    /// * `inputs` - A list of [`InferInput`] objects representing the input tensors
-    ///   (name, datatype, shape, and values) that will be sent to the model.
-    /// * `model_name` - The name of the deployed model to query on the Triton server.
+    /// By default such values are skipped, so a corrupted response yields an
+    /// output shorter than its shape.
+    pub fn strict_outputs(mut self) -> Self {
+        self.strict_outputs = true;
+        self
+    }
+
+    /// Send a `traceparent` header starting a new trace with every inference
+    /// that doesn't carry a [`TraceContext`](crate::client::trace::TraceContext),
+    /// so Triton's OpenTelemetry traces can be found from the client logs.
     ///
-    /// # Returns
-    /// * `Ok(InferResults)` - On success, containing a vector of [`InferOutput`] entries.
-    /// * `Err(TrustonError)` - On failure, with possible variants:
-    ///   - [`TrustonError::InferenceError`] if the server returned a non-2xx response
-    ///     (includes the error body if available).
-    ///   - [`TrustonError::ParseError`] if the response could not be deserialized into [`InferResponse`].
-    ///   - Any other error bubbled up from the HTTP client (e.g., connection failure).
+    /// See [`trace`](crate::client::trace).
+    pub fn trace_propagation(mut self) -> Self {
+        self.trace_propagation = true;
+        self
+    }
+
+    /// How long model metadata and configs are cached (5 minutes by
+    /// default, `Duration::ZERO` disables the cache).
     ///
-    /// # Supported Datatypes
-    /// The server response is parsed into [`DataType`] variants depending on `datatype`:
-    /// - `"UINT8"`, `"UINT16"`, `"UINT64"` → parsed into [`DataType::U8`], [`DataType::U16`], [`DataType::U64`]
-    /// - `"INT8"`, `"INT16"`, `"INT32"`, `"INT64"` → parsed into [`DataType::I8`], [`DataType::I16`], [`DataType::I32`], [`DataType::I64`]
-    /// - `"FP32"`, `"FP64"` → parsed into [`DataType::F32`], [`DataType::F64`]
-    /// - `"BF16"` → parsed as `u16` and wrapped in [`DataType::Bf16`]
-    /// - `"STRING"` → parsed into [`DataType::String`]
-    /// - Any unknown datatype → stored raw in [`DataType::Raw`] with the original JSON payload.
+    /// See [`cache`](crate::client::cache).
+    pub fn metadata_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.metadata_cache_ttl = ttl;
+        self
+    }
+
+    /// How affinity tokens are sent ([`HeaderCarrier`] with `x-affinity-token` by default).
     ///
-    /// # Example
-    /// ```ignore
-    /// let client = TritonRestClient::new("http://localhost:8000");
-    ///
-    /// let input = InferInput::new("input_tensor", vec![1, 16], DataType::F32, vec![0.1f32; 16]);
-    ///
-    /// match client.infer(vec![input], "my_model").await {
-    ///     Ok(results) => {
-    ///         for out in results.outputs {
-    ///             println!("Output {}: {:?}", out.name, out.data);
-    ///         }
-    ///     }
-    ///     Err(e) => eprintln!("Inference failed: {:?}", e),
-    /// }
-    /// ```
+    /// See [`affinity`](crate::client::affinity) for session affinity.
+    pub fn affinity_carrier(mut self, carrier: impl AffinityCarrier + 'static) -> Self {
+        self.affinity_carrier = Arc::new(carrier);
+        self
+    }
+
+    /// How long an affinity token stays pinned to its endpoint without being
+    /// used (10 minutes by default).
+    pub fn affinity_ttl(mut self, ttl: Duration) -> Self {
+        self.affinity_ttl = ttl;
+        self
+    }
+
+    /// Report every inference to `recorder` (see [`metrics`](crate::client::metrics)).
     ///
-    /// # Notes
-    /// - Automatically converts JSON output values into the appropriate Rust types.
-    /// - If conversion fails for a particular value, it will be skipped silently.
-    /// - Any datatype not explicitly supported will be returned as raw JSON via `DataType::Raw`.
-    pub async fn infer(
+    /// Can be called several times; each recorder gets every sample.
+    pub fn metrics_recorder(mut self, recorder: impl InferRecorder + 'static) -> Self {
+        self.recorders.push(Arc::new(recorder));
+        self
+    }
+
+    /// Run `interceptor` around every request of the client (see
+    /// [`interceptor`](crate::client::interceptor)).
+    ///
+    /// Can be called several times; requests go through the interceptors in
+    /// the order they were added.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Inject faults into the requests of the client, for chaos tests.
+    #[cfg(feature = "test-util")]
+    pub fn fault_injector(mut self, faults: FaultInjector) -> Self {
+        self.faults = Some(Arc::new(faults));
+        self
+    }
+
+    /// Build the client.
+    ///
+    /// # Errors
+    /// - [`TrustonError::ConfigError`] if a header or the proxy URL is invalid,
+    ///   or if settings sent as default headers are combined with
+    ///   [`http_client`](Self::http_client).
+    /// - [`TrustonError::Http`] if the underlying HTTP client cannot be created.
+    pub fn build(self) -> Result<TritonRestClient, TrustonError> {
+        if self.http_client.is_some() {
+            let ignored: Vec<&str> = [
+                (!self.headers.is_empty(), "default headers"),
+                (matches!(&self.auth, Some(auth) if !matches!(auth, Auth::Provider(_))), "auth"),
+                (!self.response_codecs.is_empty(), "response codecs"),
+            ]
+            .into_iter()
+            .filter_map(|(set, name)| set.then_some(name))
+            .collect();
+            if !ignored.is_empty() {
+                return Err(TrustonError::ConfigError(format!(
+                    "{} would be ignored with a custom http_client, set them on the client",
+                    ignored.join(", ")
+                )));
+            }
+        }
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                TrustonError::ConfigError(format!("invalid header name {:?}: {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                TrustonError::ConfigError(format!("invalid value for header {}: {}", name, e))
+            })?;
+            headers.append(name, value);
+        }
+        let token_provider = match &self.auth {
+            Some(Auth::Provider(provider)) => Some(provider.clone()),
+            _ => None,
+        };
+        if let Some(auth) = &self.auth
+            && let Some((name, value)) = auth.static_header()?
+        {
+            headers.insert(name, value);
+        }
+        if !self.response_codecs.is_empty() && !headers.contains_key(ACCEPT_ENCODING) {
+            let accepted: Vec<&str> = self.response_codecs.iter().map(|c| c.name()).collect();
+            let value = HeaderValue::from_str(&accepted.join(", ")).map_err(|e| {
+                TrustonError::ConfigError(format!("invalid codec name: {}", e))
+            })?;
+            headers.insert(ACCEPT_ENCODING, value);
+        }
+
+        let (http, timeout) = match self.http_client {
+            Some(client) => (client, None),
+            None => (self.build_http(headers)?, self.timeout),
+        };
+
+        let mut endpoints = vec![self.base_url.trim_end_matches('/').to_string()];
+        for endpoint in self.failover_endpoints {
+            let endpoint = endpoint.trim_end_matches('/').to_string();
+            reqwest::Url::parse(&endpoint).map_err(|e| {
+                TrustonError::ConfigError(format!("invalid endpoint {:?}: {}", endpoint, e))
+            })?;
+            endpoints.push(endpoint);
+        }
+
+        Ok(TritonRestClient {
+            base_url: self.base_url,
+            endpoints,
+            active_endpoint: AtomicUsize::new(0),
+            http,
+            timeout,
+            retry: self.retry,
+            events: tokio::sync::broadcast::channel(self.event_capacity).0,
+            fallbacks_used: AtomicU64::new(0),
+            hedging: self.hedging,
+            latencies: LatencyWindow::default(),
+            hedges_sent: AtomicU64::new(0),
+            limiter: Limiter::new(self.max_concurrent_requests, self.rate_limit),
+            request_codec: self.request_codec,
+            response_codecs: self.response_codecs,
+            string_datatype: self.string_datatype,
+            float_format: self.float_format,
+            validate_inputs: self.validate_inputs,
+            strict_outputs: self.strict_outputs,
+            trace_propagation: self.trace_propagation,
+            metadata_cache: TtlCache::new(self.metadata_cache_ttl),
+            config_cache: TtlCache::new(self.metadata_cache_ttl),
+            affinity: AffinityTable::new(self.affinity_ttl),
+            affinity_carrier: self.affinity_carrier,
+            token_provider,
+            recorders: self.recorders,
+            interceptors: self.interceptors,
+            #[cfg(feature = "test-util")]
+            faults: self.faults,
+        })
+    }
+
+    /// The HTTP client of the transport settings, sending `headers` with every request.
+    fn build_http(&self, headers: HeaderMap) -> Result<Client, TrustonError> {
+        let mut builder = Client::builder()
+            .default_headers(headers)
+            .redirect(self.redirect.to_reqwest())
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive);
+        // with both backends compiled in, asking for rustls means using it
+        #[cfg(feature = "rustls")]
+        {
+            builder = builder.use_rustls_tls();
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        builder = match &self.user_agent {
+            Some(user_agent) => builder.user_agent(user_agent.as_str()),
+            None => builder.user_agent(capabilities::default_user_agent()),
+        };
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url.as_str()).map_err(|e| {
+                TrustonError::ConfigError(format!("invalid proxy {:?}: {}", proxy_url, e))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        #[cfg(unix)]
+        if let Some(path) = &self.unix_socket {
+            builder = builder.unix_socket(path.as_path());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Redirect handling of a [`TritonRestClient`].
+///
+/// Inference and repository calls are `POST` requests. Following a
+/// `301`/`302`/`303` redirect turns them into a `GET` without body, which the
+/// server then rejects with a confusing error. Only `307`/`308` redirects keep
+/// the method and body intact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Follow up to `max` `307`/`308` redirects, preserving method and body.
+    /// Other redirects are not followed and surface as an error carrying the
+    /// redirect status. This is the default (with `max = 10`).
+    Preserving(usize),
+    /// Follow up to `max` redirects of any kind, like a browser would
+    /// (`POST` may be downgraded to `GET` on `301`/`302`/`303`).
+    Follow(usize),
+    /// Never follow redirects.
+    Disabled,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Preserving(10)
+    }
+}
+
+impl RedirectPolicy {
+    /// Whether a redirect with `status`, after `hops` redirects already followed, is followed.
+    fn follows(self, status: StatusCode, hops: usize) -> bool {
+        match self {
+            RedirectPolicy::Preserving(max) => {
+                hops < max
+                    && matches!(
+                        status,
+                        StatusCode::TEMPORARY_REDIRECT | StatusCode::PERMANENT_REDIRECT
+                    )
+            }
+            RedirectPolicy::Follow(max) => hops < max,
+            RedirectPolicy::Disabled => false,
+        }
+    }
+
+    fn to_reqwest(self) -> reqwest::redirect::Policy {
+        match self {
+            RedirectPolicy::Disabled => reqwest::redirect::Policy::none(),
+            policy => reqwest::redirect::Policy::custom(move |attempt| {
+                // `previous` includes the original URL
+                let hops = attempt.previous().len().saturating_sub(1);
+                if policy.follows(attempt.status(), hops) {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }),
+        }
+    }
+}
+
+/// Name of the string datatype in inference requests.
+///
+/// The protocol calls it `BYTES`, model configs call it `TYPE_STRING`, and
+/// some proxies and custom backends only accept `STRING`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringDatatype {
+    #[default]
+    Bytes,
+    String,
+}
+
+impl StringDatatype {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StringDatatype::Bytes => "BYTES",
+            StringDatatype::String => "STRING",
+        }
+    }
+}
+
+/// How float values are written in the JSON of inference requests, see
+/// [`TritonRestClientBuilder::float_format`].
+///
+/// The default is the shortest decimal that reads back to the same value,
+/// with an exponent when very large or small (`1e300`, `1e-7`), which some
+/// proxies in front of Triton reject.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FloatFormat {
+    /// Round values to this many significant digits, clamped to 1..=17.
+    pub significant_digits: Option<u8>,
+    /// Write values in plain decimal notation, e.g. `0.0000001` rather
+    /// than `1e-7`.
+    pub no_exponent: bool,
+}
+
+impl FloatFormat {
+    /// Round values to `digits` significant digits before writing them, e.g.
+    /// `0.333` for `1.0 / 3.0` with 3 digits.
+    ///
+    /// `digits` is clamped to 1..=17: 0 is taken as 1, and more than 17 add
+    /// nothing, 17 digits being enough to read back any `f64`.
+    pub fn with_significant_digits(mut self, digits: u8) -> Self {
+        self.significant_digits = Some(digits);
+        self
+    }
+
+    /// Write values in plain decimal notation, e.g. `0.0000001` rather than
+    /// `1e-7`, and integral values with a trailing `.0`.
+    ///
+    /// Very large or small values take many digits this way: `1e300` is
+    /// written with 301.
+    pub fn without_exponent(mut self) -> Self {
+        self.no_exponent = true;
+        self
+    }
+}
+
+/// Header announcing the JSON part length of a binary tensor extension body.
+pub(crate) const INFERENCE_HEADER_CONTENT_LENGTH: &str = "inference-header-content-length";
+
+/// Maximum number of characters of a body kept in error messages.
+const SNIPPET_LEN: usize = 200;
+
+fn is_expected_content_type(content_type: &str, binary: bool) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime == "application/json"
+        || mime.ends_with("+json")
+        || (binary && mime == "application/octet-stream")
+}
+
+/// Model named by a `/v2/models/{model}/...` or `/v2/repository/models/{model}/...` path.
+fn model_from_path(path: &str) -> Option<String> {
+    let mut segments = path.split('/');
+    segments.find(|&segment| segment == "models")?;
+    segments.next().filter(|model| !model.is_empty()).map(str::to_string)
+}
+
+fn snippet(body: &str) -> String {
+    let mut snippet: String = body.chars().take(SNIPPET_LEN).collect();
+    if snippet.len() < body.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+#[cfg_attr(not(feature = "local"), async_trait)]
+#[cfg_attr(feature = "local", async_trait(?Send))]
+impl TritonClient for TritonRestClient {
+    async fn is_server_live(&self) -> Result<bool, TrustonError> {
+        Ok(self.server_live().await?.is_healthy())
+    }
+
+    async fn is_server_ready(&self) -> Result<bool, TrustonError> {
+        Ok(self.server_ready().await?.is_healthy())
+    }
+
+    async fn is_model_ready(
+        &self,
+        model_name: &str,
+        version: Option<&str>,
+    ) -> Result<bool, TrustonError> {
+        Ok(self.model_ready(model_name, version).await?.is_healthy())
+    }
+
+    async fn model_metadata(&self, model_name: &str) -> Result<ModelMetadata, TrustonError> {
+        TritonRestClient::model_metadata(self, model_name).await
+    }
+
+    async fn infer(
         &self,
         inputs: Vec<InferInput>,
         model_name: &str,
+        options: &InferOptions,
     ) -> Result<InferResults, TrustonError> {
-        let url = format!("{}/v2/models/{}/infer", self.base_url, model_name);
+        self.infer_with_options(inputs, model_name, options).await
+    }
+}
 
-        let input_payloads: Vec<_> = inputs.iter().map(|inp| self.convert_input(inp)).collect();
+impl TritonRestClient {
+    /// Same as [`TritonClient::is_server_live`], dropping the answer of the
+    /// server that [`server_live`](Self::server_live) keeps.
+    #[deprecated(
+        since = "0.1.2",
+        note = "use `server_live`, `server_ready` or `model_ready`, which return a `Health`"
+    )]
+    pub async fn is_server_live(&self) -> Result<bool, TrustonError> {
+        TritonClient::is_server_live(self).await
+    }
 
-        let request = InferRequest {
-            inputs: input_payloads,
-        };
+    /// Number of times an inference was sent to a fallback target
+    /// (see [`InferOptions::with_fallback_version`]).
+    ///
+    /// [`InferOptions::with_fallback_version`]: crate::client::options::InferOptions::with_fallback_version
+    pub fn fallbacks_used(&self) -> u64 {
+        self.fallbacks_used.load(Ordering::Relaxed)
+    }
 
-        let resp = self.http.post(&url).json(&request).send().await?;
+    /// Base URL requests are currently sent to (see
+    /// [`TritonRestClientBuilder::failover_endpoint`]).
+    pub fn active_endpoint(&self) -> &str {
+        &self.endpoints[self.active_endpoint.load(Ordering::Relaxed)]
+    }
 
-        let status = resp.status();
+    /// Send a request, retrying transient failures according to the client's [`RetryPolicy`].
+    ///
+    /// `build` is called once per attempt. Requests that are not `idempotent`
+    /// are only retried if the policy allows it. Connection failures are sent
+    /// to the next failover endpoint right away, without counting as an
+    /// attempt. The response of the last attempt is returned as-is, whatever its status.
+    pub(crate) async fn send_request<F>(
+        &self,
+        idempotent: bool,
+        build: F,
+    ) -> Result<reqwest::Response, TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let (_slot, _waited) = self.limiter.acquire(None, 0).await?;
+        let (resp, _attempts) = self.send_request_until(idempotent, None, None, build).await?;
+        Ok(resp)
+    }
 
-        if !status.is_success() {
-            let error_body = resp
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error body".to_string());
-            return Err(TrustonError::InferenceError(error_body));
-        }
+    /// Like [`TritonRestClient::send_request`], bounded by `deadline`.
+    ///
+    /// Each attempt times out when the deadline passes, and no retry is
+    /// attempted if its backoff would end after the deadline. Returns
+    /// [`TrustonError::DeadlineExceeded`] if the deadline passes without response.
+    ///
+    /// Requests carrying an `affinity` token go to the endpoint it is pinned
+    /// to, and pin it to the endpoint that answers.
+    ///
+    /// The response is returned with the number of requests sent.
+    pub(crate) async fn send_request_until<F>(
+        &self,
+        idempotent: bool,
+        deadline: Option<Deadline>,
+        affinity: Option<&AffinityToken>,
+        build: F,
+    ) -> Result<(reqwest::Response, u32), TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.send_request_from(idempotent, deadline, affinity, None, build).await
+    }
+
+    /// Like [`TritonRestClient::send_request_until`], sent to the endpoint
+    /// of index `endpoint` (instead of the active one) until it fails over.
+    pub(crate) async fn send_request_from<F>(
+        &self,
+        idempotent: bool,
+        deadline: Option<Deadline>,
+        affinity: Option<&AffinityToken>,
+        endpoint: Option<usize>,
+        build: F,
+    ) -> Result<(reqwest::Response, u32), TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.send_attempts(idempotent, true, deadline, affinity, endpoint, build).await
+    }
+
+    /// Like [`TritonRestClient::send_request_until`] for a request whose body
+    /// can only be sent once: it is neither retried nor failed over, and fails
+    /// with [`TrustonError::InferenceError`] when the endpoint is unreachable
+    /// and a failover endpoint is left.
+    pub(crate) async fn send_request_once<F>(
+        &self,
+        deadline: Option<Deadline>,
+        affinity: Option<&AffinityToken>,
+        build: F,
+    ) -> Result<(reqwest::Response, u32), TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        self.send_attempts(false, false, deadline, affinity, None, build).await
+    }
 
-        let response_struct: InferResponse = resp
-            .json::<InferResponse>()
-            .await
-            .map_err(|e| TrustonError::ParseError(e.to_string()))?;
-
- 
-        let mut converted_outputs = Vec::new();
-        for output in &response_struct.outputs {
-            let data = match output.datatype.as_str() {
-                "UINT8" => self.convert_output::<u8>(output).map(DataType::U8), 
-                "UINT16" => self.convert_output::<u16>(output).map(DataType::U16),
-                "UINT64" => self.convert_output::<u64>(output).map(DataType::U64),
-                "INT8" => self.convert_output::<i8>(output).map(DataType::I8),
-                "INT16" => self.convert_output::<i16>(output).map(DataType::I16),
-                "INT32" => self.convert_output::<i32>(output).map(DataType::I32),
-                "INT64" => self.convert_output::<i64>(output).map(DataType::I64),
-                "FP32" => self.convert_output::<f32>(output).map(DataType::F32),
-                "FP64" => self.convert_output::<f64>(output).map(DataType::F64),
-                "BF16" => self.convert_output::<u16>(output).map(DataType::Bf16),
-                "BYTES" => self.convert_output_string(output).map(DataType::String), 
-            
-                _ => Some(DataType::Raw(output.data.clone())),
+    /// Send attempts of a request until one is not retried, resending it
+    /// only if `resend`.
+    async fn send_attempts<F>(
+        &self,
+        idempotent: bool,
+        resend: bool,
+        deadline: Option<Deadline>,
+        affinity: Option<&AffinityToken>,
+        endpoint: Option<usize>,
+        build: F,
+    ) -> Result<(reqwest::Response, u32), TrustonError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let finish = |result: Result<reqwest::Response, reqwest::Error>, sent| match result {
+            Ok(resp) => Ok((resp, sent)),
+            Err(e) if e.is_timeout() && deadline.is_some_and(|d| d.is_expired()) => {
+                Err(TrustonError::DeadlineExceeded)
+            }
+            Err(e) => Err(e.into()),
+        };
+
+        let mut attempt = 1;
+        let mut failovers = 0;
+        let mut pinned =
+            endpoint.or_else(|| affinity.and_then(|token| self.affinity.endpoint(token)));
+        loop {
+            let endpoint = pinned.unwrap_or_else(|| self.active_endpoint.load(Ordering::Relaxed));
+            let mut builder = build();
+            if let Some(deadline) = deadline {
+                if deadline.is_expired() {
+                    return Err(TrustonError::DeadlineExceeded);
+                }
+                // a per-request timeout replaces the one of the client
+                let remaining = deadline.remaining();
+                builder = builder.timeout(self.timeout.map_or(remaining, |t| t.min(remaining)));
+            }
+            if let Some(provider) = &self.token_provider {
+                let token = provider.token().await?;
+                let value = auth::sensitive(&format!("Bearer {}", token))?;
+                builder = builder.header(AUTHORIZATION, value);
+            }
+            let (client, request) = builder.build_split();
+            let mut request = request?;
+            if endpoint != 0 {
+                self.redirect_to_endpoint(&mut request, endpoint)?;
+            }
+            for interceptor in &self.interceptors {
+                interceptor.on_request(&mut request).await?;
+            }
+            let info = RequestInfo {
+                method: request.method().clone(),
+                url: request.url().clone(),
+                attempt,
             };
-        
-            if let Some(data) = data {
-                converted_outputs.push(InferOutput {
-                    name: output.name.clone(),
-                    datatype: output.datatype.clone(),
-                    shape: output.shape.clone(),
-                    data,
+            let method = info.method.to_string();
+            let url = info.url.to_string();
+
+            self.emit(|| ClientEvent::RequestStarted {
+                method: method.clone(),
+                url: url.clone(),
+                attempt,
+            });
+            let started = std::time::Instant::now();
+            #[cfg(feature = "test-util")]
+            let result = match &self.faults {
+                Some(faults) => faults.execute(&client, request).await,
+                None => client.execute(request).await,
+            };
+            #[cfg(not(feature = "test-util"))]
+            let result = client.execute(request).await;
+            let result = self.intercept_response(&info, result).await?;
+            let elapsed = started.elapsed();
+            if let (Ok(_), Some(token)) = (&result, affinity) {
+                self.affinity.pin(token, endpoint);
+            }
+            match &result {
+                Ok(resp) => {
+                    tracing::Span::current().record("status", resp.status().as_u16());
+                    self.emit(|| ClientEvent::RequestCompleted {
+                        method: method.clone(),
+                        url: url.clone(),
+                        status: resp.status().as_u16(),
+                        elapsed,
+                    })
+                }
+                Err(e) => self.emit(|| ClientEvent::RequestFailed {
+                    method: method.clone(),
+                    url: url.clone(),
+                    error: e.to_string(),
+                    elapsed,
+                }),
+            }
+
+            if let Err(e) = &result
+                && FailureKind::of(e) == FailureKind::Connection
+                && failovers + 1 < self.endpoints.len()
+            {
+                let next = (endpoint + 1) % self.endpoints.len();
+                if !resend {
+                    return Err(TrustonError::InferenceError(format!(
+                        "{} unreachable ({}), a streamed body can't fail over to {}",
+                        self.endpoints[endpoint], e, self.endpoints[next]
+                    )));
+                }
+                // another request may already have moved on
+                let _ = self.active_endpoint.compare_exchange(
+                    endpoint,
+                    next,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+                tracing::warn!(
+                    "{} unreachable ({}), failing over to {}",
+                    self.endpoints[endpoint],
+                    e,
+                    self.endpoints[next]
+                );
+                self.emit(|| ClientEvent::Failover {
+                    method: method.clone(),
+                    from: self.endpoints[endpoint].clone(),
+                    to: self.endpoints[next].clone(),
+                    error: e.to_string(),
                 });
+                if pinned.is_some() {
+                    pinned = Some(next);
+                }
+                failovers += 1;
+                continue;
             }
+
+            let retryable = match &result {
+                Ok(resp) => self.retry.is_retryable_status(resp.status().as_u16()),
+                Err(e) => self.retry.is_retryable_error(e),
+            };
+            if !retryable || !resend || !self.retry.allows_retry(attempt, idempotent) {
+                return finish(result, attempt + failovers as u32);
+            }
+
+            let delay = self.retry.backoff(attempt);
+            if deadline.is_some_and(|d| d.remaining() <= delay) {
+                tracing::warn!("attempt {} failed, no time left for a retry", attempt);
+                return finish(result, attempt + failovers as u32);
+            }
+            self.emit(|| ClientEvent::Retry {
+                method: method.clone(),
+                url: url.clone(),
+                attempt,
+                delay,
+                reason: match &result {
+                    Ok(resp) => format!("status {}", resp.status()),
+                    Err(e) => e.to_string(),
+                },
+            });
+            match &result {
+                Ok(resp) => tracing::warn!(
+                    "attempt {} got status {}, retrying in {:?}",
+                    attempt,
+                    resp.status(),
+                    delay
+                ),
+                Err(e) => {
+                    tracing::warn!("attempt {} failed: {}, retrying in {:?}", attempt, e, delay)
+                }
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Point `request`, built against `base_url`, to `endpoints[endpoint]`.
+    ///
+    /// Fails rather than sending the request to the primary endpoint when
+    /// its URL cannot be moved.
+    fn redirect_to_endpoint(
+        &self,
+        request: &mut reqwest::Request,
+        endpoint: usize,
+    ) -> Result<(), TrustonError> {
+        let url = request.url().as_str();
+        // the URL of the request is normalised, e.g. `HTTP://Host:80` became
+        // `http://host/`, so it is matched against the parsed base URL
+        let base = reqwest::Url::parse(&self.endpoints[0]).ok();
+        let path = base.as_ref().and_then(|base| {
+            let path = url.strip_prefix(base.as_str().trim_end_matches('/'))?;
+            (path.is_empty() || path.starts_with(['/', '?'])).then_some(path)
+        });
+        let redirected = path.and_then(|path| {
+            reqwest::Url::parse(&format!("{}{}", self.endpoints[endpoint], path)).ok()
+        });
+        match redirected {
+            Some(redirected) => {
+                *request.url_mut() = redirected;
+                Ok(())
+            }
+            None => Err(TrustonError::ConfigError(format!(
+                "cannot send {} to endpoint {}",
+                url, self.endpoints[endpoint]
+            ))),
+        }
+    }
+
+    /// Hand the outcome of an attempt to the interceptors, last added first.
+    ///
+    /// Fails if an interceptor rejects the response.
+    async fn intercept_response(
+        &self,
+        info: &RequestInfo,
+        result: Result<reqwest::Response, reqwest::Error>,
+    ) -> Result<Result<reqwest::Response, reqwest::Error>, TrustonError> {
+        match result {
+            Ok(mut resp) => {
+                for interceptor in self.interceptors.iter().rev() {
+                    resp = interceptor.on_response(info, resp).await?;
+                }
+                Ok(Ok(resp))
+            }
+            Err(e) => {
+                for interceptor in self.interceptors.iter().rev() {
+                    interceptor.on_error(info, &e);
+                }
+                Ok(Err(e))
+            }
+        }
+    }
+
+    /// Check the content type of a successful response and deserialize its JSON body.
+    ///
+    /// A missing `Content-Type` is tolerated. Anything other than JSON (or the
+    /// binary tensor extension format) yields [`TrustonError::UnexpectedContentType`]
+    /// with the start of the body, instead of an obscure JSON parse error.
+    /// Bodies compressed with one of the client's response codecs are decompressed first.
+    pub(crate) async fn parse_json<T: serde::de::DeserializeOwned>(
+        &self,
+        resp: reqwest::Response,
+    ) -> Result<T, TrustonError> {
+        let resp = Self::check_content_type(resp).await?;
+        let content_encoding = resp
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|ce| ce.to_str().ok())
+            .map(str::to_string);
+        let body = compression::decode_body(
+            &self.response_codecs,
+            content_encoding.as_deref(),
+            resp.bytes().await?,
+            compression::MAX_DECOMPRESSED_SIZE,
+        )?;
+        serde_json::from_slice(&body).map_err(|e| TrustonError::ParseError(e.to_string()))
+    }
+
+    /// Fail with [`TrustonError::UnexpectedContentType`] unless `resp` holds
+    /// JSON (or the binary tensor extension format), see [`TritonRestClient::parse_json`].
+    pub(crate) async fn check_content_type(
+        resp: reqwest::Response,
+    ) -> Result<reqwest::Response, TrustonError> {
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .map(str::to_string);
+        let binary = resp.headers().contains_key(INFERENCE_HEADER_CONTENT_LENGTH);
+
+        if let Some(content_type) = content_type
+            .filter(|ct| !is_expected_content_type(ct, binary))
+        {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(TrustonError::UnexpectedContentType {
+                content_type,
+                snippet: snippet(&body),
+            });
+        }
+        Ok(resp)
+    }
+
+    /// Base URL of a model, optionally pinned to one version:
+    /// `{base}/v2/models/{model_name}[/versions/{version}]`, the name and
+    /// version percent-encoded.
+    pub(crate) fn model_url(&self, model_name: &str, version: Option<&str>) -> String {
+        let path = ApiPath::model(model_name);
+        match version {
+            Some(version) => format!("{}{}", self.base_url, path.version(version)),
+            None => format!("{}{}", self.base_url, path),
+        }
+    }
+
+    /// Turns a non-success HTTP response into a [`TrustonError::ServerError`].
+    ///
+    /// The error reported by Triton is kept as the error message, along with
+    /// the URL and the model it names.
+    pub(crate) async fn server_error(resp: reqwest::Response) -> TrustonError {
+        let status = resp.status().as_u16();
+        let url = resp.url().to_string();
+        let model = model_from_path(resp.url().path());
+        if resp.status().is_redirection() {
+            let location = resp
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|loc| loc.to_str().ok())
+                .unwrap_or("<missing>");
+            let message = format!(
+                "redirect to {} was not followed (see RedirectPolicy)",
+                location
+            );
+            return TrustonError::ServerError { status, message, url, model };
         }
-        Ok(InferResults { outputs: converted_outputs })
+        let message = match resp.text().await {
+            Ok(body) => triton_message(&body),
+            Err(_) => "No response body".to_string(),
+        };
+        TrustonError::ServerError { status, message, url, model }
     }
 }
 
@@ -362,9 +1337,13 @@ impl TritonRestClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::io::{DataType, InferInput, InferRequestedOutput};
+    use crate::client::options::InferOptions;
+    use crate::client::test_server::{answer, read_request, response, serve, serve_raw};
     use tokio;
 
     #[tokio::test]
+    #[allow(deprecated)]
     async fn test_is_server_live() {
         crate::init_tracing();
 
@@ -373,7 +1352,929 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn builder_accepts_pool_settings() {
+        let result = TritonRestClient::builder("http://localhost:8000")
+            .pool_max_idle_per_host(64)
+            .pool_idle_timeout(Some(Duration::from_secs(30)))
+            .tcp_keepalive(Duration::from_secs(15))
+            .tcp_nodelay(false)
+            .http2_prior_knowledge()
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn builder_rejects_invalid_header() {
+        let result = TritonRestClient::builder("http://localhost:8000")
+            .default_header("bad header", "value")
+            .build();
+        assert!(matches!(result, Err(TrustonError::ConfigError(_))));
+    }
+
+    #[test]
+    fn builder_rejects_invalid_proxy() {
+        let result = TritonRestClient::builder("http://localhost:8000")
+            .proxy("not a url")
+            .build();
+        assert!(matches!(result, Err(TrustonError::ConfigError(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_socket_transport() {
+        let path = std::env::temp_dir().join(format!("truston-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            answer(socket, &response(200, "{}")).await.head.to_lowercase()
+        });
+
+        let client = TritonRestClient::builder("http://triton.local")
+            .unix_socket(&path)
+            .build()
+            .unwrap();
+        assert!(client.server_live().await.unwrap().is_healthy());
+        let request = server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(request.starts_with("get /v2/health/live "), "{}", request);
+        assert!(request.contains("host: triton.local\r\n"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn custom_http_client() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            answer(socket, &response(200, "{}")).await.head.to_lowercase()
+        });
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-transport", HeaderValue::from_static("custom"));
+        let http = Client::builder().default_headers(headers).build().unwrap();
+        let client = TritonRestClient::builder(&base_url)
+            .http_client(http.clone())
+            .build()
+            .unwrap();
+        assert!(client.server_live().await.unwrap().is_healthy());
+        let request = server.await.unwrap();
+        assert!(request.contains("x-transport: custom\r\n"), "{}", request);
+
+        // settings sent as default headers would be silently dropped
+        let ignored = TritonRestClient::builder(&base_url)
+            .default_header("x-ignored", "1")
+            .auth(Auth::Bearer("secret".into()))
+            .http_client(http.clone())
+            .build();
+        assert!(matches!(
+            ignored,
+            Err(TrustonError::ConfigError(message))
+                if message.starts_with("default headers, auth would be ignored")
+        ));
+
+        // tokens of a provider are added per request, not as default headers
+        #[derive(Debug)]
+        struct Token;
+        #[async_trait::async_trait]
+        impl TokenProvider for Token {
+            async fn token(&self) -> Result<String, TrustonError> {
+                Ok("t".into())
+            }
+        }
+        let provider = TritonRestClient::builder(&base_url)
+            .auth(Auth::provider(Token))
+            .http_client(http)
+            .build();
+        assert!(provider.is_ok());
+    }
+
+    #[test]
+    fn redirect_policy_decisions() {
+        let preserving = RedirectPolicy::default();
+        assert!(preserving.follows(StatusCode::TEMPORARY_REDIRECT, 0));
+        assert!(preserving.follows(StatusCode::PERMANENT_REDIRECT, 9));
+        assert!(!preserving.follows(StatusCode::PERMANENT_REDIRECT, 10));
+        assert!(!preserving.follows(StatusCode::FOUND, 0));
+        assert!(!preserving.follows(StatusCode::SEE_OTHER, 0));
+
+        assert!(RedirectPolicy::Follow(2).follows(StatusCode::FOUND, 1));
+        assert!(!RedirectPolicy::Follow(2).follows(StatusCode::FOUND, 2));
+        assert!(!RedirectPolicy::Disabled.follows(StatusCode::TEMPORARY_REDIRECT, 0));
+    }
+
+    #[test]
+    fn content_type_validation() {
+        assert!(is_expected_content_type("application/json", false));
+        assert!(is_expected_content_type("Application/JSON; charset=utf-8", false));
+        assert!(is_expected_content_type("application/problem+json", false));
+        assert!(is_expected_content_type("application/octet-stream", true));
+        assert!(!is_expected_content_type("application/octet-stream", false));
+        assert!(!is_expected_content_type("text/html; charset=utf-8", false));
+    }
+
+    #[test]
+    fn snippet_truncates_long_bodies() {
+        assert_eq!(snippet("<html>"), "<html>");
+        let long = "x".repeat(SNIPPET_LEN + 10);
+        assert_eq!(snippet(&long).len(), SNIPPET_LEN + 3);
+    }
+
+    #[test]
+    fn model_url_with_and_without_version() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        assert_eq!(
+            client.model_url("bert", None),
+            "http://localhost:8000/v2/models/bert"
+        );
+        assert_eq!(
+            client.model_url("bert", Some("3")),
+            "http://localhost:8000/v2/models/bert/versions/3"
+        );
+        assert_eq!(
+            client.model_url("team/bert v2", None),
+            "http://localhost:8000/v2/models/team%2Fbert%20v2"
+        );
+    }
+
+    #[cfg(feature = "local")]
+    #[tokio::test]
+    async fn local_client_runs_on_local_set() {
+        use std::rc::Rc;
+
+        struct RcClient(Rc<bool>);
+
+        #[async_trait(?Send)]
+        impl TritonClient for RcClient {
+            async fn is_server_live(&self) -> Result<bool, TrustonError> {
+                Ok(*self.0)
+            }
+
+            async fn is_server_ready(&self) -> Result<bool, TrustonError> {
+                Ok(*self.0)
+            }
+
+            async fn is_model_ready(&self, _: &str, _: Option<&str>) -> Result<bool, TrustonError> {
+                Ok(*self.0)
+            }
+
+            async fn model_metadata(&self, _: &str) -> Result<ModelMetadata, TrustonError> {
+                Err(TrustonError::InferenceError("no model".to_string()))
+            }
+
+            async fn infer(
+                &self,
+                _: Vec<InferInput>,
+                _: &str,
+                _: &InferOptions,
+            ) -> Result<InferResults, TrustonError> {
+                Ok(InferResults::default())
+            }
+        }
+
+        let local = tokio::task::LocalSet::new();
+        let live = local
+            .run_until(async {
+                let client = RcClient(Rc::new(true));
+                tokio::task::spawn_local(async move { client.is_server_live().await })
+                    .await
+                    .unwrap()
+            })
+            .await;
+        assert!(live.unwrap());
+    }
+
+    #[tokio::test]
+    async fn infer_falls_back_on_server_error() {
+        let ok = r#"{"model_name":"m","model_version":"1","parameters":{"cache_hit":true},
+            "outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = serve(vec![(500, "{}"), (200, ok)]).await;
+        let client = TritonRestClient::new(&base_url);
+        let mut events = client.events();
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let options = InferOptions::new()
+            .with_model_version("2")
+            .with_fallback_version("1");
+        let results = client.infer_with_options(vec![input], "m", &options).await.unwrap();
+
+        assert_eq!(results.outputs[0].data.as_i32_vec(), Some(vec![7]));
+        assert_eq!(client.fallbacks_used(), 1);
+        let provenance = &results.provenance;
+        assert_eq!(provenance.endpoint, format!("{}/v2/models/m/versions/1/infer", base_url));
+        assert_eq!(provenance.model_version, "1");
+        assert_eq!(provenance.attempts, 2);
+        assert_eq!(provenance.cache_hit, Some(true));
+        assert!(provenance.elapsed > Duration::ZERO);
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].line(), "POST /v2/models/m/versions/2/infer HTTP/1.1");
+        assert_eq!(requests[1].line(), "POST /v2/models/m/versions/1/infer HTTP/1.1");
+        let fallback = std::iter::from_fn(|| events.try_recv().ok())
+            .find(|e| matches!(e, ClientEvent::Fallback { .. }));
+        assert!(matches!(
+            fallback,
+            Some(ClientEvent::Fallback { status: 500, fallback_version: Some(v), .. }) if v == "1"
+        ));
+    }
+
+    #[tokio::test]
+    async fn non_idempotent_infer_does_not_fall_back() {
+        let (base_url, server) = serve(vec![(500, r#"{"error":"failed"}"#)]).await;
+        let client = TritonRestClient::new(&base_url);
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let options = InferOptions::new().with_fallback_version("1").with_non_idempotent();
+        let result = client.infer_with_options(vec![input], "m", &options).await;
+
+        assert!(matches!(result, Err(TrustonError::ServerError { status: 500, .. })));
+        assert_eq!(client.fallbacks_used(), 0);
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].line(), "POST /v2/models/m/infer HTTP/1.1");
+    }
+
+    #[cfg(feature = "half")]
+    #[tokio::test]
+    async fn infer_parses_fp16_outputs() {
+        let body = r#"{"outputs":[{"name":"y","datatype":"FP16","shape":[2],"data":[0.5,-1.0]}]}"#;
+        let (base_url, _server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::new(&base_url);
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let results = client.infer(vec![input], "m", None).await.unwrap();
+        let expected = vec![half::f16::from_f32(0.5), half::f16::from_f32(-1.0)];
+        assert_eq!(results.outputs[0].data.as_f16_vec(), Some(expected));
+    }
+
+    #[tokio::test]
+    async fn infer_keeps_unknown_outputs_raw() {
+        let body = r#"{"outputs":[{"name":"y","datatype":"FP8","shape":[2],"data":[0.5,1]},
+            {"name":"z","datatype":"INT32","shape":[1],"data":[3]}]}"#;
+        let (base_url, _server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::new(&base_url);
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let results = client.infer(vec![input], "m", None).await.unwrap();
+        let y = &results.outputs[0];
+        assert_eq!(y.datatype, crate::client::dtype::TritonDtype::Other("FP8".into()));
+        assert!(matches!(&y.data, DataType::Raw(v) if *v == serde_json::json!([0.5, 1])));
+        assert_eq!(results.outputs[1].data.as_i32_vec(), Some(vec![3]));
+    }
+
+    #[tokio::test]
+    async fn infer_casts_outputs_when_datatype_request_is_ignored() {
+        let body = r#"{"outputs":[{"name":"y","datatype":"FP32","shape":[2],"data":[1.0,2.0]},
+            {"name":"z","datatype":"INT64","shape":[1],"data":[-1]}]}"#;
+        let (base_url, _server) = serve(vec![(200, body), (200, body)]).await;
+        let client = TritonRestClient::new(&base_url);
+        let input = || InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+
+        let options = InferOptions::new()
+            .with_outputs([InferRequestedOutput::new("y").with_datatype("INT64"), "z".into()]);
+        let results = client.infer_with_options(vec![input()], "m", &options).await.unwrap();
+        assert_eq!(results.outputs[0].datatype, "INT64");
+        assert_eq!(results.outputs[0].data.as_i64_vec(), Some(vec![1, 2]));
+        assert_eq!(results.outputs[1].data.as_i64_vec(), Some(vec![-1]));
+
+        let options = InferOptions::new()
+            .with_outputs([InferRequestedOutput::new("z").with_datatype("UINT8")]);
+        let err = client.infer_with_options(vec![input()], "m", &options).await.unwrap_err();
+        assert!(matches!(err, TrustonError::InferenceError(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn infer_rejects_overflowing_output_shapes() {
+        let body = r#"{"outputs":[{"name":"y","datatype":"FP32",
+            "shape":[4294967296,4294967296,4294967296],"data":[1.0]}]}"#;
+        let (base_url, _server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::new(&base_url);
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let err = client.infer(vec![input], "m", None).await.unwrap_err();
+        assert!(matches!(err, TrustonError::InvalidShape(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn validate_inputs_checks_cached_metadata() {
+        let metadata = r#"{"name":"m","versions":["1"],"platform":"onnxruntime_onnx",
+            "inputs":[{"name":"x","datatype":"INT32","shape":[-1]}],"outputs":[]}"#;
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = serve(vec![(200, metadata), (200, ok)]).await;
+        let client = TritonRestClient::builder(&base_url).validate_inputs().build().unwrap();
+
+        let wrong = InferInput::new("x".into(), vec![1], DataType::F32(vec![1.0]));
+        let err = client.infer(vec![wrong], "m", None).await.unwrap_err();
+        assert!(matches!(err, TrustonError::ValidationError { .. }), "{:?}", err);
+
+        let right = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        client.infer(vec![right], "m", None).await.unwrap();
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].line(), "GET /v2/models/m HTTP/1.1");
+        assert_eq!(requests[1].line(), "POST /v2/models/m/infer HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn trait_methods_use_rest_endpoints() {
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = serve(vec![(503, ""), (503, ""), (200, ""), (200, ok)]).await;
+        let client: &dyn TritonClient = &TritonRestClient::new(&base_url);
+
+        assert!(!client.is_server_live().await.unwrap());
+        assert!(!client.is_server_ready().await.unwrap());
+        assert!(client.is_model_ready("m", None).await.unwrap());
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let results = client.infer(vec![input], "m", &InferOptions::new()).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_i32_vec(), Some(vec![7]));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].line(), "GET /v2/health/live HTTP/1.1");
+        assert_eq!(requests[1].line(), "GET /v2/health/ready HTTP/1.1");
+        assert_eq!(requests[2].line(), "GET /v2/models/m/ready HTTP/1.1");
+        assert_eq!(requests[3].line(), "POST /v2/models/m/infer HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn string_datatype_aliases() {
+        let body = r#"{"outputs":[{"name":"y","datatype":"STRING","shape":[1],"data":["cat"]}]}"#;
+        let (base_url, _server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::builder(&base_url)
+            .string_datatype(StringDatatype::String)
+            .build()
+            .unwrap();
+
+        let input = || InferInput::new("x".into(), vec![1], DataType::String(vec!["a".into()]));
+        let (request, _) = client.encode_infer_body(&[input()], &InferOptions::new()).unwrap();
+        let request: serde_json::Value = serde_json::from_slice(&request).unwrap();
+        assert_eq!(request["inputs"][0]["datatype"], "STRING");
+
+        let results = client.infer(vec![input()], "m", None).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_str_vec(), Some(vec!["cat".to_string()]));
+    }
+
+    /// Base URL of a port nothing listens on.
+    async fn unreachable_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn connection_failure_fails_over_even_when_not_idempotent() {
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = serve(vec![(200, ok)]).await;
+        let primary = unreachable_url().await;
+        let client = TritonRestClient::builder(&primary)
+            .failover_endpoint(format!("{}/", base_url))
+            .build()
+            .unwrap();
+        let mut events = client.events();
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let options = InferOptions::new().with_non_idempotent();
+        let results = client.infer_with_options(vec![input], "m", &options).await.unwrap();
+
+        assert_eq!(results.outputs[0].data.as_i32_vec(), Some(vec![7]));
+        assert_eq!(results.provenance.endpoint, format!("{}/v2/models/m/infer", base_url));
+        assert_eq!(results.provenance.attempts, 2);
+        assert_eq!(client.active_endpoint(), base_url);
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].line(), "POST /v2/models/m/infer HTTP/1.1");
+        let failover = std::iter::from_fn(|| events.try_recv().ok())
+            .find(|e| matches!(e, ClientEvent::Failover { .. }));
+        assert!(matches!(failover, Some(ClientEvent::Failover { from, .. }) if from == primary));
+    }
+
+    #[tokio::test]
+    async fn infer_rejects_inconsistent_shapes() {
+        let client = TritonRestClient::new(&unreachable_url().await);
+        let input = InferInput::new("x".into(), vec![2, 3], DataType::F32(vec![0.0; 5]));
+        let err = client.infer(vec![input], "m", None).await.unwrap_err();
+        assert!(
+            matches!(err, TrustonError::ShapeMismatch { expected: 6, actual: 5, .. }),
+            "{:?}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn affinity_token_sticks_to_its_endpoint() {
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (primary, primary_server) = serve(vec![(200, ok)]).await;
+        let (secondary, secondary_server) = serve(vec![(200, ok)]).await;
+        let client = TritonRestClient::builder(&primary)
+            .failover_endpoint(&secondary)
+            .build()
+            .unwrap();
+        let token = AffinityToken::from("conversation-1");
+        client.affinity.pin(&token, 1);
+
+        let input = || InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let options = InferOptions::new().with_affinity(token.clone());
+        client.infer_with_options(vec![input()], "m", &options).await.unwrap();
+        assert_eq!(secondary_server.await.unwrap().len(), 1);
+        assert_eq!(client.affinity_endpoint(&token), Some(secondary.as_str()));
+
+        // requests without a token still go to the active endpoint
+        client.infer(vec![input()], "m", None).await.unwrap();
+        assert_eq!(primary_server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn redirect_matches_normalised_base_urls() {
+        let ok = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (secondary, server) = serve(vec![(200, ok)]).await;
+        // requests are built against `HTTP://...` and sent to `http://...`
+        let primary = unreachable_url().await.replace("http://", "HTTP://");
+        let client = TritonRestClient::builder(&primary)
+            .failover_endpoint(&secondary)
+            .build()
+            .unwrap();
+        let token = AffinityToken::from("conversation-1");
+        client.affinity.pin(&token, 1);
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let options = InferOptions::new().with_affinity(token);
+        let results = client.infer_with_options(vec![input], "m", &options).await.unwrap();
+        assert_eq!(results.provenance.endpoint, format!("{}/v2/models/m/infer", secondary));
+        let requests = server.await.unwrap();
+        assert_eq!(requests[0].line(), "POST /v2/models/m/infer HTTP/1.1");
+
+        let mut request = client.http.get(format!("{}/v2/health/live", secondary)).build().unwrap();
+        let err = client.redirect_to_endpoint(&mut request, 1).unwrap_err();
+        assert!(matches!(err, TrustonError::ConfigError(_)), "{:?}", err);
+    }
+
+    #[tokio::test]
+    async fn infer_many_keeps_order_and_errors() {
+        let ok = r#"{"model_name":"a","outputs":[]}"#;
+        let responses = vec![(200, ok), (400, r#"{"error":"bad input"}"#), (200, ok)];
+        let (base_url, server) = serve(responses).await;
+        let client = TritonRestClient::new(&base_url);
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+
+        let requests = vec![(x(), "a"), (x(), "b"), (x(), "a")];
+        let results = client.infer_many(requests, &InferOptions::new(), 1).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().provenance.model_name, "a");
+        assert!(matches!(results[1], Err(TrustonError::ServerError { status: 400, .. })));
+        assert!(results[2].is_ok());
+        let requests = server.await.unwrap();
+        assert_eq!(requests[1].line(), "POST /v2/models/b/infer HTTP/1.1");
+    }
+
+    #[tokio::test]
+    async fn metrics_recorder_sees_every_inference() {
+        let ok = r#"{"model_name":"a","outputs":[]}"#;
+        let responses = vec![(200, ok), (400, r#"{"error":"bad input"}"#)];
+        let (base_url, server) = serve(responses).await;
+        let infer_metrics = crate::client::metrics::InferMetrics::new();
+        let client = TritonRestClient::builder(&base_url)
+            .metrics_recorder(infer_metrics.clone())
+            .build()
+            .unwrap();
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+
+        client.infer(x(), "a", None).await.unwrap();
+        client.infer(x(), "a", None).await.unwrap_err();
+        let stats = infer_metrics.stats("a").unwrap();
+        assert_eq!((stats.requests, stats.errors), (2, 1));
+        assert!(stats.request_bytes > 0);
+        assert_eq!(stats.response_bytes, (ok.len() + r#"{"error":"bad input"}"#.len()) as u64);
+        assert_eq!(stats.latency_buckets.iter().sum::<u64>(), 2);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn infer_sends_traceparent() {
+        use crate::client::trace::TraceContext;
+
+        let (base_url, server) = serve(vec![(200, r#"{"model_name":"m","outputs":[]}"#); 3]).await;
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let parent = TraceContext::parse("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01")
+            .unwrap();
+
+        let client = TritonRestClient::new(&base_url);
+        client.infer(x(), "m", None).await.unwrap();
+        let options = InferOptions::new().with_trace_context(parent);
+        client.infer_with_options(x(), "m", &options).await.unwrap();
+        let propagating = TritonRestClient::builder(&base_url).trace_propagation().build().unwrap();
+        propagating.infer(x(), "m", None).await.unwrap();
+
+        let requests = server.await.unwrap();
+        let traceparents: Vec<_> = requests.iter().map(|r| r.header("traceparent")).collect();
+        assert_eq!(traceparents[0], None);
+        let sent = TraceContext::parse(traceparents[1].unwrap()).unwrap();
+        assert_eq!(sent.trace_id, parent.trace_id);
+        assert_ne!(sent.span_id, parent.span_id);
+        let root = TraceContext::parse(traceparents[2].unwrap()).unwrap();
+        assert_ne!(root.trace_id, parent.trace_id);
+    }
+
+    #[tokio::test]
+    async fn scrape_fetches_metrics_path() {
+        let text = "nv_inference_count{model=\"m\",version=\"1\"} 3\n";
+        let (base_url, server) = serve(vec![(200, text), (200, text)]).await;
+        let metrics = crate::client::metrics::scrape(&base_url).await.unwrap();
+        assert_eq!(metrics.model("m", "1").unwrap().inference_count, 3.0);
+        let url = format!("{}/metrics/", base_url);
+        crate::client::metrics::scrape(&url).await.unwrap();
+        let requests = server.await.unwrap();
+        assert!(requests.iter().all(|request| request.line() == "GET /metrics HTTP/1.1"));
+    }
+
+    #[derive(Debug, Default)]
+    struct Signer {
+        log: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Interceptor for Arc<Signer> {
+        async fn on_request(&self, request: &mut reqwest::Request) -> Result<(), TrustonError> {
+            request.url_mut().set_query(Some("signed=1"));
+            Ok(())
+        }
+
+        async fn on_response(
+            &self,
+            request: &RequestInfo,
+            response: reqwest::Response,
+        ) -> Result<reqwest::Response, TrustonError> {
+            let line = format!("{} {} {}", request.method, request.url.path(), response.status());
+            self.log.lock().unwrap().push(line);
+            if response.status() == StatusCode::IM_A_TEAPOT {
+                return Err(TrustonError::AuthError("rejected".into()));
+            }
+            Ok(response)
+        }
+
+        fn on_error(&self, request: &RequestInfo, _: &reqwest::Error) {
+            self.log.lock().unwrap().push(format!("{} failed", request.url.path()));
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors_see_every_request() {
+        let ok = r#"{"model_name":"m","outputs":[]}"#;
+        let (base_url, server) = serve(vec![(200, ""), (200, ok), (418, "")]).await;
+        let signer = Arc::new(Signer::default());
+        let client = TritonRestClient::builder(&base_url)
+            .interceptor(signer.clone())
+            .build()
+            .unwrap();
+
+        assert!(client.server_ready().await.unwrap().is_healthy());
+        let x = vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        client.infer(x, "m", None).await.unwrap();
+        let err = client.model_metadata("m").await.unwrap_err();
+        assert!(matches!(err, TrustonError::AuthError(_)));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[1].line(), "POST /v2/models/m/infer?signed=1 HTTP/1.1");
+        assert_eq!(*signer.log.lock().unwrap(), vec![
+            "GET /v2/health/ready 200 OK",
+            "POST /v2/models/m/infer 200 OK",
+            "GET /v2/models/m 418 I'm a teapot",
+        ]);
+
+        let unreachable = TritonRestClient::builder("http://127.0.0.1:1")
+            .retry_policy(RetryPolicy::new(1))
+            .interceptor(signer.clone())
+            .build()
+            .unwrap();
+        assert!(unreachable.server_live().await.is_err());
+        assert_eq!(signer.log.lock().unwrap().last().unwrap(), "/v2/health/live failed");
+    }
+
+    #[tokio::test]
+    async fn rate_limit_delays_inferences() {
+        use crate::client::limit::RateLimit;
+
+        let ok = r#"{"model_name":"m","outputs":[]}"#;
+        let (base_url, server) = serve(vec![(200, ok), (200, ok)]).await;
+        let infer_metrics = crate::client::metrics::InferMetrics::new();
+        let client = TritonRestClient::builder(&base_url)
+            .max_concurrent_requests(1)
+            .rate_limit(RateLimit::per_second(20.0).with_burst(1))
+            .metrics_recorder(infer_metrics.clone())
+            .build()
+            .unwrap();
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+
+        let requests = vec![(x(), "m"), (x(), "m")];
+        let results = client.infer_many(requests, &InferOptions::new(), 2).await;
+        assert!(results.iter().all(Result::is_ok));
+        // the second waited about 50ms for a token
+        let waited = infer_metrics.stats("m").unwrap().queue_wait_sum;
+        assert!(waited >= Duration::from_millis(30), "{:?}", waited);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn strict_outputs_reject_undecodable_values() {
+        let body = r#"{"model_name":"m","outputs":[
+            {"name":"y","datatype":"UINT8","shape":[3],"data":[1,300,2]}]}"#;
+        let (base_url, server) = serve(vec![(200, body), (200, body)]).await;
+        let input = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+
+        let lenient = TritonRestClient::new(&base_url);
+        let results = lenient.infer(input(), "m", None).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_u8_vec(), Some(vec![1, 2]));
+
+        let strict = TritonRestClient::builder(&base_url).strict_outputs().build().unwrap();
+        match strict.infer(input(), "m", None).await {
+            Err(TrustonError::ParseError(message)) => {
+                assert_eq!(message, "output y (UINT8): element 1 cannot be decoded: 300");
+            }
+            other => panic!("{:?}", other),
+        }
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_error_does_not_fail_over() {
+        let body = r#"{"error":"model m crashed"}"#;
+        let (base_url, server) = serve(vec![(500, body)]).await;
+        let (standby_url, standby) = serve(vec![]).await;
+        let client = TritonRestClient::builder(&base_url)
+            .failover_endpoint(standby_url)
+            .build()
+            .unwrap();
+
+        let input = InferInput::new("x".into(), vec![1], DataType::I32(vec![1]));
+        let result = client.infer(vec![input], "m", None).await;
+
+        match result {
+            Err(TrustonError::ServerError { status, message, url, model }) => {
+                assert_eq!((status, message.as_str()), (500, "model m crashed"));
+                assert_eq!(url, format!("{}/v2/models/m/infer", base_url));
+                assert_eq!(model.as_deref(), Some("m"));
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(client.active_endpoint(), base_url);
+        assert_eq!(server.await.unwrap().len(), 1);
+        assert!(standby.await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn infer_stops_at_deadline() {
+        let input = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+
+        // an expired deadline fails before sending anything
+        let (base_url, server) = serve(vec![]).await;
+        let client = TritonRestClient::new(&base_url);
+        let expired = Deadline::at(std::time::Instant::now() - Duration::from_millis(1));
+        let options = InferOptions::new().with_deadline(expired);
+        let result = client.infer_with_options(input(), "m", &options).await;
+        assert!(matches!(result, Err(TrustonError::DeadlineExceeded)));
+        assert!(server.await.unwrap().is_empty());
+
+        // a server that never answers is given up on when the deadline passes
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TritonRestClient::new(&format!("http://{}", listener.local_addr().unwrap()));
+        let _hang = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(socket);
+        });
+        let deadline = Deadline::after(Duration::from_millis(100));
+        let options = InferOptions::new().with_deadline(deadline);
+        let started = std::time::Instant::now();
+        let result = client.infer_with_options(input(), "m", &options).await;
+        assert!(matches!(result, Err(TrustonError::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // a far deadline doesn't lift the timeout of the client
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let client = TritonRestClient::builder(&base_url)
+            .timeout(Duration::from_millis(100))
+            .retry_policy(RetryPolicy::new(1))
+            .build()
+            .unwrap();
+        let _hang = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            drop(socket);
+        });
+        let options = InferOptions::new().with_deadline(Deadline::after(Duration::from_secs(30)));
+        let started = std::time::Instant::now();
+        let result = client.infer_with_options(input(), "m", &options).await;
+        assert!(matches!(&result, Err(TrustonError::Http(e)) if e.is_timeout()), "{:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn infer_streams_binary_outputs() {
+        use crate::client::binary::{encode_bytes_elements, encode_tensor};
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let request = read_request(&mut socket).await;
+
+            let header = r#"{"model_name":"m","model_version":"1","outputs":[
+                {"name":"y","datatype":"FP32","shape":[3],"parameters":{"binary_data_size":12}},
+                {"name":"n","datatype":"INT32","shape":[1],"data":[7]},
+                {"name":"s","datatype":"BYTES","shape":[2],
+                 "parameters":{"binary_data_size":11}}]}"#;
+            let mut body = header.as_bytes().to_vec();
+            body.extend(encode_tensor(&DataType::F32(vec![0.5, -1.0, 2.0])).unwrap());
+            body.extend(encode_bytes_elements([&b"cat"[..], b""]));
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/octet-stream\r\n\
+                 inference-header-content-length: {}\r\ncontent-length: {}\r\n\
+                 connection: close\r\n\r\n",
+                header.len(),
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            // split within the second float
+            let (first, rest) = body.split_at(header.len() + 6);
+            socket.write_all(first).await.unwrap();
+            socket.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            socket.write_all(rest).await.unwrap();
+            request
+        });
+
+        let client = TritonRestClient::new(&base_url);
+        let input = vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let options = InferOptions::new().with_binary_outputs();
+        let results = client.infer_with_options(input, "m", &options).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_f32_vec(), Some(vec![0.5, -1.0, 2.0]));
+        assert_eq!(results.outputs[1].data.as_i32_vec(), Some(vec![7]));
+        assert_eq!(format!("{:?}", results.outputs[2].data), r#"String(["cat", ""])"#);
+
+        let request = server.await.unwrap().text();
+        assert!(request.contains(r#""binary_data_output":true"#), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn infer_does_not_trust_announced_sizes() {
+        let output = r#"{"outputs":[{"name":"y","datatype":"UINT8","shape":[10000000000000],
+            "parameters":{"binary_data_size":10000000000000}}]}"#;
+        // a header longer than the body, then an output longer than the body
+        let responses = [(10_000_000_000_000usize, "{}"), (output.len(), output)]
+            .into_iter()
+            .map(|(header_len, body)| {
+                format!(
+                    "HTTP/1.1 200 OK\r\ninference-header-content-length: {}\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{}",
+                    header_len,
+                    body.len(),
+                    body
+                )
+                .into_bytes()
+            })
+            .collect();
+        let (base_url, server) = serve_raw(responses).await;
+
+        let client = TritonRestClient::new(&base_url);
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let huge_header = client.infer(x(), "m", None).await;
+        assert!(matches!(huge_header, Err(TrustonError::ParseError(_))), "{:?}", huge_header);
+        let huge_output = client.infer(x(), "m", None).await;
+        assert!(matches!(huge_output, Err(TrustonError::ParseError(_))), "{:?}", huge_output);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn infer_into_fails_on_unwritten_buffers() {
+        use crate::client::buffers::OutputBuffers;
+
+        let y = r#"{"outputs":[{"name":"y","datatype":"INT32","shape":[1],"data":[7]}]}"#;
+        let (base_url, server) = serve(vec![(200, y)]).await;
+        let client = TritonRestClient::new(&base_url);
+        let x = vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let mut outputs = OutputBuffers::new()
+            .with_buffer("y", vec![1], DataType::I32(vec![0]))
+            .with_buffer("z", vec![1], DataType::I32(vec![5]));
+
+        match client.infer_into(x, "m", &mut outputs).await {
+            Err(TrustonError::InferenceError(message)) => {
+                assert!(message.ends_with(" z"), "{}", message)
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(outputs.get("y").and_then(DataType::as_i32_slice), Some(&[7][..]));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn infer_into_reuses_buffers() {
+        use crate::client::binary::encode_tensor;
+        use crate::client::buffers::OutputBuffers;
+
+        let outputs = [("[2]", [1.0, 2.0]), ("[2]", [3.0, 4.0]), ("[1,2]", [0.0; 2])];
+        let responses = outputs
+            .into_iter()
+            .map(|(shape, values)| {
+                let header = format!(
+                    r#"{{"outputs":[{{"name":"y","datatype":"FP32","shape":{},
+                        "parameters":{{"binary_data_size":8}}}}]}}"#,
+                    shape
+                );
+                let mut body = header.as_bytes().to_vec();
+                body.extend(encode_tensor(&DataType::F32(values.to_vec())).unwrap());
+                let mut response = format!(
+                    "HTTP/1.1 200 OK\r\ninference-header-content-length: {}\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n",
+                    header.len(),
+                    body.len()
+                )
+                .into_bytes();
+                response.extend(body);
+                response
+            })
+            .collect();
+        let (base_url, server) = serve_raw(responses).await;
+
+        let client = TritonRestClient::new(&base_url);
+        let x = || vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let mut outputs =
+            OutputBuffers::new().with_buffer("y", vec![2], DataType::F32(vec![0.0; 2]));
+        let allocation = outputs.get("y").and_then(DataType::as_f32_slice).unwrap().as_ptr();
+
+        client.infer_into(x(), "m", &mut outputs).await.unwrap();
+        assert_eq!(outputs.get("y").and_then(DataType::as_f32_slice), Some(&[1.0, 2.0][..]));
+        client.infer_into(x(), "m", &mut outputs).await.unwrap();
+        let y = outputs.get("y").and_then(DataType::as_f32_slice).unwrap();
+        assert_eq!(y, &[3.0, 4.0]);
+        assert_eq!(y.as_ptr(), allocation);
+
+        let result = client.infer_into(x(), "m", &mut outputs).await;
+        assert!(matches!(result, Err(TrustonError::InferenceError(_))));
+        assert_eq!(outputs.get("y").and_then(DataType::as_f32_slice), Some(&[3.0, 4.0][..]));
+
+        let requests = server.await.unwrap();
+        let requested = r#""outputs":[{"name":"y","parameters":{"binary_data":true}}]"#;
+        assert!(requests[0].text().contains(requested), "{}", requests[0].text());
+    }
+
+    #[tokio::test]
+    async fn slow_infer_is_hedged() {
+        use crate::client::hedge::HedgePolicy;
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            // the first request never gets an answer, and is dropped by the client
+            let (mut slow, _) = listener.accept().await.unwrap();
+            let (socket, _) = listener.accept().await.unwrap();
+            answer(socket, &response(200, r#"{"model_name":"m","outputs":[]}"#)).await;
+            let mut buf = [0u8; 4096];
+            while slow.read(&mut buf).await.unwrap_or(0) > 0 {}
+        });
+        let client = TritonRestClient::builder(&base_url)
+            .hedging(HedgePolicy::fixed(Duration::from_millis(20)))
+            .build()
+            .unwrap();
+        let mut events = client.events();
+
+        let input = vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let results = client.infer(input, "m", None).await.unwrap();
+        assert_eq!(results.provenance.attempts, 2);
+        assert_eq!(client.hedges_sent(), 1);
+        let hedged = std::iter::from_fn(|| events.try_recv().ok())
+            .any(|event| matches!(event, ClientEvent::Hedged { .. }));
+        assert!(hedged);
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn infer_aborts_when_cancelled() {
+        use crate::client::options::CancellationToken;
+        use tokio::io::AsyncReadExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = TritonRestClient::new(&format!("http://{}", listener.local_addr().unwrap()));
+        let hang = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 64 * 1024];
+            // read until the client drops the connection
+            while socket.read(&mut buf).await.unwrap_or(0) > 0 {}
+        });
+
+        let token = CancellationToken::new();
+        let options = InferOptions::new().with_cancellation(token.clone());
+        let cancel = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            token.cancel();
+        });
+        let input = vec![InferInput::new("x".into(), vec![1], DataType::I32(vec![1]))];
+        let result = client.infer_with_options(input, "m", &options).await;
+        assert!(matches!(result, Err(TrustonError::Cancelled)), "{:?}", result);
+        cancel.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), hang).await.unwrap().unwrap();
+    }
+
     #[tokio::test]
+    #[allow(deprecated)]
     async fn server_unreachable() {
         let client = TritonRestClient::new("http://localhost:12345");
         let result = client.is_server_live().await;
@@ -0,0 +1,267 @@
+//! Image preprocessing on the CPU (requires the `image` feature).
+//!
+//! Turns decoded [`DynamicImage`]s into input tensors: resize (optionally
+//! keeping the aspect ratio and center-cropping), `NCHW` or `NHWC` layout,
+//! per-channel normalization and `UINT8`, `FP32` or (with the `half`
+//! feature) `FP16` values. Images are converted to RGB first.
+//!
+//! # Example
+//! ```
+//! use image::{DynamicImage, RgbImage};
+//! use truston::client::image::ImagePreprocess;
+//! use truston::client::io::InferInput;
+//!
+//! let image = DynamicImage::ImageRgb8(RgbImage::new(640, 480));
+//!
+//! // ImageNet normalization at 224x224
+//! let preprocess = ImagePreprocess::new(224, 224)
+//!     .with_center_crop()
+//!     .with_normalization(vec![0.485, 0.456, 0.406], vec![0.229, 0.224, 0.225]);
+//! let input = InferInput::from_image("input", &image, &preprocess)?;
+//! assert_eq!(input.input_shape, vec![1, 3, 224, 224]);
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use ::image::DynamicImage;
+use ::image::imageops::FilterType;
+
+use crate::client::io::{DataType, InferInput, TensorLayout};
+use crate::utils::errors::TrustonError;
+
+const CHANNELS: usize = 3;
+
+/// Type of the values of the produced tensor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageDtype {
+    /// The raw pixels (`UINT8`); normalization is not applied.
+    U8,
+    /// Pixels scaled to `[0, 1]` then normalized (`FP32`).
+    #[default]
+    F32,
+    /// Like [`ImageDtype::F32`], as `FP16`.
+    #[cfg(feature = "half")]
+    F16,
+}
+
+/// Target size, layout, normalization and datatype of the tensor.
+///
+/// Pixels are scaled to `[0, 1]`, then normalized per channel as
+/// `(value - mean) / std`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePreprocess {
+    pub width: u32,
+    pub height: u32,
+    /// Keep the aspect ratio: resize to cover the target size, then crop
+    /// the center. Otherwise the image is stretched.
+    pub center_crop: bool,
+    /// Per-channel mean; empty for none.
+    pub mean: Vec<f32>,
+    /// Per-channel standard deviation; empty for none.
+    pub std: Vec<f32>,
+    pub layout: TensorLayout,
+    pub dtype: ImageDtype,
+    /// Resampling filter of the resize.
+    pub filter: FilterType,
+}
+
+impl ImagePreprocess {
+    /// Stretch to `width` x `height` (bilinear), scale to `[0, 1]`, no
+    /// normalization, `NCHW`, `FP32`.
+    pub fn new(width: u32, height: u32) -> Self {
+        ImagePreprocess {
+            width,
+            height,
+            center_crop: false,
+            mean: Vec::new(),
+            std: Vec::new(),
+            layout: TensorLayout::default(),
+            dtype: ImageDtype::default(),
+            filter: FilterType::Triangle,
+        }
+    }
+
+    pub fn with_center_crop(mut self) -> Self {
+        self.center_crop = true;
+        self
+    }
+
+    pub fn with_normalization(mut self, mean: Vec<f32>, std: Vec<f32>) -> Self {
+        self.mean = mean;
+        self.std = std;
+        self
+    }
+
+    pub fn with_layout(mut self, layout: TensorLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn with_dtype(mut self, dtype: ImageDtype) -> Self {
+        self.dtype = dtype;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: FilterType) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// `(mean, 1 / std)` of each channel.
+    fn normalization(&self) -> Result<[(f32, f32); CHANNELS], TrustonError> {
+        let per_channel = |values: &[f32], default: f32| match values.len() {
+            0 => Ok([default; CHANNELS]),
+            CHANNELS => Ok([values[0], values[1], values[2]]),
+            n => Err(TrustonError::InferenceError(format!(
+                "normalization needs 0 or {} values per channel, got {}",
+                CHANNELS, n
+            ))),
+        };
+        let mean = per_channel(&self.mean, 0.0)?;
+        let std = per_channel(&self.std, 1.0)?;
+        if std.contains(&0.0) {
+            return Err(TrustonError::InferenceError("normalization std of 0".into()));
+        }
+        Ok(std::array::from_fn(|c| (mean[c], 1.0 / std[c])))
+    }
+}
+
+impl InferInput {
+    /// Build a `[1, 3, height, width]` (or `NHWC`) input from an image.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the target size is empty
+    /// or the normalization doesn't have one value per channel.
+    pub fn from_image(
+        name: impl Into<String>,
+        image: &DynamicImage,
+        preprocess: &ImagePreprocess,
+    ) -> Result<InferInput, TrustonError> {
+        InferInput::from_images(name, std::slice::from_ref(image), preprocess)
+    }
+
+    /// Build a `[batch, 3, height, width]` (or `NHWC`) input from a batch of
+    /// images of any sizes, each resized to the target size.
+    ///
+    /// # Errors
+    /// Same as [`InferInput::from_image`], and if `images` is empty.
+    pub fn from_images(
+        name: impl Into<String>,
+        images: &[DynamicImage],
+        preprocess: &ImagePreprocess,
+    ) -> Result<InferInput, TrustonError> {
+        let (width, height) = (preprocess.width as usize, preprocess.height as usize);
+        if images.is_empty() || width == 0 || height == 0 {
+            return Err(TrustonError::InferenceError(format!(
+                "cannot preprocess {} images to {}x{}",
+                images.len(),
+                width,
+                height
+            )));
+        }
+        let normalization = preprocess.normalization()?;
+
+        let plane = width * height;
+        let mut pixels = vec![0u8; images.len() * plane * CHANNELS];
+        for (image, out) in images.iter().zip(pixels.chunks_exact_mut(plane * CHANNELS)) {
+            let resized = if preprocess.center_crop {
+                image.resize_to_fill(preprocess.width, preprocess.height, preprocess.filter)
+            } else {
+                image.resize_exact(preprocess.width, preprocess.height, preprocess.filter)
+            };
+            let rgb = resized.to_rgb8();
+            match preprocess.layout {
+                TensorLayout::Nhwc => out.copy_from_slice(rgb.as_raw()),
+                TensorLayout::Nchw => {
+                    for (i, pixel) in rgb.as_raw().chunks_exact(CHANNELS).enumerate() {
+                        for (c, &value) in pixel.iter().enumerate() {
+                            out[c * plane + i] = value;
+                        }
+                    }
+                }
+            }
+        }
+
+        let shape = match preprocess.layout {
+            TensorLayout::Nchw => vec![images.len(), CHANNELS, height, width],
+            TensorLayout::Nhwc => vec![images.len(), height, width, CHANNELS],
+        };
+        let channel = |i: usize| match preprocess.layout {
+            TensorLayout::Nchw => i / plane % CHANNELS,
+            TensorLayout::Nhwc => i % CHANNELS,
+        };
+        let normalized = || {
+            pixels.iter().enumerate().map(move |(i, &value)| {
+                let (mean, inv_std) = normalization[channel(i)];
+                (value as f32 / 255.0 - mean) * inv_std
+            })
+        };
+        let data = match preprocess.dtype {
+            ImageDtype::U8 => DataType::U8(pixels),
+            ImageDtype::F32 => DataType::F32(normalized().collect()),
+            #[cfg(feature = "half")]
+            ImageDtype::F16 => DataType::F16(normalized().map(half::f16::from_f32).collect()),
+        };
+        Ok(InferInput::new(name.into(), shape, data))
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::image::{Rgb, RgbImage};
+
+    /// A `width` x `height` image whose pixel (x, y) is `(x, y, 100)`.
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            Rgb([x as u8, y as u8, 100])
+        }))
+    }
+
+    #[test]
+    fn test_layouts_and_dtypes() {
+        let image = gradient(2, 2);
+        let preprocess = ImagePreprocess::new(2, 2).with_dtype(ImageDtype::U8);
+
+        let nhwc = preprocess.clone().with_layout(TensorLayout::Nhwc);
+        let input = InferInput::from_image("x", &image, &nhwc).unwrap();
+        assert_eq!(input.input_shape, vec![1, 2, 2, 3]);
+        assert_eq!(input.input_data.as_u8_slice().unwrap()[..6], [0, 0, 100, 1, 0, 100]);
+
+        let input = InferInput::from_image("x", &image, &preprocess).unwrap();
+        assert_eq!(input.input_shape, vec![1, 3, 2, 2]);
+        let planes = [0, 1, 0, 1, 0, 0, 1, 1, 100, 100, 100, 100];
+        assert_eq!(input.input_data.as_u8_slice().unwrap(), planes);
+
+        let normalized = ImagePreprocess::new(2, 2)
+            .with_normalization(vec![0.0, 0.0, 0.5], vec![1.0, 1.0, 0.5]);
+        let input = InferInput::from_images("x", &[image.clone(), image], &normalized).unwrap();
+        assert_eq!(input.input_shape, vec![2, 3, 2, 2]);
+        let values = input.input_data.as_f32_slice().unwrap();
+        assert_eq!(values[1], 1.0 / 255.0);
+        assert!((values[8] - (100.0 / 255.0 - 0.5) / 0.5).abs() < 1e-6);
+        assert_eq!(values[12..], values[..12]);
+    }
+
+    #[test]
+    fn test_center_crop_keeps_aspect_ratio() {
+        // a 4x2 image cropped to 2x2 keeps its two central columns
+        let preprocess = ImagePreprocess::new(2, 2)
+            .with_center_crop()
+            .with_dtype(ImageDtype::U8)
+            .with_filter(FilterType::Nearest)
+            .with_layout(TensorLayout::Nhwc);
+        let input = InferInput::from_image("x", &gradient(4, 2), &preprocess).unwrap();
+        let values = input.input_data.as_u8_slice().unwrap();
+        assert_eq!((values[0], values[3]), (1, 2));
+    }
+
+    #[test]
+    fn test_invalid_preprocess() {
+        let image = gradient(2, 2);
+        let bad_mean = ImagePreprocess::new(2, 2).with_normalization(vec![0.5], vec![]);
+        assert!(InferInput::from_image("x", &image, &bad_mean).is_err());
+        assert!(InferInput::from_image("x", &image, &ImagePreprocess::new(0, 2)).is_err());
+        assert!(InferInput::from_images("x", &[], &ImagePreprocess::new(2, 2)).is_err());
+    }
+}
@@ -0,0 +1,1157 @@
+//! Inference over the REST protocol.
+//!
+//! Encodes [`InferInput`]s into the JSON (or binary tensor extension)
+//! request body, sends it with the retry, failover and fallback logic of the
+//! client, and decodes the response into [`InferResults`]. The payload types
+//! exchanged with the server are defined in [`client::io`](crate::client::io).
+
+use std::io;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime};
+
+use bytes::Bytes;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use num_traits::NumCast;
+use reqwest::header::CONTENT_ENCODING;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter};
+use tracing::Instrument;
+use tracing::field::Empty;
+
+use crate::client::binary::{self, TensorDecoder};
+use crate::client::buffers::OutputBuffers;
+use crate::client::compression;
+use crate::client::dtype::TritonDtype;
+use crate::client::events::ClientEvent;
+use crate::client::http::{FloatFormat, INFERENCE_HEADER_CONTENT_LENGTH, TritonRestClient};
+use crate::client::io::{
+    DataType, InferInput, InferInputPayload, InferOutput, InferRequest, InferRequestedOutput,
+    InferResponse, InferResults, Provenance, TensorData, TritonServerResponse, element_count,
+};
+use crate::client::metrics::InferSample;
+use crate::client::options::InferOptions;
+use crate::client::trace::{TRACEPARENT, TraceContext};
+use crate::client::validation;
+use crate::utils::errors::TrustonError;
+
+/// Rough size of the JSON encoding of `inputs`, used to preallocate request bodies.
+fn estimate_json_len(inputs: &[InferInput]) -> usize {
+    let data_len: usize = inputs
+        .iter()
+        .map(|input| match &input.input_data {
+            // quotes and comma around each string
+            DataType::String(v) => v.iter().map(|s| s.len() + 3).sum(),
+            // sent after the JSON
+            DataType::Bytes(_) | DataType::Raw(_) => 0,
+            // a number and its comma
+            data => data.num_elements() * 8,
+        })
+        .sum();
+    256 + data_len
+}
+
+/// Most bytes preallocated for a binary response of unknown length: sizes
+/// announced by the server are not trusted beyond the body received.
+const MAX_PREALLOCATION: usize = 64 << 20;
+
+/// Whether `data` holds NaN or infinite values, which JSON has no numbers for.
+fn has_non_finite(data: &DataType) -> bool {
+    match data {
+        DataType::F32(v) => v.iter().any(|x| !x.is_finite()),
+        DataType::F64(v) => v.iter().any(|x| !x.is_finite()),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => v.iter().any(|x| !x.is_finite()),
+        _ => false,
+    }
+}
+
+/// Compact JSON, with the floats written per a [`FloatFormat`].
+struct RequestFormatter(FloatFormat);
+
+impl RequestFormatter {
+    /// Write `value`, the shortest form of which is written by `shortest`.
+    fn write_float<W: ?Sized + io::Write, T>(
+        &self,
+        writer: &mut W,
+        value: T,
+        shortest: fn(&mut CompactFormatter, &mut W, T) -> io::Result<()>,
+    ) -> io::Result<()>
+    where
+        T: Copy + std::fmt::Display + std::fmt::LowerExp + std::str::FromStr,
+    {
+        let value = match self.0.significant_digits {
+            Some(digits) => {
+                let precision = digits.clamp(1, 17) as usize - 1;
+                format!("{:.*e}", precision, value).parse().unwrap_or(value)
+            }
+            None => value,
+        };
+        if !self.0.no_exponent {
+            return shortest(&mut CompactFormatter, writer, value);
+        }
+        // `Display` never uses an exponent
+        let plain = value.to_string();
+        writer.write_all(plain.as_bytes())?;
+        if !plain.contains('.') {
+            writer.write_all(b".0")?;
+        }
+        Ok(())
+    }
+}
+
+impl Formatter for RequestFormatter {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.write_float(writer, value, CompactFormatter::write_f32)
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        self.write_float(writer, value, CompactFormatter::write_f64)
+    }
+}
+
+/// What an inference reports to the metrics recorders.
+#[derive(Default)]
+pub(crate) struct InferStats {
+    pub(crate) request: u64,
+    pub(crate) response: Option<u64>,
+    pub(crate) queue_wait: Duration,
+}
+
+/// How an output of a binary response was read.
+#[derive(Default)]
+enum BinaryOutput {
+    /// Sent as JSON values in the header.
+    #[default]
+    Json,
+    Decoded(DataType),
+    /// Decoded into the buffer of the caller.
+    Buffered,
+}
+
+/// The body of a response, handed out as it arrives.
+struct BodyReader {
+    chunks: BoxStream<'static, Result<Bytes, TrustonError>>,
+    pending: Bytes,
+}
+
+impl BodyReader {
+    /// Hand the next `len` bytes of the body to `sink`, chunk by chunk.
+    async fn read(
+        &mut self,
+        mut len: usize,
+        mut sink: impl FnMut(&[u8]),
+    ) -> Result<(), TrustonError> {
+        while len > 0 {
+            if self.pending.is_empty() {
+                self.pending = match self.chunks.next().await {
+                    Some(chunk) => chunk?,
+                    None => {
+                        return Err(TrustonError::ParseError(format!(
+                            "binary response ends {} bytes early",
+                            len
+                        )));
+                    }
+                };
+            }
+            let chunk = self.pending.split_to(len.min(self.pending.len()));
+            sink(&chunk);
+            len -= chunk.len();
+        }
+        Ok(())
+    }
+}
+
+/// Appends the leaves of `data`, nested as arrays along `shape`, to `out`.
+///
+/// Returns `false` if the nesting doesn't follow `shape`.
+fn flatten_nested<'v>(
+    data: &'v serde_json::Value,
+    shape: &[usize],
+    out: &mut Vec<&'v serde_json::Value>,
+) -> bool {
+    match (shape.split_first(), data.as_array()) {
+        (None, None) => {
+            out.push(data);
+            true
+        }
+        (Some((&len, rest)), Some(items)) => {
+            items.len() == len && items.iter().all(|item| flatten_nested(item, rest, out))
+        }
+        _ => false,
+    }
+}
+
+impl TritonRestClient {
+    /// Converts an `InferInput` into the JSON payload format required by Triton.
+    ///
+    /// This is an internal method that handles the conversion of Rust types
+    /// to Triton's JSON format.
+    /// 
+    /// The values are borrowed and serialized in place (see the `Serialize`
+    /// impl of [`DataType`]). With `binary_data_size` set, the values are
+    /// left out of the JSON and sent after it instead.
+    fn convert_input<'a>(
+        &self,
+        infer_input: &'a InferInput,
+        binary_data_size: Option<usize>,
+        nested: bool,
+    ) -> InferInputPayload<'a, TensorData<'a>> {
+        let mut parameters = serde_json::Map::new();
+        if let Some(size) = binary_data_size {
+            parameters.insert("binary_data_size".to_string(), size.into());
+        }
+
+        InferInputPayload {
+            name: &infer_input.input_name,
+            shape: &infer_input.input_shape,
+            datatype: match &infer_input.input_data {
+                DataType::String(_) => self.string_datatype.as_str(),
+                data => data.get_type_str(),
+            },
+            data: binary_data_size.is_none().then_some(TensorData {
+                data: &infer_input.input_data,
+                nested: nested.then_some(infer_input.input_shape.as_slice()),
+            }),
+            parameters,
+        }
+    }
+
+    /// Serialize an inference request body once, so retries and fallbacks reuse it.
+    ///
+    /// Returns the body and, for the binary extension, the length of its JSON header.
+    pub(crate) fn encode_infer_body(
+        &self,
+        inputs: &[InferInput],
+        options: &InferOptions,
+    ) -> Result<(bytes::Bytes, Option<usize>), TrustonError> {
+        // raw bytes have no JSON encoding, so they always go in binary, and
+        // so do NaN and infinite values
+        let blobs = inputs
+            .iter()
+            .map(|input| {
+                let non_finite = !options.binary_inputs && has_non_finite(&input.input_data);
+                if non_finite {
+                    tracing::warn!(
+                        "input {} holds NaN or infinite values, sending it in binary",
+                        input.input_name
+                    );
+                }
+                let binary = options.binary_inputs
+                    || non_finite
+                    || matches!(input.input_data, DataType::Bytes(_));
+                binary
+                    .then(|| binary::encode_tensor(&input.input_data))
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut parameters = options.request_parameters();
+        if let Some(token) = &options.affinity {
+            parameters.extend(self.affinity_carrier.parameters(token));
+        }
+        let request = InferRequest {
+            id: options.request_id.as_deref(),
+            parameters,
+            inputs: inputs
+                .iter()
+                .zip(&blobs)
+                .map(|(input, blob)| {
+                    let binary_len = blob.as_ref().map(Vec::len);
+                    self.convert_input(input, binary_len, options.is_nested(&input.input_name))
+                })
+                .collect(),
+            outputs: options.outputs.as_deref(),
+        };
+
+        let binary_len: usize = blobs.iter().flatten().map(Vec::len).sum();
+        let mut body = Vec::with_capacity(estimate_json_len(inputs) + binary_len);
+        let mut serializer = serde_json::Serializer::with_formatter(
+            &mut body,
+            RequestFormatter(self.float_format),
+        );
+        request
+            .serialize(&mut serializer)
+            .map_err(|e| TrustonError::ParseError(e.to_string()))?;
+
+        if blobs.iter().all(Option::is_none) {
+            return Ok((body.into(), None));
+        }
+        let header_len = body.len();
+        for blob in blobs.iter().flatten() {
+            body.extend_from_slice(blob);
+        }
+        Ok((body.into(), Some(header_len)))
+    }
+
+    /// Read a response of the binary tensor extension: its JSON header of
+    /// `header_len` bytes, then the outputs sent in binary, each decoded as
+    /// its bytes arrive into a buffer allocated once.
+    ///
+    /// Outputs having one of `buffers` are decoded into it instead. Returns
+    /// the header and how each of its outputs was read.
+    async fn read_binary_response(
+        &self,
+        resp: reqwest::Response,
+        header_len: usize,
+        mut buffers: Option<&mut OutputBuffers>,
+    ) -> Result<(InferResponse, Vec<BinaryOutput>), TrustonError> {
+        let resp = Self::check_content_type(resp).await?;
+        let content_encoding = resp
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|ce| ce.to_str().ok())
+            .map(str::to_string);
+        // a compressed body is decompressed whole
+        let (chunks, body_len) = match content_encoding {
+            Some(encoding) => {
+                let body = resp.bytes().await?;
+                let body = compression::decode_body(
+                    &self.response_codecs,
+                    Some(&encoding),
+                    body,
+                    compression::MAX_DECOMPRESSED_SIZE,
+                );
+                let body_len = body.as_ref().map_or(0, Bytes::len);
+                (futures::stream::once(async { body }).boxed(), body_len)
+            }
+            None => {
+                let body_len = resp.content_length().map_or(MAX_PREALLOCATION, |len| len as usize);
+                let chunks = resp.bytes_stream().map(|chunk| chunk.map_err(TrustonError::from));
+                (chunks.boxed(), body_len)
+            }
+        };
+        let mut reader = BodyReader { chunks, pending: Bytes::new() };
+
+        // the buffers grow past the body length announced if need be
+        let mut header = Vec::with_capacity(header_len.min(body_len));
+        reader.read(header_len, |bytes| header.extend_from_slice(bytes)).await?;
+        let response: InferResponse = serde_json::from_slice(&header)
+            .map_err(|e| TrustonError::ParseError(e.to_string()))?;
+
+        let mut outputs = Vec::with_capacity(response.outputs.len());
+        for output in &response.outputs {
+            let Some(size) = output.parameters.get("binary_data_size").and_then(|s| s.as_u64())
+            else {
+                outputs.push(BinaryOutput::Json);
+                continue;
+            };
+            let size = size as usize;
+            let elements = element_count(&output.name, &output.shape)?;
+            let mut decoded = None;
+            let buffer = buffers.as_deref_mut().and_then(|buffers| buffers.get_mut(&output.name));
+            let data = match buffer {
+                Some(buffer) => {
+                    buffer.check(&output.datatype, &output.shape)?;
+                    &mut buffer.data
+                }
+                // every element takes a byte at least
+                None => {
+                    let capacity = elements.min(size).min(body_len);
+                    decoded.insert(binary::tensor_buffer(&output.datatype, capacity)?)
+                }
+            };
+            let mut decoder = TensorDecoder::new(data);
+            reader.read(size, |bytes| decoder.push(bytes)).await?;
+            decoder.finish()?;
+            if data.num_elements() != elements {
+                return Err(TrustonError::ParseError(format!(
+                    "output {} has {} values for shape {:?}",
+                    output.name,
+                    data.num_elements(),
+                    output.shape
+                )));
+            }
+            outputs.push(decoded.map_or(BinaryOutput::Buffered, BinaryOutput::Decoded));
+        }
+        Ok((response, outputs))
+    }
+
+    /// Convert the output data from a Triton server response into a vector of numeric values.
+    ///
+    /// This function attempts to parse the raw JSON `data` field returned by the Triton Inference Server
+    /// into a strongly-typed `Vec<T>`, where `T` implements [`NumCast`].  
+    /// It supports multiple Triton output datatypes (`FP32`, `INT64`, `BOOL`, etc.)
+    /// and automatically casts the values into the requested type.
+    ///
+    /// # Type Parameters
+    /// * `T: NumCast` - The target numeric type (e.g., `f32`, `f64`, `i32`, `u64`, etc.).
+    ///
+    /// # Arguments
+    /// * `output_data` - A reference to a [`TritonServerResponse`] containing the model output.
+    ///
+    /// # Supported Datatypes
+    /// - Floating point: `"FP32"`, `"FP64"` → parsed as `f64` then cast into `T`.
+    /// - Unsigned integers: `"UINT8"`, `"UINT16"`, `"UINT32"`, `"UINT64"` → parsed as `u64` then cast.
+    /// - Signed integers: `"INT8"`, `"INT16"`, `"INT32"`, `"INT64"` → parsed as `i64` then cast.
+    /// - Boolean: `"BOOL"` → parsed as `bool`, then converted to `0` or `1` (`u8`) before casting.
+    /// - Anything else returns `None`.
+    ///
+    /// # Returns
+    /// * `Some(Vec<T>)` if the datatype is supported and the cast succeeds.
+    /// * `None` if the datatype is unsupported or the JSON field is invalid.
+    ///
+    /// # Behavior
+    /// - Iterates over the JSON array inside `data`, parsing each entry into a
+    ///   base type (`f64`, `i64`, `u64`, or `bool`) and casting it into `T`.
+    /// - Arrays nested along `shape` are flattened first; nesting that doesn't
+    ///   match `shape` fails with [`TrustonError::ParseError`].
+    /// - Entries failing to parse or cast are skipped, or fail the whole
+    ///   output with [`TrustonError::ParseError`] on a client built with
+    ///   [`strict_outputs`](crate::client::http::TritonRestClientBuilder::strict_outputs).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let response: TritonServerResponse = client.infer(...).await?;
+    ///
+    /// // Convert float output
+    /// if let Some(values) = my_client.convert_output::<f32>(&response)? {
+    ///     println!("Model float output: {:?}", values);
+    /// }
+    ///
+    /// // Convert integer output
+    /// if let Some(values) = my_client.convert_output::<i64>(&response)? {
+    ///     println!("Model int output: {:?}", values);
+    /// }
+    /// ```
+    ///
+    /// # Notes
+    /// - For non-numeric outputs like `"STRING"`, use [`convert_output_string`] instead.
+    fn convert_output<T: NumCast>(
+        &self,
+        output_data: &TritonServerResponse,
+    ) -> Result<Option<Vec<T>>, TrustonError> {
+        let parse: fn(&serde_json::Value) -> Option<T> = match output_data.datatype {
+            TritonDtype::Fp16 | TritonDtype::Fp32 | TritonDtype::Fp64 => {
+                |item| item.as_f64().and_then(NumCast::from)
+            }
+            TritonDtype::Uint8
+            | TritonDtype::Uint16
+            | TritonDtype::Uint32
+            | TritonDtype::Uint64 => |item| item.as_u64().and_then(NumCast::from),
+            TritonDtype::Int8 | TritonDtype::Int16 | TritonDtype::Int32 | TritonDtype::Int64 => {
+                |item| item.as_i64().and_then(NumCast::from)
+            }
+            TritonDtype::Bool => |item| item.as_bool().and_then(|b| NumCast::from(b as u8)),
+            TritonDtype::Bf16 | TritonDtype::Bytes | TritonDtype::Other(_) => return Ok(None),
+        };
+        self.decode_values(output_data, parse)
+    }
+
+    /// Convert the output data from a Triton server response into a vector of strings.
+    ///
+    /// # Arguments
+    /// * `output_data` - A reference to a [`TritonServerResponse`] object that contains
+    ///   the inference result returned by the Triton Inference Server.
+    ///
+    /// # Returns
+    /// * `Some(Vec<String>)` if:
+    ///   - The `datatype` of the output is `"BYTES"` or `"STRING"`.
+    ///   - The `data` field can be parsed as an array of string values.
+    /// * `None` if the `datatype` is not a string type or the data is not an array of strings.
+    ///
+    /// # Behavior
+    /// - When the datatype is `"BYTES"`/`"STRING"`, this function attempts to parse the `data`
+    ///   field as an array of JSON values and keep only the valid string entries.
+    /// - Non-string entries inside the array are skipped, or fail the output in strict mode
+    ///   (see [`convert_output`]).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let response: TritonServerResponse = client.infer(...).await?;
+    /// if let Some(strings) = my_client.convert_output_string(&response)? {
+    ///     println!("Model output: {:?}", strings);
+    /// } else {
+    ///     println!("No valid string output found.");
+    /// }
+    /// ```
+    ///
+    /// # Notes
+    /// - This helper is only meaningful for Triton model outputs with `datatype = "STRING"`.
+    /// - For numeric outputs (e.g., `"FP32"`, `"INT64"`), consider using a different
+    ///   converter function.
+    fn convert_output_string(
+        &self,
+        output_data: &TritonServerResponse,
+    ) -> Result<Option<Vec<String>>, TrustonError> {
+        match output_data.datatype {
+            TritonDtype::Bytes => {
+                self.decode_values(output_data, |item| item.as_str().map(str::to_string))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse every entry of the `data` array of an output with `parse`.
+    ///
+    /// Entries `parse` rejects are skipped, unless the client is in strict mode
+    /// where the first one fails with its index and raw value.
+    fn decode_values<T>(
+        &self,
+        output_data: &TritonServerResponse,
+        parse: impl Fn(&serde_json::Value) -> Option<T>,
+    ) -> Result<Option<Vec<T>>, TrustonError> {
+        let Some(arr) = output_data.data.as_array() else {
+            if self.strict_outputs {
+                return Err(TrustonError::ParseError(format!(
+                    "output {} ({}): data is not an array: {}",
+                    output_data.name, output_data.datatype, output_data.data
+                )));
+            }
+            return Ok(None);
+        };
+        // Triton may nest the values along the shape instead of flattening them
+        let items: Box<dyn Iterator<Item = &serde_json::Value>> =
+            if arr.iter().any(serde_json::Value::is_array) {
+                let mut flat = Vec::new();
+                if !flatten_nested(&output_data.data, &output_data.shape, &mut flat) {
+                    return Err(TrustonError::ParseError(format!(
+                        "output {} ({}): nested data doesn't match shape {:?}",
+                        output_data.name, output_data.datatype, output_data.shape
+                    )));
+                }
+                Box::new(flat.into_iter())
+            } else {
+                Box::new(arr.iter())
+            };
+        let mut values = Vec::with_capacity(arr.len());
+        for (index, item) in items.enumerate() {
+            match parse(item) {
+                Some(value) => values.push(value),
+                None if self.strict_outputs => {
+                    return Err(TrustonError::ParseError(format!(
+                        "output {} ({}): element {} cannot be decoded: {}",
+                        output_data.name, output_data.datatype, index, item
+                    )));
+                }
+                None => {}
+            }
+        }
+        Ok(Some(values))
+    }
+
+    /// Perform an inference request to the Triton Inference Server.
+    ///
+    /// This method sends a `POST` request to the Triton server's
+    /// `/v2/models/{model_name}/infer` endpoint with the provided input tensors,
+    /// waits for the response, parses it into structured outputs, and converts the
+    /// raw response data into strongly-typed [`InferResults`].
+    ///
+    /// # Arguments
+    /// * `inputs` - A list of [`InferInput`] objects representing the input tensors
+    ///   (name, datatype, shape, and values) that will be sent to the model.
+    /// * `model_name` - The name of the deployed model to query on the Triton server.
+    /// * `outputs` - The outputs to return (with optional per-output parameters).
+    ///   `None` lets the server return every output of the model.
+    ///
+    /// # Returns
+    /// * `Ok(InferResults)` - On success, containing a vector of [`InferOutput`] entries.
+    /// * `Err(TrustonError)` - On failure, with possible variants:
+    ///   - [`TrustonError::ShapeMismatch`] if the shape of an input doesn't match
+    ///     its number of values (nothing is sent).
+    ///   - [`TrustonError::ServerError`] if the server returned a non-2xx response
+    ///     (with the error reported by Triton, the URL and the model).
+    ///   - [`TrustonError::ParseError`] if the response could not be deserialized.
+    ///   - [`TrustonError::UnexpectedContentType`] if the response is not JSON
+    ///     (e.g. an HTML page served by a proxy).
+    ///   - Any other error bubbled up from the HTTP client (e.g., connection failure).
+    ///
+    /// # Supported Datatypes
+    /// The server response is parsed into [`DataType`] variants depending on `datatype`:
+    /// - `"UINT8"`, `"UINT16"`, `"UINT32"`, `"UINT64"` → parsed into [`DataType::U8`], [`DataType::U16`], [`DataType::U32`], [`DataType::U64`]
+    /// - `"INT8"`, `"INT16"`, `"INT32"`, `"INT64"` → parsed into [`DataType::I8`], [`DataType::I16`], [`DataType::I32`], [`DataType::I64`]
+    /// - `"FP32"`, `"FP64"` → parsed into [`DataType::F32`], [`DataType::F64`]
+    /// - `"BF16"` → parsed as `u16` and wrapped in [`DataType::Bf16`]
+    /// - `"BYTES"` (or its alias `"STRING"`) → parsed into [`DataType::String`]
+    /// - Any unknown datatype → stored raw in [`DataType::Raw`] with the original JSON payload.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    ///
+    /// let input = InferInput::new("input_tensor", vec![1, 16], DataType::F32, vec![0.1f32; 16]);
+    ///
+    /// match client.infer(vec![input], "my_model", None).await {
+    ///     Ok(results) => {
+    ///         for out in results.outputs {
+    ///             println!("Output {}: {:?}", out.name, out.data);
+    ///         }
+    ///     }
+    ///     Err(e) => eprintln!("Inference failed: {:?}", e),
+    /// }
+    /// ```
+    ///
+    /// # Notes
+    /// - Automatically converts JSON output values into the appropriate Rust types.
+    /// - If conversion fails for a particular value, it is skipped silently, unless the
+    ///   client was built with
+    ///   [`strict_outputs`](crate::client::http::TritonRestClientBuilder::strict_outputs).
+    /// - Any datatype not explicitly supported will be returned as raw JSON via `DataType::Raw`.
+    pub async fn infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        outputs: Option<Vec<InferRequestedOutput>>,
+    ) -> Result<InferResults, TrustonError> {
+        let options = InferOptions {
+            outputs,
+            ..InferOptions::default()
+        };
+        self.infer_with_options(inputs, model_name, &options).await
+    }
+
+    /// Perform an inference request against a specific version of a model.
+    ///
+    /// Sends the request to `/v2/models/{model_name}/versions/{version}/infer`,
+    /// bypassing the server's version policy. Useful to pin traffic to one
+    /// version during an A/B rollout.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # use truston::client::io::{DataType, InferInput};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let input = InferInput::new("input".into(), vec![1, 2], DataType::F32(vec![0.5, 1.5]));
+    /// let results = client.infer_version(vec![input], "my_model", "2", None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn infer_version(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        version: &str,
+        outputs: Option<Vec<InferRequestedOutput>>,
+    ) -> Result<InferResults, TrustonError> {
+        let options = InferOptions {
+            model_version: Some(version.to_string()),
+            outputs,
+            ..InferOptions::default()
+        };
+        self.infer_with_options(inputs, model_name, &options).await
+    }
+
+    /// Perform an inference request with per-request [`InferOptions`].
+    ///
+    /// Behaves like [`TritonRestClient::infer`], additionally:
+    /// - sending `options.request_id` as the request `id`,
+    /// - sending `priority`, `timeout` and the custom parameters in the request `parameters`,
+    /// - requesting only `options.outputs` when set,
+    /// - targeting `/v2/models/{model_name}/versions/{version}/infer` when
+    ///   `options.model_version` is set,
+    /// - trying `options.fallbacks` in order while the server answers with a `5xx` status,
+    ///   unless `options.non_idempotent` is set,
+    /// - bounding the server timeout, HTTP timeout and retries by `options.deadline`,
+    /// - failing with [`TrustonError::Cancelled`] as soon as `options.cancellation` is cancelled.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # use truston::client::io::{DataType, InferInput};
+    /// # use truston::client::options::InferOptions;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let input = InferInput::new("input".into(), vec![1, 2], DataType::F32(vec![0.5, 1.5]));
+    /// let options = InferOptions::new().with_request_id("req-1").with_priority(1);
+    ///
+    /// let results = client.infer_with_options(vec![input], "my_model", &options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn infer_with_options(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<InferResults, TrustonError> {
+        self.run_infer(inputs, model_name, options, None).await
+    }
+
+    /// [`TritonRestClient::infer_with_options`], the outputs having one of
+    /// `buffers` decoded into it.
+    pub(crate) async fn run_infer(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+        buffers: Option<&mut OutputBuffers>,
+    ) -> Result<InferResults, TrustonError> {
+        let started = std::time::Instant::now();
+        let mut stats = InferStats::default();
+        let span = tracing::info_span!(
+            "infer",
+            model = model_name,
+            version = options.model_version.as_deref(),
+            request_id = options.request_id.as_deref(),
+            request_bytes = Empty,
+            status = Empty,
+            trace_id = Empty,
+        );
+        let results = async {
+            let infer = self.infer_uncancellable(inputs, model_name, options, &mut stats, buffers);
+            let Some(token) = &options.cancellation else {
+                return infer.await;
+            };
+            tokio::select! {
+                biased;
+                _ = token.cancelled() => {
+                    tracing::debug!("infer: request to {} cancelled", model_name);
+                    Err(TrustonError::Cancelled)
+                }
+                results = infer => results,
+            }
+        }
+        .instrument(span)
+        .await;
+        self.record_infer(model_name, results.is_ok(), &stats, started.elapsed());
+        results
+    }
+
+    /// Report an inference to the metrics recorders.
+    pub(crate) fn record_infer(
+        &self,
+        model_name: &str,
+        success: bool,
+        stats: &InferStats,
+        elapsed: Duration,
+    ) {
+        if self.recorders.is_empty() {
+            return;
+        }
+        let sample = InferSample {
+            model: model_name,
+            success,
+            request_bytes: stats.request,
+            response_bytes: stats.response,
+            queue_wait: stats.queue_wait,
+            elapsed,
+        };
+        for recorder in &self.recorders {
+            recorder.record(&sample);
+        }
+    }
+
+    /// Run several inferences, possibly on different models, at most
+    /// `concurrency` at a time over the shared connection pool.
+    ///
+    /// Returns one result per request, in the order of `requests`; a failing
+    /// request doesn't stop the others. `options` apply to every request.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # use truston::client::io::{DataType, InferInput};
+    /// # use truston::client::options::InferOptions;
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let x = || vec![InferInput::new("x".into(), vec![1], DataType::F32(vec![0.5]))];
+    /// let requests = vec![(x(), "detector"), (x(), "classifier"), (x(), "classifier")];
+    ///
+    /// let results = client.infer_many(requests, &InferOptions::new(), 8).await;
+    /// assert_eq!(results.len(), 3);
+    /// # }
+    /// ```
+    pub async fn infer_many(
+        &self,
+        requests: Vec<(Vec<InferInput>, &str)>,
+        options: &InferOptions,
+        concurrency: usize,
+    ) -> Vec<Result<InferResults, TrustonError>> {
+        futures::stream::iter(requests)
+            .map(|(inputs, model_name)| self.infer_with_options(inputs, model_name, options))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    async fn infer_uncancellable(
+        &self,
+        inputs: Vec<InferInput>,
+        model_name: &str,
+        options: &InferOptions,
+        stats: &mut InferStats,
+        buffers: Option<&mut OutputBuffers>,
+    ) -> Result<InferResults, TrustonError> {
+        for input in &inputs {
+            input.check_shape()?;
+        }
+        if self.validate_inputs {
+            let metadata = self.cached_model_metadata(model_name).await?;
+            validation::validate_inputs(&inputs, &metadata)?;
+        }
+
+        let (body, header_len) = self.encode_infer_body(&inputs, options)?;
+        let body = match &self.request_codec {
+            Some(codec) => bytes::Bytes::from(codec.compress(&body)?),
+            None => body,
+        };
+        stats.request = body.len() as u64;
+        tracing::Span::current().record("request_bytes", stats.request);
+
+        let trace_context = match options.trace_context {
+            Some(parent) => Some(parent.child()),
+            None => self.trace_propagation.then(TraceContext::new_root),
+        };
+        let traceparent = trace_context.map(|context| {
+            tracing::Span::current().record("trace_id", context.trace_id_hex());
+            context.to_string()
+        });
+
+        let (_slot, waited) =
+            self.limiter.acquire(options.deadline, options.queue_priority).await?;
+        stats.queue_wait = waited;
+        let started_at = SystemTime::now();
+        let started = std::time::Instant::now();
+        let mut targets = vec![(model_name, options.model_version.as_deref())];
+        // a server error doesn't tell whether a request with side effects was
+        // applied, so it isn't sent again to a fallback
+        let fallbacks = if options.non_idempotent { &[][..] } else { &options.fallbacks[..] };
+        for fallback in fallbacks {
+            targets.push((
+                fallback.model_name.as_deref().unwrap_or(model_name),
+                fallback.model_version.as_deref(),
+            ));
+        }
+
+        let affinity_headers = options
+            .affinity
+            .as_ref()
+            .map(|token| self.affinity_carrier.headers(token))
+            .unwrap_or_default();
+
+        let mut index = 0;
+        let mut attempts = 0;
+        let resp = loop {
+            let (model, version) = targets[index];
+            let url = format!("{}/infer", self.model_url(model, version));
+            let (resp, target_attempts) = self
+                .send_hedged(options, || {
+                    let mut builder = self.http.post(&url).body(body.clone());
+                    if let Some(codec) = &self.request_codec {
+                        builder = builder.header(CONTENT_ENCODING, codec.name());
+                    }
+                    for (name, value) in affinity_headers.iter().chain(&options.headers) {
+                        builder = builder.header(name, value);
+                    }
+                    if let Some(traceparent) = &traceparent {
+                        builder = builder.header(TRACEPARENT, traceparent);
+                    }
+                    match header_len {
+                        Some(len) => builder
+                            .header(INFERENCE_HEADER_CONTENT_LENGTH, len)
+                            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream"),
+                        None => builder.header(reqwest::header::CONTENT_TYPE, "application/json"),
+                    }
+                })
+                .await?;
+            attempts += target_attempts;
+
+            let status = resp.status();
+            index += 1;
+            match targets.get(index) {
+                Some(&(fallback_model, fallback_version)) if status.is_server_error() => {
+                    tracing::warn!(
+                        "infer: {} -> {}, falling back to {} (version {:?})",
+                        url,
+                        status,
+                        fallback_model,
+                        fallback_version
+                    );
+                    self.fallbacks_used.fetch_add(1, Ordering::Relaxed);
+                    self.emit(|| ClientEvent::Fallback {
+                        model: model.to_string(),
+                        version: version.map(str::to_string),
+                        status: status.as_u16(),
+                        fallback_model: fallback_model.to_string(),
+                        fallback_version: fallback_version.map(str::to_string),
+                    });
+                }
+                _ => break resp,
+            }
+        };
+        self.infer_results(resp, options, attempts, (started_at, started), stats, buffers).await
+    }
+
+    /// Decode the response of an inference sent at `started` after `attempts`
+    /// requests, the outputs having one of `buffers` into it.
+    pub(crate) async fn infer_results(
+        &self,
+        resp: reqwest::Response,
+        options: &InferOptions,
+        attempts: u32,
+        (started_at, started): (SystemTime, std::time::Instant),
+        stats: &mut InferStats,
+        mut buffers: Option<&mut OutputBuffers>,
+    ) -> Result<InferResults, TrustonError> {
+        let endpoint = resp.url().to_string();
+        let status = resp.status();
+        stats.response = resp.content_length();
+
+        if !status.is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        let header_len = resp
+            .headers()
+            .get(INFERENCE_HEADER_CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok()?.parse::<usize>().ok());
+        let (response_struct, mut binary_outputs) = match header_len {
+            Some(header_len) => {
+                self.read_binary_response(resp, header_len, buffers.as_deref_mut()).await?
+            }
+            None => (self.parse_json::<InferResponse>(resp).await?, Vec::new()),
+        };
+
+ 
+        let mut converted_outputs = Vec::new();
+        // outputs decoded into their buffer
+        let mut written = Vec::new();
+        for (index, output) in response_struct.outputs.iter().enumerate() {
+            element_count(&output.name, &output.shape)?;
+            let binary = match binary_outputs.get_mut(index).map(std::mem::take) {
+                Some(BinaryOutput::Buffered) => {
+                    written.push(output.name.as_str());
+                    continue;
+                }
+                Some(BinaryOutput::Decoded(data)) => Some(data),
+                _ => None,
+            };
+            let data = match output.datatype {
+                _ if binary.is_some() => binary,
+                TritonDtype::Uint8 => self.convert_output::<u8>(output)?.map(DataType::U8), 
+                TritonDtype::Uint16 => self.convert_output::<u16>(output)?.map(DataType::U16),
+                TritonDtype::Uint32 => self.convert_output::<u32>(output)?.map(DataType::U32),
+                TritonDtype::Uint64 => self.convert_output::<u64>(output)?.map(DataType::U64),
+                TritonDtype::Int8 => self.convert_output::<i8>(output)?.map(DataType::I8),
+                TritonDtype::Int16 => self.convert_output::<i16>(output)?.map(DataType::I16),
+                TritonDtype::Int32 => self.convert_output::<i32>(output)?.map(DataType::I32),
+                TritonDtype::Int64 => self.convert_output::<i64>(output)?.map(DataType::I64),
+                TritonDtype::Fp32 => self.convert_output::<f32>(output)?.map(DataType::F32),
+                TritonDtype::Fp64 => self.convert_output::<f64>(output)?.map(DataType::F64),
+                TritonDtype::Bf16 => self.convert_output::<u16>(output)?.map(DataType::Bf16),
+                #[cfg(feature = "half")]
+                TritonDtype::Fp16 => self.convert_output::<half::f16>(output)?.map(DataType::F16),
+                TritonDtype::Bytes => self.convert_output_string(output)?.map(DataType::String),
+            
+                _ => Some(DataType::Raw(output.data.clone())),
+            };
+        
+            let Some(mut data) = data else { continue };
+            let mut datatype = output.datatype.clone();
+            let requested = options
+                .outputs
+                .iter()
+                .flatten()
+                .find(|requested| requested.name == output.name)
+                .and_then(InferRequestedOutput::requested_datatype);
+            let ignored = |&requested: &&str| {
+                requested != datatype.as_str() && requested != data.get_type_str()
+            };
+            if let Some(requested) = requested.filter(ignored) {
+                tracing::debug!(
+                    "infer: {} returned as {} instead of {}, casting",
+                    output.name,
+                    datatype,
+                    requested
+                );
+                data = data.cast(requested).map_err(|_| {
+                    TrustonError::InferenceError(format!(
+                        "output {} was returned as {} and cannot be cast to {}",
+                        output.name, datatype, requested
+                    ))
+                })?;
+                datatype = requested.parse()?;
+            }
+            // sent as JSON despite the request for binary
+            if let Some(buffer) = buffers.as_deref_mut().and_then(|b| b.get_mut(&output.name)) {
+                buffer.check(&datatype, &output.shape)?;
+                buffer.data = data;
+                written.push(output.name.as_str());
+                continue;
+            }
+            converted_outputs.push(InferOutput {
+                name: output.name.clone(),
+                datatype,
+                shape: output.shape.clone(),
+                data,
+            });
+        }
+        // a buffer left alone still holds the values of an earlier response
+        if let Some(buffers) = buffers {
+            let stale: Vec<&str> = buffers
+                .iter()
+                .map(|buffer| buffer.name.as_str())
+                .filter(|name| !written.contains(name))
+                .collect();
+            if !stale.is_empty() {
+                return Err(TrustonError::InferenceError(format!(
+                    "no values received for the output buffers {}",
+                    stale.join(", ")
+                )));
+            }
+        }
+        let provenance = Provenance {
+            endpoint,
+            model_name: response_struct.model_name,
+            model_version: response_struct.model_version,
+            attempts,
+            cache_hit: response_struct
+                .parameters
+                .get("cache_hit")
+                .and_then(serde_json::Value::as_bool),
+            started_at,
+            elapsed: started.elapsed(),
+        };
+        Ok(InferResults { outputs: converted_outputs, provenance })
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn infer_body_json_and_binary() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        let inputs = vec![InferInput::new(
+            "text".into(),
+            vec![2],
+            DataType::String(vec!["hi".into(), "a\"b".into()]),
+        )];
+
+        let (body, header_len) = client.encode_infer_body(&inputs, &InferOptions::new()).unwrap();
+        assert_eq!(header_len, None);
+        assert_eq!(
+            std::str::from_utf8(&body).unwrap(),
+            r#"{"inputs":[{"name":"text","shape":[2],"datatype":"BYTES","data":["hi","a\"b"]}]}"#
+        );
+
+        let options = InferOptions::new().with_binary_inputs();
+        let (body, header_len) = client.encode_infer_body(&inputs, &options).unwrap();
+        let header_len = header_len.unwrap();
+        let header: serde_json::Value = serde_json::from_slice(&body[..header_len]).unwrap();
+        assert_eq!(header["inputs"][0]["parameters"]["binary_data_size"], 13);
+        assert!(header["inputs"][0].get("data").is_none());
+        assert_eq!(&body[header_len..header_len + 6], &[2, 0, 0, 0, b'h', b'i']);
+        assert_eq!(body.len(), header_len + 13);
+    }
+
+    #[test]
+    fn infer_body_sends_raw_bytes_in_binary() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        let jpeg = vec![0xff, 0xd8, 0xff, 0x00];
+        let inputs = vec![
+            InferInput::new("image".into(), vec![1], DataType::Bytes(vec![jpeg.clone()])),
+            InferInput::new("scale".into(), vec![1], DataType::F32(vec![0.5])),
+        ];
+
+        // without binary_inputs, only the bytes leave the JSON
+        let (body, header_len) = client.encode_infer_body(&inputs, &InferOptions::new()).unwrap();
+        let header_len = header_len.unwrap();
+        let header: serde_json::Value = serde_json::from_slice(&body[..header_len]).unwrap();
+        assert_eq!(header["inputs"][0]["datatype"], "BYTES");
+        assert_eq!(header["inputs"][0]["parameters"]["binary_data_size"], 8);
+        assert!(header["inputs"][0].get("data").is_none());
+        assert_eq!(header["inputs"][1]["data"], serde_json::json!([0.5]));
+        assert_eq!(&body[header_len..], &[4, 0, 0, 0, 0xff, 0xd8, 0xff, 0x00]);
+    }
+
+    #[test]
+    fn infer_body_float_format() {
+        let inputs = vec![
+            InferInput::new("x".into(), vec![3], DataType::F64(vec![1e300, 1.0, 1.0 / 3.0])),
+            InferInput::new("y".into(), vec![1], DataType::F32(vec![2.5e-7])),
+        ];
+        let json = |format: FloatFormat| {
+            let client = TritonRestClient::builder("http://localhost:8000")
+                .float_format(format)
+                .build()
+                .unwrap();
+            let (body, _) = client.encode_infer_body(&inputs, &InferOptions::new()).unwrap();
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            let data = |name: &str| {
+                let start = body.find(&format!(r#""name":"{}""#, name)).unwrap();
+                let start = start + body[start..].find(r#""data":["#).unwrap() + 8;
+                body[start..start + body[start..].find(']').unwrap()].to_string()
+            };
+            (data("x"), data("y"))
+        };
+
+        let (x, y) = json(FloatFormat::default());
+        assert_eq!(x, "1e300,1.0,0.3333333333333333");
+        assert_eq!(y, "2.5e-7");
+        let (x, y) = json(FloatFormat::default().with_significant_digits(3));
+        assert_eq!(x, "1e300,1.0,0.333");
+        assert_eq!(y, "2.5e-7");
+        let (x, y) = json(FloatFormat::default().with_significant_digits(2).without_exponent());
+        assert_eq!(x, format!("1{}.0,1.0,0.33", "0".repeat(300)));
+        assert_eq!(y, "0.00000025");
+    }
+
+    #[test]
+    fn infer_body_sends_non_finite_floats_in_binary() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        let inputs = vec![
+            InferInput::new("x".into(), vec![2], DataType::F64(vec![f64::NAN, 1.0])),
+            InferInput::new("y".into(), vec![1], DataType::F32(vec![0.5])),
+        ];
+        let (body, header_len) = client.encode_infer_body(&inputs, &InferOptions::new()).unwrap();
+        let header_len = header_len.unwrap();
+        let header: serde_json::Value = serde_json::from_slice(&body[..header_len]).unwrap();
+        assert_eq!(header["inputs"][0]["parameters"]["binary_data_size"], 16);
+        assert!(header["inputs"][0].get("data").is_none());
+        assert_eq!(header["inputs"][1]["data"], serde_json::json!([0.5]));
+        let values = binary::decode_tensor(&TritonDtype::Fp64, &body[header_len..]).unwrap();
+        assert!(values.as_f64_slice().unwrap()[0].is_nan());
+    }
+
+    #[test]
+    fn nested_output_data_is_flattened() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        let output = |shape: Vec<usize>, data| TritonServerResponse {
+            name: "y".into(),
+            shape,
+            datatype: TritonDtype::Int32,
+            data,
+            parameters: Default::default(),
+        };
+
+        let nested = output(vec![2, 2], serde_json::json!([[1, 2], [3, 4]]));
+        let values = client.convert_output::<i32>(&nested).unwrap();
+        assert_eq!(values, Some(vec![1, 2, 3, 4]));
+
+        let ragged = output(vec![2, 2], serde_json::json!([[1, 2], [3]]));
+        assert!(matches!(
+            client.convert_output::<i32>(&ragged),
+            Err(TrustonError::ParseError(_))
+        ));
+        let too_deep = output(vec![2], serde_json::json!([[1], [2]]));
+        assert!(client.convert_output::<i32>(&too_deep).is_err());
+    }
+
+    #[test]
+    fn infer_body_nests_selected_inputs() {
+        let client = TritonRestClient::new("http://localhost:8000");
+        let inputs = vec![
+            InferInput::new("a".into(), vec![2, 1, 2], DataType::I32(vec![1, 2, 3, 4])),
+            InferInput::new("b".into(), vec![1, 2], DataType::F32(vec![0.5, 1.5])),
+            InferInput::new("c".into(), vec![2], DataType::U8(vec![1, 2])),
+        ];
+
+        let options = InferOptions::new().with_nested_input("a");
+        let (body, _) = client.encode_infer_body(&inputs, &options).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["inputs"][0]["data"], serde_json::json!([[[1, 2]], [[3, 4]]]));
+        assert_eq!(body["inputs"][1]["data"], serde_json::json!([0.5, 1.5]));
+
+        let (body, _) = client.encode_infer_body(&inputs, &options.with_nested_data()).unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["inputs"][1]["data"], serde_json::json!([[0.5, 1.5]]));
+        assert_eq!(body["inputs"][2]["data"], serde_json::json!([1, 2]));
+    }
+}
@@ -0,0 +1,98 @@
+//! Request/response interceptors.
+//!
+//! An [`Interceptor`] registered with
+//! [`TritonRestClientBuilder::interceptor`](crate::client::http::TritonRestClientBuilder::interceptor)
+//! sees every HTTP request of the REST client, whatever the endpoint, once
+//! per attempt: it can sign or rewrite the request, inspect or replace the
+//! response, and observe transport errors. Typical uses are custom request
+//! signing, audit logs and chaos tests.
+//!
+//! Interceptors run in the order they were registered for requests, and in
+//! the reverse order for responses and errors. They run after the client
+//! added its own headers (authentication included) and after failover
+//! picked the endpoint.
+//!
+//! # Example
+//! ```
+//! use async_trait::async_trait;
+//! use truston::TrustonError;
+//! use truston::client::http::TritonRestClient;
+//! use truston::client::interceptor::{Interceptor, RequestInfo};
+//!
+//! #[derive(Debug)]
+//! struct AuditLog;
+//!
+//! #[async_trait]
+//! impl Interceptor for AuditLog {
+//!     async fn on_request(&self, request: &mut reqwest::Request) -> Result<(), TrustonError> {
+//!         request.headers_mut().insert("x-caller", "billing".parse().unwrap());
+//!         Ok(())
+//!     }
+//!
+//!     async fn on_response(
+//!         &self,
+//!         request: &RequestInfo,
+//!         response: reqwest::Response,
+//!     ) -> Result<reqwest::Response, TrustonError> {
+//!         println!("{} {} -> {}", request.method, request.url, response.status());
+//!         Ok(response)
+//!     }
+//! }
+//!
+//! let client = TritonRestClient::builder("http://localhost:8000")
+//!     .interceptor(AuditLog)
+//!     .build()?;
+//! # Ok::<(), TrustonError>(())
+//! ```
+
+use std::fmt;
+
+use async_trait::async_trait;
+
+use crate::utils::errors::TrustonError;
+
+/// The request an attempt was made with, as seen after every
+/// [`Interceptor::on_request`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestInfo {
+    pub method: reqwest::Method,
+    pub url: reqwest::Url,
+    /// Attempt number, starting at 1; a failover to another endpoint keeps it.
+    pub attempt: u32,
+}
+
+/// Hooks called around every request attempt of a client.
+///
+/// Every method has a default that does nothing; implement the ones needed.
+#[async_trait]
+pub trait Interceptor: fmt::Debug + Send + Sync {
+    /// Called before the request is sent; may change its headers, URL or body.
+    ///
+    /// # Errors
+    /// Its error fails the call without sending the request, and is not retried.
+    async fn on_request(&self, request: &mut reqwest::Request) -> Result<(), TrustonError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Called with the response of the server, whatever its status; returns
+    /// the response handed on to the client, which may be another one.
+    ///
+    /// # Errors
+    /// Its error fails the call, and is not retried.
+    async fn on_response(
+        &self,
+        request: &RequestInfo,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, TrustonError> {
+        let _ = request;
+        Ok(response)
+    }
+
+    /// Called when no response was received (connection error, timeout, ...).
+    ///
+    /// The error is then handled by the client as usual (retry, failover).
+    fn on_error(&self, request: &RequestInfo, error: &reqwest::Error) {
+        let _ = (request, error);
+    }
+}
@@ -9,9 +9,20 @@
 //! The [`DataType`] enum represents typed inference outputs returned
 //! by Triton. Each variant corresponds to a supported Triton datatype.
 
-use ndarray::ArrayD;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use num_traits::{AsPrimitive, Bounded, NumCast, Zero};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::client::dtype::TritonDtype;
+#[cfg(feature = "ndarray")]
+pub use crate::client::ndarray::Rows;
+use crate::utils::errors::TrustonError;
+
 /// Represents a typed output tensor returned from a Triton model inference.
 ///
 /// Each variant corresponds to one of the supported Triton datatypes,
@@ -23,6 +34,7 @@ use serde::{Deserialize, Serialize};
 /// - [`DataType::Bool(Vec<bool>)`] — Boolean outputs (`BOOL`).
 /// - [`DataType::U8(Vec<u8>)`] — Unsigned 8-bit integers (`UINT8`).
 /// - [`DataType::U16(Vec<u16>)`] — Unsigned 16-bit integers (`UINT16`).
+/// - [`DataType::U32(Vec<u32>)`] — Unsigned 32-bit integers (`UINT32`).
 /// - [`DataType::U64(Vec<u64>)`] — Unsigned 64-bit integers (`UINT64`).
 /// - [`DataType::I8(Vec<i8>)`] — Signed 8-bit integers (`INT8`).
 /// - [`DataType::I16(Vec<i16>)`] — Signed 16-bit integers (`INT16`).
@@ -30,7 +42,10 @@ use serde::{Deserialize, Serialize};
 /// - [`DataType::I64(Vec<i64>)`] — Signed 64-bit integers (`INT64`).
 /// - [`DataType::F32(Vec<f32>)`] — 32-bit floats (`FP32`).
 /// - [`DataType::F64(Vec<f64>)`] — 64-bit floats (`FP64`).
+/// - `DataType::F16(Vec<half::f16>)` — 16-bit floats (`FP16`), requires the `half` feature.
 /// - [`DataType::String(Vec<String>)`] — UTF-8 encoded strings (`STRING`).
+/// - [`DataType::Bytes(Vec<Vec<u8>>)`] — Arbitrary byte strings (`BYTES`), e.g. encoded
+///   images. Always sent with the binary tensor data extension.
 /// - [`DataType::Bf16(Vec<u16>)`] — Brain floating point 16 (`BF16`), represented as raw `u16`.
 /// - [`DataType::Raw(serde_json::Value)`] — Fallback for unrecognized datatypes; holds raw JSON.
 ///
@@ -48,6 +63,7 @@ pub enum DataType {
     Bool(Vec<bool>),
     U8(Vec<u8>),
     U16(Vec<u16>),
+    U32(Vec<u32>),
     U64(Vec<u64>),
     I8(Vec<i8>),
     I16(Vec<i16>),
@@ -56,7 +72,10 @@ pub enum DataType {
     F32(Vec<f32>),
     F64(Vec<f64>),
     String(Vec<String>),
+    Bytes(Vec<Vec<u8>>),
     Bf16(Vec<u16>),
+    #[cfg(feature = "half")]
+    F16(Vec<half::f16>),
     Raw(serde_json::Value),
 }
 
@@ -69,7 +88,7 @@ impl DataType {
     /// A static string slice matching Triton's datatype names, e.g.:
     /// - `"FP32"` for [`DataType::F32`]
     /// - `"INT64"` for [`DataType::I64`]
-    /// - `"STRING"` for [`DataType::String`]
+    /// - `"BYTES"` for [`DataType::String`] and [`DataType::Bytes`]
     /// - `"none"` for [`DataType::Raw`]
     ///
     /// # Example
@@ -79,24 +98,87 @@ impl DataType {
     /// assert_eq!(dtype.get_type_str(), "FP32");
     /// ```
     pub fn get_type_str(&self) -> &'static str {
+        self.dtype().and_then(|dtype| dtype.known_name()).unwrap_or("none")
+    }
+
+    /// The Triton datatype of the values, `None` for [`DataType::Raw`].
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::DataType;
+    ///
+    /// assert_eq!(DataType::Bf16(vec![0x3f80]).dtype(), Some(TritonDtype::Bf16));
+    /// assert_eq!(DataType::String(vec![]).dtype(), Some(TritonDtype::Bytes));
+    /// ```
+    pub fn dtype(&self) -> Option<TritonDtype> {
+        Some(match self {
+            DataType::Bool(_) => TritonDtype::Bool,
+            DataType::U8(_) => TritonDtype::Uint8,
+            DataType::U16(_) => TritonDtype::Uint16,
+            DataType::U32(_) => TritonDtype::Uint32,
+            DataType::U64(_) => TritonDtype::Uint64,
+            DataType::I8(_) => TritonDtype::Int8,
+            DataType::I16(_) => TritonDtype::Int16,
+            DataType::I32(_) => TritonDtype::Int32,
+            DataType::I64(_) => TritonDtype::Int64,
+            DataType::F32(_) => TritonDtype::Fp32,
+            DataType::F64(_) => TritonDtype::Fp64,
+            DataType::String(_) | DataType::Bytes(_) => TritonDtype::Bytes,
+            DataType::Bf16(_) => TritonDtype::Bf16,
+            #[cfg(feature = "half")]
+            DataType::F16(_) => TritonDtype::Fp16,
+            DataType::Raw(_) => return None,
+        })
+    }
+    
+    /// Number of values held (for [`DataType::Raw`], the length of the JSON
+    /// array, or `1` for any other JSON value).
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    /// assert_eq!(DataType::I64(vec![1, 2, 3]).num_elements(), 3);
+    /// ```
+    pub fn num_elements(&self) -> usize {
         match self {
-            DataType::Bool(_) => "BOOL",
-            DataType::U8(_) => "UINT8",
-            DataType::U16(_) => "UINT16",
-            DataType::U64(_) => "UINT64",
-            DataType::I8(_) => "INT8",
-            DataType::I16(_) => "INT16",
-            DataType::I32(_) => "INT32",
-            DataType::I64(_) => "INT64",
-            DataType::F32(_) => "FP32",
-            DataType::F64(_) => "FP64",
-            DataType::String(_) => "BYTES",
-            DataType::Bf16(_) => "BF16",
-            DataType::Raw(_) => "none"
+            DataType::Bool(v) => v.len(),
+            DataType::U8(v) => v.len(),
+            DataType::U16(v) => v.len(),
+            DataType::U32(v) => v.len(),
+            DataType::U64(v) => v.len(),
+            DataType::I8(v) => v.len(),
+            DataType::I16(v) => v.len(),
+            DataType::I32(v) => v.len(),
+            DataType::I64(v) => v.len(),
+            DataType::F32(v) => v.len(),
+            DataType::F64(v) => v.len(),
+            DataType::String(v) => v.len(),
+            DataType::Bytes(v) => v.len(),
+            DataType::Bf16(v) => v.len(),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => v.len(),
+            DataType::Raw(v) => v.as_array().map_or(1, Vec::len),
         }
     }
-    
-    /// Attempts to extract the underlying values as a `Vec<u8>`.
+
+    /// Copy the values out, for any element type `T` (`to_vec::<f32>()`,
+    /// `to_vec::<String>()`, ...); `None` if they are not of type `T`.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    ///
+    /// let data = DataType::I64(vec![3, 1]);
+    /// assert_eq!(data.to_vec::<i64>(), Some(vec![3, 1]));
+    /// assert_eq!(data.to_vec::<f32>(), None);
+    /// ```
+    pub fn to_vec<T: TensorElement>(&self) -> Option<Vec<T>> {
+        T::values(self).map(<[T]>::to_vec)
+    }
+
+    /// Attempts to extract the underlying values as a `Vec<u8>`, like
+    /// [`to_vec::<u8>`](Self::to_vec).
     ///
     /// # Returns
     /// - `Some(Vec<u8>)` if this is a [`DataType::U8`] variant.
@@ -112,74 +194,37 @@ impl DataType {
     /// assert_eq!(dtype.as_u8_vec(), None);
     /// ```
     pub fn as_u8_vec(&self) -> Option<Vec<u8>> {
-        if let DataType::U8(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_u16_vec(&self) -> Option<Vec<u16>> {
-        if let DataType::U16(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
+    }
+    pub fn as_u32_vec(&self) -> Option<Vec<u32>> {
+        self.to_vec()
     }
     pub fn as_u64_vec(&self) -> Option<Vec<u64>> {
-        if let DataType::U64(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_i8_vec(&self) -> Option<Vec<i8>> {
-        if let DataType::I8(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_i16_vec(&self) -> Option<Vec<i16>> {
-        if let DataType::I16(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_i32_vec(&self) -> Option<Vec<i32>> {
-        if let DataType::I32(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_i64_vec(&self) -> Option<Vec<i64>> {
-        if let DataType::I64(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_f32_vec(&self) -> Option<Vec<f32>> {
-        if let DataType::F32(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_f64_vec(&self) -> Option<Vec<f64>> {
-        if let DataType::F64(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_bool_vec(&self) -> Option<Vec<bool>> {
-        if let DataType::Bool(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
     }
     pub fn as_bf16_vec(&self) -> Option<Vec<u16>> {
         if let DataType::Bf16(v) = self {
@@ -188,157 +233,500 @@ impl DataType {
             None
         }
     }
+    #[cfg(feature = "half")]
+    pub fn as_f16_vec(&self) -> Option<Vec<half::f16>> {
+        self.to_vec()
+    }
     pub fn as_str_vec(&self) -> Option<Vec<String>> {
-        if let DataType::String(v) = self {
-            Some(v.to_vec())
-        } else {
-            None
-        }
+        self.to_vec()
+    }
+    pub fn as_bytes_vec(&self) -> Option<Vec<Vec<u8>>> {
+        self.to_vec()
     }   
 
-    /// Convert `DataType::Bool` into an `ndarray::ArrayD<bool>` with the given shape.
+    /// Borrow the values of a [`DataType::F32`] without copying them, unlike
+    /// [`as_f32_vec`](Self::as_f32_vec).
     ///
-    /// # Arguments
-    /// * `shape` - The shape of the target ndarray.
+    /// One accessor exists per variant (`as_i64_slice`, `as_str_slice`, ...).
     ///
-    /// # Returns
-    /// * `Some(ArrayD<bool>)` if the variant is `Bool`
-    /// * `None` otherwise
-    ///
-    /// # Notes
-    /// - This is a straightforward conversion for MVP1.
-    /// - In future versions (MVP2), this will be optimized using macros/traits
-    ///   to reduce boilerplate and improve maintainability.
-    pub fn to_ndarray_bool(&self, shape: &[usize]) -> Option<ArrayD<bool>> {
-        if let DataType::Bool(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    /// let dtype = DataType::F32(vec![0.1, 0.2]);
+    /// assert_eq!(dtype.as_f32_slice(), Some(&[0.1, 0.2][..]));
+    /// assert_eq!(dtype.as_i32_slice(), None);
+    /// ```
+    pub fn as_f32_slice(&self) -> Option<&[f32]> {
+        if let DataType::F32(v) = self { Some(v) } else { None }
     }
 
-    /// Convert `DataType::U8` into an `ndarray::ArrayD<u8>`.
-    ///
-    /// Same notes as [`to_ndarray_bool`]: MVP1 implementation, planned
-    /// optimization in MVP2.
-    pub fn to_ndarray_u8(&self, shape: &[usize]) -> Option<ArrayD<u8>> {
-        if let DataType::U8(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    pub fn as_u8_slice(&self) -> Option<&[u8]> {
+        if let DataType::U8(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::U16` into an `ndarray::ArrayD<u16>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_u16(&self, shape: &[usize]) -> Option<ArrayD<u16>> {
-        if let DataType::U16(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    pub fn as_u16_slice(&self) -> Option<&[u16]> {
+        if let DataType::U16(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::U64` into an `ndarray::ArrayD<u64>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_u64(&self, shape: &[usize]) -> Option<ArrayD<u64>> {
-        if let DataType::U64(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    pub fn as_u32_slice(&self) -> Option<&[u32]> {
+        if let DataType::U32(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::I8` into an `ndarray::ArrayD<i8>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_i8(&self, shape: &[usize]) -> Option<ArrayD<i8>> {
-        if let DataType::I8(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    pub fn as_u64_slice(&self) -> Option<&[u64]> {
+        if let DataType::U64(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::i16` into an `ndarray::ArrayD<i16>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_i16(&self, shape: &[usize]) -> Option<ArrayD<i16>> {
-        if let DataType::I16(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    pub fn as_i8_slice(&self) -> Option<&[i8]> {
+        if let DataType::I8(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_i16_slice(&self) -> Option<&[i16]> {
+        if let DataType::I16(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_i32_slice(&self) -> Option<&[i32]> {
+        if let DataType::I32(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_i64_slice(&self) -> Option<&[i64]> {
+        if let DataType::I64(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_f64_slice(&self) -> Option<&[f64]> {
+        if let DataType::F64(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_bool_slice(&self) -> Option<&[bool]> {
+        if let DataType::Bool(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_bf16_slice(&self) -> Option<&[u16]> {
+        if let DataType::Bf16(v) = self { Some(v) } else { None }
+    }
+
+    #[cfg(feature = "half")]
+    pub fn as_f16_slice(&self) -> Option<&[half::f16]> {
+        if let DataType::F16(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::i32` into an `ndarray::ArrayD<i32>`.
+    pub fn as_str_slice(&self) -> Option<&[String]> {
+        if let DataType::String(v) = self { Some(v) } else { None }
+    }
+
+    pub fn as_bytes_slice(&self) -> Option<&[Vec<u8>]> {
+        if let DataType::Bytes(v) = self { Some(v) } else { None }
+    }
+
+    /// Take the values of a [`DataType::F32`], without the copy of
+    /// [`as_f32_vec`](Self::as_f32_vec).
     ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_i32(&self, shape: &[usize]) -> Option<ArrayD<i32>> {
-        if let DataType::I32(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    /// One method exists per variant (`into_i64_vec`, `into_str_vec`, ...).
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    /// assert_eq!(DataType::F32(vec![0.5]).into_f32_vec(), Some(vec![0.5]));
+    /// assert_eq!(DataType::I32(vec![1]).into_f32_vec(), None);
+    /// ```
+    pub fn into_f32_vec(self) -> Option<Vec<f32>> {
+        if let DataType::F32(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_u8_vec(self) -> Option<Vec<u8>> {
+        if let DataType::U8(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_u16_vec(self) -> Option<Vec<u16>> {
+        if let DataType::U16(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_u32_vec(self) -> Option<Vec<u32>> {
+        if let DataType::U32(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_u64_vec(self) -> Option<Vec<u64>> {
+        if let DataType::U64(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_i8_vec(self) -> Option<Vec<i8>> {
+        if let DataType::I8(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_i16_vec(self) -> Option<Vec<i16>> {
+        if let DataType::I16(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_i32_vec(self) -> Option<Vec<i32>> {
+        if let DataType::I32(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_i64_vec(self) -> Option<Vec<i64>> {
+        if let DataType::I64(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_f64_vec(self) -> Option<Vec<f64>> {
+        if let DataType::F64(v) = self { Some(v) } else { None }
     }
 
-    // Convert `DataType::i64` into an `ndarray::ArrayD<i64>`.
+    pub fn into_bool_vec(self) -> Option<Vec<bool>> {
+        if let DataType::Bool(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_bf16_vec(self) -> Option<Vec<u16>> {
+        if let DataType::Bf16(v) = self { Some(v) } else { None }
+    }
+
+    #[cfg(feature = "half")]
+    pub fn into_f16_vec(self) -> Option<Vec<half::f16>> {
+        if let DataType::F16(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_str_vec(self) -> Option<Vec<String>> {
+        if let DataType::String(v) = self { Some(v) } else { None }
+    }
+
+    pub fn into_bytes_vec(self) -> Option<Vec<Vec<u8>>> {
+        if let DataType::Bytes(v) = self { Some(v) } else { None }
+    }
+
+    /// Converts numeric (and boolean) values to the Triton datatype
+    /// `datatype`, like [`cast_to`](Self::cast_to) in [`CastMode::Checked`]
+    /// mode.
     ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_i64(&self, shape: &[usize]) -> Option<ArrayD<i64>> {
-        if let DataType::I64(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
-        }
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] for an unknown datatype, and
+    /// [`TrustonError::InferenceError`] if either datatype is not numeric
+    /// (`BYTES`, raw JSON) or a value does not fit the target.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    ///
+    /// let logits = DataType::F64(vec![0.5, 2.0]);
+    /// assert_eq!(logits.cast("FP32").unwrap().as_f32_vec(), Some(vec![0.5, 2.0]));
+    /// assert!(logits.cast("INT32").is_err());
+    /// assert_eq!(logits.cast("BOOL").unwrap().as_bool_vec(), Some(vec![true, true]));
+    /// assert!(DataType::I32(vec![-1]).cast("UINT8").is_err());
+    /// ```
+    pub fn cast(&self, datatype: &str) -> Result<DataType, TrustonError> {
+        self.cast_to(datatype.parse()?, CastMode::Checked)
     }
 
-    // Convert `DataType::F32` into an `ndarray::ArrayD<f32>`.
+    /// Converts numeric (and boolean) values to `dtype`, failing, clamping or
+    /// wrapping values the target can't hold, as set by `mode`.
     ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_f32(&self, shape: &[usize]) -> Option<ArrayD<f32>> {
-        if let DataType::F32(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
+    /// Unlike [`cast`](Self::cast), floats with a fraction don't silently
+    /// become integers in [`CastMode::Checked`] mode, finite values don't
+    /// overflow to infinity, and `BF16` values take part. `BOOL` is `true` for
+    /// non-zero values.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if either datatype is not
+    /// numeric (`BYTES`, raw JSON, or `FP16` without the `half` feature), or,
+    /// in checked mode, naming the first value the target can't hold.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{CastMode, DataType};
+    ///
+    /// let ids = DataType::I64(vec![7, 3_000_000_000]);
+    /// assert!(ids.cast_to(TritonDtype::Int32, CastMode::Checked).is_err());
+    /// let ids = ids.cast_to(TritonDtype::Int32, CastMode::Saturating)?;
+    /// assert_eq!(ids.as_i32_vec(), Some(vec![7, i32::MAX]));
+    ///
+    /// let scores = DataType::F64(vec![0.25, 1e40]);
+    /// assert!(scores.cast_to(TritonDtype::Fp32, CastMode::Checked).is_err());
+    /// assert_eq!(
+    ///     scores.cast_to(TritonDtype::Fp32, CastMode::Saturating)?.as_f32_vec(),
+    ///     Some(vec![0.25, f32::MAX])
+    /// );
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn cast_to(&self, dtype: TritonDtype, mode: CastMode) -> Result<DataType, TrustonError> {
+        let cast = match dtype {
+            TritonDtype::Bool => self.non_zero().map(|v| Ok(DataType::Bool(v))),
+            TritonDtype::Uint8 => self.cast_values(mode).map(|v| v.map(DataType::U8)),
+            TritonDtype::Uint16 => self.cast_values(mode).map(|v| v.map(DataType::U16)),
+            TritonDtype::Uint32 => self.cast_values(mode).map(|v| v.map(DataType::U32)),
+            TritonDtype::Uint64 => self.cast_values(mode).map(|v| v.map(DataType::U64)),
+            TritonDtype::Int8 => self.cast_values(mode).map(|v| v.map(DataType::I8)),
+            TritonDtype::Int16 => self.cast_values(mode).map(|v| v.map(DataType::I16)),
+            TritonDtype::Int32 => self.cast_values(mode).map(|v| v.map(DataType::I32)),
+            TritonDtype::Int64 => self.cast_values(mode).map(|v| v.map(DataType::I64)),
+            TritonDtype::Fp32 => self.cast_values(mode).map(|v| v.map(DataType::F32)),
+            TritonDtype::Fp64 => self.cast_values(mode).map(|v| v.map(DataType::F64)),
+            #[cfg(feature = "half")]
+            TritonDtype::Fp16 => self.cast_values::<f32>(mode).map(|v| {
+                v.and_then(|v| {
+                    let bound = half::f16::MAX.to_f32();
+                    let values = v.iter().map(|&x| narrow(x, -bound..=bound, mode));
+                    values.map(|x| x.map(half::f16::from_f32)).collect::<Result<_, _>>()
+                })
+                .map(DataType::F16)
+            }),
+            TritonDtype::Bf16 => self.cast_values::<f32>(mode).map(|v| {
+                v.and_then(|v| {
+                    let bound = bf16_to_f32(0x7f7f);
+                    let values = v.iter().map(|&x| narrow(x, -bound..=bound, mode));
+                    values.map(|x| x.map(f32_to_bf16)).collect::<Result<_, _>>()
+                })
+                .map(DataType::Bf16)
+            }),
+            _ => None,
+        };
+        match cast {
+            Some(Ok(data)) => Ok(data),
+            Some(Err(value)) => Err(TrustonError::InferenceError(format!(
+                "{} value {} does not fit {}",
+                self.get_type_str(),
+                value,
+                dtype
+            ))),
+            None => Err(TrustonError::InferenceError(format!(
+                "cannot cast {} values to {}",
+                self.get_type_str(),
+                dtype
+            ))),
         }
     }
 
-    // Convert `DataType::F64` into an `ndarray::ArrayD<f64>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_f64(&self, shape: &[usize]) -> Option<ArrayD<f64>> {
-        if let DataType::F64(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
+    /// Every value converted to `T` as `mode` says, or the first value that
+    /// does not fit; `None` if the values are not numeric.
+    fn cast_values<T>(&self, mode: CastMode) -> Option<Result<Vec<T>, String>>
+    where
+        T: NumCast + Bounded + Zero + Copy + 'static,
+        u8: AsPrimitive<T>,
+        u16: AsPrimitive<T>,
+        u32: AsPrimitive<T>,
+        u64: AsPrimitive<T>,
+        i8: AsPrimitive<T>,
+        i16: AsPrimitive<T>,
+        i32: AsPrimitive<T>,
+        i64: AsPrimitive<T>,
+        f32: AsPrimitive<T>,
+        f64: AsPrimitive<T>,
+    {
+        fn all<S, T>(values: &[S], mode: CastMode) -> Result<Vec<T>, String>
+        where
+            S: NumCast + AsPrimitive<T> + fmt::Display,
+            T: NumCast + Bounded + Zero + Copy + 'static,
+        {
+            values
+                .iter()
+                .map(|&x| cast_value(x, mode).ok_or_else(|| x.to_string()))
+                .collect()
         }
+        Some(match self {
+            DataType::Bool(v) => Ok(v.iter().map(|&b| (b as u8).as_()).collect()),
+            DataType::U8(v) => all(v, mode),
+            DataType::U16(v) => all(v, mode),
+            DataType::U32(v) => all(v, mode),
+            DataType::U64(v) => all(v, mode),
+            DataType::I8(v) => all(v, mode),
+            DataType::I16(v) => all(v, mode),
+            DataType::I32(v) => all(v, mode),
+            DataType::I64(v) => all(v, mode),
+            DataType::F32(v) => all(v, mode),
+            DataType::F64(v) => all(v, mode),
+            DataType::Bf16(v) => {
+                let v: Vec<f32> = v.iter().map(|&x| bf16_to_f32(x)).collect();
+                all(&v, mode)
+            }
+            #[cfg(feature = "half")]
+            DataType::F16(v) => {
+                let v: Vec<f32> = v.iter().map(|x| x.to_f32()).collect();
+                all(&v, mode)
+            }
+            DataType::String(_) | DataType::Bytes(_) | DataType::Raw(_) => return None,
+        })
+    }
+
+    /// Whether each value is non-zero, or `None` if the values are not numeric.
+    fn non_zero(&self) -> Option<Vec<bool>> {
+        Some(match self {
+            DataType::Bool(v) => v.clone(),
+            DataType::U8(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::U16(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::U32(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::U64(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::I8(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::I16(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::I32(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::I64(v) => v.iter().map(|&x| x != 0).collect(),
+            DataType::F32(v) => v.iter().map(|&x| x != 0.0).collect(),
+            DataType::F64(v) => v.iter().map(|&x| x != 0.0).collect(),
+            DataType::Bf16(v) => v.iter().map(|&x| bf16_to_f32(x) != 0.0).collect(),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => v.iter().map(|x| x.to_f32() != 0.0).collect(),
+            DataType::String(_) | DataType::Bytes(_) | DataType::Raw(_) => return None,
+        })
     }
+}
 
-    // Convert `DataType::String` into an `ndarray::ArrayD<String>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_string(&self, shape: &[usize]) -> Option<ArrayD<String>> {
-        if let DataType::String(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
+/// How [`DataType::cast_to`] handles values the target datatype can't hold
+/// exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CastMode {
+    /// Fail on values out of the range of the target and on floats with a
+    /// fraction cast to an integer type. Rounding a float to a narrower float
+    /// type is allowed.
+    #[default]
+    Checked,
+    /// Clamp values to the range of the target and truncate fractions;
+    /// `NaN` becomes `0` in integer types.
+    Saturating,
+    /// Convert like Rust's `as`: integers wrap around, floats are truncated
+    /// and saturate, finite floats may overflow to infinity.
+    Lossy,
+}
+
+/// `x` converted to `T` as `mode` says, or `None` if it doesn't fit.
+fn cast_value<S, T>(x: S, mode: CastMode) -> Option<T>
+where
+    S: NumCast + AsPrimitive<T>,
+    T: NumCast + Bounded + Zero + Copy + 'static,
+{
+    if mode == CastMode::Lossy {
+        return Some(x.as_());
+    }
+    let wide = x.to_f64()?;
+    // a finite value must not overflow to infinity, and in checked mode a
+    // fraction must not be dropped
+    let exact = T::from(x).filter(|y| {
+        let narrow = y.to_f64().unwrap_or(f64::NAN);
+        !(wide.is_finite() && narrow.is_infinite())
+            && (mode != CastMode::Checked || wide.fract() == 0.0 || narrow.fract() != 0.0)
+    });
+    match (exact, mode) {
+        (Some(y), _) => Some(y),
+        (None, CastMode::Checked) => None,
+        _ if wide.is_nan() => Some(T::zero()),
+        _ if wide < 0.0 => Some(T::min_value()),
+        _ => Some(T::max_value()),
+    }
+}
+
+/// `x` kept within `range`, the finite values of a narrower float type, as
+/// `mode` says; the value itself if it doesn't fit in checked mode.
+fn narrow(x: f32, range: std::ops::RangeInclusive<f32>, mode: CastMode) -> Result<f32, String> {
+    if !x.is_finite() || range.contains(&x) {
+        return Ok(x);
+    }
+    match mode {
+        CastMode::Checked => Err(x.to_string()),
+        CastMode::Saturating => Ok(x.clamp(*range.start(), *range.end())),
+        CastMode::Lossy => Ok(x),
+    }
+}
+
+fn bf16_to_f32(bits: u16) -> f32 {
+    f32::from_bits((bits as u32) << 16)
+}
+
+/// The `BF16` nearest to `x` (ties to even).
+fn f32_to_bf16(x: f32) -> u16 {
+    if x.is_nan() {
+        return 0x7fc0;
+    }
+    let bits = x.to_bits();
+    ((bits + 0x7fff + ((bits >> 16) & 1)) >> 16) as u16
+}
+
+/// Serializes the values as a flat JSON array (the `data` field of a request input).
+///
+/// Values are written straight to the serializer, without building an
+/// intermediate `serde_json::Value`, which matters for large `BYTES` batches.
+///
+/// # Example
+/// ```
+/// use truston::client::io::DataType;
+///
+/// let data = DataType::String(vec!["a".into(), "b".into()]);
+/// assert_eq!(serde_json::to_string(&data).unwrap(), r#"["a","b"]"#);
+/// ```
+impl Serialize for DataType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DataType::Bool(v) => v.serialize(serializer),
+            DataType::U8(v) => v.serialize(serializer),
+            DataType::U16(v) => v.serialize(serializer),
+            DataType::U32(v) => v.serialize(serializer),
+            DataType::U64(v) => v.serialize(serializer),
+            DataType::I8(v) => v.serialize(serializer),
+            DataType::I16(v) => v.serialize(serializer),
+            DataType::I32(v) => v.serialize(serializer),
+            DataType::I64(v) => v.serialize(serializer),
+            DataType::F32(v) => v.serialize(serializer),
+            DataType::F64(v) => v.serialize(serializer),
+            DataType::String(v) => v.serialize(serializer),
+            // JSON can't carry raw bytes; requests send them in binary instead
+            DataType::Bytes(v) => serializer.collect_seq(v.iter().map(|x| BASE64.encode(x))),
+            DataType::Bf16(v) => v.serialize(serializer),
+            // as numbers, like the other float types
+            #[cfg(feature = "half")]
+            DataType::F16(v) => serializer.collect_seq(v.iter().map(|x| x.to_f32())),
+            DataType::Raw(v) => v.serialize(serializer),
         }
     }
+}
 
-    // Convert `DataType::Bf16` into an `ndarray::ArrayD<u16>`.
-    ///
-    /// Straightforward MVP1 implementation. Will be macro-driven in MVP2.
-    pub fn to_ndarray_bf16(&self, shape: &[usize]) -> Option<ArrayD<u16>> {
-        if let DataType::Bf16(v) = self {
-            ArrayD::from_shape_vec(shape, v.clone()).ok()
-        } else {
-            None
+/// JSON values of a request input, flat or nested along `shape`.
+pub(crate) struct TensorData<'a> {
+    pub data: &'a DataType,
+    /// Nest the values along this shape; flat when `None`.
+    pub nested: Option<&'a [usize]>,
+}
+
+impl Serialize for TensorData<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(shape) = self.nested.filter(|shape| shape.len() > 1) else {
+            return self.data.serialize(serializer);
+        };
+        match self.data {
+            DataType::Bool(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::U8(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::U16(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::U32(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::U64(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::I8(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::I16(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::I32(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::I64(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::F32(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::F64(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::String(v) => Nested { values: v, shape }.serialize(serializer),
+            DataType::Bf16(v) => Nested { values: v, shape }.serialize(serializer),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => {
+                let values: Vec<f32> = v.iter().map(|x| x.to_f32()).collect();
+                Nested { values: &values, shape }.serialize(serializer)
+            }
+            // bytes are always sent in binary, raw values as given
+            DataType::Bytes(_) | DataType::Raw(_) => self.data.serialize(serializer),
         }
     }
+}
 
+/// `values` as arrays nested along `shape`, which must hold exactly
+/// `values.len()` elements.
+struct Nested<'a, T> {
+    values: &'a [T],
+    shape: &'a [usize],
+}
+
+impl<T: Serialize> Serialize for Nested<'_, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some((&rows, row_shape)) = self.shape.split_first().filter(|_| self.shape.len() > 1)
+        else {
+            return self.values.serialize(serializer);
+        };
+        let row_len: usize = row_shape.iter().product();
+        serializer.collect_seq((0..rows).map(|row| Nested {
+            values: &self.values[row * row_len..(row + 1) * row_len],
+            shape: row_shape,
+        }))
+    }
 }
 
 /// A convenience trait for converting common Rust collection types
@@ -357,7 +745,7 @@ impl DataType {
 ///
 /// let labels: Vec<String> = vec!["cat".into(), "dog".into()];
 /// let dtype: DataType = labels.into_infer_data();
-/// assert_eq!(dtype.get_type_str(), "STRING");
+/// assert_eq!(dtype.get_type_str(), "BYTES");
 /// ```
 ///
 /// # Notes
@@ -383,6 +771,11 @@ impl IntoInferData for Vec<u16> {
         DataType::U16(self)
     }
 }
+impl IntoInferData for Vec<u32> {
+    fn into_infer_data(self) -> DataType {
+        DataType::U32(self)
+    }
+}
 impl IntoInferData for Vec<u64> {
     fn into_infer_data(self) -> DataType {
         DataType::U64(self)
@@ -423,7 +816,173 @@ impl IntoInferData for Vec<String> {
         DataType::String(self)
     }
 }
+impl IntoInferData for Vec<Vec<u8>> {
+    fn into_infer_data(self) -> DataType {
+        DataType::Bytes(self)
+    }
+}
+#[cfg(feature = "half")]
+impl IntoInferData for Vec<half::f16> {
+    fn into_infer_data(self) -> DataType {
+        DataType::F16(self)
+    }
+}
+
+/// Element type of the values of one [`DataType`] variant, for the generic
+/// [`DataType::to_vec`] and the `ndarray` conversions and views
+/// ([`InferOutput::rows`], ...).
+///
+/// `u16` only matches `UINT16` values: the raw bits of `BF16` values are
+/// read with [`DataType::as_bf16_slice`].
+pub trait TensorElement: Clone {
+    /// The values of `data`, if they are of this type.
+    fn values(data: &DataType) -> Option<&[Self]>;
+}
+
+impl TensorElement for bool {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::Bool(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for u8 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::U8(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for u16 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::U16(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for u32 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::U32(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for u64 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::U64(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for i8 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::I8(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for i16 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::I16(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for i32 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::I32(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for i64 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::I64(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for f32 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::F32(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for f64 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::F64(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for String {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::String(v) = data { Some(v) } else { None }
+    }
+}
+impl TensorElement for Vec<u8> {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::Bytes(v) = data { Some(v) } else { None }
+    }
+}
+#[cfg(feature = "half")]
+impl TensorElement for half::f16 {
+    fn values(data: &DataType) -> Option<&[Self]> {
+        if let DataType::F16(v) = data { Some(v) } else { None }
+    }
+}
 
+/// The values of `columns`, of one variant and `rows` values each, row by
+/// row: the `[rows, columns]` tensor of a table.
+#[cfg(any(feature = "arrow", feature = "polars"))]
+pub(crate) fn interleave_columns(columns: &[DataType], rows: usize) -> DataType {
+    fn row_major<T: Clone>(columns: &[&[T]], rows: usize) -> Vec<T> {
+        (0..rows).flat_map(|row| columns.iter().map(move |column| column[row].clone())).collect()
+    }
+    fn interleave<T: TensorElement>(columns: &[DataType], rows: usize) -> Vec<T> {
+        let columns: Vec<&[T]> = columns.iter().filter_map(T::values).collect();
+        row_major(&columns, rows)
+    }
+    match columns.first() {
+        None => DataType::F32(Vec::new()),
+        Some(DataType::Bool(_)) => DataType::Bool(interleave(columns, rows)),
+        Some(DataType::U8(_)) => DataType::U8(interleave(columns, rows)),
+        Some(DataType::U16(_)) => DataType::U16(interleave(columns, rows)),
+        Some(DataType::U32(_)) => DataType::U32(interleave(columns, rows)),
+        Some(DataType::U64(_)) => DataType::U64(interleave(columns, rows)),
+        Some(DataType::I8(_)) => DataType::I8(interleave(columns, rows)),
+        Some(DataType::I16(_)) => DataType::I16(interleave(columns, rows)),
+        Some(DataType::I32(_)) => DataType::I32(interleave(columns, rows)),
+        Some(DataType::I64(_)) => DataType::I64(interleave(columns, rows)),
+        Some(DataType::F32(_)) => DataType::F32(interleave(columns, rows)),
+        Some(DataType::F64(_)) => DataType::F64(interleave(columns, rows)),
+        #[cfg(feature = "half")]
+        Some(DataType::F16(_)) => DataType::F16(interleave(columns, rows)),
+        Some(DataType::String(_)) => DataType::String(interleave(columns, rows)),
+        Some(DataType::Bytes(_)) => DataType::Bytes(interleave(columns, rows)),
+        Some(DataType::Bf16(_)) => {
+            let columns: Vec<&[u16]> = columns.iter().filter_map(DataType::as_bf16_slice).collect();
+            DataType::Bf16(row_major(&columns, rows))
+        }
+        // raw JSON is never read from a table
+        Some(DataType::Raw(_)) => DataType::Raw(serde_json::Value::Null),
+    }
+}
+
+/// Number of values of the tensor `name` of shape `shape`.
+///
+/// # Errors
+/// Returns [`TrustonError::InvalidShape`] if the product of the dims
+/// overflows `usize`.
+///
+/// # Example
+/// ```
+/// use truston::client::io::element_count;
+///
+/// assert_eq!(element_count("x", &[2, 3, 4]).unwrap(), 24);
+/// assert!(element_count("x", &[usize::MAX, 2]).is_err());
+/// ```
+pub fn element_count(name: &str, shape: &[usize]) -> Result<usize, TrustonError> {
+    shape
+        .iter()
+        .try_fold(1usize, |count, &dim| count.checked_mul(dim))
+        .ok_or_else(|| {
+            TrustonError::InvalidShape(format!(
+                "{} with shape {:?} has too many values",
+                name, shape
+            ))
+        })
+}
+
+/// Memory layout of an image tensor, as produced by the `gpu` and `image`
+/// preprocessing modules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TensorLayout {
+    /// `[batch, channels, height, width]`, expected by most PyTorch/ONNX models.
+    #[default]
+    Nchw,
+    /// `[batch, height, width, channels]`, expected by most TensorFlow models.
+    Nhwc,
+}
 
 /// Represents a single input tensor for inference requests.
 ///
@@ -451,15 +1010,15 @@ impl IntoInferData for Vec<String> {
 /// use ndarray::array;
 /// use truston::client::io::InferInput;
 ///
-/// let arr = array![[1.0f32, 2.0], [3.0, 4.0]].into_dyn();
+/// let arr = array![[1.0f32, 2.0], [3.0, 4.0]];
 /// let input = InferInput::from_ndarray("matrix_input", arr);
 /// assert_eq!(input.input_shape, vec![2, 2]);
 /// ```
 ///
 /// # Notes
-/// - **MVP1**: only `ArrayD<T>` with `Vec<T>: IntoInferData` is supported.
-/// - **MVP2**: future versions may support zero-copy or borrowed buffers
-///   for better performance.
+/// - Any array with `Vec<T>: IntoInferData` is supported: owned arrays of
+///   any dimension, views ([`InferInput::from_ndarray_view`]) and
+///   non-contiguous arrays, which are copied in logical order.
 #[derive(Debug)]
 pub struct InferInput {
     pub input_name: String,
@@ -481,18 +1040,76 @@ impl InferInput {
             }
     }
 
-    pub fn from_ndarray<T>(name: impl Into<String>, arr: ArrayD<T>) -> Self
-    where
-        T: Clone + 'static,
-        Vec<T>: IntoInferData,
-    {
-        let shape = arr.shape().to_vec();
-        let (data, _) = arr.into_raw_vec_and_offset();
-        Self {
-            input_name: name.into(),
-            input_shape: shape,
-            input_data: data.into_infer_data(),
+    /// Like [`InferInput::new`], checking that `input_shape` matches the
+    /// number of values in `input_data`.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ShapeMismatch`] if it doesn't.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::{DataType, InferInput};
+    ///
+    /// assert!(InferInput::try_new("x".into(), vec![2, 2], DataType::F32(vec![0.0; 4])).is_ok());
+    /// assert!(InferInput::try_new("x".into(), vec![2, 3], DataType::F32(vec![0.0; 4])).is_err());
+    /// ```
+    pub fn try_new(
+        input_name: String,
+        input_shape: Vec<usize>,
+        input_data: DataType,
+    ) -> Result<Self, TrustonError> {
+        let input = InferInput::new(input_name, input_shape, input_data);
+        input.check_shape()?;
+        Ok(input)
+    }
+
+    /// Check that the shape matches the number of values.
+    ///
+    /// [`DataType::Raw`] data is not checked, since it may be nested.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ShapeMismatch`] if it doesn't, and
+    /// [`TrustonError::InvalidShape`] if the shape overflows.
+    pub fn check_shape(&self) -> Result<(), TrustonError> {
+        if matches!(self.input_data, DataType::Raw(_)) {
+            return Ok(());
         }
+        let expected = element_count(&self.input_name, &self.input_shape)?;
+        let actual = self.input_data.num_elements();
+        if expected != actual {
+            return Err(TrustonError::ShapeMismatch {
+                input: self.input_name.clone(),
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Build a `[1]` `BYTES` input holding an encoded file (JPEG, WAV, ...), for
+    /// models decoding it on the server, e.g. DALI pipelines or ensembles
+    /// starting with a Python decoding step.
+    ///
+    /// The bytes are sent as-is with the binary tensor extension, prefixed by
+    /// their length as Triton expects for `BYTES` elements.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use truston::client::io::InferInput;
+    ///
+    /// let jpeg = std::fs::read("cat.jpg").unwrap();
+    /// let input = InferInput::from_bytes("encoded_image", jpeg);
+    /// assert_eq!(input.input_shape, vec![1]);
+    /// ```
+    pub fn from_bytes(name: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        InferInput::new(name.into(), vec![1], DataType::Bytes(vec![bytes.into()]))
+    }
+
+    /// Build a `[len]` `UINT8` input from an encoded file, for models taking
+    /// the raw bytes as a `UINT8` vector instead of a `BYTES` element.
+    pub fn from_bytes_as_u8(name: impl Into<String>, bytes: impl Into<Vec<u8>>) -> Self {
+        let bytes = bytes.into();
+        InferInput::new(name.into(), vec![bytes.len()], DataType::U8(bytes))
     }
 }
 
@@ -508,9 +1125,97 @@ impl InferInput {
 ///   representation (e.g. Vecs, slices, etc.).
 /// - **MVP1**: Data is always cloned into JSON-friendly structures.
 /// - **MVP2**: Could add zero-copy or shared-buffer support.
+/// - `id` and `parameters` are omitted from the JSON when not set.
 #[derive(Serialize)]
-pub struct InferRequest<'a, T> {
+pub(crate) struct InferRequest<'a, T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<&'a str>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
     pub inputs: Vec<InferInputPayload<'a, T>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<&'a [InferRequestedOutput]>,
+}
+
+/// An output requested in an inference request, with optional per-output parameters.
+///
+/// When no outputs are requested the server returns all of them; listing only
+/// the needed ones saves bandwidth for models with many or large outputs.
+///
+/// # Example JSON
+/// ```json
+/// { "name": "probabilities", "parameters": { "classification": 3 } }
+/// ```
+///
+/// # Example
+/// ```
+/// use truston::client::io::InferRequestedOutput;
+///
+/// let top3 = InferRequestedOutput::new("probabilities").with_classification(3);
+/// assert_eq!(top3.parameters["classification"], 3);
+/// ```
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct InferRequestedOutput {
+    pub name: String,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+}
+
+impl InferRequestedOutput {
+    pub fn new(name: impl Into<String>) -> Self {
+        InferRequestedOutput {
+            name: name.into(),
+            parameters: serde_json::Map::new(),
+        }
+    }
+
+    /// Ask the server to return the top `count` classes instead of the raw tensor
+    /// (classification extension). The output is then returned as `BYTES` entries
+    /// of the form `"score:index[:label]"`.
+    pub fn with_classification(self, count: u64) -> Self {
+        self.with_parameter("classification", count)
+    }
+
+    /// Ask for the output in the binary tensor data extension format.
+    pub fn with_binary_data(self, binary_data: bool) -> Self {
+        self.with_parameter("binary_data", binary_data)
+    }
+
+    /// Ask the server to return the output as `datatype` (e.g. `"FP16"`),
+    /// for backends that convert outputs on request.
+    ///
+    /// Servers ignoring the request return the model's datatype; numeric
+    /// outputs are then cast client-side (see [`DataType::cast`]), so the
+    /// results always hold `datatype`.
+    pub fn with_datatype(self, datatype: impl Into<String>) -> Self {
+        self.with_parameter("datatype", datatype.into())
+    }
+
+    /// Datatype requested with [`with_datatype`](Self::with_datatype).
+    pub fn requested_datatype(&self) -> Option<&str> {
+        self.parameters.get("datatype").and_then(serde_json::Value::as_str)
+    }
+
+    pub fn with_parameter(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.parameters.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl From<&str> for InferRequestedOutput {
+    fn from(name: &str) -> Self {
+        InferRequestedOutput::new(name)
+    }
+}
+
+impl From<String> for InferRequestedOutput {
+    fn from(name: String) -> Self {
+        InferRequestedOutput::new(name)
+    }
 }
 
 /// Represents a single input payload entry in an inference request.
@@ -519,7 +1224,9 @@ pub struct InferRequest<'a, T> {
 /// - `name`: the tensor name, must match the model definition.
 /// - `shape`: dimensions of the tensor.
 /// - `datatype`: Triton-compatible datatype string (e.g. `"FP32"`, `"INT64"`).
-/// - `data`: the raw input data (usually a Vec or slice).
+/// - `data`: the raw input data (usually a Vec or slice); omitted when the
+///   tensor is sent with the binary extension.
+/// - `parameters`: input parameters such as `binary_data_size`; omitted when empty.
 ///
 /// # Example JSON
 /// ```json
@@ -531,11 +1238,14 @@ pub struct InferRequest<'a, T> {
 /// }
 /// ```
 #[derive(Serialize)]
-pub struct InferInputPayload<'a, T> {
+pub(crate) struct InferInputPayload<'a, T> {
     pub name: &'a str,
-    pub shape: Vec<usize>,
+    pub shape: &'a [usize],
     pub datatype: &'a str,
-    pub data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents a single output returned by Triton.
@@ -544,27 +1254,37 @@ pub struct InferInputPayload<'a, T> {
 /// - `name`: the output tensor name.
 /// - `shape`: dimensions of the output tensor.
 /// - `datatype`: datatype string, e.g. `"FP32"`.
-/// - `data`: raw data as `serde_json::Value` (to be converted later).
+/// - `data`: raw data as `serde_json::Value` (to be converted later); null
+///   when the output is sent in binary, its size being in `parameters`.
 #[derive(Debug, Deserialize, Clone)]
-pub struct TritonServerResponse {
+pub(crate) struct TritonServerResponse {
     pub name: String,
     pub shape: Vec<usize>,
-    pub datatype: String,
+    pub datatype: TritonDtype,
+    #[serde(default)]
     pub data: serde_json::Value,
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
 }
 
 /// Represents the full inference response returned by Triton.
 ///
 /// Usually contains multiple output tensors under `outputs`.
 #[derive(Debug, Deserialize, Clone)]
-pub struct InferResponse {
+pub(crate) struct InferResponse {
+    #[serde(default)]
+    pub model_name: String,
+    #[serde(default)]
+    pub model_version: String,
+    #[serde(default)]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
     pub outputs: Vec<TritonServerResponse>,
 }
 
 
 
 /// Represents a single output tensor after being parsed and converted
-/// from a raw [`TritonServerResponse`].
+/// from the raw JSON response of the server.
 ///
 /// Unlike the raw server response (which stores `data` as JSON),
 /// this struct already wraps the data into a strongly typed [`DataType`].
@@ -577,11 +1297,12 @@ pub struct InferResponse {
 ///
 /// # Example
 /// ```
+/// use truston::client::dtype::TritonDtype;
 /// use truston::client::io::{InferOutput, DataType};
 ///
 /// let output = InferOutput {
 ///     name: "probabilities".into(),
-///     datatype: "FP32".into(),
+///     datatype: TritonDtype::Fp32,
 ///     shape: vec![1, 3],
 ///     data: DataType::F32(vec![0.1, 0.7, 0.2]),
 /// };
@@ -592,11 +1313,19 @@ pub struct InferResponse {
 #[derive(Debug, Clone)]
 pub struct InferOutput {
     pub name: String,
-    pub datatype: String,
+    pub datatype: TritonDtype,
     pub shape: Vec<usize>,
     pub data: DataType,
 }
 
+/// Feeds an output to another model (e.g. in a [`Pipeline`](crate::client::pipeline::Pipeline))
+/// without copying its values.
+impl From<InferOutput> for InferInput {
+    fn from(output: InferOutput) -> Self {
+        InferInput::new(output.name, output.shape, output.data)
+    }
+}
+
 /// Represents the collection of all output tensors returned from
 /// a single inference request.
 ///
@@ -605,22 +1334,275 @@ pub struct InferOutput {
 ///
 /// # Example
 /// ```
+/// use truston::client::dtype::TritonDtype;
 /// use truston::client::io::{InferResults, InferOutput, DataType};
 ///
 /// let results = InferResults {
 ///     outputs: vec![InferOutput {
 ///         name: "predictions".into(),
-///         datatype: "INT64".into(),
+///         datatype: TritonDtype::Int64,
 ///         shape: vec![1],
 ///         data: DataType::I64(vec![42]),
 ///     }],
+///     ..Default::default()
 /// };
 ///
 /// assert_eq!(results.outputs.len(), 1);
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct InferResults {
     pub outputs: Vec<InferOutput>, 
+    /// Where and how the results were obtained.
+    pub provenance: Provenance,
+}
+
+impl InferResults {
+    /// The output called `name`, if the server returned it.
+    pub fn get(&self, name: &str) -> Option<&InferOutput> {
+        self.outputs.iter().find(|output| output.name == name)
+    }
+
+    /// The `BYTES` output called `name`, with one JSON document per element,
+    /// decoded as `T`s (e.g. a Python backend returning a JSON blob per
+    /// batch element).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if there is no such output,
+    /// and [`TrustonError::ParseError`] if it is not `BYTES` or an element
+    /// is not valid JSON for `T` (with its index).
+    ///
+    /// # Example
+    /// ```
+    /// use serde::Deserialize;
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput, InferResults};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Detection {
+    ///     label: String,
+    ///     score: f32,
+    /// }
+    ///
+    /// let results = InferResults {
+    ///     outputs: vec![InferOutput {
+    ///         name: "detections".into(),
+    ///         datatype: TritonDtype::Bytes,
+    ///         shape: vec![1],
+    ///         data: DataType::String(vec![r#"{"label": "cat", "score": 0.9}"#.into()]),
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let detections: Vec<Detection> = results.deserialize_output("detections")?;
+    /// assert_eq!(detections[0].label, "cat");
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn deserialize_output<T: DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Vec<T>, TrustonError> {
+        let output = self
+            .get(name)
+            .ok_or_else(|| TrustonError::InferenceError(format!("no output named {}", name)))?;
+        let element_error = |index: usize, e: serde_json::Error| {
+            TrustonError::ParseError(format!("output {} element {}: {}", name, index, e))
+        };
+        match &output.data {
+            DataType::String(v) => v
+                .iter()
+                .enumerate()
+                .map(|(i, element)| serde_json::from_str(element).map_err(|e| element_error(i, e)))
+                .collect(),
+            DataType::Bytes(v) => v
+                .iter()
+                .enumerate()
+                .map(|(i, element)| {
+                    serde_json::from_slice(element).map_err(|e| element_error(i, e))
+                })
+                .collect(),
+            data => Err(TrustonError::ParseError(format!(
+                "output {} is {}, not BYTES",
+                name,
+                data.get_type_str()
+            ))),
+        }
+    }
+}
+
+/// Number of values shown by [`InferResults::summary`] and the `Display`
+/// impls of the results.
+pub const SUMMARY_VALUES: usize = 8;
+
+/// A short description of an output: its name, datatype, shape and first
+/// values. Printing it with `{}` gives e.g.
+/// `probs: FP32 [1, 1000] [0.01, 0.2, 0.03, ... 997 more]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSummary {
+    pub name: String,
+    pub datatype: TritonDtype,
+    pub shape: Vec<usize>,
+    /// Number of values of the output.
+    pub num_values: usize,
+    /// The first values, formatted.
+    pub head: Vec<String>,
+}
+
+impl fmt::Display for OutputSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} {:?} [{}", self.name, self.datatype, self.shape, self.head.join(", "))?;
+        if self.num_values > self.head.len() {
+            let sep = if self.head.is_empty() { "" } else { ", " };
+            write!(f, "{}... {} more", sep, self.num_values - self.head.len())?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl InferOutput {
+    /// Name, datatype, shape and the first `max_values` values of the output.
+    pub fn summary(&self, max_values: usize) -> OutputSummary {
+        fn head<T: ToString>(v: &[T], n: usize) -> Vec<String> {
+            v.iter().take(n).map(T::to_string).collect()
+        }
+        let n = max_values;
+        let head = match &self.data {
+            DataType::Bool(v) => head(v, n),
+            DataType::U8(v) => head(v, n),
+            DataType::U16(v) => head(v, n),
+            DataType::U32(v) => head(v, n),
+            DataType::U64(v) => head(v, n),
+            DataType::I8(v) => head(v, n),
+            DataType::I16(v) => head(v, n),
+            DataType::I32(v) => head(v, n),
+            DataType::I64(v) => head(v, n),
+            DataType::F32(v) => head(v, n),
+            DataType::F64(v) => head(v, n),
+            #[cfg(feature = "half")]
+            DataType::F16(v) => head(v, n),
+            DataType::Bf16(v) => {
+                v.iter().take(n).map(|&x| f32::from_bits((x as u32) << 16).to_string()).collect()
+            }
+            DataType::String(v) => v.iter().take(n).map(|x| format!("{:?}", x)).collect(),
+            DataType::Bytes(v) => {
+                v.iter().take(n).map(|x| format!("<{} bytes>", x.len())).collect()
+            }
+            DataType::Raw(serde_json::Value::Array(v)) => head(v, n),
+            DataType::Raw(v) => head(std::slice::from_ref(v), n),
+        };
+        OutputSummary {
+            name: self.name.clone(),
+            datatype: self.datatype.clone(),
+            shape: self.shape.clone(),
+            num_values: self.data.num_elements(),
+            head,
+        }
+    }
+}
+
+/// Prints the [`summary`](InferOutput::summary) of the output, with its first
+/// [`SUMMARY_VALUES`] values.
+impl fmt::Display for InferOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.summary(SUMMARY_VALUES), f)
+    }
+}
+
+impl InferResults {
+    /// A summary of each output, with its first [`SUMMARY_VALUES`] values:
+    /// unlike `{:?}`, which prints every value, fit for logs.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput, InferResults};
+    ///
+    /// let results = InferResults {
+    ///     outputs: vec![InferOutput {
+    ///         name: "logits".into(),
+    ///         datatype: TritonDtype::Fp32,
+    ///         shape: vec![1, 1000],
+    ///         data: DataType::F32(vec![0.5; 1000]),
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let summary = &results.summary()[0];
+    /// assert_eq!(summary.num_values, 1000);
+    /// assert_eq!(
+    ///     summary.to_string(),
+    ///     "logits: FP32 [1, 1000] [0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, ... 992 more]"
+    /// );
+    /// ```
+    pub fn summary(&self) -> Vec<OutputSummary> {
+        self.outputs.iter().map(|output| output.summary(SUMMARY_VALUES)).collect()
+    }
+}
+
+/// Prints the model that produced the results, if known, then the
+/// [`summary`](InferResults::summary) of each output on its own line.
+impl fmt::Display for InferResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = &self.provenance;
+        if !p.model_name.is_empty() {
+            write!(f, "{} v{}: ", p.model_name, p.model_version)?;
+        }
+        write!(f, "{} outputs", self.outputs.len())?;
+        for output in &self.outputs {
+            write!(f, "\n  {}", output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Where an [`InferResults`] came from, for logging and auditing.
+///
+/// # Example
+/// ```no_run
+/// # use truston::client::http::TritonRestClient;
+/// # use truston::client::io::{DataType, InferInput};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = TritonRestClient::new("http://localhost:8000");
+/// let input = InferInput::new("x".into(), vec![1], DataType::F32(vec![1.0]));
+/// let results = client.infer(vec![input], "model", None).await?;
+///
+/// let p = &results.provenance;
+/// println!("{} v{} via {} ({} attempts, {:?})",
+///     p.model_name, p.model_version, p.endpoint, p.attempts, p.elapsed);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// URL (or gRPC endpoint) that produced the results.
+    pub endpoint: String,
+    /// Model that served the request, as reported by the server.
+    pub model_name: String,
+    /// Model version that served the request, as reported by the server.
+    pub model_version: String,
+    /// Requests sent, counting retries and fallbacks.
+    pub attempts: u32,
+    /// Whether the server answered from its response cache, when it reports
+    /// it (`cache_hit` response parameter).
+    pub cache_hit: Option<bool>,
+    /// Wall-clock time at which the call started.
+    pub started_at: SystemTime,
+    /// Wall time from the start of the call to the decoded results.
+    pub elapsed: Duration,
+}
+
+impl Default for Provenance {
+    fn default() -> Self {
+        Provenance {
+            endpoint: String::new(),
+            model_name: String::new(),
+            model_version: String::new(),
+            attempts: 0,
+            cache_hit: None,
+            started_at: SystemTime::UNIX_EPOCH,
+            elapsed: Duration::ZERO,
+        }
+    }
 }
 
 
@@ -629,15 +1611,72 @@ pub struct InferResults {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ndarray::array;
 
     // ============ DataType Tests ============
     
     #[test]
+    fn test_cast() {
+        let ints = DataType::I64(vec![0, 3, 200]);
+        assert_eq!(ints.cast("FP32").unwrap().as_f32_vec(), Some(vec![0.0, 3.0, 200.0]));
+        assert_eq!(ints.cast("BOOL").unwrap().as_bool_vec(), Some(vec![false, true, true]));
+        let flags = DataType::I32(vec![-1, 256, 0]).cast("BOOL").unwrap();
+        assert_eq!(flags.as_bool_vec(), Some(vec![true, true, false]));
+        let flags = DataType::F32(vec![0.5, 0.0]).cast("BOOL").unwrap();
+        assert_eq!(flags.as_bool_vec(), Some(vec![true, false]));
+        assert_eq!(ints.cast("UINT8").unwrap().as_u8_vec(), Some(vec![0, 3, 200]));
+        assert!(ints.cast("INT8").is_err());
+        assert!(DataType::F32(vec![2.5]).cast("INT32").is_err());
+        assert!(ints.cast("BYTES").is_err());
+        assert!(DataType::String(vec!["1".into()]).cast("INT32").is_err());
+
+        let requested = InferRequestedOutput::new("y").with_datatype("FP16");
+        assert_eq!(requested.requested_datatype(), Some("FP16"));
+        assert_eq!(
+            serde_json::to_string(&requested).unwrap(),
+            r#"{"name":"y","parameters":{"datatype":"FP16"}}"#
+        );
+    }
+
+    #[test]
+    fn test_cast_to_modes() {
+        use TritonDtype::*;
+        let floats = DataType::F64(vec![1.5, -300.0, f64::NAN]);
+        let checked = floats.cast_to(Int8, CastMode::Checked).unwrap_err();
+        assert_eq!(checked.to_string(), "Inference error: FP64 value 1.5 does not fit INT8");
+        let saturated = floats.cast_to(Int8, CastMode::Saturating).unwrap();
+        assert_eq!(saturated.as_i8_vec(), Some(vec![1, -128, 0]));
+        assert!(DataType::F64(vec![2.0]).cast_to(Int8, CastMode::Checked).is_ok());
+
+        let ints = DataType::I32(vec![-1, 256]);
+        assert!(ints.cast_to(Uint8, CastMode::Checked).is_err());
+        let saturated = ints.cast_to(Uint8, CastMode::Saturating).unwrap();
+        assert_eq!(saturated.as_u8_vec(), Some(vec![0, 255]));
+        let wrapped = ints.cast_to(Uint8, CastMode::Lossy).unwrap();
+        assert_eq!(wrapped.as_u8_vec(), Some(vec![255, 0]));
+        let flags = ints.cast_to(Bool, CastMode::Checked).unwrap();
+        assert_eq!(flags.as_bool_vec(), Some(vec![true, true]));
+
+        let bf16 = DataType::F32(vec![1.0, -2.5]).cast_to(Bf16, CastMode::Checked).unwrap();
+        assert_eq!(bf16.as_bf16_vec(), Some(vec![0x3f80, 0xc020]));
+        let back = bf16.cast_to(Fp64, CastMode::Checked).unwrap();
+        assert_eq!(back.as_f64_vec(), Some(vec![1.0, -2.5]));
+        assert!(DataType::String(vec![]).cast_to(Fp32, CastMode::Lossy).is_err());
+        assert!(DataType::I64(vec![]).cast_to(Bytes, CastMode::Lossy).is_err());
+    }
+
+    #[test]
+    fn test_overflowing_shapes_are_rejected() {
+        let input = InferInput::new("x".into(), vec![usize::MAX, 2], DataType::F32(vec![0.0]));
+        assert!(matches!(input.check_shape(), Err(TrustonError::InvalidShape(_))));
+    }
+
+    #[test]
+    #[allow(clippy::approx_constant)]
     fn test_get_type_str() {
         assert_eq!(DataType::Bool(vec![true, false]).get_type_str(), "BOOL");
         assert_eq!(DataType::U8(vec![1, 2, 3]).get_type_str(), "UINT8");
         assert_eq!(DataType::U16(vec![1, 2]).get_type_str(), "UINT16");
+        assert_eq!(DataType::U32(vec![1, 2]).get_type_str(), "UINT32");
         assert_eq!(DataType::U64(vec![1]).get_type_str(), "UINT64");
         assert_eq!(DataType::I8(vec![-1, 2]).get_type_str(), "INT8");
         assert_eq!(DataType::I16(vec![-100]).get_type_str(), "INT16");
@@ -670,6 +1709,18 @@ mod tests {
         assert_eq!(wrong_type.as_i32_vec(), None);
     }
 
+    #[test]
+    fn test_borrowing_and_consuming_accessors() {
+        let data = DataType::Bf16(vec![1, 2]);
+        assert_eq!(data.as_bf16_slice(), Some(&[1, 2][..]));
+        assert_eq!(data.as_u16_slice(), None);
+        assert_eq!(data.into_bf16_vec(), Some(vec![1, 2]));
+
+        let data = DataType::String(vec!["a".into()]);
+        assert_eq!(data.as_str_slice(), Some(&["a".to_string()][..]));
+        assert_eq!(data.into_bytes_vec(), None);
+    }
+
     #[test]
     fn test_as_f32_vec() {
         let data = DataType::F32(vec![1.5, 2.5, 3.5]);
@@ -677,6 +1728,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_as_f64_vec() {
         let data = DataType::F64(vec![3.14159, 2.71828]);
         assert_eq!(data.as_f64_vec(), Some(vec![3.14159, 2.71828]));
@@ -710,75 +1762,10 @@ mod tests {
     #[test]
     fn test_all_unsigned_int_vecs() {
         assert_eq!(DataType::U16(vec![1, 2, 3]).as_u16_vec(), Some(vec![1, 2, 3]));
+        assert_eq!(DataType::U32(vec![1, 2, 3]).as_u32_vec(), Some(vec![1, 2, 3]));
         assert_eq!(DataType::U64(vec![u64::MAX]).as_u64_vec(), Some(vec![u64::MAX]));
     }
 
-    // ============ NDArray Conversion Tests ============
-    
-    #[test]
-    fn test_to_ndarray_f32() {
-        let data = DataType::F32(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
-        let arr = data.to_ndarray_f32(&[2, 3]).unwrap();
-        
-        assert_eq!(arr.shape(), &[2, 3]);
-        assert_eq!(arr[[0, 0]], 1.0);
-        assert_eq!(arr[[1, 2]], 6.0);
-    }
-
-    #[test]
-    fn test_to_ndarray_i32() {
-        let data = DataType::I32(vec![1, 2, 3, 4]);
-        let arr = data.to_ndarray_i32(&[2, 2]).unwrap();
-        
-        assert_eq!(arr.shape(), &[2, 2]);
-        assert_eq!(arr[[0, 1]], 2);
-        assert_eq!(arr[[1, 1]], 4);
-    }
-
-    #[test]
-    fn test_to_ndarray_bool() {
-        let data = DataType::Bool(vec![true, false, true, false]);
-        let arr = data.to_ndarray_bool(&[2, 2]).unwrap();
-        
-        assert_eq!(arr[[0, 0]], true);
-        assert_eq!(arr[[0, 1]], false);
-    }
-
-    #[test]
-    fn test_to_ndarray_string() {
-        let data = DataType::String(vec!["a".into(), "b".into(), "c".into()]);
-        let arr = data.to_ndarray_string(&[3]).unwrap();
-        
-        assert_eq!(arr.shape(), &[3]);
-        assert_eq!(arr[[1]], "b");
-    }
-
-    #[test]
-    fn test_to_ndarray_wrong_shape() {
-        let data = DataType::F32(vec![1.0, 2.0, 3.0]);
-        // Shape [2, 2] requires 4 elements, but we only have 3
-        let result = data.to_ndarray_f32(&[2, 2]);
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn test_to_ndarray_wrong_type() {
-        let data = DataType::I32(vec![1, 2, 3, 4]);
-        // Trying to convert I32 to F32 ndarray
-        let result = data.to_ndarray_f32(&[2, 2]);
-        assert!(result.is_none());
-    }
-
-    #[test]
-    fn test_to_ndarray_multidimensional() {
-        let data = DataType::U8(vec![1, 2, 3, 4, 5, 6, 7, 8]);
-        let arr = data.to_ndarray_u8(&[2, 2, 2]).unwrap();
-        
-        assert_eq!(arr.shape(), &[2, 2, 2]);
-        assert_eq!(arr[[0, 0, 0]], 1);
-        assert_eq!(arr[[1, 1, 1]], 8);
-    }
-
     // ============ IntoInferData Trait Tests ============
     
     #[test]
@@ -792,6 +1779,7 @@ mod tests {
     fn test_into_infer_data_numeric_types() {
         assert!(matches!(vec![1u8].into_infer_data(), DataType::U8(_)));
         assert!(matches!(vec![1u16].into_infer_data(), DataType::U16(_)));
+        assert!(matches!(vec![1u32].into_infer_data(), DataType::U32(_)));
         assert!(matches!(vec![1u64].into_infer_data(), DataType::U64(_)));
         assert!(matches!(vec![1i8].into_infer_data(), DataType::I8(_)));
         assert!(matches!(vec![1i16].into_infer_data(), DataType::I16(_)));
@@ -828,35 +1816,68 @@ mod tests {
     }
     
     #[test]
-    fn test_infer_input_from_ndarray_i32() {
-        let arr = array![1, 2, 3, 4, 5, 6].into_dyn();
-        let input = InferInput::from_ndarray("int_input", arr);
-        
-        assert_eq!(input.input_name, "int_input");
-        assert_eq!(input.input_shape, vec![6]);
-        
-        let vec = input.input_data.as_i32_vec().unwrap();
-        assert_eq!(vec, vec![1, 2, 3, 4, 5, 6]);
+    fn test_infer_input_from_bytes() {
+        let input = InferInput::from_bytes("file", &b"\xff\xd8\xff"[..]);
+        assert_eq!(input.input_shape, vec![1]);
+        assert_eq!(input.input_data.get_type_str(), "BYTES");
+        assert!(input.check_shape().is_ok());
+
+        let input = InferInput::from_bytes_as_u8("file", vec![0xff, 0xd8, 0xff]);
+        assert_eq!(input.input_shape, vec![3]);
+        assert_eq!(input.input_data.as_u8_slice(), Some(&[0xff, 0xd8, 0xff][..]));
     }
 
+    // ============ Request Serialization Tests ============
+
     #[test]
-    fn test_infer_input_from_ndarray_3d() {
-        let data: Vec<f64> = (0..24).map(|x| x as f64).collect();
-        let arr = ArrayD::from_shape_vec(vec![2, 3, 4], data).unwrap();
-        let input = InferInput::from_ndarray("3d_tensor", arr);
-        
-        assert_eq!(input.input_shape, vec![2, 3, 4]);
-        assert_eq!(input.input_data.as_f64_vec().unwrap().len(), 24);
+    fn test_infer_request_omits_unset_fields() {
+        let request = InferRequest::<Vec<f32>> {
+            id: None,
+            parameters: serde_json::Map::new(),
+            inputs: vec![],
+            outputs: None,
+        };
+        assert_eq!(serde_json::to_string(&request).unwrap(), r#"{"inputs":[]}"#);
     }
 
     #[test]
-    fn test_infer_input_string_name_conversion() {
-        let arr = array![1.0f32].into_dyn();
-        let input = InferInput::from_ndarray("literal_str", arr);
-        assert_eq!(input.input_name, "literal_str");
-        
-        let input2 = InferInput::from_ndarray(String::from("string_type"), array![1.0f32].into_dyn());
-        assert_eq!(input2.input_name, "string_type");
+    fn test_infer_request_with_id_and_parameters() {
+        let mut parameters = serde_json::Map::new();
+        parameters.insert("priority".into(), serde_json::json!(1));
+        let request = InferRequest::<Vec<f32>> {
+            id: Some("req-1"),
+            parameters,
+            inputs: vec![],
+            outputs: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({"id": "req-1", "parameters": {"priority": 1}, "inputs": []})
+        );
+    }
+
+    #[test]
+    fn test_infer_request_with_requested_outputs() {
+        let outputs = vec![
+            InferRequestedOutput::new("probs").with_classification(2),
+            InferRequestedOutput::from("embedding"),
+        ];
+        let request = InferRequest::<Vec<f32>> {
+            id: None,
+            parameters: serde_json::Map::new(),
+            inputs: vec![],
+            outputs: Some(&outputs),
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({
+                "inputs": [],
+                "outputs": [
+                    {"name": "probs", "parameters": {"classification": 2}},
+                    {"name": "embedding"}
+                ]
+            })
+        );
     }
 
     // ============ Edge Cases ============
@@ -865,24 +1886,6 @@ mod tests {
     fn test_empty_vectors() {
         let data = DataType::F32(vec![]);
         assert_eq!(data.as_f32_vec(), Some(vec![]));
-        
-        let arr_result = data.to_ndarray_f32(&[0]);
-        assert!(arr_result.is_some());
-    }
-
-    #[test]
-    fn test_single_element() {
-        let data = DataType::I32(vec![42]);
-        let arr = data.to_ndarray_i32(&[1]).unwrap();
-        assert_eq!(arr[[0]], 42);
-    }
-
-    #[test]
-    fn test_large_shape() {
-        let size = 1000;
-        let data = DataType::U8((0..size).map(|x| (x % 256) as u8).collect());
-        let arr = data.to_ndarray_u8(&[10, 10, 10]).unwrap();
-        assert_eq!(arr.shape(), &[10, 10, 10]);
     }
 
     // ============ Type Safety Tests ============
@@ -894,7 +1897,6 @@ mod tests {
         assert!(data.as_i32_vec().is_none());
         assert!(data.as_bool_vec().is_none());
         assert!(data.as_str_vec().is_none());
-        assert!(data.to_ndarray_i32(&[2]).is_none());
     }
 
     #[test]
@@ -907,4 +1909,83 @@ mod tests {
         assert_eq!(cloned, vec![1, 2, 3]);
         assert_eq!(original, vec![1, 2, 3]);
     }
+
+    #[test]
+    fn test_generic_extraction() {
+        let data = DataType::String(vec!["a".into(), "b".into()]);
+        assert_eq!(data.to_vec::<String>(), data.as_str_vec());
+        assert_eq!(data.to_vec::<Vec<u8>>(), None);
+
+        // BF16 bits are not UINT16 values
+        let bf16 = DataType::Bf16(vec![0x3f80]);
+        assert_eq!(bf16.to_vec::<u16>(), None);
+        assert_eq!(u16::values(&bf16), None);
+        assert_eq!(bf16.as_u16_vec(), None);
+        assert_eq!(bf16.as_bf16_vec(), Some(vec![0x3f80]));
+    }
+
+    #[test]
+    fn test_results_summary() {
+        let output = |name: &str, shape: Vec<usize>, data: DataType| InferOutput {
+            name: name.into(),
+            datatype: data.dtype().unwrap(),
+            shape,
+            data,
+        };
+        let mut results = InferResults {
+            outputs: vec![
+                output("ids", vec![2], DataType::I64(vec![3, 4])),
+                output("text", vec![10], DataType::String(vec!["a".into(); 10])),
+                output("empty", vec![0], DataType::Bytes(Vec::new())),
+            ],
+            ..Default::default()
+        };
+        let summary = results.summary();
+        assert_eq!(summary[1].head.len(), SUMMARY_VALUES);
+        assert_eq!(summary[1].num_values, 10);
+        assert_eq!(results.outputs[2].summary(0).to_string(), "empty: BYTES [0] []");
+        assert_eq!(results.outputs[0].summary(0).to_string(), "ids: INT64 [2] [... 2 more]");
+
+        results.provenance.model_name = "m".into();
+        results.provenance.model_version = "1".into();
+        assert_eq!(
+            results.to_string(),
+            "m v1: 3 outputs\n  ids: INT64 [2] [3, 4]\n  text: BYTES [10] \
+             [\"a\", \"a\", \"a\", \"a\", \"a\", \"a\", \"a\", \"a\", ... 2 more]\n  \
+             empty: BYTES [0] []"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_output() {
+        let output = |name: &str, data: DataType| InferOutput {
+            name: name.into(),
+            datatype: TritonDtype::Bytes,
+            shape: vec![data.num_elements()],
+            data,
+        };
+        let results = InferResults {
+            outputs: vec![
+                output("json", DataType::String(vec!["[1, 2]".into(), "[]".into()])),
+                output("raw", DataType::Bytes(vec![b"[3]".to_vec()])),
+                output("broken", DataType::String(vec!["[1]".into(), "{".into()])),
+                output("ids", DataType::I64(vec![1])),
+            ],
+            ..Default::default()
+        };
+
+        let json: Vec<Vec<u32>> = results.deserialize_output("json").unwrap();
+        assert_eq!(json, vec![vec![1, 2], vec![]]);
+        assert_eq!(results.deserialize_output::<Vec<u32>>("raw").unwrap(), vec![vec![3]]);
+        match results.deserialize_output::<Vec<u32>>("broken") {
+            Err(TrustonError::ParseError(message)) => {
+                assert!(message.starts_with("output broken element 1:"), "{}", message)
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(results.deserialize_output::<u32>("ids").is_err());
+        let missing = results.deserialize_output::<u32>("missing");
+        assert!(matches!(missing, Err(TrustonError::InferenceError(_))));
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,343 @@
+//! Client-side concurrency and rate limits.
+//!
+//! Many tasks sharing one client can send bursts that fill Triton's request
+//! queue and time out there. A cap on the requests in flight
+//! ([`TritonRestClientBuilder::max_concurrent_requests`](crate::client::http::TritonRestClientBuilder::max_concurrent_requests))
+//! and a token bucket ([`TritonRestClientBuilder::rate_limit`](crate::client::http::TritonRestClientBuilder::rate_limit))
+//! make the excess wait in the client instead.
+//!
+//! An inference holds its slot from the first attempt until its results are
+//! decoded, retries and fallbacks included; other calls hold one per call.
+//! The time an inference waited is reported to the
+//! [metrics recorders](crate::client::metrics::InferRecorder), and the wait
+//! is bounded by the deadline of the request, if any.
+//!
+//! Inferences waiting for a slot are served by
+//! [queue priority](crate::client::options::InferOptions::with_queue_priority),
+//! lowest value first, then in arrival order: online requests sent with the
+//! default priority of `0` jump ahead of bulk scoring sent with a higher value
+//! through the same client. Requests already sent are never interrupted; the
+//! token bucket serves requests in arrival order whatever their priority.
+//!
+//! # Example
+//! ```
+//! use truston::client::http::TritonRestClient;
+//! use truston::client::limit::RateLimit;
+//! use truston::client::options::InferOptions;
+//!
+//! let client = TritonRestClient::builder("http://localhost:8000")
+//!     .max_concurrent_requests(64)
+//!     .rate_limit(RateLimit::per_second(500.0).with_burst(50))
+//!     .build()?;
+//!
+//! // nightly scoring only gets the slots online traffic leaves free
+//! let bulk = InferOptions::new().with_queue_priority(10);
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tokio::sync::oneshot;
+
+use crate::client::deadline::Deadline;
+use crate::utils::errors::TrustonError;
+
+/// Token bucket: `rate` requests per second on average, up to `burst` at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub rate: f64,
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// `rate` requests per second, in bursts of up to one second's worth.
+    pub fn per_second(rate: f64) -> Self {
+        RateLimit { rate, burst: rate.ceil().max(1.0) as u32 }
+    }
+
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    /// Tokens left; negative when requests are waiting for tokens.
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Slots of the requests in flight, handed to waiters by priority.
+#[derive(Debug)]
+struct Slots {
+    max: usize,
+    state: Mutex<SlotState>,
+}
+
+#[derive(Debug, Default)]
+struct SlotState {
+    in_use: usize,
+    /// Waiters by (priority, arrival), the smallest popped first.
+    waiters: BinaryHeap<Reverse<Waiter>>,
+    arrivals: u64,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    priority: u32,
+    arrival: u64,
+    slot: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        (self.priority, self.arrival) == (other.priority, other.arrival)
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.priority, self.arrival).cmp(&(other.priority, other.arrival))
+    }
+}
+
+impl Slots {
+    async fn acquire(&self, priority: u32) -> Slot<'_> {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_use < self.max && state.waiters.is_empty() {
+                state.in_use += 1;
+                return Slot { slots: self };
+            }
+            let (slot, receiver) = oneshot::channel();
+            let arrival = state.arrivals;
+            state.arrivals += 1;
+            state.waiters.push(Reverse(Waiter { priority, arrival, slot }));
+            receiver
+        };
+        let mut waiting = Waiting { slots: self, receiver: Some(receiver) };
+        // the sender is only dropped after handing the slot over
+        let _ = waiting.receiver.as_mut().unwrap().await;
+        waiting.receiver = None;
+        Slot { slots: self }
+    }
+
+    fn stats(&self) -> SlotStats {
+        let state = self.state.lock().unwrap();
+        SlotStats {
+            available: self.max.saturating_sub(state.in_use),
+            // waiters that gave up stay in the heap until a slot is released
+            waiters: state.waiters.iter().filter(|w| !w.0.slot.is_closed()).count(),
+        }
+    }
+
+    /// Hand a freed slot to the first waiter still waiting.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(Reverse(waiter)) = state.waiters.pop() {
+            if waiter.slot.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_use -= 1;
+    }
+}
+
+/// Slots of a [`Limiter`] at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SlotStats {
+    pub(crate) available: usize,
+    pub(crate) waiters: usize,
+}
+
+/// A waiter giving up (deadline, cancellation) passes on a slot it was
+/// handed but did not see.
+struct Waiting<'a> {
+    slots: &'a Slots,
+    receiver: Option<oneshot::Receiver<()>>,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if let Some(mut receiver) = self.receiver.take() {
+            receiver.close();
+            if receiver.try_recv().is_ok() {
+                self.slots.release();
+            }
+        }
+    }
+}
+
+/// A slot of the concurrency cap, released on drop.
+#[derive(Debug)]
+pub(crate) struct Slot<'a> {
+    slots: &'a Slots,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.slots.release();
+    }
+}
+
+/// The limits of a client.
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    slots: Option<Slots>,
+    bucket: Option<Mutex<Bucket>>,
+}
+
+impl Limiter {
+    pub(crate) fn new(max_concurrent: Option<usize>, rate: Option<RateLimit>) -> Self {
+        Limiter {
+            slots: max_concurrent
+                .map(|max| Slots { max: max.max(1), state: Mutex::new(SlotState::default()) }),
+            bucket: rate.map(|limit| {
+                Mutex::new(Bucket { limit, tokens: limit.burst as f64, updated: Instant::now() })
+            }),
+        }
+    }
+
+    /// Wait for a token and a free slot, returning the slot (released on
+    /// drop) and the time waited. Waiters of lower `priority` get slots first.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::DeadlineExceeded`] if `deadline` passes first.
+    pub(crate) async fn acquire(
+        &self,
+        deadline: Option<Deadline>,
+        priority: u32,
+    ) -> Result<(Option<Slot<'_>>, Duration), TrustonError> {
+        let started = Instant::now();
+        if let Some(wait) = self.reserve_token(deadline)? {
+            tracing::debug!("rate limited, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+        let slot = match &self.slots {
+            None => None,
+            Some(slots) => {
+                let acquire = slots.acquire(priority);
+                Some(match deadline {
+                    None => acquire.await,
+                    Some(deadline) => tokio::time::timeout(deadline.remaining(), acquire)
+                        .await
+                        .map_err(|_| TrustonError::DeadlineExceeded)?,
+                })
+            }
+        };
+        Ok((slot, started.elapsed()))
+    }
+
+    /// Free slots and requests waiting for one, if requests in flight are capped.
+    pub(crate) fn slot_stats(&self) -> Option<SlotStats> {
+        self.slots.as_ref().map(Slots::stats)
+    }
+
+    /// Take a token, returning how long to wait until it is available.
+    fn reserve_token(&self, deadline: Option<Deadline>) -> Result<Option<Duration>, TrustonError> {
+        let Some(bucket) = &self.bucket else {
+            return Ok(None);
+        };
+        let mut bucket = bucket.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * bucket.limit.rate;
+        bucket.tokens = (bucket.tokens + refill).min(bucket.limit.burst as f64);
+        bucket.updated = now;
+
+        let missing = 1.0 - bucket.tokens;
+        let wait = if missing <= 0.0 {
+            None
+        } else if bucket.limit.rate > 0.0 {
+            Some(Duration::from_secs_f64(missing / bucket.limit.rate))
+        } else {
+            Some(Duration::MAX)
+        };
+        if let (Some(wait), Some(deadline)) = (wait, deadline)
+            && wait > deadline.remaining()
+        {
+            return Err(TrustonError::DeadlineExceeded);
+        }
+        bucket.tokens -= 1.0;
+        Ok(wait)
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_token_bucket() {
+        let limiter = Limiter::new(None, Some(RateLimit::per_second(100.0).with_burst(2)));
+        assert_eq!(limiter.reserve_token(None).unwrap(), None);
+        assert_eq!(limiter.reserve_token(None).unwrap(), None);
+        // the third waits for a token, about 10ms
+        let wait = limiter.reserve_token(None).unwrap().unwrap();
+        assert!(wait > Duration::from_millis(5) && wait <= Duration::from_millis(10));
+
+        let expired = Deadline::after(Duration::ZERO);
+        let result = limiter.reserve_token(Some(expired));
+        assert!(matches!(result, Err(TrustonError::DeadlineExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let limiter = Limiter::new(Some(1), None);
+        let (held, waited) = limiter.acquire(None, 0).await.unwrap();
+        assert!(held.is_some());
+        assert!(waited < Duration::from_millis(100));
+
+        let deadline = Deadline::after(Duration::from_millis(20));
+        let result = limiter.acquire(Some(deadline), 0).await;
+        assert!(matches!(result, Err(TrustonError::DeadlineExceeded)));
+        drop(held);
+        assert!(limiter.acquire(None, 0).await.is_ok());
+        assert!(Limiter::default().acquire(None, 0).await.unwrap().0.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_waiters_served_by_priority() {
+        let limiter = Arc::new(Limiter::new(Some(1), None));
+        let (held, _) = limiter.acquire(None, 0).await.unwrap();
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut waiters = Vec::new();
+        for (name, priority) in [("bulk", 10), ("online", 0), ("batch", 5), ("online2", 0)] {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            waiters.push(tokio::spawn(async move {
+                let _slot = limiter.acquire(None, priority).await.unwrap();
+                order.lock().unwrap().push(name);
+            }));
+            // queue them in this order
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // a waiter giving up does not take a slot
+        let deadline = Deadline::after(Duration::from_millis(5));
+        assert!(limiter.acquire(Some(deadline), 0).await.is_err());
+        assert_eq!(limiter.slot_stats(), Some(SlotStats { available: 0, waiters: 4 }));
+
+        drop(held);
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec!["online", "online2", "batch", "bulk"]);
+        assert_eq!(limiter.slots.as_ref().unwrap().state.lock().unwrap().in_use, 0);
+        assert_eq!(limiter.slot_stats(), Some(SlotStats { available: 1, waiters: 0 }));
+        assert_eq!(Limiter::default().slot_stats(), None);
+    }
+}
@@ -0,0 +1,155 @@
+//! Logging extension of Triton Inference Server.
+//!
+//! Wraps `/v2/logging`, which reads and changes the server's log settings at
+//! runtime, e.g. to turn verbose logging on while debugging a production
+//! instance without restarting it.
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::http::TritonRestClient;
+use crate::utils::errors::TrustonError;
+
+/// Log settings of the server.
+///
+/// Returned in full by the server; in an update, only the fields that are
+/// set are changed.
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "log_file": "",
+///   "log_info": true,
+///   "log_warning": true,
+///   "log_error": true,
+///   "log_verbose_level": 0,
+///   "log_format": "default"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct LogSettings {
+    /// File the server logs to, empty for the console.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_info: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_warning: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_error: Option<bool>,
+    /// `0` disables verbose logging; higher levels log more.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_verbose_level: Option<u32>,
+    /// `default` or `ISO8601`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_format: Option<String>,
+}
+
+impl LogSettings {
+    /// Settings changing nothing, to be filled with the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_log_file(mut self, log_file: impl Into<String>) -> Self {
+        self.log_file = Some(log_file.into());
+        self
+    }
+
+    pub fn with_log_info(mut self, enabled: bool) -> Self {
+        self.log_info = Some(enabled);
+        self
+    }
+
+    pub fn with_log_warning(mut self, enabled: bool) -> Self {
+        self.log_warning = Some(enabled);
+        self
+    }
+
+    pub fn with_log_error(mut self, enabled: bool) -> Self {
+        self.log_error = Some(enabled);
+        self
+    }
+
+    pub fn with_verbose_level(mut self, level: u32) -> Self {
+        self.log_verbose_level = Some(level);
+        self
+    }
+
+    pub fn with_log_format(mut self, format: impl Into<String>) -> Self {
+        self.log_format = Some(format.into());
+        self
+    }
+}
+
+impl TritonRestClient {
+    /// Fetch the current log settings of the server.
+    ///
+    /// Sends `GET /v2/logging`.
+    pub async fn get_log_settings(&self) -> Result<LogSettings, TrustonError> {
+        let url = format!("{}/v2/logging", self.base_url);
+        let resp = self.send_request(true, || self.http.get(&url)).await?;
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+
+    /// Change the log settings of the server and return the resulting settings.
+    ///
+    /// Sends `POST /v2/logging` with the fields of `settings` that are set.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// use truston::client::logging::LogSettings;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let settings = client
+    ///     .update_log_settings(&LogSettings::new().with_verbose_level(1))
+    ///     .await?;
+    /// assert_eq!(settings.log_verbose_level, Some(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update_log_settings(
+        &self,
+        settings: &LogSettings,
+    ) -> Result<LogSettings, TrustonError> {
+        let url = format!("{}/v2/logging", self.base_url);
+        let resp = self
+            .send_request(true, || self.http.post(&url).json(settings))
+            .await?;
+
+        tracing::info!("update_log_settings: {} -> {}", url, resp.status());
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_sends_only_set_fields() {
+        let settings = LogSettings::new().with_verbose_level(2).with_log_info(false);
+        assert_eq!(
+            serde_json::to_value(&settings).unwrap(),
+            serde_json::json!({"log_info": false, "log_verbose_level": 2})
+        );
+
+        let body = r#"{"log_file":"","log_info":true,"log_warning":true,"log_error":true,
+            "log_verbose_level":0,"log_format":"default"}"#;
+        let current: LogSettings = serde_json::from_str(body).unwrap();
+        assert_eq!(current.log_verbose_level, Some(0));
+        assert_eq!(current.log_format.as_deref(), Some("default"));
+    }
+}
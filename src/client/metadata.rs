@@ -0,0 +1,145 @@
+//! Model metadata for Triton Inference Server.
+//!
+//! Wraps `GET /v2/models/{model_name}`, which describes the inputs and
+//! outputs (name, datatype, shape) a model expects.
+
+use serde::Deserialize;
+
+use crate::client::dtype::TritonDtype;
+use crate::client::http::TritonRestClient;
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+/// Describes one input or output tensor of a model.
+///
+/// Dynamic dimensions are reported by Triton as `-1`. For models with
+/// `max_batch_size > 0` the first dimension is the (dynamic) batch dimension.
+///
+/// # Example JSON
+/// ```json
+/// { "name": "input_ids", "datatype": "INT64", "shape": [-1, 128] }
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TensorMetadata {
+    pub name: String,
+    pub datatype: TritonDtype,
+    pub shape: Vec<i64>,
+}
+
+/// Metadata of a model as returned by `GET /v2/models/{model_name}`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ModelMetadata {
+    pub name: String,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub platform: String,
+    #[serde(default)]
+    pub inputs: Vec<TensorMetadata>,
+    #[serde(default)]
+    pub outputs: Vec<TensorMetadata>,
+}
+
+impl TritonRestClient {
+    /// Fetch the metadata (inputs, outputs, versions) of a model.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let metadata = client.model_metadata("resnet50").await?;
+    /// for input in &metadata.inputs {
+    ///     println!("{} {} {:?}", input.name, input.datatype, input.shape);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn model_metadata(&self, model_name: &str) -> Result<ModelMetadata, TrustonError> {
+        self.model_metadata_with_options(model_name, &InferOptions::new()).await
+    }
+
+    /// Like [`model_metadata`](TritonRestClient::model_metadata), for the
+    /// model version, with the extra headers and within the deadline of
+    /// `options`; its other fields don't apply.
+    #[tracing::instrument(
+        name = "model_metadata",
+        skip_all,
+        fields(model = model_name, status = tracing::field::Empty)
+    )]
+    pub async fn model_metadata_with_options(
+        &self,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<ModelMetadata, TrustonError> {
+        let url = self.model_url(model_name, options.model_version.as_deref());
+        let (_slot, _waited) =
+            self.limiter.acquire(options.deadline, options.queue_priority).await?;
+        let (resp, _attempts) = self
+            .send_request_until(true, options.deadline, None, || {
+                let mut builder = self.http.get(&url);
+                for (name, value) in &options.headers {
+                    builder = builder.header(name, value);
+                }
+                builder
+            })
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_metadata() {
+        let body = r#"{
+            "name": "bert",
+            "versions": ["1", "2"],
+            "platform": "onnxruntime_onnx",
+            "inputs": [{"name": "input_ids", "datatype": "INT64", "shape": [-1, 128]}],
+            "outputs": [
+                {"name": "logits", "datatype": "FP32", "shape": [-1, 2]},
+                {"name": "scale", "datatype": "FP8", "shape": [1]}
+            ]
+        }"#;
+        let metadata: ModelMetadata = serde_json::from_str(body).unwrap();
+
+        assert_eq!(metadata.versions, vec!["1", "2"]);
+        assert_eq!(metadata.inputs[0].shape, vec![-1, 128]);
+        assert_eq!(metadata.outputs[0].datatype, "FP32");
+        assert_eq!(metadata.outputs[1].datatype, "FP8");
+    }
+
+    #[tokio::test]
+    async fn test_metadata_with_headers() {
+        use crate::client::test_server::serve;
+
+        let body = r#"{"name":"bert","versions":["2"]}"#;
+        let (base_url, server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::builder(&base_url)
+            .default_header("x-model-tier", "bronze")
+            .default_header("x-team", "search")
+            .build()
+            .unwrap();
+
+        let options =
+            InferOptions::new().with_model_version("2").with_header("x-model-tier", "gold");
+        let metadata = client.model_metadata_with_options("bert", &options).await.unwrap();
+        assert_eq!(metadata.versions, vec!["2"]);
+
+        let request = server.await.unwrap().remove(0).head.to_lowercase();
+        assert!(request.starts_with("get /v2/models/bert/versions/2 "), "{}", request);
+        assert!(request.contains("x-model-tier: gold\r\n"), "{}", request);
+        assert!(!request.contains("bronze"), "{}", request);
+        assert!(request.contains("x-team: search\r\n"), "{}", request);
+    }
+}
@@ -0,0 +1,886 @@
+//! Prometheus exposition of the client's internal state.
+//!
+//! Server metrics show how busy Triton is; they do not show a client
+//! saturating on its own side (a queue filling up, requests sent to a
+//! failover endpoint, metadata refetched on every call). The gauges and
+//! counters below, all prefixed with `truston_`, are rendered in the
+//! Prometheus text format so they can be appended to the `/metrics` output
+//! of the application. Render several sources (clients, queues, recorders)
+//! through one [`MetricsWriter`]: it groups their samples by family, so each
+//! family has a single `# HELP` and `# TYPE` header.
+//!
+//! Per-model request counts, error counts, payload sizes and latencies are
+//! reported to the [`InferRecorder`]s registered with
+//! [`TritonRestClientBuilder::metrics_recorder`](crate::client::http::TritonRestClientBuilder::metrics_recorder).
+//! [`InferMetrics`] keeps them in memory, with latency histograms, and
+//! renders them in the same format.
+//!
+//! The other way round, [`scrape`] reads the metrics Triton itself exposes
+//! (on port 8002 by default) into [`TritonMetrics`].
+//!
+//! # Example
+//! ```
+//! use truston::client::http::TritonRestClient;
+//! use truston::client::metrics::{InferMetrics, MetricsWriter};
+//!
+//! let infer_metrics = InferMetrics::new();
+//! let client = TritonRestClient::builder("http://localhost:8000")
+//!     .metrics_recorder(infer_metrics.clone())
+//!     .build()?;
+//!
+//! // served on the `/metrics` endpoint of the application
+//! let mut writer = MetricsWriter::new();
+//! client.write_metrics(&mut writer);
+//! infer_metrics.write_metrics(&mut writer);
+//! let text = writer.render();
+//! assert!(text.contains("# TYPE truston_infer_duration_seconds histogram"));
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::client::cache::CacheStats;
+use crate::client::http::TritonRestClient;
+use crate::client::queue::{FlushReason, InferQueue};
+use crate::utils::errors::TrustonError;
+
+/// Prefix of every metric name.
+pub const METRIC_PREFIX: &str = "truston";
+
+/// Collects metric families from several sources and renders them in the
+/// Prometheus text format.
+///
+/// Samples written under the same family name by different sources (two
+/// queues, say) are rendered together, under a single `# HELP` and `# TYPE`
+/// header, as the format requires.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsWriter {
+    /// Name, header lines and sample lines of each family, in the order first written.
+    families: Vec<(String, String, String)>,
+}
+
+impl MetricsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sample lines of the family `name`, added with its header if it is new.
+    fn family(&mut self, name: &str, kind: &str, help: &str) -> &mut String {
+        let index = match self.families.iter().position(|(family, ..)| family == name) {
+            Some(index) => index,
+            None => {
+                let header = format!(
+                    "# HELP {prefix}_{name} {help}\n# TYPE {prefix}_{name} {kind}\n",
+                    prefix = METRIC_PREFIX,
+                );
+                self.families.push((name.to_string(), header, String::new()));
+                self.families.len() - 1
+            }
+        };
+        &mut self.families[index].2
+    }
+
+    /// Every family written so far, in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (_, header, samples) in &self.families {
+            out.push_str(header);
+            out.push_str(samples);
+        }
+        out
+    }
+}
+
+/// Appends the samples of one metric family to `out`. Each sample is a list
+/// of labels and a value.
+fn write_family(
+    out: &mut MetricsWriter,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: &[(Vec<(&str, &str)>, f64)],
+) {
+    let lines = out.family(name, kind, help);
+    for (labels, value) in samples {
+        write_sample(lines, name, labels, *value);
+    }
+}
+
+/// Appends one sample line to `out`.
+fn write_sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    let _ = write!(out, "{}_{}", METRIC_PREFIX, name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+/// Label value with `\`, `"` and newlines escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl TritonRestClient {
+    /// State of the client in the Prometheus text format, every series
+    /// labelled with the base URL of the client as `client`:
+    /// - `truston_endpoint_active`: 1 for the endpoint requests are sent to, 0 for the
+    ///   failover endpoints,
+    /// - `truston_fallbacks_total`: inferences sent to a fallback target,
+    /// - `truston_hedges_total`: duplicates sent for slow inferences (see
+    ///   [`hedge`](crate::client::hedge)),
+    /// - `truston_metadata_cache_hits_total` and `truston_metadata_cache_misses_total`:
+    ///   lookups of the metadata and config [`cache`](crate::client::cache),
+    /// - `truston_metadata_cache_entries`: entries currently cached,
+    /// - `truston_affinity_tokens`: affinity tokens pinned to an endpoint,
+    /// - `truston_client_slots_available` and `truston_client_slot_waiters`: free
+    ///   slots of the [concurrency cap](crate::client::limit) and requests waiting
+    ///   for one, when the cap is set.
+    ///
+    /// # Example
+    /// ```
+    /// # use truston::client::http::TritonRestClient;
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let metrics = client.metrics();
+    /// let active = r#"{client="http://localhost:8000",endpoint="http://localhost:8000"} 1"#;
+    /// assert!(metrics.contains(&format!("truston_endpoint_active{}", active)));
+    /// ```
+    pub fn metrics(&self) -> String {
+        let mut out = MetricsWriter::new();
+        self.write_metrics(&mut out);
+        out.render()
+    }
+
+    /// Write the metrics of [`metrics`](Self::metrics) to `out`.
+    pub fn write_metrics(&self, out: &mut MetricsWriter) {
+        // the series of several clients written to `out` stay apart
+        let client = ("client", self.endpoints[0].as_str());
+        let labels = vec![client];
+        let active = self.active_endpoint.load(Ordering::Relaxed);
+        let endpoints: Vec<_> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(i, endpoint)| {
+                let value = if i == active { 1.0 } else { 0.0 };
+                (vec![client, ("endpoint", endpoint.as_str())], value)
+            })
+            .collect();
+        write_family(
+            out,
+            "endpoint_active",
+            "gauge",
+            "Whether requests are sent to the endpoint.",
+            &endpoints,
+        );
+        write_family(
+            out,
+            "fallbacks_total",
+            "counter",
+            "Inferences sent to a fallback target.",
+            &[(labels.clone(), self.fallbacks_used() as f64)],
+        );
+        write_family(
+            out,
+            "hedges_total",
+            "counter",
+            "Duplicate requests sent for slow inferences.",
+            &[(labels.clone(), self.hedges_sent() as f64)],
+        );
+
+        let metadata = self.metadata_cache.stats();
+        let config = self.config_cache.stats();
+        let kinds = [("metadata", metadata), ("config", config)];
+        let samples = |value: fn(&CacheStats) -> f64| -> Vec<_> {
+            kinds
+                .iter()
+                .map(|(kind, stats)| (vec![client, ("kind", *kind)], value(stats)))
+                .collect()
+        };
+        write_family(
+            out,
+            "metadata_cache_hits_total",
+            "counter",
+            "Lookups served from the metadata cache.",
+            &samples(|stats| stats.hits as f64),
+        );
+        write_family(
+            out,
+            "metadata_cache_misses_total",
+            "counter",
+            "Lookups fetching from the server.",
+            &samples(|stats| stats.misses as f64),
+        );
+        write_family(
+            out,
+            "metadata_cache_entries",
+            "gauge",
+            "Entries in the metadata cache.",
+            &samples(|stats| stats.entries as f64),
+        );
+
+        write_family(
+            out,
+            "affinity_tokens",
+            "gauge",
+            "Affinity tokens pinned to an endpoint.",
+            &[(labels.clone(), self.affinity.len() as f64)],
+        );
+
+        if let Some(slots) = self.limiter.slot_stats() {
+            write_family(
+                out,
+                "client_slots_available",
+                "gauge",
+                "Free slots of the concurrency cap.",
+                &[(labels.clone(), slots.available as f64)],
+            );
+            write_family(
+                out,
+                "client_slot_waiters",
+                "gauge",
+                "Requests waiting for a slot of the concurrency cap.",
+                &[(labels, slots.waiters as f64)],
+            );
+        }
+    }
+}
+
+impl InferQueue {
+    /// State of the queue in the Prometheus text format, labelled with the model:
+    /// - `truston_queue_depth`: samples waiting to be picked up by the batching task,
+    /// - `truston_queue_capacity`: samples the queue holds before `infer` waits,
+    /// - `truston_queue_batches_total`: batches sent,
+    /// - `truston_queue_average_batch_size`: rows per batch sent, on average,
+    /// - `truston_queue_flushes_total`: batches sent, labelled with the
+    ///   [`FlushReason`] (`full`, `delay`, `overflow` or `closed`).
+    pub fn metrics(&self) -> String {
+        let mut out = MetricsWriter::new();
+        self.write_metrics(&mut out);
+        out.render()
+    }
+
+    /// Write the metrics of [`metrics`](Self::metrics) to `out`.
+    pub fn write_metrics(&self, out: &mut MetricsWriter) {
+        let labels = vec![("model", self.model_name())];
+        write_family(
+            out,
+            "queue_depth",
+            "gauge",
+            "Samples waiting to be batched.",
+            &[(labels.clone(), self.depth() as f64)],
+        );
+        write_family(
+            out,
+            "queue_capacity",
+            "gauge",
+            "Samples the queue holds.",
+            &[(labels.clone(), self.capacity() as f64)],
+        );
+
+        let stats = self.stats();
+        write_family(
+            out,
+            "queue_batches_total",
+            "counter",
+            "Batches sent.",
+            &[(labels.clone(), stats.batches as f64)],
+        );
+        write_family(
+            out,
+            "queue_average_batch_size",
+            "gauge",
+            "Rows per batch sent, on average.",
+            &[(labels.clone(), stats.average_batch_size().unwrap_or(0.0))],
+        );
+        let flushes: Vec<_> = FlushReason::ALL
+            .iter()
+            .map(|&reason| {
+                let mut labels = labels.clone();
+                labels.push(("reason", reason.as_str()));
+                (labels, stats.flushes(reason) as f64)
+            })
+            .collect();
+        write_family(out, "queue_flushes_total", "counter", "Batches sent, by reason.", &flushes);
+    }
+}
+
+/// One inference, as reported to an [`InferRecorder`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct InferSample<'a> {
+    /// Model the inference was sent to (before any fallback).
+    pub model: &'a str,
+    /// Whether results were returned.
+    pub success: bool,
+    /// Size of the encoded request body; 0 if the inputs could not be encoded.
+    pub request_bytes: u64,
+    /// `Content-Length` of the response, when one was received.
+    pub response_bytes: Option<u64>,
+    /// Time from the call until the results (or the error), retries included.
+    pub elapsed: Duration,
+    /// Part of `elapsed` spent waiting for the client's
+    /// [limits](crate::client::limit).
+    pub queue_wait: Duration,
+}
+
+/// Receives a sample for every inference of a client.
+///
+/// Called on the request path, so implementations should only update
+/// counters; export them from elsewhere. [`InferMetrics`] is the built-in one.
+pub trait InferRecorder: Debug + Send + Sync {
+    fn record(&self, sample: &InferSample<'_>);
+}
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+pub const LATENCY_BUCKETS: [f64; 14] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Counters and latency histogram of one model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    /// Inferences per bucket of [`LATENCY_BUCKETS`] (not cumulative), the
+    /// last one counting those above every bound.
+    pub latency_buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    pub latency_sum: Duration,
+    /// Time spent waiting for the client's [limits](crate::client::limit).
+    pub queue_wait_sum: Duration,
+}
+
+impl ModelStats {
+    fn record(&mut self, sample: &InferSample<'_>) {
+        self.requests += 1;
+        if !sample.success {
+            self.errors += 1;
+        }
+        self.request_bytes += sample.request_bytes;
+        self.response_bytes += sample.response_bytes.unwrap_or(0);
+        let seconds = sample.elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| seconds <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket] += 1;
+        self.latency_sum += sample.elapsed;
+        self.queue_wait_sum += sample.queue_wait;
+    }
+
+    /// Estimated `quantile` (0.5 for the median, 0.99, ...) of the
+    /// latencies, interpolated within its histogram bucket like Prometheus'
+    /// `histogram_quantile`. `None` before the first inference.
+    ///
+    /// Latencies above the last bucket are reported as its bound.
+    pub fn latency_quantile(&self, quantile: f64) -> Option<Duration> {
+        if self.requests == 0 {
+            return None;
+        }
+        let rank = quantile.clamp(0.0, 1.0) * self.requests as f64;
+        let mut below = 0;
+        for (i, &count) in self.latency_buckets.iter().enumerate() {
+            let Some(&upper) = LATENCY_BUCKETS.get(i) else { break };
+            if count > 0 && (below + count) as f64 >= rank {
+                let lower = if i == 0 { 0.0 } else { LATENCY_BUCKETS[i - 1] };
+                let within = (rank - below as f64) / count as f64;
+                return Some(Duration::from_secs_f64(lower + (upper - lower) * within));
+            }
+            below += count;
+        }
+        LATENCY_BUCKETS.last().map(|&bound| Duration::from_secs_f64(bound))
+    }
+}
+
+/// In-memory [`InferRecorder`] keeping [`ModelStats`] per model.
+///
+/// Clones share the same stats: register one clone with the client and
+/// render another.
+#[derive(Debug, Clone, Default)]
+pub struct InferMetrics {
+    models: Arc<Mutex<BTreeMap<String, ModelStats>>>,
+}
+
+impl InferMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stats of `model`, if it was called.
+    pub fn stats(&self, model: &str) -> Option<ModelStats> {
+        self.models.lock().unwrap().get(model).cloned()
+    }
+
+    /// Stats of every model called, by model name.
+    pub fn snapshot(&self) -> BTreeMap<String, ModelStats> {
+        self.models.lock().unwrap().clone()
+    }
+
+    /// The stats in the Prometheus text format, labelled with the model:
+    /// - `truston_infer_requests_total` and `truston_infer_errors_total`,
+    /// - `truston_infer_request_bytes_total` and `truston_infer_response_bytes_total`,
+    /// - `truston_infer_queue_wait_seconds_total`: time spent waiting for the client's limits,
+    /// - `truston_infer_duration_seconds`: histogram of the latencies.
+    pub fn render(&self) -> String {
+        let mut out = MetricsWriter::new();
+        self.write_metrics(&mut out);
+        out.render()
+    }
+
+    /// Write the metrics of [`render`](Self::render) to `out`.
+    pub fn write_metrics(&self, out: &mut MetricsWriter) {
+        let models = self.snapshot();
+        let mut counter = |name: &str, help: &str, value: fn(&ModelStats) -> f64| {
+            let samples: Vec<_> = models
+                .iter()
+                .map(|(model, stats)| (vec![("model", model.as_str())], value(stats)))
+                .collect();
+            write_family(out, name, "counter", help, &samples);
+        };
+        counter("infer_requests_total", "Inferences sent.", |stats| stats.requests as f64);
+        counter("infer_errors_total", "Inferences that failed.", |stats| stats.errors as f64);
+        counter("infer_request_bytes_total", "Bytes of request bodies.", |stats| {
+            stats.request_bytes as f64
+        });
+        counter("infer_response_bytes_total", "Bytes of response bodies.", |stats| {
+            stats.response_bytes as f64
+        });
+        counter("infer_queue_wait_seconds_total", "Time waiting for client limits.", |stats| {
+            stats.queue_wait_sum.as_secs_f64()
+        });
+
+        let out = out.family("infer_duration_seconds", "histogram", "Latency of inferences.");
+        for (model, stats) in &models {
+            let mut cumulative = 0;
+            for (i, count) in stats.latency_buckets.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BUCKETS.get(i).map_or("+Inf".to_string(), f64::to_string);
+                let labels = [("model", model.as_str()), ("le", le.as_str())];
+                write_sample(out, "infer_duration_seconds_bucket", &labels, cumulative as f64);
+            }
+            let labels = [("model", model.as_str())];
+            let sum = stats.latency_sum.as_secs_f64();
+            write_sample(out, "infer_duration_seconds_sum", &labels, sum);
+            write_sample(out, "infer_duration_seconds_count", &labels, cumulative as f64);
+        }
+    }
+}
+
+impl InferRecorder for InferMetrics {
+    fn record(&self, sample: &InferSample<'_>) {
+        let mut models = self.models.lock().unwrap();
+        match models.get_mut(sample.model) {
+            Some(stats) => stats.record(sample),
+            None => {
+                let mut stats = ModelStats::default();
+                stats.record(sample);
+                models.insert(sample.model.to_string(), stats);
+            }
+        }
+    }
+}
+
+/// One sample of a Prometheus text exposition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// Counters of one model version, from the `nv_inference_*` metrics.
+///
+/// Durations are cumulative, in microseconds, since the model was loaded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelMetrics {
+    pub model: String,
+    pub version: String,
+    pub request_success: f64,
+    /// Failed requests, whatever the reason.
+    pub request_failure: f64,
+    /// Inferences performed; a batch of 8 counts as 8.
+    pub inference_count: f64,
+    /// Model executions; a batch of 8 counts as 1.
+    pub execution_count: f64,
+    pub request_duration_us: f64,
+    pub queue_duration_us: f64,
+    pub compute_input_duration_us: f64,
+    pub compute_infer_duration_us: f64,
+    pub compute_output_duration_us: f64,
+    /// Requests waiting in the scheduler queue (a gauge).
+    pub pending_requests: f64,
+}
+
+impl ModelMetrics {
+    /// Average time a successful request spent queued.
+    pub fn avg_queue_time(&self) -> Option<Duration> {
+        self.average(self.queue_duration_us)
+    }
+
+    /// Average end-to-end time of a successful request in the server.
+    pub fn avg_request_time(&self) -> Option<Duration> {
+        self.average(self.request_duration_us)
+    }
+
+    fn average(&self, total_us: f64) -> Option<Duration> {
+        (self.request_success > 0.0)
+            .then(|| Duration::from_secs_f64(total_us / self.request_success / 1e6))
+    }
+}
+
+/// State of one GPU, from the `nv_gpu_*` metrics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuMetrics {
+    pub uuid: String,
+    /// Utilization over the last sampling interval, in `[0, 1]`.
+    pub utilization: f64,
+    pub memory_total_bytes: f64,
+    pub memory_used_bytes: f64,
+    pub power_usage_watts: f64,
+    pub power_limit_watts: f64,
+}
+
+/// The metrics of a Triton server, parsed from its `/metrics` endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TritonMetrics {
+    /// One entry per model version, sorted by model then version.
+    pub models: Vec<ModelMetrics>,
+    /// One entry per GPU, sorted by UUID.
+    pub gpus: Vec<GpuMetrics>,
+    /// Every sample, including those without a typed field.
+    pub samples: Vec<MetricSample>,
+}
+
+impl TritonMetrics {
+    /// Parse the Prometheus text format.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] on a malformed sample line.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::metrics::TritonMetrics;
+    ///
+    /// let text = r#"
+    /// ## TYPE nv_inference_count counter
+    /// nv_inference_count{model="bert",version="1"} 12
+    /// nv_gpu_utilization{gpu_uuid="GPU-0"} 0.5
+    /// "#;
+    /// let metrics = TritonMetrics::parse(text)?;
+    /// assert_eq!(metrics.model("bert", "1").unwrap().inference_count, 12.0);
+    /// assert_eq!(metrics.gpus[0].utilization, 0.5);
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn parse(text: &str) -> Result<TritonMetrics, TrustonError> {
+        let samples = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                parse_sample(line).ok_or_else(|| {
+                    TrustonError::ParseError(format!("invalid metrics line: {}", line))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut models: BTreeMap<(String, String), ModelMetrics> = BTreeMap::new();
+        let mut gpus: BTreeMap<String, GpuMetrics> = BTreeMap::new();
+        for sample in &samples {
+            let label = |name: &str| sample.labels.get(name).cloned().unwrap_or_default();
+            if let Some(metric) = sample.name.strip_prefix("nv_inference_") {
+                let key = (label("model"), label("version"));
+                let model = models.entry(key.clone()).or_insert_with(|| ModelMetrics {
+                    model: key.0,
+                    version: key.1,
+                    ..ModelMetrics::default()
+                });
+                let field = match metric {
+                    "request_success" => &mut model.request_success,
+                    "request_failure" => &mut model.request_failure,
+                    "count" => &mut model.inference_count,
+                    "exec_count" => &mut model.execution_count,
+                    "request_duration_us" => &mut model.request_duration_us,
+                    "queue_duration_us" => &mut model.queue_duration_us,
+                    "compute_input_duration_us" => &mut model.compute_input_duration_us,
+                    "compute_infer_duration_us" => &mut model.compute_infer_duration_us,
+                    "compute_output_duration_us" => &mut model.compute_output_duration_us,
+                    "pending_request_count" => &mut model.pending_requests,
+                    _ => continue,
+                };
+                // failures are split by reason in recent releases
+                *field += sample.value;
+            } else if let Some(metric) = sample.name.strip_prefix("nv_gpu_") {
+                let uuid = label("gpu_uuid");
+                let gpu = gpus.entry(uuid.clone()).or_insert_with(|| GpuMetrics {
+                    uuid,
+                    ..GpuMetrics::default()
+                });
+                let field = match metric {
+                    "utilization" => &mut gpu.utilization,
+                    "memory_total_bytes" => &mut gpu.memory_total_bytes,
+                    "memory_used_bytes" => &mut gpu.memory_used_bytes,
+                    "power_usage" => &mut gpu.power_usage_watts,
+                    "power_limit" => &mut gpu.power_limit_watts,
+                    _ => continue,
+                };
+                *field = sample.value;
+            }
+        }
+        Ok(TritonMetrics {
+            models: models.into_values().collect(),
+            gpus: gpus.into_values().collect(),
+            samples,
+        })
+    }
+
+    /// The metrics of `version` of `model`.
+    pub fn model(&self, model: &str, version: &str) -> Option<&ModelMetrics> {
+        self.models.iter().find(|m| m.model == model && m.version == version)
+    }
+
+    /// The value of the sample named `name` whose labels include `labels`.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> Option<f64> {
+        self.samples
+            .iter()
+            .find(|sample| {
+                let has = |&(label, value): &(&str, &str)| {
+                    sample.labels.get(label).map(String::as_str) == Some(value)
+                };
+                sample.name == name && labels.iter().all(has)
+            })
+            .map(|sample| sample.value)
+    }
+}
+
+/// Parses `name{label="value",...} value [timestamp]`.
+fn parse_sample(line: &str) -> Option<MetricSample> {
+    let name_end = line.find(|c: char| c == '{' || c.is_whitespace())?;
+    let name = line[..name_end].to_string();
+    let mut rest = &line[name_end..];
+    let mut labels = BTreeMap::new();
+    if let Some(mut inner) = rest.strip_prefix('{') {
+        loop {
+            inner = inner.trim_start_matches([',', ' ']);
+            if let Some(after) = inner.strip_prefix('}') {
+                rest = after;
+                break;
+            }
+            let (label, after) = inner.split_once('=')?;
+            let mut chars = after.strip_prefix('"')?.char_indices();
+            let mut value = String::new();
+            let end = loop {
+                match chars.next()? {
+                    (i, '"') => break i,
+                    (_, '\\') => match chars.next()?.1 {
+                        'n' => value.push('\n'),
+                        c => value.push(c),
+                    },
+                    (_, c) => value.push(c),
+                }
+            };
+            labels.insert(label.trim().to_string(), value);
+            inner = &after[end + 2..];
+        }
+    }
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+    Some(MetricSample { name, labels, value })
+}
+
+/// Fetch and parse the metrics of a Triton server.
+///
+/// `base_metrics_url` is the metrics endpoint (`http://host:8002`), with or
+/// without the `/metrics` path.
+///
+/// # Errors
+/// - [`TrustonError::Http`] if the server can't be reached.
+/// - [`TrustonError::ServerError`] if it doesn't answer with a success status.
+/// - [`TrustonError::ParseError`] if the body is not in the Prometheus text format.
+///
+/// # Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let metrics = truston::client::metrics::scrape("http://localhost:8002").await?;
+/// for model in &metrics.models {
+///     println!("{} v{}: {:?} queued", model.model, model.version, model.avg_queue_time());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn scrape(base_metrics_url: &str) -> Result<TritonMetrics, TrustonError> {
+    let base = base_metrics_url.trim_end_matches('/');
+    let url = if base.ends_with("/metrics") {
+        base.to_string()
+    } else {
+        format!("{}/metrics", base)
+    };
+    let resp = reqwest::get(&url).await?;
+    if !resp.status().is_success() {
+        return Err(TritonRestClient::server_error(resp).await);
+    }
+    TritonMetrics::parse(&resp.text().await?)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_family() {
+        let mut out = MetricsWriter::new();
+        let samples = vec![(vec![("model", "a\"b")], 2.0), (vec![], 0.5)];
+        write_family(&mut out, "x", "gauge", "An x.", &samples);
+        assert_eq!(
+            out.render(),
+            "# HELP truston_x An x.\n# TYPE truston_x gauge\n\
+             truston_x{model=\"a\\\"b\"} 2\ntruston_x 0.5\n"
+        );
+
+        // a second source adds its samples under the same header
+        write_family(&mut out, "y", "counter", "A y.", &[(vec![], 1.0)]);
+        write_family(&mut out, "x", "gauge", "An x.", &[(vec![("model", "c")], 3.0)]);
+        assert_eq!(
+            out.render(),
+            "# HELP truston_x An x.\n# TYPE truston_x gauge\n\
+             truston_x{model=\"a\\\"b\"} 2\ntruston_x 0.5\ntruston_x{model=\"c\"} 3\n\
+             # HELP truston_y A y.\n# TYPE truston_y counter\ntruston_y 1\n"
+        );
+    }
+
+    fn sample(success: bool, millis: u64) -> InferSample<'static> {
+        InferSample {
+            model: "m",
+            success,
+            request_bytes: 10,
+            response_bytes: Some(4),
+            elapsed: Duration::from_millis(millis),
+            queue_wait: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_infer_metrics() {
+        let metrics = InferMetrics::new();
+        assert_eq!(metrics.stats("m"), None);
+        for millis in [2, 2, 2, 40] {
+            metrics.record(&sample(true, millis));
+        }
+        metrics.record(&sample(false, 60_000));
+
+        let stats = metrics.stats("m").unwrap();
+        assert_eq!((stats.requests, stats.errors), (5, 1));
+        assert_eq!((stats.request_bytes, stats.response_bytes), (50, 20));
+        assert_eq!(stats.queue_wait_sum, Duration::from_millis(5));
+        // rank 2.5 of the 3 samples in (1ms, 2.5ms]
+        let median = stats.latency_quantile(0.5).unwrap().as_secs_f64();
+        assert!((median - 0.00225).abs() < 1e-9);
+        assert_eq!(stats.latency_quantile(1.0), Some(Duration::from_secs(30)));
+
+        let text = metrics.render();
+        assert!(text.contains("truston_infer_errors_total{model=\"m\"} 1\n"));
+        let bucket = |le: &str, count: u64| {
+            let name = "truston_infer_duration_seconds_bucket";
+            format!("{}{{model=\"m\",le=\"{}\"}} {}\n", name, le, count)
+        };
+        assert!(text.contains(&bucket("0.001", 0)));
+        assert!(text.contains(&bucket("0.05", 4)));
+        assert!(text.contains(&bucket("+Inf", 5)));
+        assert!(text.contains("truston_infer_duration_seconds_count{model=\"m\"} 5\n"));
+    }
+
+    #[test]
+    fn test_parse_triton_metrics() {
+        let text = r#"
+# HELP nv_inference_request_success Number of successful inference requests
+# TYPE nv_inference_request_success counter
+nv_inference_request_success{model="bert",version="1"} 40
+nv_inference_request_failure{model="bert",reason="BACKEND",version="1"} 2
+nv_inference_request_failure{model="bert",reason="REJECTED",version="1"} 1
+nv_inference_count{model="bert",version="1"} 64
+nv_inference_queue_duration_us{model="bert",version="1"} 2000
+nv_inference_count{model="resnet \"v2\"",version="3"} 5 1700000000000
+nv_gpu_utilization{gpu_uuid="GPU-a"} 0.75
+nv_gpu_memory_used_bytes{gpu_uuid="GPU-a"} 1.5e+09
+nv_cpu_utilization 0.2
+"#;
+        let metrics = TritonMetrics::parse(text).unwrap();
+        assert_eq!(metrics.samples.len(), 9);
+        let bert = metrics.model("bert", "1").unwrap();
+        assert_eq!((bert.request_success, bert.request_failure), (40.0, 3.0));
+        assert_eq!(bert.inference_count, 64.0);
+        assert_eq!(bert.avg_queue_time(), Some(Duration::from_micros(50)));
+        assert_eq!(metrics.model("resnet \"v2\"", "3").unwrap().inference_count, 5.0);
+        assert_eq!(metrics.gpus, vec![GpuMetrics {
+            uuid: "GPU-a".into(),
+            utilization: 0.75,
+            memory_used_bytes: 1.5e9,
+            ..GpuMetrics::default()
+        }]);
+        assert_eq!(metrics.value("nv_cpu_utilization", &[]), Some(0.2));
+        let backend = [("model", "bert"), ("reason", "BACKEND")];
+        assert_eq!(metrics.value("nv_inference_request_failure", &backend), Some(2.0));
+
+        assert!(TritonMetrics::parse("nv_gpu_utilization{gpu_uuid=\"a} 1").is_err());
+        assert!(TritonMetrics::parse("nv_gpu_utilization high").is_err());
+    }
+
+    #[test]
+    fn test_client_metrics() {
+        let client = TritonRestClient::builder("http://a:8000")
+            .failover_endpoint("http://b:8000")
+            .build()
+            .unwrap();
+        let metrics = client.metrics();
+        let a = r#"client="http://a:8000""#;
+        let series = |name: &str, labels: &str, value: u32| {
+            format!("truston_{}{{{}{}}} {}\n", name, a, labels, value)
+        };
+        let endpoint = |url: &str| format!(r#",endpoint="{}""#, url);
+        assert!(metrics.contains(&series("endpoint_active", &endpoint("http://a:8000"), 1)));
+        assert!(metrics.contains(&series("endpoint_active", &endpoint("http://b:8000"), 0)));
+        assert!(metrics.contains(&series("fallbacks_total", "", 0)));
+        assert!(metrics.contains(&series("hedges_total", "", 0)));
+        assert!(metrics.contains(&series("affinity_tokens", "", 0)));
+        for kind in ["metadata", "config"] {
+            let kind = format!(r#",kind="{}""#, kind);
+            assert!(metrics.contains(&series("metadata_cache_hits_total", &kind, 0)));
+            assert!(metrics.contains(&series("metadata_cache_misses_total", &kind, 0)));
+            assert!(metrics.contains(&series("metadata_cache_entries", &kind, 0)));
+        }
+        assert!(!metrics.contains("truston_client_slots_available"));
+
+        // the series of two clients don't collide
+        let other = TritonRestClient::new("http://c:8000");
+        let mut writer = MetricsWriter::new();
+        client.write_metrics(&mut writer);
+        other.write_metrics(&mut writer);
+        let metrics = writer.render();
+        assert_eq!(metrics.matches("# TYPE truston_endpoint_active gauge").count(), 1);
+        let c = r#"client="http://c:8000""#;
+        assert!(metrics.contains(&format!("truston_hedges_total{{{}}} 0", c)));
+        let samples: Vec<&str> = metrics.lines().filter(|line| !line.starts_with('#')).collect();
+        let mut names: Vec<&str> =
+            samples.iter().map(|line| line.rsplit_once(' ').unwrap().0).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), samples.len());
+
+        let capped = TritonRestClient::builder("http://a:8000")
+            .max_concurrent_requests(4)
+            .build()
+            .unwrap();
+        let metrics = capped.metrics();
+        assert!(metrics.contains(&series("client_slots_available", "", 4)));
+        assert!(metrics.contains(&series("client_slot_waiters", "", 0)));
+    }
+}
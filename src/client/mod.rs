@@ -1,2 +1,57 @@
+pub mod admission;
+pub mod affinity;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod auth;
+pub mod batch;
+pub mod binary;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod buffers;
+pub mod cache;
+pub mod compression;
+pub mod config;
+pub mod deadline;
+pub mod dtype;
+pub mod events;
+#[cfg(feature = "test-util")]
+pub mod fault;
+pub mod generate;
+#[cfg(feature = "wgpu")]
+pub mod gpu;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod health;
+pub mod hedge;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod infer;
+pub mod interceptor;
 pub mod io;
-pub mod http;
\ No newline at end of file
+pub mod http;
+pub mod limit;
+pub mod logging;
+pub mod metadata;
+pub mod metrics;
+pub mod model_repository;
+#[cfg(feature = "ndarray")]
+pub mod ndarray;
+pub mod options;
+pub mod padding;
+pub mod pipeline;
+#[cfg(feature = "polars")]
+pub mod polars;
+pub mod postprocess;
+pub mod queue;
+pub mod raw;
+pub mod retry;
+pub mod schema;
+pub mod selector;
+pub mod sequence;
+#[cfg(test)]
+pub(crate) mod test_server;
+pub mod trace;
+pub mod upload;
+pub mod validation;
\ No newline at end of file
@@ -0,0 +1,357 @@
+//! Model repository extension for Triton Inference Server.
+//!
+//! Wraps the `/v2/repository` endpoints so models can be listed, loaded and
+//! unloaded at runtime (Triton must run with `--model-control-mode=explicit`
+//! for load/unload to be accepted), and a [`RepositoryWatcher`] reporting
+//! how the repository changes over time.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::client::http::TritonRestClient;
+use crate::client::raw::ApiPath;
+use crate::utils::errors::TrustonError;
+
+/// A single entry returned by the repository index.
+///
+/// # Example JSON
+/// ```json
+/// { "name": "resnet50", "version": "1", "state": "READY" }
+/// ```
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RepositoryModel {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Body of a repository index request.
+#[derive(Serialize)]
+struct RepositoryIndexRequest {
+    ready: bool,
+}
+
+/// Body of a load/unload request, carrying the optional `parameters` object.
+#[derive(Serialize)]
+struct RepositoryControlRequest {
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    parameters: Map<String, Value>,
+}
+
+impl TritonRestClient {
+    /// List every model known to the model repository, loaded or not.
+    ///
+    /// Sends `POST /v2/repository/index`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// for model in client.repository_index().await? {
+    ///     println!("{} -> {:?}", model.name, model.state);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn repository_index(&self) -> Result<Vec<RepositoryModel>, TrustonError> {
+        let url = format!("{}/v2/repository/index", self.base_url);
+        let body = RepositoryIndexRequest { ready: false };
+        let resp = self
+            .send_request(true, || self.http.post(&url).json(&body))
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(Self::server_error(resp).await);
+        }
+
+        self.parse_json(resp).await
+    }
+
+    /// Load (or reload) a model from the repository.
+    ///
+    /// Sends `POST /v2/repository/models/{model_name}/load`.
+    pub async fn load_model(&self, model_name: &str) -> Result<(), TrustonError> {
+        self.repository_control(model_name, "load", Map::new()).await
+    }
+
+    /// Load a model using `config` instead of the `config.pbtxt` stored in the repository.
+    ///
+    /// The config is sent as the JSON-serialized `config` parameter, as expected by Triton.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TritonRestClient::new("http://localhost:8000");
+    /// let config = serde_json::json!({ "max_batch_size": 16 });
+    /// client.load_model_with_config("resnet50", &config).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn load_model_with_config(
+        &self,
+        model_name: &str,
+        config: &Value,
+    ) -> Result<(), TrustonError> {
+        let mut parameters = Map::new();
+        parameters.insert("config".to_string(), Value::String(config.to_string()));
+        self.repository_control(model_name, "load", parameters).await
+    }
+
+    /// Unload a model, leaving the models it depends on loaded.
+    ///
+    /// Sends `POST /v2/repository/models/{model_name}/unload`.
+    pub async fn unload_model(&self, model_name: &str) -> Result<(), TrustonError> {
+        self.repository_control(model_name, "unload", Map::new()).await
+    }
+
+    /// Unload a model together with its dependents (e.g. the composing models of an ensemble).
+    pub async fn unload_model_with_dependents(&self, model_name: &str) -> Result<(), TrustonError> {
+        let mut parameters = Map::new();
+        parameters.insert("unload_dependents".to_string(), Value::Bool(true));
+        self.repository_control(model_name, "unload", parameters).await
+    }
+
+    async fn repository_control(
+        &self,
+        model_name: &str,
+        action: &str,
+        parameters: Map<String, Value>,
+    ) -> Result<(), TrustonError> {
+        let path = ApiPath::v2().join("repository").join("models").join(model_name).join(action);
+        let url = format!("{}{}", self.base_url, path);
+        let body = RepositoryControlRequest { parameters };
+        let resp = self
+            .send_request(false, || self.http.post(&url).json(&body))
+            .await?;
+
+        tracing::info!("{}_model: {} -> {}", action, url, resp.status());
+
+        if resp.status().is_success() {
+            self.forget_metadata(model_name);
+            Ok(())
+        } else {
+            Err(Self::server_error(resp).await)
+        }
+    }
+}
+
+/// A difference between two repository indexes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RepositoryChange {
+    /// A model (version) appeared in the index.
+    Added(RepositoryModel),
+    /// A model (version) disappeared from the index.
+    Removed(RepositoryModel),
+    /// The state of a model (version) changed, e.g. from `LOADING` to `READY`.
+    StateChanged {
+        previous: RepositoryModel,
+        current: RepositoryModel,
+    },
+}
+
+/// Changes turning the index `previous` into `current`.
+///
+/// Entries are matched by name and version; removals come first, then
+/// additions and state changes in the order of `current`.
+///
+/// # Example
+/// ```
+/// use truston::client::model_repository::{diff_index, RepositoryChange, RepositoryModel};
+///
+/// let model = |state: &str| RepositoryModel {
+///     name: "bert".into(),
+///     version: Some("1".into()),
+///     state: Some(state.into()),
+///     reason: None,
+/// };
+/// let changes = diff_index(&[model("LOADING")], &[model("READY")]);
+/// assert!(matches!(&changes[..], [RepositoryChange::StateChanged { .. }]));
+/// ```
+pub fn diff_index(
+    previous: &[RepositoryModel],
+    current: &[RepositoryModel],
+) -> Vec<RepositoryChange> {
+    let same = |a: &RepositoryModel, b: &RepositoryModel| {
+        a.name == b.name && a.version == b.version
+    };
+
+    let mut changes: Vec<RepositoryChange> = previous
+        .iter()
+        .filter(|old| !current.iter().any(|new| same(old, new)))
+        .map(|old| RepositoryChange::Removed(old.clone()))
+        .collect();
+    for new in current {
+        match previous.iter().find(|old| same(old, new)) {
+            None => changes.push(RepositoryChange::Added(new.clone())),
+            Some(old) if old.state != new.state => changes.push(RepositoryChange::StateChanged {
+                previous: old.clone(),
+                current: new.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    changes
+}
+
+/// Polls the repository index and reports what changed between polls, e.g.
+/// to keep the routing table of a gateway up to date.
+///
+/// The first poll reports every model as [`RepositoryChange::Added`].
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// # use truston::client::http::TritonRestClient;
+/// use truston::client::model_repository::{RepositoryChange, RepositoryWatcher};
+/// # #[tokio::main]
+/// # async fn main() {
+/// let client = Arc::new(TritonRestClient::new("http://localhost:8000"));
+/// let mut watcher = RepositoryWatcher::new(client, Duration::from_secs(5));
+/// tokio::spawn(async move {
+///     loop {
+///         match watcher.changes().await {
+///             Ok(changes) => {
+///                 for change in changes {
+///                     if let RepositoryChange::Added(model) = change {
+///                         println!("new model {}", model.name);
+///                     }
+///                 }
+///             }
+///             Err(e) => eprintln!("repository index failed: {}", e),
+///         }
+///     }
+/// });
+/// # }
+/// ```
+pub struct RepositoryWatcher {
+    client: Arc<TritonRestClient>,
+    interval: Interval,
+    known: Vec<RepositoryModel>,
+}
+
+impl RepositoryWatcher {
+    /// Watch the repository of `client`, polling its index every `period`.
+    ///
+    /// # Panics
+    /// If `period` is zero.
+    pub fn new(client: Arc<TritonRestClient>, period: Duration) -> Self {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        RepositoryWatcher { client, interval, known: Vec::new() }
+    }
+
+    /// Index as of the last successful poll.
+    pub fn models(&self) -> &[RepositoryModel] {
+        &self.known
+    }
+
+    /// Wait for the next poll finding changes, and return them.
+    ///
+    /// # Errors
+    /// Returns the error of a failed poll; the following call polls again
+    /// and diffs against the last successful poll.
+    pub async fn changes(&mut self) -> Result<Vec<RepositoryChange>, TrustonError> {
+        loop {
+            self.interval.tick().await;
+            let current = self.client.repository_index().await?;
+            let changes = diff_index(&self.known, &current);
+            self.known = current;
+            if !changes.is_empty() {
+                return Ok(changes);
+            }
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repository_index() {
+        let body = r#"[
+            {"name": "resnet50", "version": "1", "state": "READY"},
+            {"name": "bert", "state": "UNAVAILABLE", "reason": "unloaded"}
+        ]"#;
+        let models: Vec<RepositoryModel> = serde_json::from_str(body).unwrap();
+
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].version.as_deref(), Some("1"));
+        assert_eq!(models[1].version, None);
+        assert_eq!(models[1].reason.as_deref(), Some("unloaded"));
+    }
+
+    #[test]
+    fn test_diff_index() {
+        let model = |name: &str, version: &str, state: &str| RepositoryModel {
+            name: name.into(),
+            version: Some(version.into()),
+            state: Some(state.into()),
+            reason: None,
+        };
+        let previous = [
+            model("a", "1", "READY"),
+            model("a", "2", "LOADING"),
+            model("b", "1", "READY"),
+        ];
+        let current = [
+            model("a", "2", "READY"),
+            model("b", "1", "READY"),
+            model("c", "1", "READY"),
+        ];
+
+        assert_eq!(
+            diff_index(&previous, &current),
+            vec![
+                RepositoryChange::Removed(model("a", "1", "READY")),
+                RepositoryChange::StateChanged {
+                    previous: model("a", "2", "LOADING"),
+                    current: model("a", "2", "READY"),
+                },
+                RepositoryChange::Added(model("c", "1", "READY")),
+            ]
+        );
+        assert!(diff_index(&current, &current).is_empty());
+    }
+
+    #[test]
+    fn test_control_request_omits_empty_parameters() {
+        let body = RepositoryControlRequest { parameters: Map::new() };
+        assert_eq!(serde_json::to_string(&body).unwrap(), "{}");
+
+        let mut parameters = Map::new();
+        parameters.insert("unload_dependents".to_string(), Value::Bool(true));
+        let body = RepositoryControlRequest { parameters };
+        assert_eq!(
+            serde_json::to_string(&body).unwrap(),
+            r#"{"parameters":{"unload_dependents":true}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_control_encodes_model_names() {
+        use crate::client::test_server::serve;
+
+        let (base_url, server) = serve(vec![(200, "")]).await;
+        let client = TritonRestClient::new(&base_url);
+        client.unload_model("team/bert v2").await.unwrap();
+
+        let requests = server.await.unwrap();
+        let unload = "POST /v2/repository/models/team%2Fbert%20v2/unload HTTP/1.1";
+        assert_eq!(requests[0].line(), unload);
+    }
+}
@@ -0,0 +1,712 @@
+//! `ndarray` arrays (requires the `ndarray` feature, on by default).
+//!
+//! Conversions between arrays and tensors:
+//! - [`InferInput::from_ndarray`] and [`InferInput::from_ndarray_view`]
+//!   build inputs from arrays of any dimension,
+//! - [`DataType::to_ndarray`] and its per-type shorthands copy values into
+//!   an array of a given shape,
+//! - [`InferOutput::view`], [`InferOutput::to_ndarray`] and
+//!   [`InferOutput::rows`] give an output as an array of its own shape, and
+//!   [`InferResults::get_f32_array`] and co. look it up by name first.
+//!
+//! Without the feature, tensors are plain `Vec`s ([`DataType::as_f32_slice`],
+//! [`DataType::to_vec`], ...).
+//!
+//! # Example
+//! ```
+//! use ndarray::array;
+//! use truston::client::io::InferInput;
+//!
+//! let input = InferInput::from_ndarray("x", array![[1.0f32, 2.0], [3.0, 4.0]]);
+//! assert_eq!(input.input_shape, vec![2, 2]);
+//! assert_eq!(input.input_data.to_ndarray::<f32>(&[4]).unwrap()[[3]], 4.0);
+//! ```
+
+use ::ndarray::{ArrayBase, ArrayD, ArrayView, ArrayViewD, Data, Dimension, IxDyn};
+
+use crate::client::io::{
+    DataType, InferInput, InferOutput, InferResults, IntoInferData, TensorElement,
+    element_count,
+};
+use crate::utils::errors::TrustonError;
+
+impl DataType {
+    /// The values as an array of `shape`, for any element type `T`
+    /// (`to_ndarray::<f32>(..)`, `to_ndarray::<String>(..)`, ...).
+    ///
+    /// # Returns
+    /// `None` if the values are not of type `T` or don't fit `shape`.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::io::DataType;
+    ///
+    /// let data = DataType::F32(vec![0.1, 0.7, 0.2, 0.4]);
+    /// assert_eq!(data.to_ndarray::<f32>(&[2, 2]).unwrap()[[1, 0]], 0.2);
+    /// assert!(data.to_ndarray::<f32>(&[3]).is_none());
+    /// assert!(data.to_ndarray::<i64>(&[2, 2]).is_none());
+    /// ```
+    pub fn to_ndarray<T: TensorElement>(&self, shape: &[usize]) -> Option<ArrayD<T>> {
+        ArrayD::from_shape_vec(shape, self.to_vec()?).ok()
+    }
+
+    /// Shorthand for [`to_ndarray::<bool>`](Self::to_ndarray); one exists per
+    /// variant (`to_ndarray_f32`, `to_ndarray_string`, ...). `to_ndarray_bf16`
+    /// gives the raw bits of `BF16` values.
+    pub fn to_ndarray_bool(&self, shape: &[usize]) -> Option<ArrayD<bool>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_u8(&self, shape: &[usize]) -> Option<ArrayD<u8>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_u16(&self, shape: &[usize]) -> Option<ArrayD<u16>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_u32(&self, shape: &[usize]) -> Option<ArrayD<u32>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_u64(&self, shape: &[usize]) -> Option<ArrayD<u64>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_i8(&self, shape: &[usize]) -> Option<ArrayD<i8>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_i16(&self, shape: &[usize]) -> Option<ArrayD<i16>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_i32(&self, shape: &[usize]) -> Option<ArrayD<i32>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_i64(&self, shape: &[usize]) -> Option<ArrayD<i64>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_f32(&self, shape: &[usize]) -> Option<ArrayD<f32>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_f64(&self, shape: &[usize]) -> Option<ArrayD<f64>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_string(&self, shape: &[usize]) -> Option<ArrayD<String>> {
+        self.to_ndarray(shape)
+    }
+
+    pub fn to_ndarray_bf16(&self, shape: &[usize]) -> Option<ArrayD<u16>> {
+        if let DataType::Bf16(v) = self {
+            ArrayD::from_shape_vec(shape, v.clone()).ok()
+        } else {
+            None
+        }
+    }
+
+    #[cfg(feature = "half")]
+    pub fn to_ndarray_f16(&self, shape: &[usize]) -> Option<ArrayD<half::f16>> {
+        self.to_ndarray(shape)
+    }
+}
+
+impl InferInput {
+    /// Build an input from an array of any dimension (`Array1`, `Array2`, ...,
+    /// `ArrayD`), owned or borrowed, without calling `into_dyn` first.
+    ///
+    /// The values are sent in logical (row-major) order whatever the memory
+    /// layout of the array: an owned array in standard layout is moved
+    /// without copy, anything else (views, transposed or sliced arrays) is
+    /// copied.
+    ///
+    /// # Example
+    /// ```
+    /// use ndarray::array;
+    /// use truston::client::io::InferInput;
+    ///
+    /// let transposed = array![[1, 2], [3, 4]].reversed_axes();
+    /// let input = InferInput::from_ndarray("x", transposed);
+    /// assert_eq!(input.input_data.as_i32_slice(), Some(&[1, 3, 2, 4][..]));
+    /// ```
+    pub fn from_ndarray<T, S, D>(name: impl Into<String>, arr: ArrayBase<S, D>) -> Self
+    where
+        T: Clone + 'static,
+        S: Data<Elem = T>,
+        D: Dimension,
+        Vec<T>: IntoInferData,
+    {
+        let shape = arr.shape().to_vec();
+        let data = if arr.is_standard_layout() {
+            // contiguous in logical order from the offset of the first element
+            let len = arr.len();
+            let (mut data, offset) = arr.into_owned().into_raw_vec_and_offset();
+            data.drain(..offset.unwrap_or(0));
+            data.truncate(len);
+            data
+        } else {
+            arr.iter().cloned().collect()
+        };
+        Self {
+            input_name: name.into(),
+            input_shape: shape,
+            input_data: data.into_infer_data(),
+        }
+    }
+
+    /// Build an input from a borrowed array view of any dimension, copying
+    /// its values.
+    ///
+    /// # Example
+    /// ```
+    /// use ndarray::{Array2, s};
+    /// use truston::client::io::InferInput;
+    ///
+    /// let batch = Array2::<f32>::zeros((8, 3));
+    /// let input = InferInput::from_ndarray_view("x", batch.slice(s![..2, ..]));
+    /// assert_eq!(input.input_shape, vec![2, 3]);
+    /// ```
+    pub fn from_ndarray_view<T, D>(name: impl Into<String>, view: ArrayView<'_, T, D>) -> Self
+    where
+        T: Clone + 'static,
+        D: Dimension,
+        Vec<T>: IntoInferData,
+    {
+        Self::from_ndarray(name, view)
+    }
+}
+
+impl InferOutput {
+    /// The output as an array view of its shape, borrowing its values
+    /// instead of copying them like [`DataType::to_ndarray_f32`] and co.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the values are not of type `T`
+    /// or their number doesn't match the shape.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput};
+    ///
+    /// let output = InferOutput {
+    ///     name: "probs".into(),
+    ///     datatype: TritonDtype::Fp32,
+    ///     shape: vec![1, 3],
+    ///     data: DataType::F32(vec![0.1, 0.7, 0.2]),
+    /// };
+    /// assert_eq!(output.view::<f32>()?[[0, 1]], 0.7);
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn view<T: TensorElement>(&self) -> Result<ArrayViewD<'_, T>, TrustonError> {
+        let values = self.values::<T>()?;
+        ArrayViewD::from_shape(IxDyn(&self.shape), values).map_err(|_| {
+            TrustonError::ParseError(format!(
+                "output {} has {} values, which don't fit shape {:?}",
+                self.name,
+                values.len(),
+                self.shape
+            ))
+        })
+    }
+
+    /// The output as an owned array of its shape, for any element type `T`
+    /// (see [`TensorElement`]).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the values are not of type `T`
+    /// or their number doesn't match the shape.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput};
+    ///
+    /// let output = InferOutput {
+    ///     name: "ids".into(),
+    ///     datatype: TritonDtype::Int64,
+    ///     shape: vec![2, 1],
+    ///     data: DataType::I64(vec![4, 2]),
+    /// };
+    /// assert_eq!(output.to_ndarray::<i64>()?[[1, 0]], 2);
+    /// assert!(output.to_ndarray::<f32>().is_err());
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn to_ndarray<T: TensorElement>(&self) -> Result<ArrayD<T>, TrustonError> {
+        let values = self.values::<T>()?;
+        ArrayD::from_shape_vec(IxDyn(&self.shape), values.to_vec()).map_err(|_| {
+            TrustonError::ParseError(format!(
+                "output {} has {} values, which don't fit shape {:?}",
+                self.name,
+                values.len(),
+                self.shape
+            ))
+        })
+    }
+
+    fn values<T: TensorElement>(&self) -> Result<&[T], TrustonError> {
+        T::values(&self.data).ok_or_else(|| {
+            TrustonError::ParseError(format!(
+                "output {} is {}, not {}",
+                self.name,
+                self.data.get_type_str(),
+                std::any::type_name::<T>()
+            ))
+        })
+    }
+
+    /// Iterate lazily over the rows of the output (its slices along the
+    /// leading dimension), as views into its values.
+    ///
+    /// Each row has the shape of the output without its first dim, so
+    /// per-sample postprocessing can start without splitting the batch first.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ParseError`] if the values are not of type
+    /// `T`, the output has no dims, or its number of values doesn't match
+    /// its shape; [`TrustonError::InvalidShape`] if the shape overflows.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput};
+    ///
+    /// let output = InferOutput {
+    ///     name: "probs".into(),
+    ///     datatype: TritonDtype::Fp32,
+    ///     shape: vec![2, 3],
+    ///     data: DataType::F32(vec![0.1, 0.7, 0.2, 0.5, 0.3, 0.2]),
+    /// };
+    /// let best: Vec<f32> = output
+    ///     .rows::<f32>()?
+    ///     .map(|row| row.iter().cloned().fold(f32::MIN, f32::max))
+    ///     .collect();
+    /// assert_eq!(best, vec![0.7, 0.5]);
+    /// # Ok::<(), truston::TrustonError>(())
+    /// ```
+    pub fn rows<T: TensorElement>(&self) -> Result<Rows<'_, T>, TrustonError> {
+        let values = self.values::<T>()?;
+        let Some((&count, row_shape)) = self.shape.split_first() else {
+            return Err(TrustonError::ParseError(format!("output {} has no dims", self.name)));
+        };
+        let row_len = element_count(&self.name, row_shape)?;
+        let total = element_count(&self.name, &self.shape)?;
+        if values.len() != total {
+            return Err(TrustonError::ParseError(format!(
+                "output {} has {} values, shape {:?} needs {}",
+                self.name,
+                values.len(),
+                self.shape,
+                total
+            )));
+        }
+        Ok(Rows { values, row_shape, row_len, next: 0, count })
+    }
+}
+
+/// Iterator over the rows of an output, see [`InferOutput::rows`].
+#[derive(Debug, Clone)]
+pub struct Rows<'a, T> {
+    values: &'a [T],
+    row_shape: &'a [usize],
+    row_len: usize,
+    next: usize,
+    count: usize,
+}
+
+impl<'a, T> Iterator for Rows<'a, T> {
+    type Item = ArrayViewD<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.count {
+            return None;
+        }
+        let start = self.next * self.row_len;
+        self.next += 1;
+        let row = &self.values[start..start + self.row_len];
+        // the length was checked against the shape when creating the iterator
+        Some(ArrayViewD::from_shape(IxDyn(self.row_shape), row).expect("row matches its shape"))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count - self.next;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<T> ExactSizeIterator for Rows<'_, T> {}
+
+impl InferResults {
+    /// The output called `name` as an array of its shape.
+    ///
+    /// One accessor exists per datatype (`get_i64_array`, `get_string_array`, ...).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if there is no such output,
+    /// and [`TrustonError::ParseError`] if it has another datatype or its
+    /// number of values doesn't match its shape.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::dtype::TritonDtype;
+    /// use truston::client::io::{DataType, InferOutput, InferResults};
+    ///
+    /// let results = InferResults {
+    ///     outputs: vec![InferOutput {
+    ///         name: "probs".into(),
+    ///         datatype: TritonDtype::Fp32,
+    ///         shape: vec![1, 3],
+    ///         data: DataType::F32(vec![0.1, 0.7, 0.2]),
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let probs = results.get_f32_array("probs").unwrap();
+    /// assert_eq!(probs[[0, 1]], 0.7);
+    /// assert!(results.get_i64_array("probs").is_err());
+    /// ```
+    pub fn get_f32_array(&self, name: &str) -> Result<ArrayD<f32>, TrustonError> {
+        self.typed_array(name, "FP32", DataType::to_ndarray_f32)
+    }
+
+    pub fn get_bool_array(&self, name: &str) -> Result<ArrayD<bool>, TrustonError> {
+        self.typed_array(name, "BOOL", DataType::to_ndarray_bool)
+    }
+
+    pub fn get_u8_array(&self, name: &str) -> Result<ArrayD<u8>, TrustonError> {
+        self.typed_array(name, "UINT8", DataType::to_ndarray_u8)
+    }
+
+    pub fn get_u16_array(&self, name: &str) -> Result<ArrayD<u16>, TrustonError> {
+        self.typed_array(name, "UINT16", DataType::to_ndarray_u16)
+    }
+
+    pub fn get_u32_array(&self, name: &str) -> Result<ArrayD<u32>, TrustonError> {
+        self.typed_array(name, "UINT32", DataType::to_ndarray_u32)
+    }
+
+    pub fn get_u64_array(&self, name: &str) -> Result<ArrayD<u64>, TrustonError> {
+        self.typed_array(name, "UINT64", DataType::to_ndarray_u64)
+    }
+
+    pub fn get_i8_array(&self, name: &str) -> Result<ArrayD<i8>, TrustonError> {
+        self.typed_array(name, "INT8", DataType::to_ndarray_i8)
+    }
+
+    pub fn get_i16_array(&self, name: &str) -> Result<ArrayD<i16>, TrustonError> {
+        self.typed_array(name, "INT16", DataType::to_ndarray_i16)
+    }
+
+    pub fn get_i32_array(&self, name: &str) -> Result<ArrayD<i32>, TrustonError> {
+        self.typed_array(name, "INT32", DataType::to_ndarray_i32)
+    }
+
+    pub fn get_i64_array(&self, name: &str) -> Result<ArrayD<i64>, TrustonError> {
+        self.typed_array(name, "INT64", DataType::to_ndarray_i64)
+    }
+
+    pub fn get_f64_array(&self, name: &str) -> Result<ArrayD<f64>, TrustonError> {
+        self.typed_array(name, "FP64", DataType::to_ndarray_f64)
+    }
+
+    pub fn get_string_array(&self, name: &str) -> Result<ArrayD<String>, TrustonError> {
+        self.typed_array(name, "BYTES", DataType::to_ndarray_string)
+    }
+
+    pub fn get_bf16_array(&self, name: &str) -> Result<ArrayD<u16>, TrustonError> {
+        self.typed_array(name, "BF16", DataType::to_ndarray_bf16)
+    }
+
+    #[cfg(feature = "half")]
+    pub fn get_f16_array(&self, name: &str) -> Result<ArrayD<half::f16>, TrustonError> {
+        self.typed_array(name, "FP16", DataType::to_ndarray_f16)
+    }
+
+    fn typed_array<T>(
+        &self,
+        name: &str,
+        datatype: &str,
+        convert: fn(&DataType, &[usize]) -> Option<ArrayD<T>>,
+    ) -> Result<ArrayD<T>, TrustonError> {
+        let output = self
+            .get(name)
+            .ok_or_else(|| TrustonError::InferenceError(format!("no output named {}", name)))?;
+        if output.data.get_type_str() != datatype {
+            return Err(TrustonError::ParseError(format!(
+                "output {} is {}, not {}",
+                name,
+                output.data.get_type_str(),
+                datatype
+            )));
+        }
+        let expected = element_count(name, &output.shape)?;
+        if output.data.num_elements() != expected {
+            return Err(TrustonError::ParseError(format!(
+                "output {} has {} values, shape {:?} needs {}",
+                name,
+                output.data.num_elements(),
+                output.shape,
+                expected
+            )));
+        }
+        convert(&output.data, &output.shape).ok_or_else(|| {
+            TrustonError::ParseError(format!("output {} cannot be converted to an array", name))
+        })
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use ::ndarray::array;
+
+    #[test]
+    fn test_to_ndarray_f32() {
+        let data = DataType::F32(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let arr = data.to_ndarray_f32(&[2, 3]).unwrap();
+
+        assert_eq!(arr.shape(), &[2, 3]);
+        assert_eq!(arr[[0, 0]], 1.0);
+        assert_eq!(arr[[1, 2]], 6.0);
+    }
+
+    #[test]
+    fn test_to_ndarray_i32() {
+        let data = DataType::I32(vec![1, 2, 3, 4]);
+        let arr = data.to_ndarray_i32(&[2, 2]).unwrap();
+
+        assert_eq!(arr.shape(), &[2, 2]);
+        assert_eq!(arr[[0, 1]], 2);
+        assert_eq!(arr[[1, 1]], 4);
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
+    fn test_to_ndarray_bool() {
+        let data = DataType::Bool(vec![true, false, true, false]);
+        let arr = data.to_ndarray_bool(&[2, 2]).unwrap();
+
+        assert_eq!(arr[[0, 0]], true);
+        assert_eq!(arr[[0, 1]], false);
+    }
+
+    #[test]
+    fn test_to_ndarray_string() {
+        let data = DataType::String(vec!["a".into(), "b".into(), "c".into()]);
+        let arr = data.to_ndarray_string(&[3]).unwrap();
+
+        assert_eq!(arr.shape(), &[3]);
+        assert_eq!(arr[[1]], "b");
+    }
+
+    #[test]
+    fn test_to_ndarray_wrong_shape() {
+        let data = DataType::F32(vec![1.0, 2.0, 3.0]);
+        // Shape [2, 2] requires 4 elements, but we only have 3
+        let result = data.to_ndarray_f32(&[2, 2]);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_to_ndarray_wrong_type() {
+        let data = DataType::I32(vec![1, 2, 3, 4]);
+        // Trying to convert I32 to F32 ndarray
+        let result = data.to_ndarray_f32(&[2, 2]);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn test_to_ndarray_multidimensional() {
+        let data = DataType::U8(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let arr = data.to_ndarray_u8(&[2, 2, 2]).unwrap();
+
+        assert_eq!(arr.shape(), &[2, 2, 2]);
+        assert_eq!(arr[[0, 0, 0]], 1);
+        assert_eq!(arr[[1, 1, 1]], 8);
+    }
+
+    #[test]
+    fn test_single_element() {
+        let data = DataType::I32(vec![42]);
+        let arr = data.to_ndarray_i32(&[1]).unwrap();
+        assert_eq!(arr[[0]], 42);
+    }
+
+    #[test]
+    fn test_large_shape() {
+        let size = 1000;
+        let data = DataType::U8((0..size).map(|x| (x % 256) as u8).collect());
+        let arr = data.to_ndarray_u8(&[10, 10, 10]).unwrap();
+        assert_eq!(arr.shape(), &[10, 10, 10]);
+    }
+
+    #[test]
+    fn test_generic_conversions() {
+        let data = DataType::String(vec!["a".into(), "b".into()]);
+        let array = data.to_ndarray::<String>(&[1, 2]).unwrap();
+        assert_eq!(array[[0, 1]], "b");
+        assert!(DataType::F32(vec![1.0, 2.0]).to_ndarray_i32(&[2]).is_none());
+        assert!(DataType::F32(vec![]).to_ndarray_f32(&[0]).is_some());
+
+        let output = InferOutput {
+            name: "y".into(),
+            datatype: TritonDtype::Uint8,
+            shape: vec![3],
+            data: DataType::U8(vec![1, 2]),
+        };
+        assert!(matches!(output.to_ndarray::<u8>(), Err(TrustonError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_infer_input_from_ndarray_i32() {
+        let arr = array![1, 2, 3, 4, 5, 6].into_dyn();
+        let input = InferInput::from_ndarray("int_input", arr);
+
+        assert_eq!(input.input_name, "int_input");
+        assert_eq!(input.input_shape, vec![6]);
+
+        let vec = input.input_data.as_i32_vec().unwrap();
+        assert_eq!(vec, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_infer_input_from_ndarray_3d() {
+        let data: Vec<f64> = (0..24).map(|x| x as f64).collect();
+        let arr = ArrayD::from_shape_vec(vec![2, 3, 4], data).unwrap();
+        let input = InferInput::from_ndarray("3d_tensor", arr);
+
+        assert_eq!(input.input_shape, vec![2, 3, 4]);
+        assert_eq!(input.input_data.as_f64_vec().unwrap().len(), 24);
+    }
+
+    #[test]
+    fn test_infer_input_from_fixed_dim_ndarray() {
+        let input = InferInput::from_ndarray("x", array![[1u8, 2, 3], [4, 5, 6]]);
+        assert_eq!(input.input_shape, vec![2, 3]);
+        assert_eq!(input.input_data.as_u8_slice(), Some(&[1, 2, 3, 4, 5, 6][..]));
+
+        let scalar = InferInput::from_ndarray("s", ndarray::arr0(7i64));
+        assert_eq!(scalar.input_shape, Vec::<usize>::new());
+        assert_eq!(scalar.input_data.as_i64_slice(), Some(&[7][..]));
+    }
+
+    #[test]
+    fn test_infer_input_from_sliced_and_strided_ndarray() {
+        let arr = ndarray::Array::from_iter(0..6).into_shape_with_order((3, 2)).unwrap();
+
+        // standard layout, but starting after the first row of the buffer
+        let tail = arr.clone().slice_move(ndarray::s![1.., ..]);
+        let input = InferInput::from_ndarray("tail", tail);
+        assert_eq!(input.input_shape, vec![2, 2]);
+        assert_eq!(input.input_data.as_i32_slice(), Some(&[2, 3, 4, 5][..]));
+
+        let column = InferInput::from_ndarray("column", arr.column(1));
+        assert_eq!(column.input_data.as_i32_slice(), Some(&[1, 3, 5][..]));
+
+        let view = InferInput::from_ndarray_view("t", arr.t());
+        assert_eq!(view.input_shape, vec![2, 3]);
+        assert_eq!(view.input_data.as_i32_slice(), Some(&[0, 2, 4, 1, 3, 5][..]));
+    }
+
+    #[test]
+    fn test_infer_input_string_name_conversion() {
+        let arr = array![1.0f32].into_dyn();
+        let input = InferInput::from_ndarray("literal_str", arr);
+        assert_eq!(input.input_name, "literal_str");
+
+        let arr = array![1.0f32].into_dyn();
+        let input2 = InferInput::from_ndarray(String::from("string_type"), arr);
+        assert_eq!(input2.input_name, "string_type");
+    }
+
+    #[test]
+    fn test_output_view() {
+        let output = InferOutput {
+            name: "y".into(),
+            datatype: TritonDtype::Int64,
+            shape: vec![2, 2],
+            data: DataType::I64(vec![1, 2, 3, 4]),
+        };
+        let view = output.view::<i64>().unwrap();
+        assert_eq!(view[[1, 0]], 3);
+        assert_eq!(view.as_ptr(), output.data.as_i64_slice().unwrap().as_ptr());
+        assert!(output.view::<f32>().is_err());
+
+        let short = InferOutput { shape: vec![3, 2], ..output };
+        assert!(matches!(short.view::<i64>(), Err(TrustonError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_output_rows() {
+        let output = InferOutput {
+            name: "boxes".into(),
+            datatype: TritonDtype::Int32,
+            shape: vec![3, 2, 2],
+            data: DataType::I32((0..12).collect()),
+        };
+        let rows: Vec<_> = output.rows::<i32>().unwrap().collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1].shape(), &[2, 2]);
+        assert_eq!(rows[1][[1, 0]], 6);
+        assert_eq!(output.rows::<i32>().unwrap().len(), 3);
+        assert!(output.rows::<f32>().is_err());
+
+        let mismatched = InferOutput { shape: vec![5, 2], ..output.clone() };
+        assert!(mismatched.rows::<i32>().is_err());
+        let scalar = InferOutput { shape: vec![], ..output };
+        assert!(scalar.rows::<i32>().is_err());
+
+        let huge = InferOutput {
+            name: "y".into(),
+            datatype: TritonDtype::Fp32,
+            shape: vec![2, usize::MAX / 2 + 1],
+            data: DataType::F32(vec![0.0; 2]),
+        };
+        assert!(matches!(huge.rows::<f32>(), Err(TrustonError::InvalidShape(_))));
+    }
+
+    #[test]
+    fn test_typed_output_accessors() {
+        let output = |name: &str, shape: Vec<usize>, data: DataType| InferOutput {
+            name: name.into(),
+            datatype: data.dtype().unwrap(),
+            shape,
+            data,
+        };
+        let results = InferResults {
+            outputs: vec![
+                output("ids", vec![2, 2], DataType::I64(vec![1, 2, 3, 4])),
+                output("labels", vec![2], DataType::String(vec!["a".into(), "b".into()])),
+                output("short", vec![3], DataType::F32(vec![0.5])),
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(results.get("labels").unwrap().shape, vec![2]);
+        assert!(results.get("missing").is_none());
+        assert_eq!(results.get_i64_array("ids").unwrap(), array![[1, 2], [3, 4]].into_dyn());
+        assert_eq!(results.get_string_array("labels").unwrap()[[1]], "b");
+
+        assert!(matches!(results.get_f32_array("missing"), Err(TrustonError::InferenceError(_))));
+        assert!(matches!(results.get_f32_array("ids"), Err(TrustonError::ParseError(_))));
+        assert!(matches!(results.get_f32_array("short"), Err(TrustonError::ParseError(_))));
+    }
+
+    #[cfg(feature = "half")]
+    #[test]
+    fn test_f16_inputs_serialize_as_numbers() {
+        use half::f16;
+
+        let arr = array![[f16::from_f32(0.5), f16::from_f32(-2.0)]].into_dyn();
+        let input = InferInput::from_ndarray("x", arr.clone());
+        assert_eq!(input.input_data.get_type_str(), "FP16");
+        assert_eq!(input.input_data.to_ndarray_f16(&input.input_shape), Some(arr));
+        assert_eq!(serde_json::to_string(&input.input_data).unwrap(), "[0.5,-2.0]");
+    }
+}
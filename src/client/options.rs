@@ -0,0 +1,447 @@
+//! Per-request options for inference calls.
+//!
+//! [`InferOptions`] carries the optional, request-level settings of the
+//! v2 inference protocol (request id, priority, timeout, custom parameters)
+//! as well as client-side routing such as the targeted model version.
+
+use serde_json::{Map, Value};
+pub use tokio_util::sync::CancellationToken;
+
+use crate::client::affinity::AffinityToken;
+use crate::client::deadline::Deadline;
+use crate::client::io::InferRequestedOutput;
+use crate::client::trace::TraceContext;
+
+/// Optional settings for a single inference request.
+///
+/// Used with [`TritonRestClient::infer_with_options`](crate::client::http::TritonRestClient::infer_with_options).
+/// Every field defaults to "not set", in which case the server defaults apply.
+///
+/// # Example
+/// ```
+/// use truston::client::options::InferOptions;
+///
+/// let options = InferOptions::new()
+///     .with_request_id("req-42")
+///     .with_priority(1)
+///     .with_timeout_us(50_000)
+///     .with_parameter("custom_flag", true);
+///
+/// assert_eq!(options.request_id.as_deref(), Some("req-42"));
+/// assert_eq!(options.request_parameters()["priority"], 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InferOptions {
+    /// Identifier echoed back by the server, used to correlate requests and responses.
+    pub request_id: Option<String>,
+    /// Model version to target; the server picks one according to its version policy when unset.
+    pub model_version: Option<String>,
+    /// Request priority for models configured with priority levels (lower value = higher priority).
+    pub priority: Option<u64>,
+    /// Server-side timeout for the request, in microseconds.
+    pub timeout_us: Option<u64>,
+    /// Arbitrary request parameters forwarded as-is to the server.
+    pub parameters: Map<String, Value>,
+    /// Outputs to return; the server returns every output when unset.
+    pub outputs: Option<Vec<InferRequestedOutput>>,
+    /// The request has side effects and must not be retried blindly
+    /// (see [`RetryPolicy`](crate::client::retry::RetryPolicy)).
+    pub non_idempotent: bool,
+    /// Targets tried in order when the previous one fails with a server
+    /// error (`5xx`), e.g. the previous version during a rollout. Not used
+    /// by non-idempotent requests.
+    pub fallbacks: Vec<Fallback>,
+    /// Sequence (correlation) ID for stateful models using sequence batching.
+    pub sequence_id: Option<SequenceId>,
+    /// This request starts the sequence.
+    pub sequence_start: bool,
+    /// This request ends the sequence.
+    pub sequence_end: bool,
+    /// Send the input tensors with the binary tensor extension instead of JSON.
+    pub binary_inputs: bool,
+    /// Ask for every output in binary (`binary_data_output`) instead of JSON.
+    pub binary_outputs: bool,
+    /// Send the JSON values of every input as arrays nested along its shape.
+    pub nested_data: bool,
+    /// Inputs whose JSON values are sent nested along their shape.
+    pub nested_inputs: Vec<String>,
+    /// Point in time by which the caller needs the result.
+    pub deadline: Option<Deadline>,
+    /// Conversation the request belongs to, kept on the same backend.
+    pub affinity: Option<AffinityToken>,
+    /// Token aborting the request when cancelled.
+    pub cancellation: Option<CancellationToken>,
+    /// Trace the request belongs to, sent in a `traceparent` header.
+    pub trace_context: Option<TraceContext>,
+    /// Extra HTTP headers of the request, e.g. for a gateway routing on them.
+    pub headers: Vec<(String, String)>,
+    /// Rank among requests waiting for a slot of the client (lower value first).
+    pub queue_priority: u32,
+}
+
+/// Identifier of a sequence; Triton accepts unsigned integers or strings,
+/// depending on the model's `control_input` configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SequenceId {
+    Number(u64),
+    Text(String),
+}
+
+impl From<u64> for SequenceId {
+    fn from(id: u64) -> Self {
+        SequenceId::Number(id)
+    }
+}
+
+impl From<&str> for SequenceId {
+    fn from(id: &str) -> Self {
+        SequenceId::Text(id.to_string())
+    }
+}
+
+impl From<String> for SequenceId {
+    fn from(id: String) -> Self {
+        SequenceId::Text(id)
+    }
+}
+
+impl From<&SequenceId> for Value {
+    fn from(id: &SequenceId) -> Self {
+        match id {
+            SequenceId::Number(n) => Value::from(*n),
+            SequenceId::Text(s) => Value::from(s.as_str()),
+        }
+    }
+}
+
+/// Model (and/or version) an inference falls back to, see [`InferOptions::with_fallback_version`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Fallback {
+    /// Model to call instead; the primary model when `None`.
+    pub model_name: Option<String>,
+    /// Version to target; the server's version policy applies when `None`.
+    pub model_version: Option<String>,
+}
+
+impl InferOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    pub fn with_model_version(mut self, version: impl Into<String>) -> Self {
+        self.model_version = Some(version.into());
+        self
+    }
+
+    pub fn with_priority(mut self, priority: u64) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn with_timeout_us(mut self, timeout_us: u64) -> Self {
+        self.timeout_us = Some(timeout_us);
+        self
+    }
+
+    pub fn with_parameter(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.insert(key.into(), value.into());
+        self
+    }
+
+    /// Request only these outputs; accepts names or [`InferRequestedOutput`]s.
+    pub fn with_outputs<O>(mut self, outputs: impl IntoIterator<Item = O>) -> Self
+    where
+        O: Into<InferRequestedOutput>,
+    {
+        self.outputs = Some(outputs.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Mark the request as having side effects, so it is not retried unless
+    /// the retry policy explicitly allows retrying non-idempotent requests,
+    /// and never sent to a fallback.
+    pub fn with_non_idempotent(mut self) -> Self {
+        self.non_idempotent = true;
+        self
+    }
+
+    /// Bound the request by `deadline` (see [`Deadline`] for what it limits).
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use truston::client::deadline::Deadline;
+    /// use truston::client::options::InferOptions;
+    ///
+    /// let deadline = Deadline::after(Duration::from_millis(200));
+    /// let options = InferOptions::new().with_deadline(deadline);
+    /// let timeout_us = options.request_parameters()["timeout"].as_u64().unwrap();
+    /// assert!(timeout_us <= 200_000);
+    /// ```
+    pub fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Abort the request when `token` is cancelled, failing it with
+    /// [`TrustonError::Cancelled`](crate::utils::errors::TrustonError::Cancelled).
+    ///
+    /// The in-flight HTTP request (or gRPC call) is dropped, and no retry or
+    /// fallback is attempted. Combine with [`with_deadline`](Self::with_deadline)
+    /// to also bound the request in time.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use truston::client::http::TritonRestClient;
+    /// # use truston::client::io::{DataType, InferInput};
+    /// use truston::client::options::{CancellationToken, InferOptions};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let client = TritonRestClient::new("http://localhost:8000");
+    /// # let input = InferInput::new("x".into(), vec![1], DataType::F32(vec![0.5]));
+    /// let token = CancellationToken::new();
+    /// let options = InferOptions::new().with_cancellation(token.clone());
+    ///
+    /// // e.g. when the user closes the page
+    /// tokio::spawn(async move { token.cancel() });
+    /// let result = client.infer_with_options(vec![input], "my_model", &options).await;
+    /// # }
+    /// ```
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Keep this request on the backend that served the previous requests
+    /// carrying `token` (see [`affinity`](crate::client::affinity)).
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::options::InferOptions;
+    ///
+    /// let options = InferOptions::new().with_affinity("conversation-7");
+    /// assert_eq!(options.affinity.unwrap().as_str(), "conversation-7");
+    /// ```
+    pub fn with_affinity(mut self, token: impl Into<AffinityToken>) -> Self {
+        self.affinity = Some(token.into());
+        self
+    }
+
+    /// Send the request as part of the trace of `parent` (see
+    /// [`trace`](crate::client::trace)); the `traceparent` header names a new
+    /// span below it.
+    pub fn with_trace_context(mut self, parent: TraceContext) -> Self {
+        self.trace_context = Some(parent);
+        self
+    }
+
+    /// Send the HTTP header `name: value` with the request, e.g. for a
+    /// gateway routing on it. It replaces a
+    /// [default header](crate::client::http::TritonRestClientBuilder::default_header)
+    /// of the client with the same name.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::options::InferOptions;
+    ///
+    /// let options = InferOptions::new().with_header("x-model-tier", "gold");
+    /// assert_eq!(options.headers, vec![("x-model-tier".to_string(), "gold".to_string())]);
+    /// ```
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Wait behind requests of lower `priority` for a slot of the client's
+    /// [concurrency cap](crate::client::limit); `0`, the most urgent, by default.
+    ///
+    /// Unlike [`with_priority`](InferOptions::with_priority), this orders the
+    /// requests of this client only, and needs no priority levels in the model.
+    pub fn with_queue_priority(mut self, priority: u32) -> Self {
+        self.queue_priority = priority;
+        self
+    }
+
+    /// Send the input tensors as raw bytes after the JSON header (binary tensor
+    /// extension), skipping JSON encoding of the values.
+    ///
+    /// Much cheaper for large tensors, especially `BYTES` batches of many
+    /// short texts. Inputs holding [`DataType::Raw`](crate::client::io::DataType::Raw)
+    /// data can't be sent this way.
+    pub fn with_binary_inputs(mut self) -> Self {
+        self.binary_inputs = true;
+        self
+    }
+
+    /// Ask the server for every output as raw bytes after the JSON header
+    /// (binary tensor extension), instead of JSON values.
+    ///
+    /// The bytes are decoded as they arrive, straight into the buffer of each
+    /// output, so large outputs are neither buffered twice nor parsed as JSON.
+    /// Single outputs can be asked in binary with
+    /// [`InferRequestedOutput::with_binary_data`](crate::client::io::InferRequestedOutput::with_binary_data).
+    pub fn with_binary_outputs(mut self) -> Self {
+        self.binary_outputs = true;
+        self
+    }
+
+    /// Send the values of every input as JSON arrays nested along its shape
+    /// (`[[1, 2], [3, 4]]` for a `[2, 2]` tensor) instead of a flat array.
+    ///
+    /// Some Python backends and proxies only accept this form. Inputs sent
+    /// with the binary extension are not affected.
+    pub fn with_nested_data(mut self) -> Self {
+        self.nested_data = true;
+        self
+    }
+
+    /// Send the values of the input `name` nested along its shape, see
+    /// [`with_nested_data`](Self::with_nested_data).
+    pub fn with_nested_input(mut self, name: impl Into<String>) -> Self {
+        self.nested_inputs.push(name.into());
+        self
+    }
+
+    /// Whether the JSON values of the input `name` are sent nested.
+    pub(crate) fn is_nested(&self, name: &str) -> bool {
+        self.nested_data || self.nested_inputs.iter().any(|input| input == name)
+    }
+
+    /// Send the request as part of the sequence `id`, with its start/end flags.
+    ///
+    /// See [`SequenceSession`](crate::client::sequence::SequenceSession) to
+    /// have the flags tracked across calls.
+    pub fn with_sequence(mut self, id: impl Into<SequenceId>, start: bool, end: bool) -> Self {
+        self.sequence_id = Some(id.into());
+        self.sequence_start = start;
+        self.sequence_end = end;
+        self
+    }
+
+    /// On a server error, retry the inference against `version` of the same model.
+    ///
+    /// Can be called several times to chain fallbacks. Fallbacks are skipped
+    /// for requests marked [`with_non_idempotent`](Self::with_non_idempotent).
+    /// Each use of a fallback
+    /// is reported as a [`ClientEvent::Fallback`](crate::client::events::ClientEvent::Fallback)
+    /// and counted by [`TritonRestClient::fallbacks_used`](crate::client::http::TritonRestClient::fallbacks_used).
+    ///
+    /// # Example
+    /// ```
+    /// use truston::client::options::InferOptions;
+    ///
+    /// let options = InferOptions::new()
+    ///     .with_model_version("3")
+    ///     .with_fallback_version("2")
+    ///     .with_fallback_model("resnet50_stable", None);
+    /// assert_eq!(options.fallbacks.len(), 2);
+    /// ```
+    pub fn with_fallback_version(mut self, version: impl Into<String>) -> Self {
+        self.fallbacks.push(Fallback {
+            model_name: None,
+            model_version: Some(version.into()),
+        });
+        self
+    }
+
+    /// On a server error, retry the inference against another model (optionally a given version).
+    pub fn with_fallback_model(
+        mut self,
+        model_name: impl Into<String>,
+        version: Option<String>,
+    ) -> Self {
+        self.fallbacks.push(Fallback {
+            model_name: Some(model_name.into()),
+            model_version: version,
+        });
+        self
+    }
+
+    /// Build the `parameters` object of the v2 request body.
+    ///
+    /// `priority`, `timeout` and the sequence fields are merged into the custom
+    /// parameters; they take precedence over custom entries with the same key.
+    /// The sequence flags are only sent along with a sequence ID. With a
+    /// deadline, `timeout` is capped to the time left at the moment of the call,
+    /// and is at least 1µs.
+    pub fn request_parameters(&self) -> Map<String, Value> {
+        let mut parameters = self.parameters.clone();
+        if let Some(sequence_id) = &self.sequence_id {
+            parameters.insert("sequence_id".to_string(), Value::from(sequence_id));
+            parameters.insert("sequence_start".to_string(), Value::from(self.sequence_start));
+            parameters.insert("sequence_end".to_string(), Value::from(self.sequence_end));
+        }
+        if let Some(priority) = self.priority {
+            parameters.insert("priority".to_string(), Value::from(priority));
+        }
+        // a timeout of 0 would mean none at all to the server
+        let remaining_us = self
+            .deadline
+            .map(|deadline| deadline.remaining().as_micros().clamp(1, u64::MAX as u128) as u64);
+        let timeout_us = match (self.timeout_us, remaining_us) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        };
+        if let Some(timeout_us) = timeout_us {
+            parameters.insert("timeout".to_string(), Value::from(timeout_us));
+        }
+        if self.binary_outputs {
+            parameters.insert("binary_data_output".to_string(), Value::from(true));
+        }
+        parameters
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_has_no_parameters() {
+        assert!(InferOptions::default().request_parameters().is_empty());
+    }
+
+    #[test]
+    fn test_typed_fields_override_custom_parameters() {
+        let options = InferOptions::new()
+            .with_parameter("priority", 9)
+            .with_priority(2)
+            .with_timeout_us(1_000);
+        let parameters = options.request_parameters();
+
+        assert_eq!(parameters["priority"], 2);
+        assert_eq!(parameters["timeout"], 1_000);
+    }
+
+    #[test]
+    fn test_deadline_caps_timeout() {
+        let deadline = Deadline::after(std::time::Duration::from_millis(10));
+        let options = InferOptions::new().with_timeout_us(1_000_000).with_deadline(deadline);
+        assert!(options.request_parameters()["timeout"].as_u64().unwrap() <= 10_000);
+
+        let options = InferOptions::new().with_timeout_us(5).with_deadline(deadline);
+        assert_eq!(options.request_parameters()["timeout"], 5);
+
+        let expired = Deadline::after(std::time::Duration::ZERO);
+        let options = InferOptions::new().with_deadline(expired);
+        assert_eq!(options.request_parameters()["timeout"], 1);
+    }
+
+    #[test]
+    fn test_sequence_parameters() {
+        let parameters = InferOptions::new().with_sequence(42, true, false).request_parameters();
+        assert_eq!(parameters["sequence_id"], 42);
+        assert_eq!(parameters["sequence_start"], true);
+        assert_eq!(parameters["sequence_end"], false);
+
+        let options = InferOptions::new().with_sequence("utt-7", false, true);
+        let parameters = options.request_parameters();
+        assert_eq!(parameters["sequence_id"], "utt-7");
+    }
+}
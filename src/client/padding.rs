@@ -0,0 +1,312 @@
+//! Pad or truncate inputs to the fixed dims of a model.
+//!
+//! Models exported with fixed dims (e.g. a sequence length of 128) reject any
+//! other shape. [`InputPadder`] reads the dims from the [`ModelConfig`] and
+//! resizes user inputs to match, optionally generating an attention-style
+//! mask that marks the original (`1`) and padded (`0`) positions.
+//!
+//! Padding is opt-in: nothing is changed unless the inputs go through
+//! [`InputPadder::pad`].
+
+use std::collections::HashMap;
+
+use num_traits::NumCast;
+
+use crate::client::config::{ModelConfig, ModelConfigTensor};
+use crate::client::http::TritonRestClient;
+use crate::client::io::{DataType, InferInput, element_count};
+use crate::utils::errors::TrustonError;
+
+/// Resizes inputs to the dims declared in a model configuration.
+///
+/// Each fixed dim is padded at its end, or truncated, to the configured size.
+/// Variable dims (`-1`) and the leading batch dimension are left untouched.
+/// Inputs that are not declared in the configuration pass through unchanged.
+///
+/// # Example
+/// ```
+/// use truston::client::config::ModelConfig;
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::padding::InputPadder;
+///
+/// let config: ModelConfig = serde_json::from_str(r#"{
+///     "name": "bert",
+///     "max_batch_size": 8,
+///     "input": [
+///         {"name": "input_ids", "data_type": "TYPE_INT64", "dims": [4]},
+///         {"name": "attention_mask", "data_type": "TYPE_INT64", "dims": [4]}
+///     ]
+/// }"#)?;
+///
+/// let padder = InputPadder::new(&config)
+///     .with_pad_value("input_ids", 0.0)
+///     .with_mask("input_ids", "attention_mask");
+///
+/// let ids = InferInput::new("input_ids".into(), vec![1, 2], DataType::I64(vec![101, 102]));
+/// let inputs = padder.pad(vec![ids])?;
+///
+/// assert_eq!(inputs[0].input_shape, vec![1, 4]);
+/// assert_eq!(inputs[0].input_data.as_i64_vec(), Some(vec![101, 102, 0, 0]));
+/// assert_eq!(inputs[1].input_data.as_i64_vec(), Some(vec![1, 1, 0, 0]));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputPadder {
+    tensors: Vec<ModelConfigTensor>,
+    pad_values: HashMap<String, f64>,
+    masks: Vec<(String, String)>,
+}
+
+impl InputPadder {
+    /// Pad to the inputs of `config`, with `0` (or `false` / `""`) as pad value.
+    pub fn new(config: &ModelConfig) -> Self {
+        InputPadder {
+            tensors: config.input.clone(),
+            pad_values: HashMap::new(),
+            masks: Vec::new(),
+        }
+    }
+
+    /// Value used to pad `input` (cast to the input's datatype; ignored for strings).
+    pub fn with_pad_value(mut self, input: impl Into<String>, value: f64) -> Self {
+        self.pad_values.insert(input.into(), value);
+        self
+    }
+
+    /// Generate the input `mask_input` from the padding applied to `input`.
+    ///
+    /// The mask has the padded shape of `input` and the datatype declared for
+    /// `mask_input` in the configuration (`INT64` if it is not declared).
+    /// A mask already present in the inputs is kept as given.
+    pub fn with_mask(mut self, input: impl Into<String>, mask_input: impl Into<String>) -> Self {
+        self.masks.push((input.into(), mask_input.into()));
+        self
+    }
+
+    /// Pad or truncate `inputs`, appending the generated masks.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if an input has fewer
+    /// dimensions than configured, its data doesn't match its shape, or it
+    /// holds [`DataType::Raw`] data.
+    pub fn pad(&self, inputs: Vec<InferInput>) -> Result<Vec<InferInput>, TrustonError> {
+        let mut padded = Vec::with_capacity(inputs.len() + self.masks.len());
+        let mut masks = Vec::new();
+
+        for input in inputs {
+            let Some(tensor) = self.tensors.iter().find(|t| t.name == input.input_name) else {
+                padded.push(input);
+                continue;
+            };
+            let target = target_shape(&input, tensor)?;
+            element_count(&input.input_name, &target)?;
+            let expected = element_count(&input.input_name, &input.input_shape)?;
+            if input.input_data.num_elements() != expected {
+                return Err(TrustonError::InferenceError(format!(
+                    "data of input {} does not match its shape {:?}",
+                    input.input_name, input.input_shape
+                )));
+            }
+
+            for (source, mask_input) in &self.masks {
+                if *source == input.input_name {
+                    let ones = vec![1i64; expected];
+                    let mask = resize(&ones, &input.input_shape, &target, 0);
+                    masks.push(InferInput::new(
+                        mask_input.clone(),
+                        target.clone(),
+                        self.mask_data(mask_input, mask),
+                    ));
+                }
+            }
+
+            let pad = self.pad_values.get(&input.input_name).copied().unwrap_or(0.0);
+            let data = resize_data(&input.input_data, &input.input_shape, &target, pad)?;
+            padded.push(InferInput::new(input.input_name, target, data));
+        }
+
+        for mask in masks {
+            if !padded.iter().any(|i| i.input_name == mask.input_name) {
+                padded.push(mask);
+            }
+        }
+        Ok(padded)
+    }
+
+    fn mask_data(&self, mask_input: &str, mask: Vec<i64>) -> DataType {
+        let datatype = self
+            .tensors
+            .iter()
+            .find(|t| t.name == mask_input)
+            .map(ModelConfigTensor::datatype)
+            .unwrap_or("INT64");
+        match datatype {
+            "BOOL" => DataType::Bool(mask.iter().map(|&m| m != 0).collect()),
+            "INT32" => DataType::I32(mask.iter().map(|&m| m as i32).collect()),
+            "FP32" => DataType::F32(mask.iter().map(|&m| m as f32).collect()),
+            _ => DataType::I64(mask),
+        }
+    }
+}
+
+impl TritonRestClient {
+    /// Fetch the configuration of `model_name` and build an [`InputPadder`] from it.
+    pub async fn input_padder(&self, model_name: &str) -> Result<InputPadder, TrustonError> {
+        Ok(InputPadder::new(&*self.cached_model_config(model_name).await?))
+    }
+}
+
+/// Shape of `input` once resized to the configured dims.
+fn target_shape(
+    input: &InferInput,
+    tensor: &ModelConfigTensor,
+) -> Result<Vec<usize>, TrustonError> {
+    let leading = input
+        .input_shape
+        .len()
+        .checked_sub(tensor.dims.len())
+        .ok_or_else(|| {
+            TrustonError::InferenceError(format!(
+                "input {} has shape {:?}, the model expects dims {:?}",
+                input.input_name, input.input_shape, tensor.dims
+            ))
+        })?;
+
+    let mut target = input.input_shape.clone();
+    for (dim, &size) in target[leading..].iter_mut().zip(&tensor.dims) {
+        if size >= 0 {
+            *dim = size as usize;
+        }
+    }
+    Ok(target)
+}
+
+fn resize_data(
+    data: &DataType,
+    shape: &[usize],
+    target: &[usize],
+    pad: f64,
+) -> Result<DataType, TrustonError> {
+    let resized = match data {
+        DataType::Bool(v) => DataType::Bool(resize(v, shape, target, pad != 0.0)),
+        DataType::U8(v) => DataType::U8(resize(v, shape, target, cast(pad))),
+        DataType::U16(v) => DataType::U16(resize(v, shape, target, cast(pad))),
+        DataType::U32(v) => DataType::U32(resize(v, shape, target, cast(pad))),
+        DataType::U64(v) => DataType::U64(resize(v, shape, target, cast(pad))),
+        DataType::I8(v) => DataType::I8(resize(v, shape, target, cast(pad))),
+        DataType::I16(v) => DataType::I16(resize(v, shape, target, cast(pad))),
+        DataType::I32(v) => DataType::I32(resize(v, shape, target, cast(pad))),
+        DataType::I64(v) => DataType::I64(resize(v, shape, target, cast(pad))),
+        DataType::F32(v) => DataType::F32(resize(v, shape, target, pad as f32)),
+        DataType::F64(v) => DataType::F64(resize(v, shape, target, pad)),
+        DataType::String(v) => DataType::String(resize(v, shape, target, String::new())),
+        DataType::Bytes(v) => DataType::Bytes(resize(v, shape, target, Vec::new())),
+        // bf16 is the upper half of an f32
+        DataType::Bf16(v) => {
+            DataType::Bf16(resize(v, shape, target, ((pad as f32).to_bits() >> 16) as u16))
+        }
+        #[cfg(feature = "half")]
+        DataType::F16(v) => DataType::F16(resize(v, shape, target, half::f16::from_f64(pad))),
+        DataType::Raw(_) => {
+            return Err(TrustonError::InferenceError(
+                "raw JSON data cannot be padded".to_string(),
+            ));
+        }
+    };
+    Ok(resized)
+}
+
+fn cast<T: NumCast + Default>(value: f64) -> T {
+    T::from(value).unwrap_or_default()
+}
+
+/// Copy the row-major `data` of `shape` into `target`, truncating each
+/// dimension or filling the new positions with `pad`.
+fn resize<T: Clone>(data: &[T], shape: &[usize], target: &[usize], pad: T) -> Vec<T> {
+    let mut strides = vec![1usize; shape.len()];
+    for d in (0..shape.len().saturating_sub(1)).rev() {
+        strides[d] = strides[d + 1] * shape[d + 1];
+    }
+
+    let total: usize = target.iter().product();
+    let mut out = Vec::with_capacity(total);
+    let mut index = vec![0usize; target.len()];
+    for _ in 0..total {
+        if index.iter().zip(shape).all(|(i, size)| i < size) {
+            let offset: usize = index.iter().zip(&strides).map(|(i, s)| i * s).sum();
+            out.push(data[offset].clone());
+        } else {
+            out.push(pad.clone());
+        }
+        for d in (0..target.len()).rev() {
+            index[d] += 1;
+            if index[d] < target[d] {
+                break;
+            }
+            index[d] = 0;
+        }
+    }
+    out
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(dims: Vec<i64>) -> ModelConfig {
+        ModelConfig {
+            name: "model".into(),
+            max_batch_size: 4,
+            input: vec![ModelConfigTensor {
+                name: "x".into(),
+                data_type: "TYPE_FP32".into(),
+                dims,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resize_pads_and_truncates_each_dim() {
+        // [[1, 2, 3], [4, 5, 6]] -> 3x2
+        let out = resize(&[1, 2, 3, 4, 5, 6], &[2, 3], &[3, 2], 0);
+        assert_eq!(out, vec![1, 2, 4, 5, 0, 0]);
+    }
+
+    #[test]
+    fn test_variable_and_batch_dims_are_kept() {
+        let padder = InputPadder::new(&config(vec![-1, 3])).with_pad_value("x", -1.0);
+        let x = InferInput::new("x".into(), vec![2, 1, 2], DataType::F32(vec![1.0, 2.0, 3.0, 4.0]));
+        let inputs = padder.pad(vec![x]).unwrap();
+
+        assert_eq!(inputs[0].input_shape, vec![2, 1, 3]);
+        assert_eq!(
+            inputs[0].input_data.as_f32_vec(),
+            Some(vec![1.0, 2.0, -1.0, 3.0, 4.0, -1.0])
+        );
+    }
+
+    #[test]
+    fn test_truncation_mask_and_passthrough() {
+        let padder = InputPadder::new(&config(vec![2])).with_mask("x", "x_mask");
+        let x = InferInput::new("x".into(), vec![1, 3], DataType::F32(vec![1.0, 2.0, 3.0]));
+        let other = InferInput::new("other".into(), vec![1], DataType::I32(vec![7]));
+        let inputs = padder.pad(vec![x, other]).unwrap();
+
+        assert_eq!(inputs[0].input_data.as_f32_vec(), Some(vec![1.0, 2.0]));
+        assert_eq!(inputs[1].input_name, "other");
+        assert_eq!(inputs[2].input_name, "x_mask");
+        assert_eq!(inputs[2].input_data.as_i64_vec(), Some(vec![1, 1]));
+    }
+
+    #[test]
+    fn test_rank_and_length_mismatch_are_errors() {
+        let padder = InputPadder::new(&config(vec![2, 2]));
+        let x = InferInput::new("x".into(), vec![2], DataType::F32(vec![1.0, 2.0]));
+        assert!(padder.pad(vec![x]).is_err());
+
+        let x = InferInput::new("x".into(), vec![1, 2, 2], DataType::F32(vec![1.0]));
+        assert!(padder.pad(vec![x]).is_err());
+    }
+}
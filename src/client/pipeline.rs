@@ -0,0 +1,287 @@
+//! Client-side model pipelines.
+//!
+//! Models chained by the application (tokenizer → encoder → classifier)
+//! without being packaged as a Triton ensemble. A [`Pipeline`] runs its
+//! [`Stage`]s in order, feeding the outputs of each stage to the next one as
+//! inputs. The tensors are moved from one stage to the next: renaming and
+//! reshaping only change their name and shape, never their values.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::client::http::TritonRestClient;
+use crate::client::io::{InferInput, InferResults};
+use crate::client::options::InferOptions;
+use crate::utils::errors::TrustonError;
+
+type Hook = Arc<dyn Fn(Vec<InferInput>) -> Result<Vec<InferInput>, TrustonError> + Send + Sync>;
+
+/// One model of a [`Pipeline`], with the wiring of its inputs.
+///
+/// The tensors reaching a stage (the pipeline inputs for the first stage,
+/// the outputs of the previous stage otherwise) are renamed, filtered,
+/// reshaped and finally passed to the hook, in that order.
+#[derive(Clone)]
+pub struct Stage {
+    pub model_name: String,
+    /// Options of the stage's request; use [`InferOptions::with_outputs`]
+    /// to limit what is passed on to the next stage.
+    pub options: InferOptions,
+    renames: Vec<(String, String)>,
+    inputs: Option<Vec<String>>,
+    reshapes: Vec<(String, Vec<usize>)>,
+    hook: Option<Hook>,
+}
+
+impl fmt::Debug for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stage")
+            .field("model_name", &self.model_name)
+            .field("options", &self.options)
+            .field("renames", &self.renames)
+            .field("inputs", &self.inputs)
+            .field("reshapes", &self.reshapes)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+impl Stage {
+    pub fn new(model_name: impl Into<String>) -> Self {
+        Stage {
+            model_name: model_name.into(),
+            options: InferOptions::new(),
+            renames: Vec::new(),
+            inputs: None,
+            reshapes: Vec::new(),
+            hook: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: InferOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Feed the tensor `from` as the input `to`.
+    pub fn with_rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.renames.push((from.into(), to.into()));
+        self
+    }
+
+    /// Send only these inputs (named after renaming), dropping the others.
+    pub fn with_inputs<S: Into<String>>(mut self, names: impl IntoIterator<Item = S>) -> Self {
+        self.inputs = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Give the input `name` (after renaming) the shape `shape`, which must
+    /// hold the same number of values.
+    pub fn with_reshape(mut self, name: impl Into<String>, shape: Vec<usize>) -> Self {
+        self.reshapes.push((name.into(), shape));
+        self
+    }
+
+    /// Transform the inputs arbitrarily once renamed and reshaped, e.g. to
+    /// add a constant input or cast a tensor.
+    pub fn with_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(Vec<InferInput>) -> Result<Vec<InferInput>, TrustonError> + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Apply the wiring of this stage to the tensors reaching it.
+    fn wire(&self, mut inputs: Vec<InferInput>) -> Result<Vec<InferInput>, TrustonError> {
+        for input in &mut inputs {
+            if let Some((_, to)) = self.renames.iter().find(|(from, _)| *from == input.input_name) {
+                input.input_name = to.clone();
+            }
+        }
+        if let Some(names) = &self.inputs {
+            inputs.retain(|input| names.contains(&input.input_name));
+            if let Some(missing) = names
+                .iter()
+                .find(|&name| !inputs.iter().any(|input| input.input_name == *name))
+            {
+                return Err(TrustonError::InferenceError(format!(
+                    "stage {}: no tensor feeds input {}",
+                    self.model_name, missing
+                )));
+            }
+        }
+        for (name, shape) in &self.reshapes {
+            let input = inputs
+                .iter_mut()
+                .find(|input| input.input_name == *name)
+                .ok_or_else(|| {
+                    TrustonError::InferenceError(format!(
+                        "stage {}: cannot reshape missing input {}",
+                        self.model_name, name
+                    ))
+                })?;
+            input.input_shape = shape.clone();
+            input.check_shape()?;
+        }
+        match &self.hook {
+            Some(hook) => hook(inputs),
+            None => Ok(inputs),
+        }
+    }
+}
+
+/// Models run one after the other, each fed with the outputs of the previous one.
+///
+/// A pipeline holds no client and can be shared to run many requests.
+///
+/// # Example
+/// ```no_run
+/// # use truston::client::http::TritonRestClient;
+/// # use truston::client::io::{DataType, InferInput};
+/// use truston::client::options::InferOptions;
+/// use truston::client::pipeline::{Pipeline, Stage};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = TritonRestClient::new("http://localhost:8000");
+/// let pipeline = Pipeline::new()
+///     .stage(Stage::new("tokenizer"))
+///     .stage(
+///         Stage::new("encoder")
+///             .with_rename("input_ids", "ids")
+///             .with_inputs(["ids", "attention_mask"])
+///             .with_options(InferOptions::new().with_outputs(["pooled"])),
+///     )
+///     .stage(Stage::new("classifier").with_rename("pooled", "features"));
+///
+/// let text = InferInput::new("text".into(), vec![1], DataType::String(vec!["hello".into()]));
+/// let results = pipeline.run(&client, vec![text]).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Pipeline {
+    pub stages: Vec<Stage>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `stage` to the pipeline.
+    pub fn stage(mut self, stage: Stage) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Run every stage on `inputs` and return the results of the last one.
+    ///
+    /// # Errors
+    /// Fails as soon as a stage does, with the error of its inference or
+    /// wiring; [`TrustonError::InferenceError`] if the pipeline is empty.
+    pub async fn run(
+        &self,
+        client: &TritonRestClient,
+        inputs: Vec<InferInput>,
+    ) -> Result<InferResults, TrustonError> {
+        Ok(self.run_all(client, inputs).await?.pop().unwrap_or_default())
+    }
+
+    /// Like [`run`](Self::run), also returning the results of the
+    /// intermediate stages. Their outputs are moved to the next stage, so
+    /// only their provenance is kept.
+    pub async fn run_all(
+        &self,
+        client: &TritonRestClient,
+        inputs: Vec<InferInput>,
+    ) -> Result<Vec<InferResults>, TrustonError> {
+        if self.stages.is_empty() {
+            return Err(TrustonError::InferenceError("pipeline has no stage".to_string()));
+        }
+
+        let mut all = Vec::with_capacity(self.stages.len());
+        let mut inputs = inputs;
+        for (i, stage) in self.stages.iter().enumerate() {
+            let wired = stage.wire(inputs)?;
+            let mut results = client
+                .infer_with_options(wired, &stage.model_name, &stage.options)
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!("pipeline stage {} ({}) failed: {}", i, stage.model_name, e)
+                })?;
+            inputs = if i + 1 < self.stages.len() {
+                std::mem::take(&mut results.outputs).into_iter().map(InferInput::from).collect()
+            } else {
+                Vec::new()
+            };
+            all.push(results);
+        }
+        Ok(all)
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use crate::client::io::{DataType, InferOutput};
+    use crate::client::test_server::serve;
+
+    #[test]
+    fn test_stage_wiring() {
+        let outputs = vec![
+            InferOutput {
+                name: "input_ids".into(),
+                datatype: TritonDtype::Int64,
+                shape: vec![4],
+                data: DataType::I64(vec![1, 2, 3, 4]),
+            },
+            InferOutput {
+                name: "offsets".into(),
+                datatype: TritonDtype::Int64,
+                shape: vec![1],
+                data: DataType::I64(vec![0]),
+            },
+        ];
+        let inputs = outputs.into_iter().map(InferInput::from).collect();
+        let stage = Stage::new("encoder")
+            .with_rename("input_ids", "ids")
+            .with_inputs(["ids"])
+            .with_reshape("ids", vec![2, 2]);
+        let wired = stage.wire(inputs).unwrap();
+        assert_eq!(wired.len(), 1);
+        assert_eq!(wired[0].input_name, "ids");
+        assert_eq!(wired[0].input_shape, vec![2, 2]);
+
+        let bad = Stage::new("encoder").with_reshape("ids", vec![3]);
+        let input = InferInput::new("ids".into(), vec![2], DataType::I64(vec![1, 2]));
+        assert!(matches!(bad.wire(vec![input]), Err(TrustonError::ShapeMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_outputs_feed_the_next_stage() {
+        let tokens = r#"{"outputs":[{"name":"tokens","datatype":"INT32","shape":[2],
+            "data":[5,6]}]}"#;
+        let label = r#"{"outputs":[{"name":"label","datatype":"BYTES","shape":[1],
+            "data":["cat"]}]}"#;
+        let (base_url, server) = serve(vec![(200, tokens), (200, label)]).await;
+        let client = TritonRestClient::new(&base_url);
+        let classifier = Stage::new("classifier")
+            .with_rename("tokens", "x")
+            .with_reshape("x", vec![1, 2]);
+        let pipeline = Pipeline::new().stage(Stage::new("tokenizer")).stage(classifier);
+
+        let text = InferInput::new("text".into(), vec![1], DataType::String(vec!["hi".into()]));
+        let results = pipeline.run_all(&client, vec![text]).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].outputs.is_empty());
+        assert_eq!(results[1].outputs[0].data.as_str_vec(), Some(vec!["cat".to_string()]));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests[1].line(), "POST /v2/models/classifier/infer HTTP/1.1");
+        let fed = r#""name":"x","shape":[1,2],"datatype":"INT32","data":[5,6]"#;
+        assert!(requests[1].text().contains(fed));
+    }
+}
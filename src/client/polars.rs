@@ -0,0 +1,271 @@
+//! Polars data frames (requires the `polars` feature).
+//!
+//! Scoring a feature table held in a [`DataFrame`]:
+//! - [`InferInput::from_series`]: one `[rows, 1]` input per column, named
+//!   after it,
+//! - [`InferInput::from_dataframe`]: every column in one `[rows, columns]`
+//!   input, as the FIL backend expects.
+//!
+//! The Triton datatype follows the dtype of the columns (`Float32` is `FP32`,
+//! `String` is `BYTES`, ...). [`InferResults::to_dataframe`] turns the
+//! outputs back into a frame, rows along their first dimension; an output of
+//! `n` values per row (e.g. class probabilities) is split into the columns
+//! `{name}_0` to `{name}_{n-1}`.
+//!
+//! Columns with null values are rejected: Triton tensors have no nulls.
+//!
+//! # Example
+//! ```
+//! use polars::prelude::*;
+//! use truston::client::io::InferInput;
+//!
+//! let df = df!(
+//!     "age" => [31.0f32, 45.0],
+//!     "income" => [52.0f32, 87.5],
+//! )?;
+//! let input = InferInput::from_dataframe("input__0", &df)?;
+//! assert_eq!(input.input_shape, vec![2, 2]);
+//! assert_eq!(input.input_data.as_f32_vec(), Some(vec![31.0, 52.0, 45.0, 87.5]));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use ::polars::prelude::{
+    ChunkedArray, Column, DataFrame, DataType as PolarsType, IntoColumn, NamedFrom, PolarsError,
+    PolarsNumericType, PolarsResult, Series,
+};
+
+use crate::client::io::{DataType, InferInput, InferResults, interleave_columns};
+use crate::utils::errors::TrustonError;
+
+impl InferInput {
+    /// A `[rows, 1]` input of the values of `series`, named after it.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the series has nulls or a
+    /// dtype without Triton equivalent.
+    pub fn from_series(series: &Series) -> Result<InferInput, TrustonError> {
+        let data = series_data(series)?;
+        Ok(InferInput::new(series.name().to_string(), vec![series.len(), 1], data))
+    }
+
+    /// Every column of `df` in one `[rows, columns]` input, row by row.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the columns don't all have
+    /// the same dtype (cast them first, e.g. with `DataFrame::select` and
+    /// `cast`), or one of them has nulls.
+    pub fn from_dataframe(
+        name: impl Into<String>,
+        df: &DataFrame,
+    ) -> Result<InferInput, TrustonError> {
+        let mut columns = Vec::with_capacity(df.width());
+        for column in df.get_columns() {
+            let data = series_data(column.as_materialized_series())?;
+            if let Some(first) = columns.first()
+                && data.get_type_str() != DataType::get_type_str(first)
+            {
+                return Err(TrustonError::InferenceError(format!(
+                    "column {} is {}, the first column is {}",
+                    column.name(),
+                    data.get_type_str(),
+                    DataType::get_type_str(first)
+                )));
+            }
+            columns.push(data);
+        }
+        let data = interleave_columns(&columns, df.height());
+        Ok(InferInput::new(name.into(), vec![df.height(), columns.len()], data))
+    }
+}
+
+impl InferResults {
+    /// The outputs as the columns of a frame, rows along their first
+    /// dimension; an output of `n` values per row becomes the columns
+    /// `{name}_0` to `{name}_{n-1}`.
+    ///
+    /// `BF16` outputs become `UInt16` columns of the raw bits, `FP16` outputs
+    /// `Float32` columns.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the outputs have different
+    /// numbers of rows, their values don't fill their shape, or one of them
+    /// holds raw JSON.
+    pub fn to_dataframe(&self) -> Result<DataFrame, TrustonError> {
+        let mut columns = Vec::new();
+        for output in &self.outputs {
+            let rows = output.shape.first().copied().unwrap_or(1);
+            let per_row: usize = output.shape.iter().skip(1).product();
+            if output.data.num_elements() != rows * per_row {
+                return Err(TrustonError::InferenceError(format!(
+                    "output {} has {} values, its shape {:?} holds {}",
+                    output.name,
+                    output.data.num_elements(),
+                    output.shape,
+                    rows * per_row
+                )));
+            }
+            if per_row == 1 {
+                columns.push(strided_column(&output.name, &output.data, 1, 0)?);
+            } else {
+                for i in 0..per_row {
+                    let name = format!("{}_{}", output.name, i);
+                    columns.push(strided_column(&name, &output.data, per_row, i)?);
+                }
+            }
+        }
+        DataFrame::new(columns).map_err(polars_error)
+    }
+}
+
+/// The values of `series`.
+fn series_data(series: &Series) -> Result<DataType, TrustonError> {
+    if series.null_count() > 0 {
+        return Err(TrustonError::InferenceError(format!(
+            "column {} has {} null values",
+            series.name(),
+            series.null_count()
+        )));
+    }
+    let data = match series.dtype() {
+        PolarsType::Boolean => DataType::Bool(
+            series.bool().map_err(polars_error)?.into_no_null_iter().collect(),
+        ),
+        PolarsType::UInt8 => DataType::U8(values(series.u8())?),
+        PolarsType::UInt16 => DataType::U16(values(series.u16())?),
+        PolarsType::UInt32 => DataType::U32(values(series.u32())?),
+        PolarsType::UInt64 => DataType::U64(values(series.u64())?),
+        PolarsType::Int8 => DataType::I8(values(series.i8())?),
+        PolarsType::Int16 => DataType::I16(values(series.i16())?),
+        PolarsType::Int32 => DataType::I32(values(series.i32())?),
+        PolarsType::Int64 => DataType::I64(values(series.i64())?),
+        PolarsType::Float32 => DataType::F32(values(series.f32())?),
+        PolarsType::Float64 => DataType::F64(values(series.f64())?),
+        PolarsType::String => DataType::String(
+            series.str().map_err(polars_error)?.into_no_null_iter().map(str::to_string).collect(),
+        ),
+        PolarsType::Binary => DataType::Bytes(
+            series.binary().map_err(polars_error)?.into_no_null_iter().map(<[u8]>::to_vec).collect(),
+        ),
+        other => {
+            return Err(TrustonError::InferenceError(format!(
+                "column {} has dtype {}, which has no Triton datatype",
+                series.name(),
+                other
+            )));
+        }
+    };
+    Ok(data)
+}
+
+/// The values of a numeric column without nulls.
+fn values<T: PolarsNumericType>(
+    column: PolarsResult<&ChunkedArray<T>>,
+) -> Result<Vec<T::Native>, TrustonError> {
+    Ok(column.map_err(polars_error)?.into_no_null_iter().collect())
+}
+
+/// The column `name` of every `step`th value of `data`, from `offset`.
+fn strided_column(
+    name: &str,
+    data: &DataType,
+    step: usize,
+    offset: usize,
+) -> Result<Column, TrustonError> {
+    fn strided<T: Clone>(values: &[T], step: usize, offset: usize) -> Vec<T> {
+        values.iter().skip(offset).step_by(step).cloned().collect()
+    }
+    let name = name.into();
+    let series = match data {
+        DataType::Bool(v) => Series::new(name, strided(v, step, offset)),
+        DataType::U8(v) => Series::new(name, strided(v, step, offset)),
+        DataType::U16(v) | DataType::Bf16(v) => Series::new(name, strided(v, step, offset)),
+        DataType::U32(v) => Series::new(name, strided(v, step, offset)),
+        DataType::U64(v) => Series::new(name, strided(v, step, offset)),
+        DataType::I8(v) => Series::new(name, strided(v, step, offset)),
+        DataType::I16(v) => Series::new(name, strided(v, step, offset)),
+        DataType::I32(v) => Series::new(name, strided(v, step, offset)),
+        DataType::I64(v) => Series::new(name, strided(v, step, offset)),
+        DataType::F32(v) => Series::new(name, strided(v, step, offset)),
+        DataType::F64(v) => Series::new(name, strided(v, step, offset)),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => {
+            let v: Vec<f32> = v.iter().skip(offset).step_by(step).map(|x| x.to_f32()).collect();
+            Series::new(name, v)
+        }
+        DataType::String(v) => Series::new(name, strided(v, step, offset)),
+        DataType::Bytes(v) => {
+            let v: Vec<&[u8]> = v.iter().skip(offset).step_by(step).map(Vec::as_slice).collect();
+            Series::new(name, v)
+        }
+        DataType::Raw(_) => {
+            return Err(TrustonError::InferenceError(format!(
+                "output {} holds raw JSON, which has no Polars dtype",
+                name
+            )));
+        }
+    };
+    Ok(series.into_column())
+}
+
+fn polars_error(e: PolarsError) -> TrustonError {
+    TrustonError::InferenceError(format!("invalid data frame: {}", e))
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use ::polars::prelude::df;
+
+    use super::*;
+    use crate::client::io::InferOutput;
+
+    #[test]
+    fn test_dataframe_to_inputs() {
+        let df = df!("id" => [7i64, 8, 9], "name" => ["a", "b", "c"]).unwrap();
+        let id = InferInput::from_series(df.column("id").unwrap().as_materialized_series()).unwrap();
+        assert_eq!(id.input_name, "id");
+        assert_eq!(id.input_shape, vec![3, 1]);
+        assert_eq!(id.input_data.as_i64_vec(), Some(vec![7, 8, 9]));
+        let name = df.column("name").unwrap().as_materialized_series();
+        let name = InferInput::from_series(name).unwrap();
+        assert_eq!(name.input_data.as_str_vec().unwrap(), vec!["a", "b", "c"]);
+
+        // mixed dtypes can't share a tensor
+        assert!(matches!(
+            InferInput::from_dataframe("x", &df),
+            Err(TrustonError::InferenceError(_))
+        ));
+
+        let nulls = Series::new("x".into(), [Some(1.0f32), None]);
+        assert!(InferInput::from_series(&nulls).is_err());
+    }
+
+    #[test]
+    fn test_results_to_dataframe() {
+        let output = |name: &str, shape: Vec<usize>, data: DataType| InferOutput {
+            name: name.into(),
+            datatype: data.dtype().unwrap(),
+            shape,
+            data,
+        };
+        let results = InferResults {
+            outputs: vec![
+                output("label", vec![2], DataType::I32(vec![1, 0])),
+                output("probs", vec![2, 2], DataType::F32(vec![0.1, 0.9, 0.8, 0.2])),
+            ],
+            ..Default::default()
+        };
+        let df = results.to_dataframe().unwrap();
+        assert_eq!(df.get_column_names(), ["label", "probs_0", "probs_1"]);
+        let probs_1 = df.column("probs_1").unwrap().as_materialized_series();
+        let probs_1 = InferInput::from_series(probs_1).unwrap();
+        assert_eq!(probs_1.input_data.as_f32_vec(), Some(vec![0.9, 0.2]));
+
+        let mut results = results;
+        results.outputs.push(output("extra", vec![3], DataType::U8(vec![0; 3])));
+        assert!(results.to_dataframe().is_err());
+        results.outputs.pop();
+        results.outputs.push(output("short", vec![2, 2], DataType::U8(vec![0; 3])));
+        assert!(results.to_dataframe().is_err());
+    }
+}
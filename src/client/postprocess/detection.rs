@@ -0,0 +1,389 @@
+//! Box decoding and non-maximum suppression for detection models (requires
+//! the `detection` feature).
+//!
+//! [`decode_yolo`] reads the raw output of YOLO exports, [`decode_boxes`]
+//! the separate box and score outputs of SSD-style exports. Both filter the
+//! candidates by confidence and run [`nms`], giving the detections of each
+//! image of the batch as [`BoundingBox`]es in `xyxy` format, in the
+//! coordinates used by the model (pixels of its input, or normalized).
+//!
+//! # Example
+//! ```
+//! use truston::client::dtype::TritonDtype;
+//! use truston::client::io::{DataType, InferOutput};
+//! use truston::client::postprocess::detection::{DetectionFilter, YoloLayout, decode_yolo};
+//!
+//! // YOLOv5: [batch, boxes, cx + cy + w + h + objectness + classes]
+//! let output = InferOutput {
+//!     name: "output0".into(),
+//!     datatype: TritonDtype::Fp32,
+//!     shape: vec![1, 2, 7],
+//!     data: DataType::F32(vec![
+//!         50.0, 50.0, 20.0, 20.0, 0.9, 0.1, 0.9, // class 1
+//!         52.0, 50.0, 20.0, 20.0, 0.8, 0.1, 0.9, // same object, suppressed
+//!     ]),
+//! };
+//! let detections = decode_yolo(&output, YoloLayout::V5, &DetectionFilter::new())?;
+//! assert_eq!(detections[0].len(), 1);
+//! assert_eq!(detections[0][0].class, 1);
+//! assert_eq!(detections[0][0].bbox.x1, 40.0);
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use crate::client::io::InferOutput;
+use crate::client::postprocess::f32_values;
+use crate::utils::errors::TrustonError;
+
+/// How the 4 coordinates of a box are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoxFormat {
+    /// `x1, y1, x2, y2`: top-left and bottom-right corners.
+    #[default]
+    Xyxy,
+    /// `cx, cy, w, h`: center and size, as output by YOLO.
+    Xywh,
+    /// `y1, x1, y2, x2`, as output by TensorFlow SSD exports.
+    Yxyx,
+}
+
+/// An axis-aligned box, by its top-left and bottom-right corners.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoundingBox {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+}
+
+impl BoundingBox {
+    /// The box whose coordinates are `coords` in `format`.
+    pub fn from_format(coords: [f32; 4], format: BoxFormat) -> Self {
+        let [a, b, c, d] = coords;
+        match format {
+            BoxFormat::Xyxy => BoundingBox { x1: a, y1: b, x2: c, y2: d },
+            BoxFormat::Xywh => {
+                BoundingBox { x1: a - c / 2.0, y1: b - d / 2.0, x2: a + c / 2.0, y2: b + d / 2.0 }
+            }
+            BoxFormat::Yxyx => BoundingBox { x1: b, y1: a, x2: d, y2: c },
+        }
+    }
+
+    /// The coordinates of the box in `format`.
+    pub fn to_format(&self, format: BoxFormat) -> [f32; 4] {
+        match format {
+            BoxFormat::Xyxy => [self.x1, self.y1, self.x2, self.y2],
+            BoxFormat::Xywh => [
+                (self.x1 + self.x2) / 2.0,
+                (self.y1 + self.y2) / 2.0,
+                self.width(),
+                self.height(),
+            ],
+            BoxFormat::Yxyx => [self.y1, self.x1, self.y2, self.x2],
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        (self.x2 - self.x1).max(0.0)
+    }
+
+    pub fn height(&self) -> f32 {
+        (self.y2 - self.y1).max(0.0)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width() * self.height()
+    }
+
+    /// Intersection over union with `other`, 0 for disjoint or empty boxes.
+    pub fn iou(&self, other: &BoundingBox) -> f32 {
+        let intersection = BoundingBox {
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+            x2: self.x2.min(other.x2),
+            y2: self.y2.min(other.y2),
+        }
+        .area();
+        let union = self.area() + other.area() - intersection;
+        if union > 0.0 { intersection / union } else { 0.0 }
+    }
+}
+
+/// A detected object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    pub bbox: BoundingBox,
+    /// Confidence of the class (times the objectness for YOLOv5).
+    pub score: f32,
+    pub class: usize,
+}
+
+/// Which candidates are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionFilter {
+    /// Candidates scoring below are dropped.
+    pub score_threshold: f32,
+    /// A detection overlapping a better one by more is suppressed.
+    pub iou_threshold: f32,
+    /// At most this many detections are kept per image, best first.
+    pub max_detections: usize,
+    /// Suppress overlapping detections whatever their classes; otherwise
+    /// only detections of the same class suppress each other.
+    pub class_agnostic: bool,
+}
+
+impl Default for DetectionFilter {
+    fn default() -> Self {
+        DetectionFilter::new()
+    }
+}
+
+impl DetectionFilter {
+    /// The defaults of the YOLO exports: score 0.25, IoU 0.45, 300
+    /// detections, per class.
+    pub fn new() -> Self {
+        DetectionFilter {
+            score_threshold: 0.25,
+            iou_threshold: 0.45,
+            max_detections: 300,
+            class_agnostic: false,
+        }
+    }
+
+    pub fn with_score_threshold(mut self, threshold: f32) -> Self {
+        self.score_threshold = threshold;
+        self
+    }
+
+    pub fn with_iou_threshold(mut self, threshold: f32) -> Self {
+        self.iou_threshold = threshold;
+        self
+    }
+
+    pub fn with_max_detections(mut self, max: usize) -> Self {
+        self.max_detections = max;
+        self
+    }
+
+    pub fn with_class_agnostic(mut self) -> Self {
+        self.class_agnostic = true;
+        self
+    }
+}
+
+/// Greedy non-maximum suppression: keeps the best detections, dropping
+/// those overlapping a kept one by more than `filter.iou_threshold`.
+///
+/// Returns at most `filter.max_detections` detections, best first.
+/// `filter.score_threshold` is not applied.
+pub fn nms(mut detections: Vec<Detection>, filter: &DetectionFilter) -> Vec<Detection> {
+    detections.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut kept: Vec<Detection> = Vec::new();
+    for detection in detections {
+        if kept.len() == filter.max_detections {
+            break;
+        }
+        let suppressed = kept.iter().any(|k| {
+            (filter.class_agnostic || k.class == detection.class)
+                && k.bbox.iou(&detection.bbox) > filter.iou_threshold
+        });
+        if !suppressed {
+            kept.push(detection);
+        }
+    }
+    kept
+}
+
+/// Layout of the raw output of a YOLO export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YoloLayout {
+    /// `[batch, boxes, 5 + classes]`: `cx, cy, w, h`, objectness, then the
+    /// class scores (YOLOv5, YOLOv7).
+    V5,
+    /// `[batch, 4 + classes, boxes]`: `cx, cy, w, h` then the class scores,
+    /// without objectness (YOLOv8 and later).
+    V8,
+}
+
+/// The detections of each image of the batch in a raw YOLO output.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] if the output is not numeric or
+/// doesn't have the shape of `layout`.
+pub fn decode_yolo(
+    output: &InferOutput,
+    layout: YoloLayout,
+    filter: &DetectionFilter,
+) -> Result<Vec<Vec<Detection>>, TrustonError> {
+    let values = f32_values(output)?;
+    let (coords, attributes, boxes) = match (layout, output.shape.as_slice()) {
+        (YoloLayout::V5, &[_, boxes, attributes]) if attributes > 5 => (5, attributes, boxes),
+        (YoloLayout::V8, &[_, attributes, boxes]) if attributes > 4 => (4, attributes, boxes),
+        _ => {
+            return Err(TrustonError::ParseError(format!(
+                "output {} has shape {:?}, not a {:?} YOLO output",
+                output.name, output.shape, layout
+            )));
+        }
+    };
+    let image_len = attributes * boxes;
+    if image_len == 0 {
+        return Ok(vec![Vec::new(); output.shape[0]]);
+    }
+
+    let detections = values
+        .chunks(image_len)
+        .map(|image| {
+            // attribute `a` of box `b`
+            let value = |b: usize, a: usize| match layout {
+                YoloLayout::V5 => image[b * attributes + a],
+                YoloLayout::V8 => image[a * boxes + b],
+            };
+            let candidates = (0..boxes)
+                .filter_map(|b| {
+                    let objectness = if layout == YoloLayout::V5 { value(b, 4) } else { 1.0 };
+                    let (class, score) = (coords..attributes)
+                        .map(|a| (a - coords, value(b, a) * objectness))
+                        .max_by(|x, y| x.1.total_cmp(&y.1))?;
+                    let xywh = [value(b, 0), value(b, 1), value(b, 2), value(b, 3)];
+                    let bbox = BoundingBox::from_format(xywh, BoxFormat::Xywh);
+                    (score >= filter.score_threshold).then_some(Detection { bbox, score, class })
+                })
+                .collect();
+            nms(candidates, filter)
+        })
+        .collect();
+    Ok(detections)
+}
+
+/// The detections of each image of the batch in separate box and score
+/// outputs, as exported by SSD-style models.
+///
+/// `boxes` is `[batch, boxes, 4]` in `format`; `scores` is either
+/// `[batch, boxes, classes]` (the best class of each box is kept) or
+/// `[batch, boxes]` for a single class.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] if an output is not numeric or the
+/// shapes don't match.
+pub fn decode_boxes(
+    boxes: &InferOutput,
+    scores: &InferOutput,
+    format: BoxFormat,
+    filter: &DetectionFilter,
+) -> Result<Vec<Vec<Detection>>, TrustonError> {
+    let box_values = f32_values(boxes)?;
+    let score_values = f32_values(scores)?;
+    let (batch, count, classes) = match (boxes.shape.as_slice(), scores.shape.as_slice()) {
+        (&[batch, count, 4], &[b, c]) if (b, c) == (batch, count) => (batch, count, 1),
+        (&[batch, count, 4], &[b, c, classes]) if (b, c) == (batch, count) && classes > 0 => {
+            (batch, count, classes)
+        }
+        _ => {
+            return Err(TrustonError::ParseError(format!(
+                "outputs {} {:?} and {} {:?} are not [batch, boxes, 4] and \
+                 [batch, boxes(, classes)]",
+                boxes.name, boxes.shape, scores.name, scores.shape
+            )));
+        }
+    };
+
+    let detections = (0..batch)
+        .map(|image| {
+            let candidates = (0..count)
+                .filter_map(|b| {
+                    let i = image * count + b;
+                    let row = &score_values[i * classes..(i + 1) * classes];
+                    let (class, &score) =
+                        row.iter().enumerate().max_by(|x, y| x.1.total_cmp(y.1))?;
+                    let c = &box_values[i * 4..i * 4 + 4];
+                    let bbox = BoundingBox::from_format([c[0], c[1], c[2], c[3]], format);
+                    (score >= filter.score_threshold).then_some(Detection { bbox, score, class })
+                })
+                .collect();
+            nms(candidates, filter)
+        })
+        .collect();
+    Ok(detections)
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use crate::client::io::DataType;
+
+    fn output(name: &str, shape: Vec<usize>, values: Vec<f32>) -> InferOutput {
+        let data = DataType::F32(values);
+        InferOutput { name: name.into(), datatype: TritonDtype::Fp32, shape, data }
+    }
+
+    fn detection(x1: f32, class: usize, score: f32) -> Detection {
+        let bbox = BoundingBox { x1, y1: 0.0, x2: x1 + 10.0, y2: 10.0 };
+        Detection { bbox, score, class }
+    }
+
+    #[test]
+    fn test_box_formats_and_iou() {
+        let bbox = BoundingBox::from_format([5.0, 5.0, 10.0, 4.0], BoxFormat::Xywh);
+        assert_eq!(bbox, BoundingBox { x1: 0.0, y1: 3.0, x2: 10.0, y2: 7.0 });
+        assert_eq!(bbox.to_format(BoxFormat::Xywh), [5.0, 5.0, 10.0, 4.0]);
+        assert_eq!(bbox.to_format(BoxFormat::Yxyx), [3.0, 0.0, 7.0, 10.0]);
+
+        let a = BoundingBox { x1: 0.0, y1: 0.0, x2: 2.0, y2: 2.0 };
+        let b = BoundingBox { x1: 1.0, y1: 0.0, x2: 3.0, y2: 2.0 };
+        assert!((a.iou(&b) - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(a.iou(&BoundingBox { x1: 5.0, y1: 5.0, x2: 6.0, y2: 6.0 }), 0.0);
+        assert_eq!(BoundingBox::default().iou(&BoundingBox::default()), 0.0);
+    }
+
+    #[test]
+    fn test_nms() {
+        let candidates = vec![
+            detection(1.0, 0, 0.8),
+            detection(0.0, 0, 0.9),
+            detection(0.0, 1, 0.7),
+            detection(50.0, 0, 0.6),
+        ];
+        let kept = nms(candidates.clone(), &DetectionFilter::new());
+        let scores: Vec<f32> = kept.iter().map(|d| d.score).collect();
+        assert_eq!(scores, vec![0.9, 0.7, 0.6]);
+
+        let agnostic = DetectionFilter::new().with_class_agnostic().with_max_detections(1);
+        assert_eq!(nms(candidates, &agnostic), vec![detection(0.0, 0, 0.9)]);
+    }
+
+    #[test]
+    fn test_decode_yolo_v8_batch() {
+        // [2 images, 4 + 2 classes, 2 boxes], attributes major
+        let image = |score: f32| {
+            vec![10.0, 30.0, 10.0, 30.0, 4.0, 4.0, 4.0, 4.0, score, 0.0, 0.1, 0.5]
+        };
+        let values = [image(0.9), image(0.1)].concat();
+        let filter = DetectionFilter::new();
+        let detections = decode_yolo(&output("y", vec![2, 6, 2], values), YoloLayout::V8, &filter)
+            .unwrap();
+        assert_eq!(detections.len(), 2);
+        assert_eq!(detections[0].len(), 2);
+        assert_eq!(detections[0][0].bbox, BoundingBox { x1: 8.0, y1: 8.0, x2: 12.0, y2: 12.0 });
+        assert_eq!((detections[0][1].class, detections[0][1].score), (1, 0.5));
+        assert_eq!(detections[1].len(), 1);
+
+        let wrong = output("y", vec![1, 4, 2], vec![0.0; 8]);
+        assert!(decode_yolo(&wrong, YoloLayout::V8, &DetectionFilter::new()).is_err());
+    }
+
+    #[test]
+    fn test_decode_boxes() {
+        let boxes = output("boxes", vec![1, 2, 4], vec![0.1, 0.2, 0.5, 0.6, 0.0, 0.0, 1.0, 1.0]);
+        let scores = output("scores", vec![1, 2], vec![0.8, 0.1]);
+        let detections =
+            decode_boxes(&boxes, &scores, BoxFormat::Yxyx, &DetectionFilter::new()).unwrap();
+        assert_eq!(detections[0].len(), 1);
+        assert_eq!(detections[0][0].bbox, BoundingBox { x1: 0.2, y1: 0.1, x2: 0.6, y2: 0.5 });
+
+        let mismatched = output("scores", vec![1, 3], vec![0.8; 3]);
+        let filter = DetectionFilter::new();
+        assert!(decode_boxes(&boxes, &mismatched, BoxFormat::Xyxy, &filter).is_err());
+    }
+}
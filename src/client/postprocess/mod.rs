@@ -0,0 +1,179 @@
+//! Post-processing of classification outputs.
+//!
+//! The helpers below read an [`InferOutput`] of scores (logits or
+//! probabilities) whose last dimension holds the classes, and work on each
+//! sample of the batch: a `[batch, classes]` output gives one result per
+//! sample, a `[classes]` output a single one. Values of any numeric
+//! datatype are read as `f32`.
+//!
+//! # Example
+//! ```
+//! use truston::client::dtype::TritonDtype;
+//! use truston::client::io::{DataType, InferOutput};
+//! use truston::client::postprocess::{argmax, softmax, top_k};
+//!
+//! let logits = InferOutput {
+//!     name: "logits".into(),
+//!     datatype: TritonDtype::Fp32,
+//!     shape: vec![2, 3],
+//!     data: DataType::F32(vec![0.1, 2.0, 0.3, 1.5, 0.2, 0.1]),
+//! };
+//! let best: Vec<usize> = argmax(&logits)?.iter().map(|p| p.index).collect();
+//! assert_eq!(best, vec![1, 0]);
+//! assert_eq!(top_k(&logits, 2)?[0][1].index, 2);
+//! assert!((softmax(&logits)?[0].iter().sum::<f32>() - 1.0).abs() < 1e-6);
+//! # Ok::<(), truston::TrustonError>(())
+//! ```
+
+use std::borrow::Cow;
+
+use crate::client::io::{InferOutput, element_count};
+use crate::utils::errors::TrustonError;
+
+/// A class and its score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub index: usize,
+    pub score: f32,
+}
+
+#[cfg(feature = "detection")]
+pub mod detection;
+
+/// The values of `output` as `f32`, checked against its shape.
+fn f32_values(output: &InferOutput) -> Result<Cow<'_, [f32]>, TrustonError> {
+    let values = match output.data.as_f32_slice() {
+        Some(values) => Cow::Borrowed(values),
+        None => {
+            let cast = output.data.cast("FP32").map_err(|_| {
+                TrustonError::ParseError(format!(
+                    "output {} is {}, not numeric",
+                    output.name,
+                    output.data.get_type_str()
+                ))
+            })?;
+            Cow::Owned(cast.into_f32_vec().unwrap_or_default())
+        }
+    };
+    let total = element_count(&output.name, &output.shape)?;
+    if values.len() != total {
+        return Err(TrustonError::ParseError(format!(
+            "output {} has {} values, shape {:?} needs {}",
+            output.name,
+            values.len(),
+            output.shape,
+            total
+        )));
+    }
+    Ok(values)
+}
+
+/// The scores of `output` as `f32`, and the number of classes per sample.
+fn scores(output: &InferOutput) -> Result<(Cow<'_, [f32]>, usize), TrustonError> {
+    let Some(&classes) = output.shape.last().filter(|&&classes| classes > 0) else {
+        return Err(TrustonError::ParseError(format!(
+            "output {} has no classes (shape {:?})",
+            output.name, output.shape
+        )));
+    };
+    Ok((f32_values(output)?, classes))
+}
+
+/// Softmax of the scores of each sample, e.g. to turn logits into
+/// probabilities.
+///
+/// # Errors
+/// Returns [`TrustonError::ParseError`] if the output is not numeric, has
+/// no dims or doesn't have as many values as its shape needs.
+pub fn softmax(output: &InferOutput) -> Result<Vec<Vec<f32>>, TrustonError> {
+    let (values, classes) = scores(output)?;
+    Ok(values.chunks(classes).map(softmax_row).collect())
+}
+
+fn softmax_row(row: &[f32]) -> Vec<f32> {
+    // shifted by the max so that exp doesn't overflow
+    let max = row.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let exps: Vec<f32> = row.iter().map(|&x| (x - max).exp()).collect();
+    let sum: f32 = exps.iter().sum();
+    exps.into_iter().map(|x| x / sum).collect()
+}
+
+/// The best class of each sample, with its raw score.
+///
+/// # Errors
+/// Same as [`softmax`].
+pub fn argmax(output: &InferOutput) -> Result<Vec<Prediction>, TrustonError> {
+    Ok(top_k(output, 1)?.into_iter().flatten().collect())
+}
+
+/// The `k` best classes of each sample, best first, with their raw scores.
+///
+/// Fewer are returned when there are less than `k` classes. Apply
+/// [`softmax`] first for probabilities.
+///
+/// # Errors
+/// Same as [`softmax`].
+pub fn top_k(output: &InferOutput, k: usize) -> Result<Vec<Vec<Prediction>>, TrustonError> {
+    let (values, classes) = scores(output)?;
+    Ok(values.chunks(classes).map(|row| top_k_row(row, k)).collect())
+}
+
+fn top_k_row(row: &[f32], k: usize) -> Vec<Prediction> {
+    let mut predictions: Vec<Prediction> = row
+        .iter()
+        .enumerate()
+        .map(|(index, &score)| Prediction { index, score })
+        .collect();
+    // stable, so ties keep the lowest index first
+    predictions.sort_by(|a, b| b.score.total_cmp(&a.score));
+    predictions.truncate(k);
+    predictions
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::DataType;
+
+    fn output(shape: Vec<usize>, data: DataType) -> InferOutput {
+        InferOutput { name: "y".into(), datatype: data.dtype().unwrap(), shape, data }
+    }
+
+    #[test]
+    fn test_batched_predictions() {
+        let logits = output(vec![2, 3], DataType::F64(vec![1.0, 3.0, 2.0, 5.0, 5.0, 0.0]));
+
+        let top = top_k(&logits, 2).unwrap();
+        assert_eq!(top[0], vec![
+            Prediction { index: 1, score: 3.0 },
+            Prediction { index: 2, score: 2.0 }
+        ]);
+        // ties keep the lowest index first
+        assert_eq!(top[1][0].index, 0);
+        assert_eq!(top_k(&logits, 10).unwrap()[0].len(), 3);
+
+        let best = argmax(&output(vec![4], DataType::I32(vec![3, 9, -1, 9]))).unwrap();
+        assert_eq!(best, vec![Prediction { index: 1, score: 9.0 }]);
+
+        let probs = softmax(&logits).unwrap();
+        assert_eq!(probs.len(), 2);
+        assert_eq!(probs[1][0], probs[1][1]);
+        assert!((probs[1].iter().sum::<f32>() - 1.0).abs() < 1e-6);
+        assert!(probs[0][1] > probs[0][2] && probs[0][2] > probs[0][0]);
+    }
+
+    #[test]
+    fn test_softmax_large_logits() {
+        let probs = softmax(&output(vec![2], DataType::F32(vec![1000.0, 1000.0]))).unwrap();
+        assert_eq!(probs, vec![vec![0.5, 0.5]]);
+    }
+
+    #[test]
+    fn test_invalid_outputs() {
+        let labels = output(vec![1], DataType::String(vec!["cat".into()]));
+        assert!(matches!(argmax(&labels), Err(TrustonError::ParseError(_))));
+        assert!(softmax(&output(vec![], DataType::F32(vec![1.0]))).is_err());
+        assert!(top_k(&output(vec![2, 2], DataType::F32(vec![1.0])), 1).is_err());
+    }
+}
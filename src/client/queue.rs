@@ -0,0 +1,413 @@
+//! Client-side dynamic batching.
+//!
+//! Services answering many small requests waste the GPU when each one is
+//! sent on its own. An [`InferQueue`] collects single samples from
+//! concurrent callers, sends them as one batched inference once enough rows
+//! are waiting or the oldest has waited long enough, and hands each caller
+//! its own slice of the outputs (see [`Batch`]).
+//!
+//! [`InferQueue::stats`] counts the batches sent, their rows and why each
+//! was flushed, to tune `max_batch_size` and `max_delay` on real traffic
+//! (also exported by [`InferQueue::metrics`]). Each batch is also reported
+//! to the client's [events](crate::client::events) as
+//! [`ClientEvent::BatchFlushed`].
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::client::batch::Batch;
+use crate::client::events::ClientEvent;
+use crate::client::http::TritonRestClient;
+use crate::client::io::{InferInput, InferResults};
+use crate::client::options::InferOptions;
+use crate::client::validation::validate_batch_dims;
+use crate::utils::errors::TrustonError;
+
+/// Settings of an [`InferQueue`].
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    /// Rows sent in one request at most, usually the model's `max_batch_size`.
+    pub max_batch_size: usize,
+    /// How long the first sample of a batch waits for others.
+    pub max_delay: Duration,
+    /// Samples waiting to be batched before callers wait to submit. Defaults to `1024`.
+    pub capacity: usize,
+    /// Options sent with every batched request.
+    pub options: InferOptions,
+}
+
+impl QueueConfig {
+    pub fn new(max_batch_size: usize, max_delay: Duration) -> Self {
+        QueueConfig {
+            max_batch_size: max_batch_size.max(1),
+            max_delay,
+            capacity: 1024,
+            options: InferOptions::new(),
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn with_options(mut self, options: InferOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// Why a batch was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlushReason {
+    /// It reached `max_batch_size` rows.
+    Full,
+    /// Its first sample waited `max_delay`.
+    Delay,
+    /// The next sample would have made it larger than `max_batch_size`.
+    Overflow,
+    /// The queue was dropped.
+    Closed,
+}
+
+impl FlushReason {
+    pub const ALL: [FlushReason; 4] =
+        [FlushReason::Full, FlushReason::Delay, FlushReason::Overflow, FlushReason::Closed];
+
+    /// Lowercase name, used as a metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlushReason::Full => "full",
+            FlushReason::Delay => "delay",
+            FlushReason::Overflow => "overflow",
+            FlushReason::Closed => "closed",
+        }
+    }
+}
+
+/// Batches sent by an [`InferQueue`] since it was created.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueStats {
+    pub batches: u64,
+    /// Rows sent, over all batches.
+    pub rows: u64,
+    /// Batches sent for each [`FlushReason`], in the order of [`FlushReason::ALL`].
+    pub flushes: [u64; 4],
+}
+
+impl QueueStats {
+    /// Average rows per batch; `None` before the first batch.
+    pub fn average_batch_size(&self) -> Option<f64> {
+        (self.batches > 0).then(|| self.rows as f64 / self.batches as f64)
+    }
+
+    /// Batches flushed for `reason`.
+    pub fn flushes(&self, reason: FlushReason) -> u64 {
+        self.flushes[reason as usize]
+    }
+}
+
+/// Counters shared by the queue and its batching task.
+#[derive(Debug, Default)]
+struct Counters {
+    rows: AtomicU64,
+    flushes: [AtomicU64; 4],
+}
+
+impl Counters {
+    fn record(&self, rows: usize, reason: FlushReason) {
+        self.rows.fetch_add(rows as u64, Ordering::Relaxed);
+        self.flushes[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+type Reply = oneshot::Sender<Result<InferResults, TrustonError>>;
+
+/// A sample waiting for its batch.
+struct Pending {
+    inputs: Vec<InferInput>,
+    rows: usize,
+    reply: Reply,
+}
+
+/// Queue batching the samples submitted to one model.
+///
+/// The batches are formed and sent by a background task, which stops once
+/// the queue is dropped and the waiting samples are sent. Batches are sent
+/// without waiting for the previous ones to complete.
+///
+/// # Example
+/// ```no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// # use truston::client::http::TritonRestClient;
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::queue::{InferQueue, QueueConfig};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = Arc::new(TritonRestClient::new("http://localhost:8000"));
+/// let config = QueueConfig::new(32, Duration::from_millis(5));
+/// let queue = Arc::new(InferQueue::new(client, "classifier", config));
+///
+/// // e.g. in each request handler
+/// let sample = vec![InferInput::new("x".into(), vec![1, 4], DataType::F32(vec![0.0; 4]))];
+/// let results = queue.infer(sample).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct InferQueue {
+    sender: mpsc::Sender<Pending>,
+    model_name: String,
+    max_batch_size: usize,
+    counters: Arc<Counters>,
+}
+
+impl InferQueue {
+    /// Start batching requests to `model_name`. Must be called within a Tokio runtime.
+    pub fn new(
+        client: Arc<TritonRestClient>,
+        model_name: impl Into<String>,
+        config: QueueConfig,
+    ) -> Self {
+        let (sender, receiver) = mpsc::channel(config.capacity);
+        let model_name = model_name.into();
+        let max_batch_size = config.max_batch_size;
+        let counters = Arc::new(Counters::default());
+        tokio::spawn(run(client, model_name.clone(), config, receiver, counters.clone()));
+        InferQueue { sender, model_name, max_batch_size, counters }
+    }
+
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Samples submitted and not yet picked up by the batching task.
+    pub fn depth(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+
+    /// Samples the queue holds before [`infer`](Self::infer) waits for room.
+    pub fn capacity(&self) -> usize {
+        self.sender.max_capacity()
+    }
+
+    /// Batches sent so far, their rows and why they were flushed.
+    pub fn stats(&self) -> QueueStats {
+        let flushes = self.counters.flushes.each_ref().map(|c| c.load(Ordering::Relaxed));
+        QueueStats {
+            batches: flushes.iter().sum(),
+            rows: self.counters.rows.load(Ordering::Relaxed),
+            flushes,
+        }
+    }
+
+    /// Submit one sample and wait for its results.
+    ///
+    /// The sample's inputs share a leading batch dimension, usually `1`.
+    ///
+    /// # Errors
+    /// - [`TrustonError::InferenceError`] if the sample has more rows than
+    ///   the maximum batch size, or if its batch could not be stacked or
+    ///   split (e.g. samples with different inputs).
+    /// - If the batched request fails, every sample of the batch gets a
+    ///   [`TrustonError::InferenceError`] describing the failure.
+    pub async fn infer(&self, inputs: Vec<InferInput>) -> Result<InferResults, TrustonError> {
+        validate_batch_dims(&inputs)?;
+        let rows = inputs
+            .first()
+            .and_then(|input| input.input_shape.first().copied())
+            .unwrap_or(0);
+        if rows == 0 || rows > self.max_batch_size {
+            return Err(TrustonError::InferenceError(format!(
+                "sample of {} rows cannot be queued (max batch size {})",
+                rows, self.max_batch_size
+            )));
+        }
+
+        let (reply, receiver) = oneshot::channel();
+        let closed = || TrustonError::InferenceError("inference queue stopped".to_string());
+        self.sender
+            .send(Pending { inputs, rows, reply })
+            .await
+            .map_err(|_| closed())?;
+        receiver.await.map_err(|_| closed())?
+    }
+}
+
+/// Form batches from `receiver` until it closes.
+async fn run(
+    client: Arc<TritonRestClient>,
+    model_name: String,
+    config: QueueConfig,
+    mut receiver: mpsc::Receiver<Pending>,
+    counters: Arc<Counters>,
+) {
+    let options = Arc::new(config.options);
+    let mut carried = None;
+    loop {
+        let first = match carried.take() {
+            Some(pending) => pending,
+            None => match receiver.recv().await {
+                Some(pending) => pending,
+                None => return,
+            },
+        };
+        let flush_at = Instant::now() + config.max_delay;
+        let mut rows = first.rows;
+        let mut batch = vec![first];
+        let reason = loop {
+            if rows >= config.max_batch_size {
+                break FlushReason::Full;
+            }
+            match tokio::time::timeout_at(flush_at, receiver.recv()).await {
+                Ok(Some(pending)) if rows + pending.rows > config.max_batch_size => {
+                    // starts the next batch
+                    carried = Some(pending);
+                    break FlushReason::Overflow;
+                }
+                Ok(Some(pending)) => {
+                    rows += pending.rows;
+                    batch.push(pending);
+                }
+                Ok(None) => break FlushReason::Closed,
+                Err(_) => break FlushReason::Delay,
+            }
+        };
+        counters.record(rows, reason);
+        client.emit(|| ClientEvent::BatchFlushed {
+            model: model_name.clone(),
+            samples: batch.len(),
+            rows,
+            reason,
+        });
+
+        tokio::spawn(send(client.clone(), model_name.clone(), options.clone(), batch));
+    }
+}
+
+/// Send one batch and distribute its results.
+async fn send(
+    client: Arc<TritonRestClient>,
+    model_name: String,
+    options: Arc<InferOptions>,
+    pending: Vec<Pending>,
+) {
+    let (replies, samples): (Vec<Reply>, Vec<_>) = pending
+        .into_iter()
+        .enumerate()
+        .map(|(index, pending)| (pending.reply, (index, pending.inputs)))
+        .unzip();
+    let result = match Batch::new(samples) {
+        Ok(batch) => client.infer_batch(batch, &model_name, &options).await,
+        Err(e) => Err(e),
+    };
+
+    // a caller that gave up has dropped its receiver; nothing to do then
+    let mut replies: Vec<Option<Reply>> = replies.into_iter().map(Some).collect();
+    match result {
+        Ok(split) => {
+            for (index, results) in split {
+                if let Some(reply) = replies[index].take() {
+                    let _ = reply.send(Ok(results));
+                }
+            }
+        }
+        Err(e) => {
+            tracing::warn!("batch of {} samples for {} failed: {}", replies.len(), model_name, e);
+            let message = format!("batch of {} samples failed: {}", replies.len(), e);
+            for reply in replies.into_iter().flatten() {
+                let _ = reply.send(Err(TrustonError::InferenceError(message.clone())));
+            }
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::DataType;
+    use crate::client::test_server::{Request, serve};
+
+    /// Answer one inference whose output echoes a batch of 3 rows.
+    async fn serve_one_batch() -> (String, tokio::task::JoinHandle<Vec<Request>>) {
+        let body = r#"{"outputs":[{"name":"y","datatype":"FP32","shape":[3,1],"data":[1,2,3]}]}"#;
+        serve(vec![(200, body)]).await
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_samples_share_one_request() {
+        let (base_url, server) = serve_one_batch().await;
+        let client = Arc::new(TritonRestClient::new(&base_url));
+        let config = QueueConfig::new(3, Duration::from_secs(5));
+        let queue = InferQueue::new(client, "m", config);
+
+        let sample = |x: f32| vec![InferInput::new("x".into(), vec![1], DataType::F32(vec![x]))];
+        let (a, b, c) = tokio::join!(
+            queue.infer(sample(1.0)),
+            queue.infer(sample(2.0)),
+            queue.infer(sample(3.0))
+        );
+        // a full batch is sent without waiting for the delay
+        assert_eq!(a.unwrap().outputs[0].data.as_f32_vec(), Some(vec![1.0]));
+        assert_eq!(b.unwrap().outputs[0].data.as_f32_vec(), Some(vec![2.0]));
+        assert_eq!(c.unwrap().outputs[0].data.as_f32_vec(), Some(vec![3.0]));
+        assert!(server.await.unwrap()[0].text().contains(r#""shape":[3]"#));
+
+        let stats = queue.stats();
+        assert_eq!((stats.batches, stats.rows), (1, 3));
+        assert_eq!(stats.flushes(FlushReason::Full), 1);
+        assert_eq!(stats.average_batch_size(), Some(3.0));
+        let metrics = queue.metrics();
+        assert!(metrics.contains("truston_queue_average_batch_size{model=\"m\"} 3\n"));
+        assert!(metrics.contains("truston_queue_flushes_total{model=\"m\",reason=\"full\"} 1\n"));
+        assert!(metrics.contains("truston_queue_flushes_total{model=\"m\",reason=\"delay\"} 0\n"));
+    }
+
+    #[tokio::test]
+    async fn test_flush_reasons() {
+        let client = Arc::new(TritonRestClient::new("http://127.0.0.1:1"));
+        let mut events = client.events();
+        let queue = InferQueue::new(client, "m", QueueConfig::new(3, Duration::from_millis(10)));
+        assert_eq!(queue.stats().average_batch_size(), None);
+
+        let sample = |rows| {
+            vec![InferInput::new("x".into(), vec![rows], DataType::F32(vec![0.0; rows]))]
+        };
+        // two rows wait for the delay, then two more do not fit the first batch
+        let _ = tokio::join!(queue.infer(sample(2)), async {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+            queue.infer(sample(2)).await
+        });
+        let stats = queue.stats();
+        assert_eq!((stats.batches, stats.rows), (2, 4));
+        assert_eq!(stats.flushes(FlushReason::Overflow), 1);
+        assert_eq!(stats.flushes(FlushReason::Delay), 1);
+        assert_eq!(stats.average_batch_size(), Some(2.0));
+
+        let flushed: Vec<_> = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                ClientEvent::BatchFlushed { model, samples, rows, reason } => {
+                    Some((model, samples, rows, reason))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(flushed, vec![
+            ("m".to_string(), 1, 2, FlushReason::Overflow),
+            ("m".to_string(), 1, 2, FlushReason::Delay),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_sample_is_rejected() {
+        let client = Arc::new(TritonRestClient::new("http://127.0.0.1:1"));
+        let queue = InferQueue::new(client, "m", QueueConfig::new(2, Duration::from_millis(1)));
+        let input = InferInput::new("x".into(), vec![3], DataType::F32(vec![0.0; 3]));
+        assert!(queue.infer(vec![input]).await.is_err());
+    }
+}
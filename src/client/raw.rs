@@ -0,0 +1,263 @@
+//! Raw calls to the v2 API.
+//!
+//! [`TritonRestClient::raw`] is an escape hatch for endpoints the client
+//! doesn't wrap yet, e.g. a new extension of the server. The requests go
+//! through the same pipeline as the wrapped calls: authentication, retries
+//! (of idempotent methods, see [`RetryPolicy`]), failover endpoints,
+//! interceptors, client events and the concurrency limit, inside a `raw`
+//! tracing span.
+//!
+//! Paths are relative to the base URL of the client. [`ApiPath`] builds them
+//! from segments, percent-encoding model names and other values; a `&str`
+//! is taken as is.
+//!
+//! [`RetryPolicy`]: crate::client::retry::RetryPolicy
+//!
+//! # Example
+//! ```no_run
+//! # use truston::client::http::TritonRestClient;
+//! use serde_json::{Value, json};
+//! use truston::client::raw::ApiPath;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = TritonRestClient::new("http://localhost:8000");
+//! let stats: Value = client.raw().get(ApiPath::model("bert").join("stats")).await?;
+//! let region: Value = client
+//!     .raw()
+//!     .post("/v2/systemsharedmemory/region/input0/register", &json!({"key": "/input0"}))
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use percent_encoding::{AsciiSet, CONTROLS, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tracing::Instrument;
+use tracing::field::Empty;
+
+use crate::client::http::TritonRestClient;
+use crate::utils::errors::TrustonError;
+
+/// Characters escaped in a path segment.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Characters escaped in a query parameter.
+const QUERY: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// A path of the API, relative to the base URL of the client, e.g.
+/// `/v2/models/bert/versions/2/stats`.
+///
+/// # Example
+/// ```
+/// use truston::client::raw::ApiPath;
+///
+/// let path = ApiPath::model("my model").version("2").join("stats");
+/// assert_eq!(path.to_string(), "/v2/models/my%20model/versions/2/stats");
+///
+/// let path = ApiPath::v2().join("trace").join("setting").query("model", "a&b");
+/// assert_eq!(path.to_string(), "/v2/trace/setting?model=a%26b");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiPath {
+    /// Already encoded.
+    path: String,
+    query: Vec<(String, String)>,
+}
+
+impl ApiPath {
+    /// `/v2`, the root of the API.
+    pub fn v2() -> Self {
+        ApiPath { path: "/v2".to_string(), query: Vec::new() }
+    }
+
+    /// `/v2/models/{model_name}`.
+    pub fn model(model_name: &str) -> Self {
+        Self::v2().join("models").join(model_name)
+    }
+
+    /// Append `/versions/{version}`.
+    pub fn version(self, version: &str) -> Self {
+        self.join("versions").join(version)
+    }
+
+    /// Append one segment, percent-encoded: a `/` in it doesn't start
+    /// another segment.
+    pub fn join(mut self, segment: impl AsRef<str>) -> Self {
+        self.path.push('/');
+        self.path.extend(utf8_percent_encode(segment.as_ref(), SEGMENT));
+        self
+    }
+
+    /// Add the query parameter `name=value`, percent-encoded.
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// A path taken as is (already percent-encoded), a leading `/` added if
+/// missing.
+impl From<&str> for ApiPath {
+    fn from(path: &str) -> Self {
+        let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+        ApiPath { path, query: Vec::new() }
+    }
+}
+
+impl From<String> for ApiPath {
+    fn from(path: String) -> Self {
+        ApiPath::from(path.as_str())
+    }
+}
+
+impl fmt::Display for ApiPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.path)?;
+        let mut separator = if self.path.contains('?') { '&' } else { '?' };
+        for (name, value) in &self.query {
+            let name = utf8_percent_encode(name, QUERY);
+            let value = utf8_percent_encode(value, QUERY);
+            write!(f, "{}{}={}", separator, name, value)?;
+            separator = '&';
+        }
+        Ok(())
+    }
+}
+
+/// Handle for raw calls to the API, see [`TritonRestClient::raw`].
+#[derive(Clone, Copy)]
+pub struct RawClient<'a> {
+    client: &'a TritonRestClient,
+}
+
+impl TritonRestClient {
+    /// A handle for calls to endpoints the client doesn't wrap, sent with
+    /// its authentication, retries and tracing (see [`crate::client::raw`]).
+    pub fn raw(&self) -> RawClient<'_> {
+        RawClient { client: self }
+    }
+}
+
+impl RawClient<'_> {
+    /// Send `method` to `path`, with `body` as JSON if any.
+    ///
+    /// The response is returned whatever its status. Only idempotent methods
+    /// (`GET`, `PUT`, `DELETE`, ...) are retried unless the retry policy
+    /// allows retrying any request.
+    pub async fn send(
+        &self,
+        method: Method,
+        path: impl Into<ApiPath>,
+        body: Option<&Value>,
+    ) -> Result<reqwest::Response, TrustonError> {
+        let path = path.into();
+        let url = format!("{}{}", self.client.base_url, path);
+        let span = tracing::info_span!("raw", method = %method, path = %path, status = Empty);
+        async {
+            let resp = self
+                .client
+                .send_request(method.is_idempotent(), || {
+                    let request = self.client.http.request(method.clone(), &url);
+                    match body {
+                        Some(body) => request.json(body),
+                        None => request,
+                    }
+                })
+                .await?;
+            tracing::info!("raw: {} {} -> {}", method, url, resp.status());
+            Ok(resp)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Send `method` to `path`, with `body` as JSON if any, and deserialize
+    /// the JSON response.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ServerError`] if the server answers with an
+    /// error status, and [`TrustonError::ParseError`] if the response isn't
+    /// a `T`.
+    pub async fn json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: impl Into<ApiPath>,
+        body: Option<&Value>,
+    ) -> Result<T, TrustonError> {
+        let resp = self.send(method, path, body).await?;
+        if !resp.status().is_success() {
+            return Err(TritonRestClient::server_error(resp).await);
+        }
+        self.client.parse_json(resp).await
+    }
+
+    /// `GET` `path` and deserialize the JSON response, see [`RawClient::json`].
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        path: impl Into<ApiPath>,
+    ) -> Result<T, TrustonError> {
+        self.json(Method::GET, path, None).await
+    }
+
+    /// `POST` `body` to `path` and deserialize the JSON response, see
+    /// [`RawClient::json`].
+    pub async fn post<T: DeserializeOwned>(
+        &self,
+        path: impl Into<ApiPath>,
+        body: &Value,
+    ) -> Result<T, TrustonError> {
+        self.json(Method::POST, path, Some(body)).await
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::test_server::serve;
+    use serde_json::json;
+
+    #[test]
+    fn test_api_path() {
+        assert_eq!(ApiPath::model("a/b").to_string(), "/v2/models/a%2Fb");
+        assert_eq!(ApiPath::from("v2/logging").to_string(), "/v2/logging");
+        let path = ApiPath::from("/v2/trace/setting?x=1").query("model", "m 1");
+        assert_eq!(path.to_string(), "/v2/trace/setting?x=1&model=m%201");
+    }
+
+    #[tokio::test]
+    async fn test_raw_requests() {
+        let responses = vec![(200, r#"{"ok":true}"#), (400, r#"{"error":"no such region"}"#)];
+        let (base_url, server) = serve(responses).await;
+        let client = TritonRestClient::new(&base_url);
+
+        let path = ApiPath::v2().join("systemsharedmemory").join("region").join("r 0");
+        let body = json!({"key": "/r0"});
+        let value: Value = client.raw().post(path.clone().join("register"), &body).await.unwrap();
+        assert_eq!(value, json!({"ok": true}));
+        let error = client.raw().get::<Value>(path.join("status")).await.unwrap_err();
+        assert!(matches!(error, TrustonError::ServerError { status: 400, .. }), "{:?}", error);
+
+        let requests = server.await.unwrap();
+        let register = "POST /v2/systemsharedmemory/region/r%200/register HTTP/1.1";
+        assert_eq!(requests[0].line(), register);
+        assert_eq!(requests[0].text(), r#"{"key":"/r0"}"#);
+        assert!(requests[1].line().starts_with("GET /v2/systemsharedmemory/region/r%200/status"));
+    }
+}
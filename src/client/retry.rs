@@ -0,0 +1,224 @@
+//! Retry policy with exponential backoff.
+//!
+//! A [`RetryPolicy`] is configured once on the client (see
+//! [`TritonRestClientBuilder::retry_policy`](crate::client::http::TritonRestClientBuilder::retry_policy))
+//! and applied to health checks, metadata lookups and inference requests.
+//! Typical use is riding out the `503`s Triton returns while a model reloads.
+
+use std::time::Duration;
+
+use rand::Rng;
+
+/// When and how often failed requests are retried.
+///
+/// A request is retried when the connection fails, the request times out, or
+/// the server answers with one of [`RetryPolicy::retry_on_status`]. Requests
+/// that are not idempotent (e.g. model load/unload, or inferences marked with
+/// [`InferOptions::with_non_idempotent`](crate::client::options::InferOptions::with_non_idempotent))
+/// are only retried when [`RetryPolicy::retry_non_idempotent`] is set.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use truston::client::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(4)
+///     .with_backoff(Duration::from_millis(50), Duration::from_secs(2))
+///     .with_retry_on_status(vec![503]);
+/// assert_eq!(policy.max_attempts, 4);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Factor applied to the delay after every retry. Values below `1.0`
+    /// (or NaN) are treated as `1.0`.
+    pub multiplier: f64,
+    /// Fraction (`0.0..=1.0`) of the delay that is randomized, so that many
+    /// clients failing at once don't retry in lockstep. Values outside the
+    /// range are clamped (NaN disables jitter).
+    pub jitter: f64,
+    /// HTTP statuses considered transient.
+    pub retry_on_status: Vec<u16>,
+    /// Also retry requests that are not idempotent.
+    pub retry_non_idempotent: bool,
+}
+
+/// How an attempt failed, which decides where it may be sent again.
+///
+/// Only connection-level failures are failed over to another endpoint (see
+/// [`TritonRestClientBuilder::failover_endpoint`](crate::client::http::TritonRestClientBuilder::failover_endpoint)):
+/// the server never saw the request, so sending it elsewhere cannot run a
+/// side-effectful inference twice, even for stateful or decoupled models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// No connection could be established (refused, unreachable, DNS, TLS
+    /// handshake): the request was never sent.
+    Connection,
+    /// The request may have reached the server: timeout, connection dropped
+    /// mid-request, or an error status. Left to the [`RetryPolicy`].
+    Application,
+}
+
+impl FailureKind {
+    pub fn of(error: &reqwest::Error) -> Self {
+        if error.is_connect() && !error.is_timeout() {
+            FailureKind::Connection
+        } else {
+            FailureKind::Application
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    /// No retries.
+    fn default() -> Self {
+        RetryPolicy::new(1)
+    }
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_attempts` attempts in total, with 100ms initial backoff
+    /// doubling up to 5s, 20% jitter, on `502`, `503` and `504`.
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_on_status: vec![502, 503, 504],
+            retry_non_idempotent: false,
+        }
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Factor applied to the delay after every retry, at least `1.0`.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = clamp_multiplier(multiplier);
+        self
+    }
+
+    /// Fraction of the delay that is randomized, clamped to `0.0..=1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = clamp_jitter(jitter);
+        self
+    }
+
+    pub fn with_retry_on_status(mut self, statuses: Vec<u16>) -> Self {
+        self.retry_on_status = statuses;
+        self
+    }
+
+    pub fn with_retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Whether a request may be attempted again after `attempt` attempts.
+    pub(crate) fn allows_retry(&self, attempt: u32, idempotent: bool) -> bool {
+        attempt < self.max_attempts && (idempotent || self.retry_non_idempotent)
+    }
+
+    pub(crate) fn is_retryable_status(&self, status: u16) -> bool {
+        self.retry_on_status.contains(&status)
+    }
+
+    pub(crate) fn is_retryable_error(&self, error: &reqwest::Error) -> bool {
+        error.is_connect() || error.is_timeout()
+    }
+
+    /// Delay to wait after the `attempt`-th failed attempt (1-based), without jitter.
+    pub fn base_backoff(&self, attempt: u32) -> Duration {
+        let factor = clamp_multiplier(self.multiplier).powi(attempt.saturating_sub(1) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * factor;
+        Duration::from_secs_f64(secs.min(self.max_backoff.as_secs_f64()))
+    }
+
+    /// Delay to wait after the `attempt`-th failed attempt, with jitter applied.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.base_backoff(attempt);
+        let jitter = clamp_jitter(self.jitter);
+        if jitter <= 0.0 {
+            return base;
+        }
+        let spread = rand::rng().random_range(0.0..=jitter);
+        base.mul_f64(1.0 - spread)
+    }
+}
+
+// The fields are public, so the values are also sanitized where they are used:
+// `Duration::from_secs_f64` and `Duration::mul_f64` panic on negative or NaN input.
+fn clamp_multiplier(multiplier: f64) -> f64 {
+    if multiplier >= 1.0 { multiplier } else { 1.0 }
+}
+
+fn clamp_jitter(jitter: f64) -> f64 {
+    if jitter.is_nan() { 0.0 } else { jitter.clamp(0.0, 1.0) }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_does_not_retry() {
+        let policy = RetryPolicy::default();
+        assert!(!policy.allows_retry(1, true));
+    }
+
+    #[test]
+    fn test_idempotency_guard() {
+        let policy = RetryPolicy::new(3);
+        assert!(policy.allows_retry(1, true));
+        assert!(policy.allows_retry(2, true));
+        assert!(!policy.allows_retry(3, true));
+        assert!(!policy.allows_retry(1, false));
+        assert!(policy.with_retry_non_idempotent(true).allows_retry(1, false));
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let policy = RetryPolicy::new(10)
+            .with_backoff(Duration::from_millis(100), Duration::from_millis(350));
+        assert_eq!(policy.base_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.base_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.base_backoff(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_jitter_stays_below_base() {
+        let policy = RetryPolicy::new(3).with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay <= Duration::from_millis(200));
+            assert!(delay >= Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_out_of_range_fields_do_not_panic() {
+        let mut policy = RetryPolicy::new(5);
+        policy.jitter = 3.0;
+        policy.multiplier = -2.0;
+        for attempt in 1..=5 {
+            assert!(policy.backoff(attempt) <= Duration::from_millis(100));
+        }
+        policy.jitter = f64::NAN;
+        policy.multiplier = f64::NAN;
+        assert_eq!(policy.backoff(3), Duration::from_millis(100));
+
+        let policy = RetryPolicy::new(3).with_multiplier(0.5).with_jitter(-1.0);
+        assert_eq!((policy.multiplier, policy.jitter), (1.0, 0.0));
+    }
+}
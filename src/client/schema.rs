@@ -0,0 +1,282 @@
+//! Client-side description of a model's tensors.
+//!
+//! A [`ModelSchema`] lists the inputs and outputs of a model. It is usually
+//! built from [`ModelMetadata`] but can also be written by hand, which makes
+//! it usable without a running server (e.g. to generate random inputs for
+//! warmup, benchmarks or fuzzing of post-processing code).
+
+use std::collections::HashMap;
+
+use num_traits::{Bounded, NumCast};
+use rand::Rng;
+
+use crate::client::dtype::TritonDtype;
+use crate::client::io::{DataType, InferInput, element_count};
+use crate::client::metadata::{ModelMetadata, TensorMetadata};
+use crate::utils::errors::TrustonError;
+
+/// Inputs and outputs of a model.
+///
+/// # Example
+/// ```
+/// use rand::SeedableRng;
+/// use truston::client::dtype::TritonDtype;
+/// use truston::client::metadata::TensorMetadata;
+/// use truston::client::schema::ModelSchema;
+///
+/// let schema = ModelSchema::new(
+///     vec![TensorMetadata {
+///         name: "input_ids".into(),
+///         datatype: TritonDtype::Int64,
+///         shape: vec![-1, 128],
+///     }],
+///     vec![],
+/// )
+/// .with_range("input_ids", 0.0, 30522.0);
+///
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+/// let inputs = schema.random_inputs(&mut rng, 4)?;
+/// assert_eq!(inputs[0].input_shape, vec![4, 128]);
+/// # Ok::<(), truston::TrustonError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ModelSchema {
+    pub inputs: Vec<TensorMetadata>,
+    pub outputs: Vec<TensorMetadata>,
+    ranges: HashMap<String, (f64, f64)>,
+    dynamic_size: usize,
+}
+
+impl From<ModelMetadata> for ModelSchema {
+    fn from(metadata: ModelMetadata) -> Self {
+        ModelSchema::new(metadata.inputs, metadata.outputs)
+    }
+}
+
+impl ModelSchema {
+    pub fn new(inputs: Vec<TensorMetadata>, outputs: Vec<TensorMetadata>) -> Self {
+        ModelSchema {
+            inputs,
+            outputs,
+            ranges: HashMap::new(),
+            dynamic_size: 1,
+        }
+    }
+
+    /// Override the value range (`min..max`) used when generating data for `input`.
+    ///
+    /// For integer inputs the range is inclusive of `min` and exclusive of `max`;
+    /// for `BYTES` inputs it bounds the length of the generated strings. The
+    /// range is clamped to the values the input's datatype can represent.
+    pub fn with_range(mut self, input: &str, min: f64, max: f64) -> Self {
+        self.ranges.insert(input.to_string(), (min, max));
+        self
+    }
+
+    /// Size used for dynamic (`-1`) dimensions other than the leading batch dimension.
+    ///
+    /// Defaults to `1`.
+    pub fn with_dynamic_size(mut self, size: usize) -> Self {
+        self.dynamic_size = size;
+        self
+    }
+
+    /// Generate one random input tensor per schema input.
+    ///
+    /// A leading dynamic dimension is treated as the batch dimension and set to
+    /// `batch`; other dynamic dimensions use [`ModelSchema::with_dynamic_size`].
+    ///
+    /// Values are drawn from a range that fits the datatype (`[0, 1)` for floats,
+    /// `[0, 100)` for integers, the full range for `UINT8`) unless overridden
+    /// with [`ModelSchema::with_range`].
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if an input uses a datatype
+    /// that cannot be represented by [`DataType`].
+    pub fn random_inputs<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        batch: usize,
+    ) -> Result<Vec<InferInput>, TrustonError> {
+        self.inputs
+            .iter()
+            .map(|tensor| {
+                let shape = self.concrete_shape(&tensor.shape, batch);
+                let count = element_count(&tensor.name, &shape)?;
+                let range = self.ranges.get(&tensor.name).copied();
+                let data = random_data(rng, &tensor.datatype, count, range)?;
+                Ok(InferInput::new(tensor.name.clone(), shape, data))
+            })
+            .collect()
+    }
+
+    fn concrete_shape(&self, shape: &[i64], batch: usize) -> Vec<usize> {
+        shape
+            .iter()
+            .enumerate()
+            .map(|(i, &dim)| match dim {
+                d if d >= 0 => d as usize,
+                _ if i == 0 => batch,
+                _ => self.dynamic_size,
+            })
+            .collect()
+    }
+}
+
+fn random_data<R: Rng + ?Sized>(
+    rng: &mut R,
+    datatype: &TritonDtype,
+    count: usize,
+    range: Option<(f64, f64)>,
+) -> Result<DataType, TrustonError> {
+    let int_range = range.unwrap_or((0.0, 100.0));
+    let data = match datatype {
+        TritonDtype::Bool => DataType::Bool((0..count).map(|_| rng.random()).collect()),
+        TritonDtype::Uint8 => DataType::U8(random_ints(rng, count, range.unwrap_or((0.0, 256.0)))),
+        TritonDtype::Uint16 => DataType::U16(random_ints(rng, count, int_range)),
+        TritonDtype::Uint32 => DataType::U32(random_ints(rng, count, int_range)),
+        TritonDtype::Uint64 => DataType::U64(random_ints(rng, count, int_range)),
+        TritonDtype::Int8 => DataType::I8(random_ints(rng, count, int_range)),
+        TritonDtype::Int16 => DataType::I16(random_ints(rng, count, int_range)),
+        TritonDtype::Int32 => DataType::I32(random_ints(rng, count, int_range)),
+        TritonDtype::Int64 => DataType::I64(random_ints(rng, count, int_range)),
+        TritonDtype::Fp32 => DataType::F32(random_floats(rng, count, range.unwrap_or((0.0, 1.0)))),
+        TritonDtype::Fp64 => DataType::F64(random_floats(rng, count, range.unwrap_or((0.0, 1.0)))),
+        TritonDtype::Bf16 => DataType::Bf16(
+            random_floats::<_, f32>(rng, count, range.unwrap_or((0.0, 1.0)))
+                .into_iter()
+                .map(|v| (v.to_bits() >> 16) as u16)
+                .collect(),
+        ),
+        #[cfg(feature = "half")]
+        TritonDtype::Fp16 => DataType::F16(random_floats(rng, count, range.unwrap_or((0.0, 1.0)))),
+        TritonDtype::Bytes => {
+            let (min, max) = range.unwrap_or((1.0, 16.0));
+            DataType::String((0..count).map(|_| random_word(rng, min, max)).collect())
+        }
+        TritonDtype::Other(name) => {
+            return Err(TrustonError::InferenceError(format!("cannot generate {} data", name)));
+        }
+        #[cfg(not(feature = "half"))]
+        TritonDtype::Fp16 => {
+            return Err(TrustonError::InferenceError(
+                "cannot generate FP16 data without the `half` feature".to_string(),
+            ));
+        }
+    };
+    Ok(data)
+}
+
+fn random_ints<R, T>(rng: &mut R, count: usize, (min, max): (f64, f64)) -> Vec<T>
+where
+    R: Rng + ?Sized,
+    T: NumCast + Bounded,
+{
+    let lower = T::min_value().to_i128().unwrap_or(i128::MIN);
+    let upper = T::max_value().to_i128().unwrap_or(i128::MAX - 1);
+    let min = (min as i128).clamp(lower, upper);
+    let max = (max as i128).clamp(min + 1, upper + 1);
+    (0..count)
+        .map(|_| T::from(rng.random_range(min..max)).expect("value is clamped to the bounds of T"))
+        .collect()
+}
+
+fn random_floats<R, T>(rng: &mut R, count: usize, (min, max): (f64, f64)) -> Vec<T>
+where
+    R: Rng + ?Sized,
+    T: NumCast + Bounded,
+{
+    let lower = T::min_value().to_f64().unwrap_or(f64::MIN);
+    let upper = T::max_value().to_f64().unwrap_or(f64::MAX);
+    let (min, max) = (min.clamp(lower, upper), max.clamp(lower, upper));
+    (0..count)
+        .map(|_| {
+            // Interpolated rather than `min + r * (max - min)`, which overflows for wide ranges.
+            let r = rng.random::<f64>();
+            let value = min * (1.0 - r) + max * r;
+            T::from(value).expect("value is clamped to the bounds of T")
+        })
+        .collect()
+}
+
+fn random_word<R: Rng + ?Sized>(rng: &mut R, min: f64, max: f64) -> String {
+    let (min, max) = (min.max(0.0) as usize, (max as usize).max(min as usize + 1));
+    let len = rng.random_range(min..max);
+    (0..len).map(|_| rng.random_range(b'a'..=b'z') as char).collect()
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn tensor(name: &str, datatype: &str, shape: Vec<i64>) -> TensorMetadata {
+        TensorMetadata { name: name.into(), datatype: datatype.parse().unwrap(), shape }
+    }
+
+    #[test]
+    fn test_random_inputs_shapes() {
+        let schema = ModelSchema::new(
+            vec![tensor("ids", "INT64", vec![-1, 16]), tensor("feat", "FP32", vec![-1, -1])],
+            vec![],
+        )
+        .with_dynamic_size(3);
+        let mut rng = StdRng::seed_from_u64(1);
+        let inputs = schema.random_inputs(&mut rng, 2).unwrap();
+
+        assert_eq!(inputs[0].input_shape, vec![2, 16]);
+        assert_eq!(inputs[0].input_data.as_i64_vec().unwrap().len(), 32);
+        assert_eq!(inputs[1].input_shape, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_random_inputs_respect_ranges() {
+        let schema = ModelSchema::new(
+            vec![tensor("ids", "INT32", vec![64]), tensor("x", "FP64", vec![64])],
+            vec![],
+        )
+        .with_range("ids", 5.0, 8.0)
+        .with_range("x", -2.0, -1.0);
+        let mut rng = StdRng::seed_from_u64(2);
+        let inputs = schema.random_inputs(&mut rng, 1).unwrap();
+
+        assert!(inputs[0].input_data.as_i32_vec().unwrap().iter().all(|v| (5..8).contains(v)));
+        assert!(inputs[1].input_data.as_f64_vec().unwrap().iter().all(|v| (-2.0..-1.0).contains(v)));
+    }
+
+    #[test]
+    fn test_random_inputs_clamp_ranges_to_datatype() {
+        let schema = ModelSchema::new(
+            vec![
+                tensor("u", "UINT8", vec![64]),
+                tensor("i", "INT8", vec![64]),
+                tensor("f", "FP32", vec![64]),
+            ],
+            vec![],
+        )
+        .with_range("u", -50.0, 1000.0)
+        .with_range("i", 500.0, 600.0)
+        .with_range("f", f64::MIN, f64::MAX);
+        let mut rng = StdRng::seed_from_u64(4);
+        let inputs = schema.random_inputs(&mut rng, 1).unwrap();
+
+        assert_eq!(inputs[0].input_data.as_u8_vec().unwrap().len(), 64);
+        assert_eq!(inputs[1].input_data.as_i8_vec().unwrap(), vec![127; 64]);
+        let floats = inputs[2].input_data.as_f32_vec().unwrap();
+        assert_eq!(floats.len(), 64);
+        assert!(floats.iter().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    #[cfg(not(feature = "half"))]
+    fn test_random_inputs_unsupported_datatype() {
+        let schema = ModelSchema::new(vec![tensor("x", "FP16", vec![1])], vec![]);
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(matches!(
+            schema.random_inputs(&mut rng, 1),
+            Err(TrustonError::InferenceError(_))
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+//! Output selection by exact name, prefix or regular expression.
+//!
+//! Multi-head models can expose dozens of outputs (`head_0`, `head_1`, ...).
+//! An [`OutputSelector`] describes a subset of them; selectors are resolved
+//! against the model metadata into concrete output names.
+
+use regex::Regex;
+
+use crate::client::http::TritonRestClient;
+use crate::client::metadata::ModelMetadata;
+use crate::utils::errors::TrustonError;
+
+/// Matches output names of a model.
+///
+/// Several selectors combine as a union, see [`OutputSelector::resolve`].
+///
+/// # Example
+/// ```
+/// use truston::client::selector::OutputSelector;
+///
+/// let selectors = vec![
+///     OutputSelector::name("logits"),
+///     OutputSelector::prefix("head_"),
+///     OutputSelector::regex(r"^aux_\d+$")?,
+/// ];
+/// assert!(selectors[1].matches("head_3"));
+/// assert!(selectors[2].matches("aux_12"));
+/// # Ok::<(), truston::TrustonError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub enum OutputSelector {
+    /// Exact output name.
+    Name(String),
+    /// Every output whose name starts with the prefix.
+    Prefix(String),
+    /// Every output whose name matches the regular expression.
+    Regex(Regex),
+}
+
+impl OutputSelector {
+    pub fn name(name: impl Into<String>) -> Self {
+        OutputSelector::Name(name.into())
+    }
+
+    pub fn prefix(prefix: impl Into<String>) -> Self {
+        OutputSelector::Prefix(prefix.into())
+    }
+
+    /// Build a regex selector.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::ConfigError`] if the pattern is not a valid regular expression.
+    pub fn regex(pattern: &str) -> Result<Self, TrustonError> {
+        Regex::new(pattern)
+            .map(OutputSelector::Regex)
+            .map_err(|e| TrustonError::ConfigError(format!("invalid output regex: {}", e)))
+    }
+
+    pub fn matches(&self, output_name: &str) -> bool {
+        match self {
+            OutputSelector::Name(name) => name == output_name,
+            OutputSelector::Prefix(prefix) => output_name.starts_with(prefix.as_str()),
+            OutputSelector::Regex(re) => re.is_match(output_name),
+        }
+    }
+
+    /// Resolve a set of selectors into the output names of `metadata` matched by any of them.
+    ///
+    /// Names are returned in the order the model declares its outputs, without duplicates.
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if a selector matches no output,
+    /// which usually indicates a typo rather than an intentionally empty selection.
+    pub fn resolve(
+        selectors: &[OutputSelector],
+        metadata: &ModelMetadata,
+    ) -> Result<Vec<String>, TrustonError> {
+        if let Some(unmatched) = selectors
+            .iter()
+            .find(|sel| !metadata.outputs.iter().any(|out| sel.matches(&out.name)))
+        {
+            return Err(TrustonError::InferenceError(format!(
+                "selector {:?} matches no output of model {}",
+                unmatched, metadata.name
+            )));
+        }
+
+        Ok(metadata
+            .outputs
+            .iter()
+            .filter(|out| selectors.iter().any(|sel| sel.matches(&out.name)))
+            .map(|out| out.name.clone())
+            .collect())
+    }
+}
+
+impl TritonRestClient {
+    /// Fetch the metadata of `model_name` and resolve `selectors` into output names.
+    ///
+    /// See [`OutputSelector::resolve`].
+    pub async fn resolve_outputs(
+        &self,
+        model_name: &str,
+        selectors: &[OutputSelector],
+    ) -> Result<Vec<String>, TrustonError> {
+        let metadata = self.model_metadata(model_name).await?;
+        OutputSelector::resolve(selectors, &metadata)
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use crate::client::metadata::TensorMetadata;
+
+    fn metadata(outputs: &[&str]) -> ModelMetadata {
+        ModelMetadata {
+            name: "multi_head".into(),
+            versions: vec![],
+            platform: String::new(),
+            inputs: vec![],
+            outputs: outputs
+                .iter()
+                .map(|name| TensorMetadata {
+                    name: name.to_string(),
+                    datatype: TritonDtype::Fp32,
+                    shape: vec![-1, 4],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_union_in_model_order() {
+        let meta = metadata(&["head_0", "logits", "head_1", "aux_1"]);
+        let selectors = vec![
+            OutputSelector::name("logits"),
+            OutputSelector::prefix("head_"),
+            OutputSelector::regex("^head_0$").unwrap(),
+        ];
+        let names = OutputSelector::resolve(&selectors, &meta).unwrap();
+        assert_eq!(names, vec!["head_0", "logits", "head_1"]);
+    }
+
+    #[test]
+    fn test_resolve_unmatched_selector_errors() {
+        let meta = metadata(&["logits"]);
+        let result = OutputSelector::resolve(&[OutputSelector::prefix("head_")], &meta);
+        assert!(matches!(result, Err(TrustonError::InferenceError(_))));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        assert!(matches!(OutputSelector::regex("("), Err(TrustonError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,154 @@
+//! Sequence (stateful) inference.
+//!
+//! Stateful models configured with sequence batching (streaming ASR, RNNs,
+//! ...) need every request of a stream to carry the same `sequence_id`, with
+//! `sequence_start` on the first request and `sequence_end` on the last one.
+//! [`SequenceSession`] keeps track of these across calls.
+
+use rand::Rng;
+
+use crate::client::http::TritonRestClient;
+use crate::client::io::{InferInput, InferResults};
+use crate::client::options::{InferOptions, SequenceId};
+use crate::utils::errors::TrustonError;
+
+/// One sequence of requests against a stateful model.
+///
+/// The first successful [`infer`](SequenceSession::infer) starts the
+/// sequence, [`finish`](SequenceSession::finish) ends it. Sequence requests
+/// change server-side state, so they are never retried unless the retry
+/// policy allows retrying non-idempotent requests.
+///
+/// # Example
+/// ```no_run
+/// # use truston::client::http::TritonRestClient;
+/// # use truston::client::io::{DataType, InferInput};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let client = TritonRestClient::new("http://localhost:8000");
+/// let mut session = client.sequence("streaming_asr");
+///
+/// for chunk in [vec![0.1f32; 1600], vec![0.2; 1600]] {
+///     let audio = InferInput::new("audio".into(), vec![1, 1600], DataType::F32(chunk));
+///     let partial = session.infer(vec![audio]).await?;
+/// }
+/// let last = InferInput::new("audio".into(), vec![1, 1600], DataType::F32(vec![0.0; 1600]));
+/// let transcript = session.finish(vec![last]).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SequenceSession<'a> {
+    client: &'a TritonRestClient,
+    model_name: String,
+    options: InferOptions,
+    started: bool,
+    ended: bool,
+}
+
+impl<'a> SequenceSession<'a> {
+    /// Start tracking the sequence `sequence_id` of `model_name`.
+    ///
+    /// `options` are applied to every request of the sequence; their
+    /// sequence fields are overwritten.
+    pub fn new(
+        client: &'a TritonRestClient,
+        model_name: impl Into<String>,
+        sequence_id: impl Into<SequenceId>,
+        options: InferOptions,
+    ) -> Self {
+        let mut options = options.with_non_idempotent();
+        options.sequence_id = Some(sequence_id.into());
+        SequenceSession {
+            client,
+            model_name: model_name.into(),
+            options,
+            started: false,
+            ended: false,
+        }
+    }
+
+    pub fn sequence_id(&self) -> &SequenceId {
+        self.options
+            .sequence_id
+            .as_ref()
+            .expect("sequence sessions always have an ID")
+    }
+
+    /// Whether a request of the sequence has already been accepted by the server.
+    pub fn is_started(&self) -> bool {
+        self.started
+    }
+
+    /// Whether the sequence has been ended with [`SequenceSession::finish`].
+    pub fn is_ended(&self) -> bool {
+        self.ended
+    }
+
+    /// Send the next request of the sequence.
+    pub async fn infer(&mut self, inputs: Vec<InferInput>) -> Result<InferResults, TrustonError> {
+        self.send(inputs, false).await
+    }
+
+    /// Send the last request of the sequence, releasing its state on the server.
+    pub async fn finish(&mut self, inputs: Vec<InferInput>) -> Result<InferResults, TrustonError> {
+        self.send(inputs, true).await
+    }
+
+    async fn send(
+        &mut self,
+        inputs: Vec<InferInput>,
+        end: bool,
+    ) -> Result<InferResults, TrustonError> {
+        if self.ended {
+            return Err(TrustonError::InferenceError(format!(
+                "sequence {:?} has already ended",
+                self.sequence_id()
+            )));
+        }
+
+        self.options.sequence_start = !self.started;
+        self.options.sequence_end = end;
+        let results = self
+            .client
+            .infer_with_options(inputs, &self.model_name, &self.options)
+            .await?;
+
+        self.started = true;
+        self.ended = end;
+        Ok(results)
+    }
+}
+
+impl TritonRestClient {
+    /// Start a sequence of `model_name` with a random sequence ID.
+    pub fn sequence(&self, model_name: &str) -> SequenceSession<'_> {
+        // 0 means "no sequence" to Triton
+        let id = rand::rng().random_range(1..=u64::MAX);
+        SequenceSession::new(self, model_name, id, InferOptions::new())
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_request_does_not_start_sequence() {
+        let client = TritonRestClient::new("http://localhost:12345");
+        let mut session = SequenceSession::new(&client, "asr", 7, InferOptions::new());
+
+        assert!(session.infer(vec![]).await.is_err());
+        assert!(!session.is_started());
+        assert!(session.options.sequence_start);
+        assert!(session.options.non_idempotent);
+        assert_eq!(session.sequence_id(), &SequenceId::Number(7));
+    }
+
+    #[test]
+    fn test_random_ids_are_non_zero() {
+        let client = TritonRestClient::new("http://localhost:12345");
+        let session = client.sequence("asr");
+        assert_ne!(session.sequence_id(), &SequenceId::Number(0));
+    }
+}
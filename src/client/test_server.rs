@@ -0,0 +1,113 @@
+//! Canned HTTP server for the unit tests of the client.
+//!
+//! Every connection carries one request: the server reads it whole (the head
+//! and a `content-length` body, however many reads that takes), writes the
+//! next canned response and closes the connection.
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A request received by the server.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub(crate) head: String,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Request {
+    /// The request line, e.g. `GET /v2/health/live HTTP/1.1`.
+    pub(crate) fn line(&self) -> &str {
+        self.head.lines().next().unwrap_or_default()
+    }
+
+    /// Value of the header `name`, matched case-insensitively.
+    pub(crate) fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// The body as text.
+    pub(crate) fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+}
+
+/// A `status` response with the JSON `body`.
+pub(crate) fn response(status: u16, body: &str) -> Vec<u8> {
+    format!(
+        "HTTP/1.1 {} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\
+         connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+    .into_bytes()
+}
+
+/// Read one request from `socket`, up to the end of its body.
+///
+/// Stops early if the client closes the connection.
+pub(crate) async fn read_request<S>(socket: &mut S) -> Request
+where
+    S: AsyncRead + Unpin,
+{
+    let mut received = Vec::new();
+    let mut buf = [0u8; 4096];
+    let mut end = None;
+    loop {
+        if let Some(body_start) = end {
+            // the head keeps the line break of its last header
+            let head = String::from_utf8_lossy(&received[..body_start - 2]).to_string();
+            let request = Request { head, body: Vec::new() };
+            let length: usize =
+                request.header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+            if received.len() >= body_start + length {
+                let body = received[body_start..body_start + length].to_vec();
+                return Request { body, ..request };
+            }
+        }
+        let n = socket.read(&mut buf).await.unwrap();
+        if n == 0 {
+            let head = String::from_utf8_lossy(&received).to_string();
+            return Request { head, body: Vec::new() };
+        }
+        received.extend_from_slice(&buf[..n]);
+        if end.is_none() {
+            end = received.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4);
+        }
+    }
+}
+
+/// Answer one request on `socket` with `response`, returning the request.
+pub(crate) async fn answer<S>(mut socket: S, response: &[u8]) -> Request
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let request = read_request(&mut socket).await;
+    socket.write_all(response).await.unwrap();
+    request
+}
+
+/// Answer one connection per raw `responses`, returning the base URL of the
+/// server and a handle to the requests received.
+pub(crate) async fn serve_raw(responses: Vec<Vec<u8>>) -> (String, JoinHandle<Vec<Request>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for response in responses {
+            let (socket, _) = listener.accept().await.unwrap();
+            requests.push(answer(socket, &response).await);
+        }
+        requests
+    });
+    (base_url, handle)
+}
+
+/// Like [`serve_raw`], with a JSON response per `(status, body)`.
+pub(crate) async fn serve(responses: Vec<(u16, &str)>) -> (String, JoinHandle<Vec<Request>>) {
+    serve_raw(responses.into_iter().map(|(status, body)| response(status, body)).collect()).await
+}
@@ -0,0 +1,145 @@
+//! Tracing spans and W3C trace context propagation.
+//!
+//! Inferences, health probes and metadata lookups run inside `tracing`
+//! spans (`infer`, `health`, `model_metadata`, `model_config`) carrying the
+//! model, the request id, the size of the request body and the HTTP status,
+//! so they show up in whatever subscriber the application installed.
+//!
+//! Triton started with `--trace-config mode=opentelemetry` continues the
+//! trace of the `traceparent` header of an inference request. Inferences
+//! carry one when their options hold a [`TraceContext`] (see
+//! [`InferOptions::with_trace_context`](crate::client::options::InferOptions::with_trace_context)),
+//! or, with [`TritonRestClientBuilder::trace_propagation`](crate::client::http::TritonRestClientBuilder::trace_propagation),
+//! one starting a new trace. Its trace id is recorded on the `infer` span,
+//! so client logs and server traces can be joined.
+//!
+//! # Example
+//! ```
+//! use truston::client::options::InferOptions;
+//! use truston::client::trace::TraceContext;
+//!
+//! // e.g. from the `traceparent` header of the incoming request
+//! let incoming = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+//! let parent = TraceContext::parse(incoming).unwrap();
+//! let options = InferOptions::new().with_trace_context(parent);
+//! ```
+
+use std::fmt;
+
+/// Name of the header carrying the trace context.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Position of a request in a distributed trace, as carried by a W3C
+/// `traceparent` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    pub trace_id: u128,
+    /// Id of the span the request is sent from.
+    pub span_id: u64,
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// A sampled context starting a new trace, with random ids.
+    pub fn new_root() -> Self {
+        TraceContext { trace_id: random_id(), span_id: random_id(), sampled: true }
+    }
+
+    /// A new span of the same trace, below this one.
+    pub fn child(&self) -> Self {
+        TraceContext { span_id: random_id(), ..*self }
+    }
+
+    /// Parse a `traceparent` header (`00-<trace id>-<span id>-<flags>`).
+    ///
+    /// Returns `None` if it is malformed or one of the ids is all zeros.
+    pub fn parse(header: &str) -> Option<Self> {
+        let mut parts = header.trim().split('-');
+        let (version, trace_id, span_id, flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        let hex = |part: &str, len: usize| {
+            part.len() == len && part.bytes().all(|b| b.is_ascii_hexdigit())
+        };
+        // later versions may append fields; version `ff` is forbidden
+        if !hex(version, 2) || version.eq_ignore_ascii_case("ff") || !hex(flags, 2) {
+            return None;
+        }
+        if version == "00" && parts.next().is_some() {
+            return None;
+        }
+        if !hex(trace_id, 32) || !hex(span_id, 16) {
+            return None;
+        }
+        let context = TraceContext {
+            trace_id: u128::from_str_radix(trace_id, 16).ok()?,
+            span_id: u64::from_str_radix(span_id, 16).ok()?,
+            sampled: u8::from_str_radix(flags, 16).ok()? & 1 == 1,
+        };
+        (context.trace_id != 0 && context.span_id != 0).then_some(context)
+    }
+
+    /// The trace id as 32 lowercase hex digits, as shown by tracing backends.
+    pub fn trace_id_hex(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+}
+
+/// The `traceparent` header value.
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "00-{:032x}-{:016x}-{:02x}", self.trace_id, self.span_id, self.sampled as u8)
+    }
+}
+
+/// A random id, never zero (which the spec reserves for "invalid").
+fn random_id<T: Default + PartialEq>() -> T
+where
+    rand::distr::StandardUniform: rand::distr::Distribution<T>,
+{
+    loop {
+        let id = rand::random::<T>();
+        if id != T::default() {
+            return id;
+        }
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let context = TraceContext::parse(header).unwrap();
+        assert_eq!(context.span_id, 0x00f067aa0ba902b7);
+        assert!(context.sampled);
+        assert_eq!(context.to_string(), header);
+        assert_eq!(context.trace_id_hex(), "4bf92f3577b34da6a3ce929d0e0e4736");
+
+        let child = context.child();
+        assert_eq!(child.trace_id, context.trace_id);
+        assert_ne!(child.span_id, context.span_id);
+
+        let root = TraceContext::new_root();
+        assert_eq!(TraceContext::parse(&root.to_string()), Some(root));
+    }
+
+    #[test]
+    fn test_invalid_traceparent() {
+        for header in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        ] {
+            assert_eq!(TraceContext::parse(header), None, "{}", header);
+        }
+        let future = "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra";
+        assert!(!TraceContext::parse(future).unwrap().sampled);
+    }
+}
@@ -0,0 +1,400 @@
+//! Streamed inference uploads.
+//!
+//! [`TritonRestClient::infer_with_options`] encodes the whole request body
+//! in memory, which takes as much memory again as the inputs for tensors of
+//! hundreds of megabytes. [`TritonRestClient::infer_streamed`] sends the
+//! inputs with the binary tensor extension instead, reading each
+//! [`StreamedInput`] from an iterator of chunks or an [`AsyncRead`] source
+//! while the body is written: only the JSON header and the chunk in flight
+//! are held in memory.
+//!
+//! A streamed body can only be sent once, so streamed inferences are never
+//! retried, hedged, compressed, sent to a fallback model or failed over to
+//! another endpoint. The rest of the
+//! options (request id, parameters, requested outputs, deadline,
+//! cancellation, queue priority, trace context) apply as usual.
+//!
+//! # Example
+//! ```no_run
+//! # use truston::client::http::TritonRestClient;
+//! # use truston::client::options::InferOptions;
+//! use truston::client::dtype::TritonDtype;
+//! use truston::client::upload::StreamedInput;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = TritonRestClient::new("http://localhost:8000");
+//! // a [1, 512, 512, 512] FP32 volume, stored as raw little-endian floats
+//! let file = tokio::fs::File::open("volume.f32").await?;
+//! let volume =
+//!     StreamedInput::from_reader("volume", vec![1, 512, 512, 512], TritonDtype::Fp32, file);
+//!
+//! let results = client.infer_streamed(vec![volume], "segmenter", &InferOptions::new()).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt};
+use tokio::io::AsyncRead;
+use tracing::Instrument;
+use tracing::field::Empty;
+
+use crate::client::binary;
+use crate::client::dtype::TritonDtype;
+use crate::client::http::{INFERENCE_HEADER_CONTENT_LENGTH, TritonRestClient};
+use crate::client::infer::InferStats;
+use crate::client::io::{DataType, InferInputPayload, InferRequest, InferResults, element_count};
+use crate::client::options::InferOptions;
+use crate::client::trace::{TRACEPARENT, TraceContext};
+use crate::utils::errors::TrustonError;
+
+/// An input tensor whose raw bytes (in the layout of
+/// [`binary::encode_tensor`]) are read while the request is sent.
+pub struct StreamedInput {
+    pub name: String,
+    pub shape: Vec<usize>,
+    pub datatype: TritonDtype,
+    /// Number of bytes the source yields; known from the shape for
+    /// fixed-size datatypes, required for `BYTES`.
+    pub byte_size: Option<usize>,
+    chunks: BoxStream<'static, io::Result<Bytes>>,
+}
+
+impl std::fmt::Debug for StreamedInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamedInput")
+            .field("name", &self.name)
+            .field("shape", &self.shape)
+            .field("datatype", &self.datatype)
+            .field("byte_size", &self.byte_size)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StreamedInput {
+    fn new(
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        datatype: TritonDtype,
+        chunks: BoxStream<'static, io::Result<Bytes>>,
+    ) -> Self {
+        let byte_size = datatype
+            .element_size()
+            .zip(shape.iter().try_fold(1usize, |n, &dim| n.checked_mul(dim)))
+            .and_then(|(size, elements)| size.checked_mul(elements));
+        StreamedInput { name: name.into(), shape, datatype, byte_size, chunks }
+    }
+
+    /// An input whose bytes are the concatenation of `chunks`, pulled one at
+    /// a time as the body is sent.
+    pub fn from_chunks<I, B>(
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        datatype: TritonDtype,
+        chunks: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = B>,
+        I::IntoIter: Send + 'static,
+        B: Into<Bytes>,
+    {
+        let chunks = stream::iter(chunks.into_iter().map(|chunk| Ok(chunk.into())));
+        StreamedInput::new(name, shape, datatype, chunks.boxed())
+    }
+
+    /// An input whose values are the concatenation of `chunks` (e.g. rows of
+    /// the tensor), each encoded as it is sent.
+    ///
+    /// The chunks should hold values of `datatype`; one that can't be binary
+    /// encoded fails the request.
+    pub fn from_tensor_chunks<I>(
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        datatype: TritonDtype,
+        chunks: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = DataType>,
+        I::IntoIter: Send + 'static,
+    {
+        let chunks = stream::iter(chunks.into_iter().map(|chunk| {
+            binary::encode_tensor(&chunk).map(Bytes::from).map_err(io::Error::other)
+        }));
+        StreamedInput::new(name, shape, datatype, chunks.boxed())
+    }
+
+    /// An input read from `reader` (e.g. a file) as the body is sent.
+    pub fn from_reader<R>(
+        name: impl Into<String>,
+        shape: Vec<usize>,
+        datatype: TritonDtype,
+        reader: R,
+    ) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        let chunks = tokio_util::io::ReaderStream::new(reader);
+        StreamedInput::new(name, shape, datatype, chunks.boxed())
+    }
+
+    /// Set the number of bytes the source yields, needed for `BYTES` inputs.
+    pub fn with_byte_size(mut self, byte_size: usize) -> Self {
+        self.byte_size = Some(byte_size);
+        self
+    }
+
+    /// The chunks of the input, failing if they don't add up to `byte_size`.
+    fn into_checked_stream(self, byte_size: usize) -> BoxStream<'static, io::Result<Bytes>> {
+        let name = self.name;
+        let mismatch = move |sent: usize| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("input {} yielded {} bytes, expected {}", name, sent, byte_size),
+            )
+        };
+        stream::unfold(Some((self.chunks, 0usize)), move |state| {
+            let mismatch = mismatch.clone();
+            async move {
+                let (mut chunks, sent) = state?;
+                match chunks.next().await {
+                    Some(Ok(chunk)) if sent + chunk.len() > byte_size => {
+                        Some((Err(mismatch(sent + chunk.len())), None))
+                    }
+                    Some(Ok(chunk)) => {
+                        let sent = sent + chunk.len();
+                        Some((Ok(chunk), Some((chunks, sent))))
+                    }
+                    Some(Err(e)) => Some((Err(e), None)),
+                    None if sent < byte_size => Some((Err(mismatch(sent)), None)),
+                    None => None,
+                }
+            }
+        })
+        .boxed()
+    }
+}
+
+impl TritonRestClient {
+    /// Run an inference whose inputs are streamed to the server instead of
+    /// encoded in memory (see [`upload`](crate::client::upload)).
+    ///
+    /// # Errors
+    /// Returns [`TrustonError::InferenceError`] if the size of a `BYTES` input
+    /// is unknown or if the endpoint is unreachable and the request would fail
+    /// over to another one, and [`TrustonError::Http`] if a source fails or yields
+    /// another number of bytes than announced, which aborts the upload. Server
+    /// errors, deadlines and cancellation are reported as for
+    /// [`infer_with_options`](TritonRestClient::infer_with_options).
+    pub async fn infer_streamed(
+        &self,
+        inputs: Vec<StreamedInput>,
+        model_name: &str,
+        options: &InferOptions,
+    ) -> Result<InferResults, TrustonError> {
+        let started = std::time::Instant::now();
+        let mut stats = InferStats::default();
+        let span = tracing::info_span!(
+            "infer",
+            model = model_name,
+            version = options.model_version.as_deref(),
+            request_id = options.request_id.as_deref(),
+            request_bytes = Empty,
+            status = Empty,
+            trace_id = Empty,
+        );
+        let results = async {
+            let infer = self.send_streamed(inputs, model_name, options, &mut stats);
+            let Some(token) = &options.cancellation else {
+                return infer.await;
+            };
+            tokio::select! {
+                biased;
+                _ = token.cancelled() => Err(TrustonError::Cancelled),
+                results = infer => results,
+            }
+        }
+        .instrument(span)
+        .await;
+        self.record_infer(model_name, results.is_ok(), &stats, started.elapsed());
+        results
+    }
+
+    async fn send_streamed(
+        &self,
+        inputs: Vec<StreamedInput>,
+        model_name: &str,
+        options: &InferOptions,
+        stats: &mut InferStats,
+    ) -> Result<InferResults, TrustonError> {
+        let mut sizes = Vec::with_capacity(inputs.len());
+        for input in &inputs {
+            element_count(&input.name, &input.shape)?;
+            let size = input.byte_size.ok_or_else(|| {
+                TrustonError::InferenceError(format!(
+                    "the byte size of {} input {} must be set with `with_byte_size`",
+                    input.datatype, input.name
+                ))
+            })?;
+            sizes.push(size);
+        }
+
+        let mut parameters = options.request_parameters();
+        if let Some(token) = &options.affinity {
+            parameters.extend(self.affinity_carrier.parameters(token));
+        }
+        let request = InferRequest::<()> {
+            id: options.request_id.as_deref(),
+            parameters,
+            inputs: inputs
+                .iter()
+                .zip(&sizes)
+                .map(|(input, &size)| InferInputPayload {
+                    name: &input.name,
+                    shape: &input.shape,
+                    datatype: input.datatype.as_str(),
+                    data: None,
+                    parameters: [("binary_data_size".to_string(), size.into())]
+                        .into_iter()
+                        .collect(),
+                })
+                .collect(),
+            outputs: options.outputs.as_deref(),
+        };
+        let header = serde_json::to_vec(&request)
+            .map_err(|e| TrustonError::ParseError(e.to_string()))?;
+        let header_len = header.len();
+        let content_length = header_len + sizes.iter().sum::<usize>();
+        stats.request = content_length as u64;
+        tracing::Span::current().record("request_bytes", stats.request);
+
+        let chunks = inputs
+            .into_iter()
+            .zip(sizes)
+            .map(|(input, size)| input.into_checked_stream(size));
+        let body = stream::once(async { Ok(Bytes::from(header)) })
+            .chain(stream::iter(chunks).flatten());
+        let body = std::sync::Mutex::new(Some(body));
+
+        let trace_context = match options.trace_context {
+            Some(parent) => Some(parent.child()),
+            None => self.trace_propagation.then(TraceContext::new_root),
+        };
+        let traceparent = trace_context.map(|context| {
+            tracing::Span::current().record("trace_id", context.trace_id_hex());
+            context.to_string()
+        });
+        let affinity_headers = options
+            .affinity
+            .as_ref()
+            .map(|token| self.affinity_carrier.headers(token))
+            .unwrap_or_default();
+
+        let (_slot, waited) =
+            self.limiter.acquire(options.deadline, options.queue_priority).await?;
+        stats.queue_wait = waited;
+        let started_at = SystemTime::now();
+        let started = std::time::Instant::now();
+        let url = format!("{}/infer", self.model_url(model_name, options.model_version.as_deref()));
+        let (resp, attempts) = self
+            .send_request_once(options.deadline, options.affinity.as_ref(), || {
+                // only called once, the body is taken out of the `Fn`
+                let body = match body.lock().unwrap().take() {
+                    Some(body) => reqwest::Body::wrap_stream(body),
+                    None => reqwest::Body::wrap_stream(stream::once(async {
+                        Err::<Bytes, _>(io::Error::other("a streamed body can only be sent once"))
+                    })),
+                };
+                let mut builder = self
+                    .http
+                    .post(&url)
+                    .body(body)
+                    .header(reqwest::header::CONTENT_LENGTH, content_length)
+                    .header(INFERENCE_HEADER_CONTENT_LENGTH, header_len)
+                    .header(reqwest::header::CONTENT_TYPE, "application/octet-stream");
+                for (name, value) in affinity_headers.iter().chain(&options.headers) {
+                    builder = builder.header(name, value);
+                }
+                if let Some(traceparent) = &traceparent {
+                    builder = builder.header(TRACEPARENT, traceparent);
+                }
+                builder
+            })
+            .await?;
+        self.infer_results(resp, options, attempts, (started_at, started), stats, None).await
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::test_server::{serve, serve_raw};
+
+    #[tokio::test]
+    async fn test_infer_streamed_sends_chunks_after_header() {
+        let body = r#"{"model_name":"m","model_version":"1",
+            "outputs":[{"name":"y","datatype":"FP32","shape":[1],"data":[2.5]}]}"#;
+        let (url, server) = serve(vec![(200, body)]).await;
+        let client = TritonRestClient::new(&url);
+        let rows = vec![DataType::F32(vec![1.0, 2.0]), DataType::F32(vec![3.0, 4.0])];
+        let x = StreamedInput::from_tensor_chunks("x", vec![2, 2], TritonDtype::Fp32, rows);
+        let text = &b"\x02\0\0\0hi"[..];
+        let text =
+            StreamedInput::from_reader("text", vec![1], TritonDtype::Bytes, text).with_byte_size(6);
+
+        let results = client.infer_streamed(vec![x, text], "m", &InferOptions::new()).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_f32_vec(), Some(vec![2.5]));
+
+        let request = server.await.unwrap().remove(0);
+        assert_eq!(request.line(), "POST /v2/models/m/infer HTTP/1.1");
+        let header_len: usize =
+            request.header("inference-header-content-length").unwrap().parse().unwrap();
+        let body = request.body;
+        let header: serde_json::Value = serde_json::from_slice(&body[..header_len]).unwrap();
+        assert_eq!(header["inputs"][0]["parameters"]["binary_data_size"], 16);
+        assert_eq!(header["inputs"][1]["parameters"]["binary_data_size"], 6);
+        let mut expected = binary::encode_tensor(&DataType::F32(vec![1.0, 2.0, 3.0, 4.0])).unwrap();
+        expected.extend_from_slice(b"\x02\0\0\0hi");
+        assert_eq!(&body[header_len..], expected.as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_infer_streamed_checks_sizes() {
+        let client = TritonRestClient::new("http://127.0.0.1:9");
+        let chunks = vec![b"\x02\0\0\0hi".to_vec()];
+        let text = StreamedInput::from_chunks("text", vec![1], TritonDtype::Bytes, chunks);
+        let result = client.infer_streamed(vec![text], "m", &InferOptions::new()).await;
+        assert!(matches!(result, Err(TrustonError::InferenceError(_))));
+
+        let chunks = vec![vec![1u8, 2, 3]];
+        let short = StreamedInput::from_chunks("x", vec![4], TritonDtype::Int8, chunks);
+        let mut chunks = short.into_checked_stream(4);
+        assert!(chunks.next().await.unwrap().is_ok());
+        let error = chunks.next().await.unwrap().unwrap_err();
+        assert_eq!(error.to_string(), "input x yielded 3 bytes, expected 4");
+        assert!(chunks.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_infer_streamed_does_not_fail_over() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let primary = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let (standby, server) = serve_raw(vec![]).await;
+        let client = TritonRestClient::builder(&primary)
+            .failover_endpoint(&standby)
+            .build()
+            .unwrap();
+
+        let x = StreamedInput::from_chunks("x", vec![1], TritonDtype::Int8, vec![vec![1u8]]);
+        let err = client.infer_streamed(vec![x], "m", &InferOptions::new()).await.unwrap_err();
+        assert!(matches!(&err, TrustonError::InferenceError(message)
+            if message.contains("a streamed body can't fail over")), "{:?}", err);
+        assert_eq!(client.active_endpoint(), primary);
+        assert!(server.await.unwrap().is_empty());
+    }
+}
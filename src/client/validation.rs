@@ -0,0 +1,239 @@
+//! Client-side shape checks across inputs.
+//!
+//! Models taking several related inputs (e.g. `input_ids` and
+//! `attention_mask`) expect them to agree on some dimensions, at least the
+//! batch dimension. The server reports a mismatch with a generic message;
+//! these helpers name the offending inputs and dims before sending.
+//!
+//! [`validate_inputs`] goes further and checks the inputs against the model
+//! metadata; [`TritonRestClientBuilder::validate_inputs`](crate::client::http::TritonRestClientBuilder::validate_inputs)
+//! runs it before every inference.
+
+use crate::client::io::InferInput;
+use crate::client::metadata::ModelMetadata;
+use crate::utils::errors::TrustonError;
+
+/// Check input names, datatypes and shapes against the model metadata.
+///
+/// Every mismatch is reported, not only the first one. Inputs declared by
+/// the model but not provided are not reported, since they may be optional.
+/// `-1` dims in the metadata match any size.
+///
+/// # Errors
+/// Returns [`TrustonError::ValidationError`] listing the mismatches.
+///
+/// # Example
+/// ```
+/// use truston::client::dtype::TritonDtype;
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::metadata::{ModelMetadata, TensorMetadata};
+/// use truston::client::validation::validate_inputs;
+///
+/// let metadata = ModelMetadata {
+///     name: "bert".into(),
+///     versions: vec!["1".into()],
+///     platform: "onnxruntime_onnx".into(),
+///     inputs: vec![TensorMetadata {
+///         name: "input_ids".into(),
+///         datatype: TritonDtype::Int64,
+///         shape: vec![-1, 128],
+///     }],
+///     outputs: vec![],
+/// };
+/// let ids = InferInput::new("input_ids".into(), vec![1, 64], DataType::I32(vec![0; 64]));
+///
+/// let err = validate_inputs(&[ids], &metadata).unwrap_err().to_string();
+/// assert!(err.contains("input_ids is INT32, model expects INT64"));
+/// assert!(err.contains("input_ids has shape [1, 64], model expects [-1, 128]"));
+/// ```
+pub fn validate_inputs(
+    inputs: &[InferInput],
+    metadata: &ModelMetadata,
+) -> Result<(), TrustonError> {
+    let mut mismatches = Vec::new();
+    for input in inputs {
+        let Some(expected) = metadata.inputs.iter().find(|t| t.name == input.input_name) else {
+            mismatches.push(format!("model has no input {}", input.input_name));
+            continue;
+        };
+
+        if let Some(datatype) = input.input_data.dtype()
+            && datatype != expected.datatype
+        {
+            mismatches.push(format!(
+                "input {} is {}, model expects {}",
+                input.input_name, datatype, expected.datatype
+            ));
+        }
+
+        let shape_matches = input.input_shape.len() == expected.shape.len()
+            && input
+                .input_shape
+                .iter()
+                .zip(&expected.shape)
+                .all(|(&dim, &expected)| expected < 0 || dim as i64 == expected);
+        if !shape_matches {
+            mismatches.push(format!(
+                "input {} has shape {:?}, model expects {:?}",
+                input.input_name, input.input_shape, expected.shape
+            ));
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(TrustonError::ValidationError { model: metadata.name.clone(), mismatches })
+    }
+}
+
+/// Check that every input has the same first (batch) dimension.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] naming the first input whose
+/// batch dimension differs from the first input's, or which is a scalar.
+///
+/// # Example
+/// ```
+/// use truston::client::io::{DataType, InferInput};
+/// use truston::client::validation::validate_batch_dims;
+///
+/// let ids = InferInput::new("input_ids".into(), vec![2, 3], DataType::I64(vec![0; 6]));
+/// let mask = InferInput::new("attention_mask".into(), vec![1, 3], DataType::I64(vec![1; 3]));
+///
+/// let err = validate_batch_dims(&[ids, mask]).unwrap_err();
+/// assert!(err.to_string().contains("input_ids and attention_mask"));
+/// ```
+pub fn validate_batch_dims(inputs: &[InferInput]) -> Result<(), TrustonError> {
+    let names: Vec<&str> = inputs.iter().map(|input| input.input_name.as_str()).collect();
+    validate_paired_dims(inputs, &names, &[0])
+}
+
+/// Check that the inputs called `names` agree on every dimension in `axes`.
+///
+/// For a BERT-like model, `validate_paired_dims(&inputs, &["input_ids",
+/// "attention_mask"], &[0, 1])` requires the same batch size and sequence length.
+///
+/// # Errors
+/// Returns [`TrustonError::InferenceError`] if one of `names` is not among
+/// `inputs`, has fewer dimensions than an axis requires, or differs from the
+/// first named input on one of `axes`.
+pub fn validate_paired_dims(
+    inputs: &[InferInput],
+    names: &[&str],
+    axes: &[usize],
+) -> Result<(), TrustonError> {
+    let mut paired = Vec::with_capacity(names.len());
+    for name in names {
+        let input = inputs
+            .iter()
+            .find(|input| input.input_name == *name)
+            .ok_or_else(|| TrustonError::InferenceError(format!("missing input {}", name)))?;
+        if let Some(&axis) = axes.iter().find(|&&axis| axis >= input.input_shape.len()) {
+            return Err(TrustonError::InferenceError(format!(
+                "input {} with shape {:?} has no dim {}",
+                input.input_name, input.input_shape, axis
+            )));
+        }
+        paired.push(input);
+    }
+
+    let Some((first, rest)) = paired.split_first() else {
+        return Ok(());
+    };
+    for other in rest {
+        for &axis in axes {
+            let (expected, actual) = (first.input_shape[axis], other.input_shape[axis]);
+            if expected != actual {
+                return Err(TrustonError::InferenceError(format!(
+                    "inputs {} and {} disagree on dim {}: {} vs {} (shapes {:?} and {:?})",
+                    first.input_name,
+                    other.input_name,
+                    axis,
+                    expected,
+                    actual,
+                    first.input_shape,
+                    other.input_shape
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::io::DataType;
+    use crate::client::metadata::TensorMetadata;
+
+    fn input(name: &str, shape: Vec<usize>) -> InferInput {
+        let len = shape.iter().product();
+        InferInput::new(name.into(), shape, DataType::I64(vec![0; len]))
+    }
+
+    #[test]
+    fn test_matching_dims_pass() {
+        let inputs = vec![
+            input("input_ids", vec![2, 8]),
+            input("attention_mask", vec![2, 8]),
+            input("lengths", vec![2]),
+        ];
+        assert!(validate_batch_dims(&inputs).is_ok());
+        assert!(validate_paired_dims(&inputs, &["input_ids", "attention_mask"], &[0, 1]).is_ok());
+    }
+
+    #[test]
+    fn test_mismatch_names_inputs_and_dim() {
+        let inputs = vec![input("input_ids", vec![2, 8]), input("attention_mask", vec![2, 6])];
+        let err = validate_paired_dims(&inputs, &["input_ids", "attention_mask"], &[0, 1])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("input_ids and attention_mask disagree on dim 1: 8 vs 6"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_input_and_short_rank() {
+        let inputs = vec![input("input_ids", vec![2, 8]), input("lengths", vec![2])];
+        assert!(validate_paired_dims(&inputs, &["input_ids", "mask"], &[0]).is_err());
+        assert!(validate_paired_dims(&inputs, &["input_ids", "lengths"], &[1]).is_err());
+    }
+
+    #[test]
+    fn test_validate_inputs_lists_every_mismatch() {
+        let tensor = |name: &str, datatype: &str, shape: Vec<i64>| TensorMetadata {
+            name: name.into(),
+            datatype: datatype.parse().unwrap(),
+            shape,
+        };
+        let metadata = ModelMetadata {
+            name: "bert".into(),
+            versions: vec![],
+            platform: "onnxruntime_onnx".into(),
+            inputs: vec![
+                tensor("input_ids", "INT64", vec![-1, 8]),
+                tensor("attention_mask", "INT64", vec![-1, 8]),
+                tensor("text", "STRING", vec![1]),
+            ],
+            outputs: vec![],
+        };
+
+        let text = InferInput::new("text".into(), vec![1], DataType::String(vec!["a".into()]));
+        assert!(validate_inputs(&[input("input_ids", vec![4, 8]), text], &metadata).is_ok());
+
+        let inputs = vec![
+            input("input_ids", vec![4, 6]),
+            input("attention_mask", vec![4, 8, 1]),
+            input("token_type_ids", vec![4, 8]),
+        ];
+        match validate_inputs(&inputs, &metadata) {
+            Err(TrustonError::ValidationError { model, mismatches }) => {
+                assert_eq!(model, "bert");
+                assert_eq!(mismatches.len(), 3, "{:?}", mismatches);
+                assert_eq!(mismatches[2], "model has no input token_type_ids");
+            }
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+    }
+}
@@ -8,11 +8,42 @@
 //! ## Features
 //!
 //! - **Type-safe inference**: Strongly-typed input/output handling with compile-time guarantees
-//! - **Multiple data types**: Support for all Triton data types (INT8, INT16, INT32, INT64, UINT8, UINT16, UINT64, FP32, FP64, BOOL, STRING, BF16)
+//! - **Multiple data types**: Support for all Triton data types (INT8, INT16, INT32, INT64, UINT8, UINT16, UINT32, UINT64, FP32, FP64, BOOL, STRING, BF16)
 //! - **NDArray integration**: Direct conversion between `ndarray::ArrayD` and Triton tensors
 //! - **Async/await**: Built on `tokio` for efficient concurrent operations
 //! - **Error handling**: Comprehensive error types with context
 //!
+//! The stable part of the API is re-exported by [`prelude`]:
+//! `use truston::prelude::*;` imports the client, tensors, options and errors.
+//!
+//! ## Optional Cargo Features
+//!
+//! - `ndarray` (on by default): conversions between `ndarray` arrays and
+//!   tensors (see [`client::ndarray`]). Without it, tensors are plain `Vec`s.
+//! - `native-tls` (on by default), `rustls`: TLS backend of the REST client,
+//!   the platform's or rustls. Build with `default-features = false` and
+//!   `rustls` for static MUSL binaries; with both, rustls is used.
+//! - `gzip`, `zstd`, `lz4`: compression codecs for request and response bodies
+//!   (see [`client::compression`]).
+//! - `half`: `FP16` tensors as `half::f16` (`DataType::F16`).
+//! - `blocking`: [`client::blocking::TritonRestClient`], a synchronous client for
+//!   code without an async runtime.
+//! - `grpc`: [`client::grpc::TritonGrpcClient`], including streaming inference
+//!   for decoupled models.
+//! - `test-util`: [`client::fault::FaultInjector`], injecting latency, dropped
+//!   connections and bad responses to chaos-test applications, and [`testing`],
+//!   an in-process mock Triton server for unit tests.
+//! - `local`: drop the `Send + Sync` requirement of [`TritonClient`] so it can be
+//!   implemented by `!Send` clients on thread-per-core runtimes.
+//! - `wgpu` (experimental): resize and normalize image batches on the GPU
+//!   (see [`client::gpu`]).
+//! - `image`: turn `image::DynamicImage`s into input tensors (resize, crop,
+//!   layout, normalization), see [`client::image`].
+//! - `detection`: box decoding and NMS for YOLO and SSD-style detection
+//!   models (see [`client::postprocess::detection`]).
+//! - `audio`: turn WAV files and PCM samples into input tensors, resampled
+//!   and optionally chunked (see [`client::audio`]).
+//!
 //! ## Quick Start
 //!
 //! ```no_run
@@ -26,15 +57,15 @@
 //!     let client = TritonRestClient::new("http://localhost:50000");
 //!     
 //!     // Check if server is alive
-//!     let is_alive = client.is_server_live().await?;
-//!     println!("Server is alive: {}", is_alive);
+//!     let live = client.server_live().await?;
+//!     println!("Server is alive: {}", live.is_healthy());
 //!     
 //!     // Prepare input data
 //!     let input_data: ArrayD<f32> = ArrayD::zeros(ndarray::IxDyn(&[1, 224, 224, 3]));
 //!     let input = InferInput::from_ndarray("input", input_data);
 //!     
 //!     // Run inference
-//!     let results = client.infer(vec![input], "my_model").await?;
+//!     let results = client.infer(vec![input], "my_model", None).await?;
 //!     
 //!     // Access results
 //!     for output in results.outputs {
@@ -53,7 +84,7 @@
 //! use truston::client::io::InferInput;
 //! use ndarray::array;
 //!
-//! let arr = array![[1.0, 2.0], [3.0, 4.0]].into_dyn();
+//! let arr = array![[1.0, 2.0], [3.0, 4.0]];
 //! let input = InferInput::from_ndarray("my_input", arr);
 //! ```
 //!
@@ -81,7 +112,7 @@
 //! # let client = TritonRestClient::new("http://localhost:8000");
 //! # let input_data: ArrayD<f32> = ArrayD::zeros(ndarray::IxDyn(&[1, 3]));
 //! # let input = InferInput::from_ndarray("input", input_data);
-//! let results = client.infer(vec![input], "my_model").await?;
+//! let results = client.infer(vec![input], "my_model", None).await?;
 //!
 //! for output in results.outputs {
 //!     // Convert to vector
@@ -105,6 +136,7 @@
 //! | `bool` | BOOL | `DataType::Bool` |
 //! | `u8` | UINT8 | `DataType::U8` |
 //! | `u16` | UINT16 | `DataType::U16` |
+//! | `u32` | UINT32 | `DataType::U32` |
 //! | `u64` | UINT64 | `DataType::U64` |
 //! | `i8` | INT8 | `DataType::I8` |
 //! | `i16` | INT16 | `DataType::I16` |
@@ -113,7 +145,9 @@
 //! | `f32` | FP32 | `DataType::F32` |
 //! | `f64` | FP64 | `DataType::F64` |
 //! | `String` | STRING | `DataType::String` |
+//! | `Vec<u8>` | BYTES | `DataType::Bytes` |
 //! | `u16` (raw) | BF16 | `DataType::Bf16` |
+//! | `half::f16` | FP16 | `DataType::F16` (`half` feature) |
 //!
 //! ## Error Handling
 //!
@@ -126,13 +160,11 @@
 //! # async fn main() {
 //! let client = TritonRestClient::new("http://localhost:50000");
 //!
-//! match client.is_server_live().await {
-//!     Ok(true) => println!("Server is ready"),
-//!     Ok(false) => println!("Server is not ready"),
+//! match client.server_ready().await {
+//!     Ok(health) if health.is_healthy() => println!("Server is ready"),
+//!     Ok(health) => println!("Server is not ready: {}", health),
 //!     Err(TrustonError::Http(msg)) => eprintln!("Connection error: {}", msg),
-//!     Err(TrustonError::ServerError{status: code, message: msg}) => {
-//!         eprintln!("Server error {}: {}", code, msg)
-//!     }
+//!     Err(e @ TrustonError::ServerError { .. }) => eprintln!("{}", e),
 //!     Err(e) => eprintln!("Error: {:?}", e),
 //! }
 //! # }
@@ -146,12 +178,17 @@
 //! Licensed under either of Apache License, Version 2.0 or MIT license at your option.
 
 pub mod client;
+pub mod prelude;
+#[cfg(feature = "test-util")]
+pub mod testing;
 pub mod utils;
 
 // Re-export commonly used items for convenience
-pub use client::http::{TritonClient, TritonRestClient};
-pub use client::io::{DataType, InferInput, InferOutput, InferResults};
-pub use utils::errors::TrustonError;
+pub use client::http::{TritonClient, TritonRestClient, TritonRestClientBuilder};
+pub use client::io::{DataType, InferInput, InferOutput, InferRequestedOutput, InferResults};
+pub use client::options::InferOptions;
+pub use utils::capabilities::{Capabilities, capabilities};
+pub use utils::errors::{ErrorKind, TrustonError};
 
 /// Initialize tracing subscriber for logging.
 ///
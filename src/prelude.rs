@@ -0,0 +1,31 @@
+//! The stable surface of the crate, for glob import.
+//!
+//! Everything re-exported here follows semantic versioning: it is only
+//! changed incompatibly with a new major (or, before 1.0, minor) version.
+//! `tests/public_api.rs` pins the signatures of these items so that
+//! accidental breakage fails the build. Other items of the crate are public
+//! for advanced use and may still change between minor versions.
+//!
+//! # Example
+//! ```no_run
+//! use truston::prelude::*;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), TrustonError> {
+//! let client = TritonRestClient::new("http://localhost:8000");
+//! let input = InferInput::new("x".into(), vec![1, 2], DataType::F32(vec![0.5, 1.5]));
+//! let options = InferOptions::new().with_request_id("req-1");
+//! let results: InferResults = client.infer_with_options(vec![input], "my_model", &options).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::client::auth::Auth;
+pub use crate::client::deadline::Deadline;
+pub use crate::client::dtype::TritonDtype;
+pub use crate::client::health::{Health, Probe};
+pub use crate::client::http::{TritonClient, TritonRestClient, TritonRestClientBuilder};
+pub use crate::client::io::{DataType, InferInput, InferOutput, InferRequestedOutput, InferResults};
+pub use crate::client::options::{CancellationToken, InferOptions};
+pub use crate::client::retry::RetryPolicy;
+pub use crate::utils::errors::{ErrorKind, TrustonError};
@@ -0,0 +1,374 @@
+//! In-process mock of the Triton REST API, for unit tests without a server.
+//!
+//! A [`MockServer`] listens on a random local port and answers the health,
+//! metadata, config and inference endpoints of the v2 protocol for the
+//! [`MockModel`]s mounted on it. Point a client at [`MockServer::url`] and
+//! test the inference code of an application without a GPU box.
+//!
+//! Only available with the `test-util` feature.
+//!
+//! # Example
+//! ```
+//! use truston::client::dtype::TritonDtype;
+//! use truston::client::http::TritonRestClient;
+//! use truston::client::io::{DataType, InferInput, InferOutput};
+//! use truston::testing::{MockModel, MockServer};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), truston::TrustonError> {
+//! let server = MockServer::start().await;
+//! server.mount(MockModel::new("classifier").with_outputs(vec![InferOutput {
+//!     name: "label".into(),
+//!     datatype: TritonDtype::Bytes,
+//!     shape: vec![1],
+//!     data: DataType::String(vec!["cat".into()]),
+//! }]));
+//!
+//! let client = TritonRestClient::new(server.url());
+//! let image = InferInput::new("image".into(), vec![1, 3], DataType::F32(vec![0.0; 3]));
+//! let results = client.infer(vec![image], "classifier", None).await?;
+//! assert_eq!(results.outputs[0].data.as_str_vec(), Some(vec!["cat".to_string()]));
+//! assert_eq!(server.requests().len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use serde_json::{Value, json};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::client::io::InferOutput;
+
+type Handler = Arc<dyn Fn(&Value) -> Result<Vec<InferOutput>, String> + Send + Sync>;
+
+/// A model served by a [`MockServer`].
+#[derive(Clone)]
+pub struct MockModel {
+    name: String,
+    ready: bool,
+    metadata: Option<Value>,
+    config: Option<Value>,
+    handler: Handler,
+}
+
+impl fmt::Debug for MockModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockModel")
+            .field("name", &self.name)
+            .field("ready", &self.ready)
+            .field("metadata", &self.metadata)
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MockModel {
+    /// A ready model with minimal metadata and config, answering every
+    /// inference with no outputs.
+    pub fn new(name: impl Into<String>) -> Self {
+        MockModel {
+            name: name.into(),
+            ready: true,
+            metadata: None,
+            config: None,
+            handler: Arc::new(|_| Ok(Vec::new())),
+        }
+    }
+
+    /// Answer `GET /v2/models/{name}` with `metadata`, e.g. `{"inputs": [...]}`.
+    /// `name`, `versions` and `platform` are filled in when missing.
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Answer `GET /v2/models/{name}/config` with `config`.
+    pub fn with_config(mut self, config: Value) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Report the model as not ready: readiness probes fail and inferences
+    /// are answered with `400`, as Triton does for unavailable models.
+    pub fn not_ready(mut self) -> Self {
+        self.ready = false;
+        self
+    }
+
+    /// Answer every inference with `outputs`.
+    pub fn with_outputs(self, outputs: Vec<InferOutput>) -> Self {
+        self.with_handler(move |_| Ok(outputs.clone()))
+    }
+
+    /// Answer each inference with the outputs computed from its JSON request
+    /// (`{"inputs": [...], ...}`); an error is returned to the client as a
+    /// `400` with the message.
+    pub fn with_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&Value) -> Result<Vec<InferOutput>, String> + Send + Sync + 'static,
+    {
+        self.handler = Arc::new(handler);
+        self
+    }
+
+    fn metadata(&self) -> Value {
+        let mut metadata = self.metadata.clone().unwrap_or_else(|| json!({}));
+        if let Some(fields) = metadata.as_object_mut() {
+            fields.entry("name").or_insert_with(|| json!(self.name));
+            fields.entry("versions").or_insert_with(|| json!(["1"]));
+            fields.entry("platform").or_insert_with(|| json!("mock"));
+            fields.entry("inputs").or_insert_with(|| json!([]));
+            fields.entry("outputs").or_insert_with(|| json!([]));
+        }
+        metadata
+    }
+}
+
+/// A request received by a [`MockServer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    /// JSON body of the request (the JSON header for binary requests),
+    /// `Null` when there is none.
+    pub body: Value,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    ready: bool,
+    models: HashMap<String, MockModel>,
+    requests: Vec<MockRequest>,
+}
+
+/// Mock Triton REST server running in the current Tokio runtime.
+///
+/// The server stops when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    url: String,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Start a live and ready server without models.
+    ///
+    /// # Panics
+    /// If no local port can be bound.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+        let url = format!("http://{}", listener.local_addr().expect("mock server address"));
+        let state = Arc::new(Mutex::new(State { ready: true, ..State::default() }));
+        let task = tokio::spawn(serve(listener, state.clone()));
+        MockServer { url, state, task }
+    }
+
+    /// Base URL to build clients with.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Serve `model`, replacing a model of the same name.
+    pub fn mount(&self, model: MockModel) {
+        let mut state = self.state.lock().unwrap();
+        state.models.insert(model.name.clone(), model);
+    }
+
+    /// Stop serving the model `name`, as if it was unloaded.
+    pub fn unmount(&self, name: &str) {
+        self.state.lock().unwrap().models.remove(name);
+    }
+
+    /// Set what `/v2/health/ready` reports.
+    pub fn set_ready(&self, ready: bool) {
+        self.state.lock().unwrap().ready = ready;
+    }
+
+    /// Requests received so far, in order.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<State>>) {
+    while let Ok((socket, _)) = listener.accept().await {
+        tokio::spawn(handle_connection(socket, state.clone()));
+    }
+}
+
+/// Answer one request, closing the connection afterwards.
+async fn handle_connection(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let Some(request) = read_request(&mut socket).await else { return };
+    let (status, body) = respond(&request, &state);
+    state.lock().unwrap().requests.push(request);
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\
+         connection: close\r\n\r\n{}",
+        status,
+        if status < 400 { "OK" } else { "Error" },
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+async fn read_request(socket: &mut TcpStream) -> Option<MockRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let header = |name: &str| {
+        head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim().parse::<usize>().ok())?
+        })
+    };
+    let content_length = header("content-length").unwrap_or(0);
+    while buf.len() < header_end + content_length {
+        let n = socket.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    let body = &buf[header_end..header_end + content_length];
+    let json_len = header("inference-header-content-length").unwrap_or(body.len());
+    let body = serde_json::from_slice(&body[..json_len.min(body.len())]).unwrap_or(Value::Null);
+    Some(MockRequest { method, path, body })
+}
+
+/// Status and body answering `request`.
+fn respond(request: &MockRequest, state: &Mutex<State>) -> (u16, Value) {
+    let state = state.lock().unwrap();
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let unknown = |name: &str| {
+        (400, json!({ "error": format!("Request for unknown model: '{}'", name) }))
+    };
+    let health = |healthy: bool| if healthy { (200, Value::Null) } else { (400, Value::Null) };
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["v2", "health", "live"]) => health(true),
+        ("GET", ["v2", "health", "ready"]) => health(state.ready),
+        ("GET", ["v2"]) => (200, json!({ "name": "triton", "version": "mock", "extensions": [] })),
+        (method, ["v2", "models", name, rest @ ..]) => {
+            // drop the version of /versions/{version}/...
+            let rest = match rest {
+                ["versions", _, rest @ ..] => rest,
+                rest => rest,
+            };
+            let Some(model) = state.models.get(*name) else {
+                return unknown(name);
+            };
+            match (method, rest) {
+                ("GET", []) => (200, model.metadata()),
+                ("GET", ["ready"]) => health(model.ready),
+                ("GET", ["config"]) => {
+                    let config = model.config.clone().unwrap_or_else(|| json!({ "name": name }));
+                    (200, config)
+                }
+                ("POST", ["infer"]) if !model.ready => unknown(name),
+                ("POST", ["infer"]) => match (model.handler)(&request.body) {
+                    Ok(outputs) => (200, infer_response(name, request, &outputs)),
+                    Err(error) => (400, json!({ "error": error })),
+                },
+                _ => (404, json!({ "error": "Not Found" })),
+            }
+        }
+        _ => (404, json!({ "error": "Not Found" })),
+    }
+}
+
+fn infer_response(model_name: &str, request: &MockRequest, outputs: &[InferOutput]) -> Value {
+    let outputs: Vec<Value> = outputs
+        .iter()
+        .map(|output| {
+            json!({
+                "name": output.name,
+                "datatype": output.datatype,
+                "shape": output.shape,
+                "data": output.data,
+            })
+        })
+        .collect();
+    let mut response = json!({ "model_name": model_name, "model_version": "1", "outputs": outputs });
+    if let Some(id) = request.body.get("id") {
+        response["id"] = id.clone();
+    }
+    response
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::dtype::TritonDtype;
+    use crate::client::http::{TritonClient, TritonRestClient};
+    use crate::client::io::{DataType, InferInput};
+    use crate::utils::errors::TrustonError;
+
+    #[tokio::test]
+    async fn test_mock_server_serves_mounted_models() {
+        let server = MockServer::start().await;
+        let metadata = json!({ "inputs": [{ "name": "x", "datatype": "INT32", "shape": [-1] }] });
+        server.mount(MockModel::new("double").with_metadata(metadata).with_handler(|request| {
+            let values = request["inputs"][0]["data"].as_array().ok_or("no data")?;
+            let doubled = values.iter().filter_map(Value::as_i64).map(|v| v as i32 * 2).collect();
+            Ok(vec![InferOutput {
+                name: "y".into(),
+                datatype: TritonDtype::Int32,
+                shape: vec![values.len()],
+                data: DataType::I32(doubled),
+            }])
+        }));
+        let client = TritonRestClient::new(server.url());
+
+        let metadata = TritonRestClient::model_metadata(&client, "double").await.unwrap();
+        assert_eq!(metadata.name, "double");
+        assert_eq!(metadata.inputs[0].shape, vec![-1]);
+        assert!(client.is_model_ready("double", None).await.unwrap());
+
+        let input = InferInput::new("x".into(), vec![2], DataType::I32(vec![1, 2]));
+        let results = client.infer(vec![input], "double", None).await.unwrap();
+        assert_eq!(results.outputs[0].data.as_i32_vec(), Some(vec![2, 4]));
+
+        let err = client.model_config("missing").await.unwrap_err();
+        assert!(matches!(err, TrustonError::ServerError { status: 400, .. }), "{:?}", err);
+
+        server.set_ready(false);
+        assert!(!client.is_server_ready().await.unwrap());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[2].path, "/v2/models/double/infer");
+        assert_eq!(requests[2].body["inputs"][0]["name"], "x");
+    }
+}
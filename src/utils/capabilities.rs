@@ -0,0 +1,129 @@
+//! Compile-time features of the crate, reported at runtime.
+
+use std::fmt;
+
+/// Which optional Cargo features the crate was built with.
+///
+/// Sent in the default `User-Agent` of the clients, so that server-side logs
+/// show what a client supports.
+///
+/// # Example
+/// ```
+/// let capabilities = truston::capabilities();
+/// if !capabilities.grpc {
+///     println!("gRPC unavailable, using REST");
+/// }
+/// println!("built with: {}", capabilities);
+/// ```
+///
+/// New flags are added with new features, so the struct can't be built
+/// outside the crate; get it from [`capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    pub ndarray: bool,
+    pub native_tls: bool,
+    pub rustls: bool,
+    pub blocking: bool,
+    pub grpc: bool,
+    pub gzip: bool,
+    pub lz4: bool,
+    pub zstd: bool,
+    pub half: bool,
+    pub local: bool,
+    pub test_util: bool,
+    pub wgpu: bool,
+    pub image: bool,
+    pub detection: bool,
+    pub audio: bool,
+    pub arrow: bool,
+    pub polars: bool,
+}
+
+impl Capabilities {
+    /// Names of the enabled features, as spelled in `Cargo.toml`.
+    pub fn enabled(&self) -> Vec<&'static str> {
+        [
+            (self.ndarray, "ndarray"),
+            (self.native_tls, "native-tls"),
+            (self.rustls, "rustls"),
+            (self.blocking, "blocking"),
+            (self.grpc, "grpc"),
+            (self.gzip, "gzip"),
+            (self.lz4, "lz4"),
+            (self.zstd, "zstd"),
+            (self.half, "half"),
+            (self.local, "local"),
+            (self.test_util, "test-util"),
+            (self.wgpu, "wgpu"),
+            (self.image, "image"),
+            (self.detection, "detection"),
+            (self.audio, "audio"),
+            (self.arrow, "arrow"),
+            (self.polars, "polars"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+/// The enabled features separated by `, `, or `none`.
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled = self.enabled();
+        if enabled.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&enabled.join(", "))
+        }
+    }
+}
+
+/// The optional features this build of the crate has.
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        ndarray: cfg!(feature = "ndarray"),
+        native_tls: cfg!(feature = "native-tls"),
+        rustls: cfg!(feature = "rustls"),
+        blocking: cfg!(feature = "blocking"),
+        grpc: cfg!(feature = "grpc"),
+        gzip: cfg!(feature = "gzip"),
+        lz4: cfg!(feature = "lz4"),
+        zstd: cfg!(feature = "zstd"),
+        half: cfg!(feature = "half"),
+        local: cfg!(feature = "local"),
+        test_util: cfg!(feature = "test-util"),
+        wgpu: cfg!(feature = "wgpu"),
+        image: cfg!(feature = "image"),
+        detection: cfg!(feature = "detection"),
+        audio: cfg!(feature = "audio"),
+        arrow: cfg!(feature = "arrow"),
+        polars: cfg!(feature = "polars"),
+    }
+}
+
+/// `User-Agent` sent unless configured otherwise, e.g. `truston/0.1.1 (grpc, gzip)`.
+pub(crate) fn default_user_agent() -> String {
+    format!("truston/{} ({})", env!("CARGO_PKG_VERSION"), capabilities())
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_match_cfg() {
+        let capabilities = capabilities();
+        assert_eq!(capabilities.grpc, cfg!(feature = "grpc"));
+        assert_eq!(capabilities.ndarray, cfg!(feature = "ndarray"));
+        assert_eq!(capabilities.enabled().contains(&"native-tls"), cfg!(feature = "native-tls"));
+        assert_eq!(capabilities.enabled().contains(&"test-util"), cfg!(feature = "test-util"));
+        assert!(default_user_agent().starts_with(concat!("truston/", env!("CARGO_PKG_VERSION"))));
+
+        let none = Capabilities { grpc: false, gzip: false, ..capabilities };
+        let some = Capabilities { grpc: true, gzip: true, ..none };
+        assert!(some.to_string().contains("grpc, gzip"));
+    }
+}
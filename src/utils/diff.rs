@@ -0,0 +1,313 @@
+//! Human-readable comparison of two [`InferResults`].
+//!
+//! Meant for canary comparison reports and test failure messages: the
+//! [`ResultsDiff`] returned by [`diff`] implements [`Display`](fmt::Display)
+//! and lists shape/datatype mismatches, error statistics per output and a
+//! sample of the mismatching element indices.
+
+use std::fmt;
+
+use crate::client::dtype::TritonDtype;
+use crate::client::io::{DataType, InferOutput, InferResults};
+
+/// Maximum number of mismatching elements kept per output.
+const MAX_SAMPLES: usize = 5;
+
+/// Comparison of one output present in both results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputDiff {
+    pub name: String,
+    /// `(expected, actual)` datatypes when they differ.
+    pub datatype_mismatch: Option<(TritonDtype, TritonDtype)>,
+    /// `(expected, actual)` shapes when they differ.
+    pub shape_mismatch: Option<(Vec<usize>, Vec<usize>)>,
+    /// Largest absolute difference between numeric elements.
+    pub max_abs_error: f64,
+    /// Mean absolute difference between numeric elements.
+    pub mean_abs_error: f64,
+    /// Number of elements whose difference exceeds the tolerance.
+    pub mismatch_count: usize,
+    /// First mismatching elements as `(flat index, expected, actual)`.
+    pub samples: Vec<(usize, String, String)>,
+}
+
+impl OutputDiff {
+    pub fn is_match(&self) -> bool {
+        self.datatype_mismatch.is_none()
+            && self.shape_mismatch.is_none()
+            && self.mismatch_count == 0
+    }
+
+    fn record(&mut self, index: usize, expected: String, actual: String) {
+        self.mismatch_count += 1;
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push((index, expected, actual));
+        }
+    }
+}
+
+/// Result of comparing two [`InferResults`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResultsDiff {
+    pub outputs: Vec<OutputDiff>,
+    /// Outputs present in the expected results only.
+    pub missing: Vec<String>,
+    /// Outputs present in the actual results only.
+    pub unexpected: Vec<String>,
+}
+
+impl ResultsDiff {
+    /// `true` when both results contain the same outputs with matching values.
+    pub fn is_match(&self) -> bool {
+        self.missing.is_empty()
+            && self.unexpected.is_empty()
+            && self.outputs.iter().all(OutputDiff::is_match)
+    }
+}
+
+/// Compare two results exactly.
+///
+/// # Example
+/// ```
+/// use truston::client::dtype::TritonDtype;
+/// use truston::client::io::{DataType, InferOutput, InferResults};
+/// use truston::utils::diff::diff;
+///
+/// let output = |values: Vec<f32>| InferResults {
+///     outputs: vec![InferOutput {
+///         name: "probs".into(),
+///         datatype: TritonDtype::Fp32,
+///         shape: vec![2],
+///         data: DataType::F32(values),
+///     }],
+///     ..Default::default()
+/// };
+///
+/// let report = diff(&output(vec![0.1, 0.9]), &output(vec![0.1, 0.8]));
+/// assert!(!report.is_match());
+/// println!("{}", report);
+/// ```
+pub fn diff(expected: &InferResults, actual: &InferResults) -> ResultsDiff {
+    diff_with_tolerance(expected, actual, 0.0)
+}
+
+/// Compare two results, treating numeric elements within `tolerance` (absolute) as equal.
+pub fn diff_with_tolerance(
+    expected: &InferResults,
+    actual: &InferResults,
+    tolerance: f64,
+) -> ResultsDiff {
+    let mut report = ResultsDiff::default();
+
+    for exp in &expected.outputs {
+        match actual.outputs.iter().find(|out| out.name == exp.name) {
+            Some(act) => report.outputs.push(diff_output(exp, act, tolerance)),
+            None => report.missing.push(exp.name.clone()),
+        }
+    }
+    report.unexpected = actual
+        .outputs
+        .iter()
+        .filter(|act| !expected.outputs.iter().any(|exp| exp.name == act.name))
+        .map(|act| act.name.clone())
+        .collect();
+
+    report
+}
+
+fn diff_output(expected: &InferOutput, actual: &InferOutput, tolerance: f64) -> OutputDiff {
+    let mut out = OutputDiff {
+        name: expected.name.clone(),
+        datatype_mismatch: (expected.datatype != actual.datatype)
+            .then(|| (expected.datatype.clone(), actual.datatype.clone())),
+        shape_mismatch: (expected.shape != actual.shape)
+            .then(|| (expected.shape.clone(), actual.shape.clone())),
+        max_abs_error: 0.0,
+        mean_abs_error: 0.0,
+        mismatch_count: 0,
+        samples: Vec::new(),
+    };
+
+    match (numeric_values(&expected.data), numeric_values(&actual.data)) {
+        (Some(exp), Some(act)) => {
+            let mut total = 0.0;
+            for (i, (e, a)) in exp.iter().zip(&act).enumerate() {
+                let err = if e == a || (e.is_nan() && a.is_nan()) {
+                    0.0
+                } else {
+                    // NaN against a number counts as an unbounded error
+                    let err = (e - a).abs();
+                    if err.is_nan() { f64::INFINITY } else { err }
+                };
+                total += err;
+                out.max_abs_error = out.max_abs_error.max(err);
+                if err > tolerance {
+                    out.record(i, e.to_string(), a.to_string());
+                }
+            }
+            let compared = exp.len().min(act.len());
+            if compared > 0 {
+                out.mean_abs_error = total / compared as f64;
+            }
+            out.mismatch_count += exp.len().abs_diff(act.len());
+        }
+        _ => {
+            let (exp, act) = (display_values(&expected.data), display_values(&actual.data));
+            for (i, (e, a)) in exp.iter().zip(&act).enumerate() {
+                if e != a {
+                    out.record(i, e.clone(), a.clone());
+                }
+            }
+            out.mismatch_count += exp.len().abs_diff(act.len());
+        }
+    }
+
+    out
+}
+
+/// Flatten numeric variants into `f64` so they can be compared uniformly.
+fn numeric_values(data: &DataType) -> Option<Vec<f64>> {
+    let values = match data {
+        DataType::Bool(v) => v.iter().map(|&b| b as u8 as f64).collect(),
+        DataType::U8(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::U16(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::U32(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::U64(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::I8(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::I16(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::I32(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::I64(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::F32(v) => v.iter().map(|&x| x as f64).collect(),
+        DataType::F64(v) => v.clone(),
+        DataType::Bf16(v) => v.iter().map(|&x| f32::from_bits((x as u32) << 16) as f64).collect(),
+        #[cfg(feature = "half")]
+        DataType::F16(v) => v.iter().map(|x| x.to_f64()).collect(),
+        DataType::String(_) | DataType::Bytes(_) | DataType::Raw(_) => return None,
+    };
+    Some(values)
+}
+
+fn display_values(data: &DataType) -> Vec<String> {
+    match data {
+        DataType::String(v) => v.clone(),
+        DataType::Bytes(v) => v.iter().map(|x| format!("{:?}", x)).collect(),
+        DataType::Raw(serde_json::Value::Array(v)) => v.iter().map(|x| x.to_string()).collect(),
+        DataType::Raw(v) => vec![v.to_string()],
+        other => numeric_values(other)
+            .unwrap_or_default()
+            .iter()
+            .map(|x| x.to_string())
+            .collect(),
+    }
+}
+
+impl fmt::Display for ResultsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_match() {
+            return writeln!(f, "results match ({} outputs)", self.outputs.len());
+        }
+        for name in &self.missing {
+            writeln!(f, "missing output {:?}", name)?;
+        }
+        for name in &self.unexpected {
+            writeln!(f, "unexpected output {:?}", name)?;
+        }
+        for out in self.outputs.iter().filter(|out| !out.is_match()) {
+            write!(f, "{}", out)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for OutputDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "output {:?}:", self.name)?;
+        if let Some((exp, act)) = &self.datatype_mismatch {
+            writeln!(f, "  datatype: expected {} got {}", exp, act)?;
+        }
+        if let Some((exp, act)) = &self.shape_mismatch {
+            writeln!(f, "  shape: expected {:?} got {:?}", exp, act)?;
+        }
+        if self.mismatch_count > 0 {
+            writeln!(
+                f,
+                "  {} mismatching elements, max abs error {}, mean abs error {}",
+                self.mismatch_count, self.max_abs_error, self.mean_abs_error
+            )?;
+            for (index, exp, act) in &self.samples {
+                writeln!(f, "    [{}] expected {} got {}", index, exp, act)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn results(outputs: Vec<(&str, Vec<usize>, DataType)>) -> InferResults {
+        InferResults {
+            outputs: outputs
+                .into_iter()
+                .map(|(name, shape, data)| InferOutput {
+                    name: name.into(),
+                    datatype: data.dtype().unwrap(),
+                    shape,
+                    data,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_identical_results_match() {
+        let a = results(vec![("out", vec![2], DataType::I64(vec![1, 2]))]);
+        let report = diff(&a, &a.clone());
+        assert!(report.is_match());
+        assert_eq!(report.to_string(), "results match (1 outputs)\n");
+    }
+
+    #[test]
+    fn test_numeric_errors_and_samples() {
+        let a = results(vec![("out", vec![3], DataType::F32(vec![1.0, 2.0, 3.0]))]);
+        let b = results(vec![("out", vec![3], DataType::F32(vec![1.0, 2.5, 4.0]))]);
+        let report = diff(&a, &b);
+        let out = &report.outputs[0];
+
+        assert_eq!(out.mismatch_count, 2);
+        assert_eq!(out.max_abs_error, 1.0);
+        assert_eq!(out.mean_abs_error, 0.5);
+        assert_eq!(out.samples[0].0, 1);
+        assert!(diff_with_tolerance(&a, &b, 1.0).is_match());
+    }
+
+    #[test]
+    fn test_shape_and_missing_outputs() {
+        let a = results(vec![
+            ("out", vec![2], DataType::I32(vec![1, 2])),
+            ("gone", vec![1], DataType::I32(vec![0])),
+        ]);
+        let b = results(vec![
+            ("out", vec![1, 2], DataType::I32(vec![1, 2])),
+            ("new", vec![1], DataType::I32(vec![0])),
+        ]);
+        let report = diff(&a, &b);
+        let text = report.to_string();
+
+        assert_eq!(report.missing, vec!["gone"]);
+        assert_eq!(report.unexpected, vec!["new"]);
+        assert!(text.contains("shape: expected [2] got [1, 2]"));
+    }
+
+    #[test]
+    fn test_string_outputs() {
+        let a = results(vec![("label", vec![2], DataType::String(vec!["cat".into(), "dog".into()]))]);
+        let b = results(vec![("label", vec![2], DataType::String(vec!["cat".into(), "fox".into()]))]);
+        let report = diff(&a, &b);
+
+        assert_eq!(report.outputs[0].samples, vec![(1, "dog".into(), "fox".into())]);
+    }
+}
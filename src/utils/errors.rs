@@ -4,7 +4,11 @@ use std::fmt;
 ///
 /// This enum represents all possible error conditions that can occur
 /// while interacting with a Triton Inference Server via HTTP.
+///
+/// New variants may be added in minor versions; match on [`kind`](Self::kind)
+/// for a stable classification.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TrustonError {
     /// Wraps any underlying HTTP/network error from [`reqwest`].
     ///
@@ -15,14 +19,16 @@ pub enum TrustonError {
     ///
     /// This is used when the server returns a valid response
     /// but indicates an error condition (e.g., 400, 404, 500).
-    ///
-    /// - `status`: HTTP status code returned by the server.
-    /// - `message`: Optional error message extracted from the response body.
     ServerError {
         /// HTTP status code returned by the server.
         status: u16,
-        /// Error message returned by the server.
+        /// Error reported by Triton (the `error` field of its JSON body), or
+        /// the whole body when it is not in that format.
         message: String,
+        /// URL of the request.
+        url: String,
+        /// Model the request was about, if any.
+        model: Option<String>,
     },
 
     /// Error occurred during inference logic.
@@ -36,17 +42,222 @@ pub enum TrustonError {
     /// Typically occurs when the server returns malformed JSON
     /// or unexpected response fields.
     ParseError(String),
+
+    /// The server (or a proxy in front of it) answered with a body that is
+    /// neither JSON nor Triton's binary tensor format.
+    ///
+    /// Typically an HTML error or login page returned by a gateway.
+    ///
+    /// - `content_type`: the `Content-Type` header of the response.
+    /// - `snippet`: the beginning of the response body, for diagnostics.
+    UnexpectedContentType {
+        /// `Content-Type` header of the response.
+        content_type: String,
+        /// Beginning of the response body.
+        snippet: String,
+    },
+
+    /// The client was configured with invalid settings.
+    ///
+    /// Returned by the client builder, e.g. for a malformed header or proxy URL.
+    ConfigError(String),
+
+    /// The credentials of the request could not be obtained (see
+    /// [`TokenProvider`](crate::client::auth::TokenProvider)).
+    AuthError(String),
+
+    /// The caller's deadline passed before a response was received.
+    DeadlineExceeded,
+
+    /// The caller cancelled the request (see
+    /// [`InferOptions::with_cancellation`](crate::client::options::InferOptions::with_cancellation)).
+    Cancelled,
+
+    /// The shape of an input does not match its number of values.
+    ShapeMismatch {
+        /// Name of the input.
+        input: String,
+        /// Number of values implied by the shape.
+        expected: usize,
+        /// Number of values held.
+        actual: usize,
+    },
+
+    /// A shape is unusable: its number of values overflows `usize`, or it
+    /// has a negative dim where a concrete size is needed.
+    ///
+    /// Guards against buggy or malicious shapes in responses, which would
+    /// otherwise panic or allocate absurd buffers.
+    InvalidShape(String),
+
+    /// The inputs of a request do not match the model metadata (see
+    /// [`validate_inputs`](crate::client::validation::validate_inputs)).
+    ValidationError {
+        /// Model the inputs were checked against.
+        model: String,
+        /// One description per mismatching input name, datatype or shape.
+        mismatches: Vec<String>,
+    },
+
+    /// The response to a request was predicted to exceed the memory budget
+    /// (see [`AdmissionControl`](crate::client::admission::AdmissionControl)),
+    /// so it was not sent.
+    AdmissionRejected {
+        /// Estimated peak memory of the response.
+        estimated_bytes: u64,
+        /// Configured budget.
+        budget_bytes: u64,
+    },
+
+    /// A body could not be compressed or decompressed, or the server used
+    /// a content encoding the client has no codec for.
+    CompressionError(String),
+
+    /// A gRPC call failed (only produced by the gRPC client).
+    ///
+    /// - `code`: the gRPC status code (e.g. `14` for `UNAVAILABLE`).
+    /// - `message`: the status message returned by the server or transport.
+    GrpcError {
+        /// gRPC status code.
+        code: i32,
+        /// Status message.
+        message: String,
+    },
+
+    /// No GPU is available or a GPU operation failed (only produced by the
+    /// `wgpu` preprocessing).
+    GpuError(String),
+}
+
+/// Stable classification of a [`TrustonError`], see [`TrustonError::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The server could not be reached or the connection failed.
+    Transport,
+    /// The server answered with an error status.
+    Server,
+    /// The inference could not be completed (e.g. its results could not be
+    /// split or cast).
+    Inference,
+    /// A response could not be parsed.
+    Parse,
+    /// The client is misconfigured.
+    Config,
+    /// Credentials could not be obtained.
+    Auth,
+    /// The caller's deadline passed.
+    DeadlineExceeded,
+    /// The caller cancelled the request.
+    Cancelled,
+    /// The inputs are invalid and were not sent.
+    InvalidInput,
+    /// The request was rejected client-side by admission control.
+    Rejected,
+    /// A body could not be (de)compressed.
+    Compression,
+    /// A GPU operation failed.
+    Gpu,
+}
+
+impl TrustonError {
+    /// Classification of the error, stable across versions unlike the
+    /// variants themselves.
+    ///
+    /// # Example
+    /// ```
+    /// use truston::utils::errors::{ErrorKind, TrustonError};
+    ///
+    /// let err = TrustonError::ShapeMismatch { input: "x".into(), expected: 4, actual: 3 };
+    /// assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            TrustonError::Http(e) if e.is_status() => ErrorKind::Server,
+            TrustonError::Http(_) => ErrorKind::Transport,
+            TrustonError::ServerError { .. } => ErrorKind::Server,
+            TrustonError::InferenceError(_) => ErrorKind::Inference,
+            TrustonError::ParseError(_) | TrustonError::UnexpectedContentType { .. } => {
+                ErrorKind::Parse
+            }
+            TrustonError::ConfigError(_) => ErrorKind::Config,
+            TrustonError::AuthError(_) => ErrorKind::Auth,
+            TrustonError::DeadlineExceeded => ErrorKind::DeadlineExceeded,
+            TrustonError::Cancelled => ErrorKind::Cancelled,
+            TrustonError::ShapeMismatch { .. }
+            | TrustonError::InvalidShape(_)
+            | TrustonError::ValidationError { .. } => ErrorKind::InvalidInput,
+            TrustonError::AdmissionRejected { .. } => ErrorKind::Rejected,
+            TrustonError::CompressionError(_) => ErrorKind::Compression,
+            // UNAVAILABLE
+            TrustonError::GrpcError { code: 14, .. } => ErrorKind::Transport,
+            TrustonError::GrpcError { .. } => ErrorKind::Server,
+            TrustonError::GpuError(_) => ErrorKind::Gpu,
+        }
+    }
+
+    /// HTTP status of a [`ServerError`](Self::ServerError).
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            TrustonError::ServerError { status, .. } => Some(*status),
+            TrustonError::Http(e) => e.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+/// The `error` field of a Triton error body (`{"error": "..."}`), or the
+/// body itself.
+pub(crate) fn triton_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json.get("error")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| body.to_string())
 }
 
 impl fmt::Display for TrustonError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TrustonError::Http(e) => write!(f, "HTTP error: {}", e),
-            TrustonError::ServerError { status, message } => {
-                write!(f, "Server error {}: {}", status, message)
-            }
+            TrustonError::ServerError { status, message, url, model } => match model {
+                Some(model) => {
+                    write!(f, "Server error {} for model {} ({}): {}", status, model, url, message)
+                }
+                None => write!(f, "Server error {} ({}): {}", status, url, message),
+            },
             TrustonError::InferenceError(msg) => write!(f, "Inference error: {}", msg),
             TrustonError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            TrustonError::UnexpectedContentType { content_type, snippet } => write!(
+                f,
+                "Unexpected content type {:?}, body starts with: {}",
+                content_type, snippet
+            ),
+            TrustonError::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
+            TrustonError::AuthError(msg) => write!(f, "Authentication error: {}", msg),
+            TrustonError::DeadlineExceeded => write!(f, "Deadline exceeded"),
+            TrustonError::Cancelled => write!(f, "Request cancelled"),
+            TrustonError::ShapeMismatch { input, expected, actual } => write!(
+                f,
+                "Input {} has {} values, its shape needs {}",
+                input, actual, expected
+            ),
+            TrustonError::InvalidShape(msg) => write!(f, "Invalid shape: {}", msg),
+            TrustonError::ValidationError { model, mismatches } => write!(
+                f,
+                "Inputs do not match model {}: {}",
+                model,
+                mismatches.join("; ")
+            ),
+            TrustonError::AdmissionRejected { estimated_bytes, budget_bytes } => write!(
+                f,
+                "Request rejected: response estimated at {} bytes, budget is {}",
+                estimated_bytes, budget_bytes
+            ),
+            TrustonError::CompressionError(msg) => write!(f, "Compression error: {}", msg),
+            TrustonError::GrpcError { code, message } => {
+                write!(f, "gRPC error {}: {}", code, message)
+            }
+            TrustonError::GpuError(msg) => write!(f, "GPU error: {}", msg),
         }
     }
 }
@@ -66,3 +277,34 @@ impl std::error::Error for TrustonError {
         }
     }
 }
+
+// ######################## UNIT TEST ###################
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triton_message() {
+        assert_eq!(triton_message(r#"{"error":"unknown model: 'm'"}"#), "unknown model: 'm'");
+        assert_eq!(triton_message("<html>bad gateway</html>"), "<html>bad gateway</html>");
+        assert_eq!(triton_message(r#"{"detail":"x"}"#), r#"{"detail":"x"}"#);
+    }
+
+    #[test]
+    fn test_kind_and_status() {
+        let err = TrustonError::ServerError {
+            status: 404,
+            message: "unknown model".into(),
+            url: "http://h/v2/models/m".into(),
+            model: Some("m".into()),
+        };
+        assert_eq!(err.kind(), ErrorKind::Server);
+        assert_eq!(err.status(), Some(404));
+        assert_eq!(
+            err.to_string(),
+            "Server error 404 for model m (http://h/v2/models/m): unknown model"
+        );
+        assert_eq!(TrustonError::Cancelled.kind(), ErrorKind::Cancelled);
+        assert_eq!(TrustonError::Cancelled.status(), None);
+    }
+}
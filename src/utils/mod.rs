@@ -1,2 +1,4 @@
+pub mod capabilities;
+pub mod diff;
 pub mod errors;
 pub mod helper;
\ No newline at end of file
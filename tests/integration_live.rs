@@ -6,6 +6,7 @@ use truston::client::http::{TritonRestClient};
 
 
 #[tokio::test]
+#[allow(deprecated)]
 async fn test_server_live() -> Result<(), TrustonError> {
     let client = TritonRestClient::new("http://localhost:50000");
     let is_alive = client.is_server_live().await?;
@@ -14,6 +15,7 @@ async fn test_server_live() -> Result<(), TrustonError> {
 }
 
 #[tokio::test]
+#[allow(deprecated)]
 async fn test_server_dead() {
     let client = TritonRestClient::new("http://127.0.0.1:9999"); // port dummy
     let result = client.is_server_live().await;
@@ -34,7 +36,7 @@ async fn run_infer(client: Arc<TritonRestClient>) -> Result<(), TrustonError> {
 
     let inputs = vec![input_ids, input_attention_mask];
 
-    let result = client.infer(inputs, "hierarchical_clf").await?;
+    let result = client.infer(inputs, "hierarchical_clf", None).await?;
 
     assert!(
         !result.outputs.is_empty(),
@@ -0,0 +1,65 @@
+//! Compile-time pins of the stable API re-exported by `truston::prelude`.
+//!
+//! Nothing here talks to a server: a signature change of one of these items
+//! makes this file fail to build, flagging the change as breaking.
+
+use std::time::Duration;
+
+use truston::prelude::*;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn prelude_signatures() {
+    let _: fn(&str) -> TritonRestClient = TritonRestClient::new;
+    let _: fn(&str) -> TritonRestClientBuilder = TritonRestClient::builder;
+    let _: fn(TritonRestClientBuilder) -> Result<TritonRestClient, TrustonError> =
+        TritonRestClientBuilder::build;
+    let _: fn(String, Vec<usize>, DataType) -> InferInput = InferInput::new;
+    let _: fn(&InferInput) -> Result<(), TrustonError> = InferInput::check_shape;
+    let _: fn(&DataType) -> &'static str = DataType::get_type_str;
+    let _: fn(&'static str) -> InferRequestedOutput = InferRequestedOutput::new;
+    let _: for<'a> fn(&'a InferResults, &str) -> Option<&'a InferOutput> = InferResults::get;
+    let _: fn() -> InferOptions = InferOptions::new;
+    let _: fn(InferOptions, Deadline) -> InferOptions = InferOptions::with_deadline;
+    let _: fn(InferOptions, CancellationToken) -> InferOptions = InferOptions::with_cancellation;
+    let _: fn(Duration) -> Deadline = Deadline::after;
+    let _: fn(u32) -> RetryPolicy = RetryPolicy::new;
+    let _: fn(&'static str) -> Auth = Auth::bearer;
+    let _: fn(&Health) -> bool = Health::is_healthy;
+
+    assert_send_sync::<TritonRestClient>();
+    assert_send_sync::<InferInput>();
+    assert_send_sync::<InferResults>();
+    assert_send_sync::<TrustonError>();
+    let _: &dyn std::error::Error = &TrustonError::Cancelled;
+    let _: fn(&TrustonError) -> ErrorKind = TrustonError::kind;
+    let _: fn(&TrustonError) -> Option<u16> = TrustonError::status;
+}
+
+#[test]
+fn prelude_fields() {
+    let output = InferOutput {
+        name: String::new(),
+        datatype: TritonDtype::Fp32,
+        shape: Vec::new(),
+        data: DataType::F32(Vec::new()),
+    };
+    let _: &Vec<InferOutput> = &InferResults::default().outputs;
+    let health = Health { probe: Probe::Live, status: 200, body: String::new() };
+    assert!(health.is_healthy());
+    assert_eq!(output.data.get_type_str(), "FP32");
+    assert_eq!(output.data.dtype(), Some(output.datatype));
+}
+
+/// Never called, only type-checked.
+#[allow(dead_code)]
+async fn async_signatures(client: &TritonRestClient, options: &InferOptions) {
+    let _: Result<InferResults, TrustonError> = client.infer(Vec::new(), "m", None).await;
+    let _: Result<InferResults, TrustonError> =
+        client.infer_with_options(Vec::new(), "m", options).await;
+    let _: Result<Health, TrustonError> = client.server_live().await;
+    let _: Result<Health, TrustonError> = client.server_ready().await;
+    let _: Result<Health, TrustonError> = client.model_ready("m", None).await;
+    let _: Result<bool, TrustonError> = TritonClient::is_server_live(client).await;
+}